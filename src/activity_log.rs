@@ -0,0 +1,43 @@
+// Log file for background activity (monitors), kept off stdout so it
+// doesn't draw over the TUI
+use anyhow::{Context, Result};
+use chrono::Local;
+use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Log file name in the data directory
+pub const LOG_FILE: &str = "monitor.log";
+
+static LOG: OnceLock<(PathBuf, Mutex<File>)> = OnceLock::new();
+
+/// Send log messages to `path` (appending) for the rest of the process
+///
+/// Until this is called, messages go to stderr.
+pub fn init(path: &Path) -> Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open log file: {}", path.display()))?;
+    let _ = LOG.set((path.to_path_buf(), Mutex::new(file)));
+    Ok(())
+}
+
+/// Path of the log file, if one was set up
+pub fn path() -> Option<&'static Path> {
+    LOG.get().map(|(path, _)| path.as_path())
+}
+
+/// Write one timestamped line
+pub fn log(message: impl Display) {
+    match LOG.get() {
+        Some((_, file)) => {
+            let mut file = file.lock().unwrap();
+            let _ = writeln!(file, "{} {}", Local::now().format("%Y-%m-%d %H:%M:%S%.3f"), message);
+        }
+        None => eprintln!("{}", message),
+    }
+}
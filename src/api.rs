@@ -0,0 +1,363 @@
+// Queries against the live indexes, shared by the HTTP server and the IPC socket
+use crate::config::Exclusions;
+use crate::content::{self, ContentSearch};
+use crate::export::{entry_json, results_json};
+use crate::file_entry::FileEntry;
+use crate::filters::{extract_search_query, SearchFilters};
+use crate::index::{IndexView, LiveIndex};
+use crate::jobs::{JobKind, JobTracker};
+use crate::monitor::{check_subtree, MonitorStatus};
+use crate::multi_volume::MultiVolumeIndex;
+use crate::search::{SearchEngine, SearchResult};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+
+/// Results returned by a search unless a limit is given
+pub const DEFAULT_LIMIT: usize = 50;
+
+/// Most results (limit + offset) a single search may ask for
+pub const MAX_LIMIT: usize = 10_000;
+
+/// A query, as sent over IPC (`{"request": "search", "query": "..."}`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum Request {
+    /// Matches for a query in the search prompt's syntax (terms and filters)
+    Search {
+        query: String,
+        #[serde(default = "default_limit")]
+        limit: usize,
+        #[serde(default)]
+        offset: usize,
+    },
+
+    /// Number of matches for a query
+    Count { query: String },
+
+    /// Entries with a file ID, in one volume or all of them
+    Entry {
+        file_id: u64,
+        #[serde(default)]
+        volume: Option<String>,
+    },
+
+    /// Entries whose parent has a file ID
+    Children {
+        file_id: u64,
+        #[serde(default)]
+        volume: Option<String>,
+    },
+
+    /// Volumes, entry counts, background jobs and monitor counters
+    Status,
+
+    /// Compare a volume (default: all) with the disk in the background
+    Rescan {
+        #[serde(default)]
+        volume: Option<String>,
+    },
+}
+
+fn default_limit() -> usize {
+    DEFAULT_LIMIT
+}
+
+/// A request that could not be answered, with the matching HTTP status
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiError {
+    pub status: u16,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// Answers requests against a set of volumes
+///
+/// Responses are JSON in the shape `export::export_json` writes. Each caller
+/// brings its own `SearchEngine`, so connections can be served in parallel.
+pub struct IndexService<I> {
+    volumes: Arc<MultiVolumeIndex<I>>,
+    status: Arc<MonitorStatus>,
+    roots: HashMap<String, PathBuf>,
+    exclusions: Exclusions,
+    jobs: Arc<JobTracker>,
+    content: ContentSearch,
+}
+
+impl<I: IndexView + LiveIndex + Send + 'static> IndexService<I> {
+    /// Serve queries against `volumes`
+    pub fn new(volumes: Arc<MultiVolumeIndex<I>>) -> Self {
+        Self {
+            volumes,
+            status: Arc::new(MonitorStatus::default()),
+            roots: HashMap::new(),
+            exclusions: Exclusions::default(),
+            jobs: Arc::new(JobTracker::default()),
+            content: ContentSearch::default(),
+        }
+    }
+
+    /// Limits of `content:` searches, and the content index if one is open
+    pub fn content(mut self, content: ContentSearch) -> Self {
+        self.content = content;
+        self
+    }
+
+    /// Share job state with whoever else works on the volumes (the daemon's scheduler)
+    pub fn jobs(mut self, jobs: Arc<JobTracker>) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// Report (and count rescans in) the counters of the monitor keeping the volumes live
+    pub fn monitor(mut self, status: Arc<MonitorStatus>) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Directory each volume is rescanned from, and what to leave out
+    pub fn rescan_roots(mut self, roots: Vec<(String, PathBuf)>, exclusions: Exclusions) -> Self {
+        self.roots = roots.into_iter().collect();
+        self.exclusions = exclusions;
+        self
+    }
+
+    /// Answer one request
+    pub fn handle(&self, engine: &mut SearchEngine, request: &Request) -> Result<Value, ApiError> {
+        match request {
+            Request::Search { query, limit, offset } => self.search(engine, query, *limit, *offset),
+            Request::Count { query } => self.count(engine, query),
+            Request::Entry { file_id, volume } => {
+                let entries = self.find_entries(volume.as_deref(), |id, _| id == *file_id)?;
+                if entries.is_empty() {
+                    return Err(ApiError::new(404, format!("No entry with id {}", file_id)));
+                }
+                Ok(entries_json(&entries))
+            }
+            Request::Children { file_id, volume } => {
+                let entries = self.find_entries(volume.as_deref(), |_, parent_id| parent_id == *file_id)?;
+                Ok(entries_json(&entries))
+            }
+            Request::Status => Ok(self.status()),
+            Request::Rescan { volume } => self.rescan(volume.as_deref()),
+        }
+    }
+
+    fn search(&self, engine: &mut SearchEngine, query: &str, limit: usize, offset: usize) -> Result<Value, ApiError> {
+        let (terms, filters) = parse_query(query)?;
+        // Both come straight from the request, so their sum may overflow
+        let Some(wanted) = limit.checked_add(offset).filter(|n| *n <= MAX_LIMIT) else {
+            return Err(ApiError::new(400, format!("limit + offset may be at most {}", MAX_LIMIT)));
+        };
+
+        let mut results = self.matches(engine, &terms, wanted, &filters);
+        results.drain(..offset.min(results.len()));
+
+        let mut body = results_json(&results);
+        body["query"] = json!(query);
+        body["offset"] = json!(offset);
+        body["limit"] = json!(limit);
+        Ok(body)
+    }
+
+    fn count(&self, engine: &mut SearchEngine, query: &str) -> Result<Value, ApiError> {
+        let (terms, filters) = parse_query(query)?;
+        let count = match filters.content {
+            Some(_) => self.matches(engine, &terms, usize::MAX, &filters).len(),
+            None => self.volumes.count_matches(engine, &terms, &filters),
+        };
+        Ok(json!({ "query": query, "count": count }))
+    }
+
+    /// Best matches, with a content search if the query has one
+    fn matches(&self, engine: &mut SearchEngine, terms: &str, limit: usize, filters: &SearchFilters) -> Vec<SearchResult> {
+        content::search(&self.volumes, engine, terms, limit, filters, &self.content, &AtomicBool::new(false)).unwrap_or_default()
+    }
+
+    /// Entries whose (file id, parent id) match, in the requested volume or all of them
+    fn find_entries(&self, volume: Option<&str>, matches: impl Fn(u64, u64) -> bool) -> Result<Vec<(String, FileEntry)>, ApiError> {
+        let volumes = match volume {
+            Some(name) => vec![self
+                .volumes
+                .volume(name)
+                .ok_or_else(|| ApiError::new(404, format!("No such volume: {}", name)))?],
+            None => self.volumes.volumes(),
+        };
+
+        let mut entries = Vec::new();
+        for volume in volumes {
+            let index = volume.index.lock().unwrap();
+            entries.extend(
+                index
+                    .entry_refs()
+                    .filter(|entry| matches(entry.file_id(), entry.parent_id()))
+                    .map(|entry| (volume.name.clone(), entry.to_entry())),
+            );
+        }
+        Ok(entries)
+    }
+
+    fn status(&self) -> Value {
+        // Volumes still being loaded or scanned have jobs but no index yet
+        let mut jobs = self.jobs.snapshot();
+        let mut names: Vec<String> = jobs.keys().cloned().collect();
+        names.extend(self.volumes.volume_names());
+        names.sort();
+        names.dedup();
+
+        let volumes: Vec<_> = names
+            .into_iter()
+            .map(|name| {
+                let counts = self.volumes.volume(&name).map(|volume| {
+                    let index = volume.index.lock().unwrap();
+                    (index.file_count(), index.directory_count())
+                });
+                json!({
+                    "name": name,
+                    "root": self.roots.get(&name),
+                    "ready": counts.is_some(),
+                    "files": counts.map(|(files, _)| files),
+                    "directories": counts.map(|(_, directories)| directories),
+                    "jobs": jobs.remove(&name).unwrap_or_default(),
+                })
+            })
+            .collect();
+
+        let last_report = self.status.last_report().map(|report| {
+            json!({
+                "root": report.root,
+                "added": report.added,
+                "removed": report.removed,
+                "updated": report.updated,
+                "examples": report.examples,
+                "seconds": report.duration.as_secs_f64(),
+            })
+        });
+
+        json!({
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "total_files": self.volumes.file_count(),
+            "total_directories": self.volumes.directory_count(),
+            "volumes": volumes,
+            "monitor": self.status.snapshot(),
+            "last_reconciliation": last_report,
+        })
+    }
+
+    /// Start reconciling volumes with the disk; the results show up in the status
+    fn rescan(&self, volume: Option<&str>) -> Result<Value, ApiError> {
+        let names = match volume {
+            Some(name) if self.volumes.volume(name).is_none() => {
+                return Err(ApiError::new(404, format!("No such volume: {}", name)));
+            }
+            Some(name) => vec![name.to_string()],
+            None => self.volumes.volume_names(),
+        };
+
+        let mut started = Vec::new();
+        let mut skipped = Vec::new();
+        let mut busy = Vec::new();
+        for name in names {
+            let (Some(volume), Some(root)) = (self.volumes.volume(&name), self.roots.get(&name).cloned()) else {
+                skipped.push(name);
+                continue;
+            };
+            // Whatever is running (a scan, another rescan) will see the same changes
+            let Some(job) = self.jobs.start(&name, JobKind::Reconcile) else {
+                busy.push(name);
+                continue;
+            };
+
+            let status = Arc::clone(&self.status);
+            let exclusions = self.exclusions.clone();
+            let mut job = job;
+            thread::spawn(move || {
+                if check_subtree(&volume.index, &root, &exclusions, &status, "Rescan requested").is_none() {
+                    job.fail("The volume could not be read; see the activity log");
+                }
+            });
+            started.push(name);
+        }
+
+        Ok(json!({ "started": started, "skipped": skipped, "busy": busy }))
+    }
+}
+
+/// Search terms and filters from a query in the search prompt's syntax
+fn parse_query(query: &str) -> Result<(String, SearchFilters), ApiError> {
+    let filters = SearchFilters::parse_filter_string(query).map_err(|e| ApiError::new(400, e.to_string()))?;
+    let terms = extract_search_query(query);
+    if terms.is_empty() && filters.content.is_none() {
+        return Err(ApiError::new(400, "Missing search terms in the query"));
+    }
+    Ok((terms, filters))
+}
+
+/// Entries found by id, in the export document shape with each entry's volume added
+fn entries_json(entries: &[(String, FileEntry)]) -> Value {
+    let results: Vec<_> = entries
+        .iter()
+        .map(|(volume, entry)| {
+            let mut json = entry_json(entry, None);
+            json["volume"] = json!(volume);
+            json
+        })
+        .collect();
+
+    json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "total_results": results.len(),
+        "results": results
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_requests() {
+        let request: Request = serde_json::from_str(r#"{"request": "search", "query": "report ext:pdf"}"#).unwrap();
+        assert_eq!(
+            request,
+            Request::Search {
+                query: "report ext:pdf".to_string(),
+                limit: DEFAULT_LIMIT,
+                offset: 0
+            }
+        );
+
+        let request: Request = serde_json::from_str(r#"{"request": "children", "file_id": 5, "volume": "C"}"#).unwrap();
+        assert_eq!(
+            request,
+            Request::Children {
+                file_id: 5,
+                volume: Some("C".to_string())
+            }
+        );
+
+        assert_eq!(serde_json::to_string(&Request::Status).unwrap(), r#"{"request":"status"}"#);
+        assert!(serde_json::from_str::<Request>(r#"{"request": "delete"}"#).is_err());
+    }
+}
@@ -0,0 +1,527 @@
+// Archive members: the contents of ZIP, 7z and TAR archives found in an
+// index, stored as virtual entries below each archive
+// (`C:\x\foo.zip!\dir\file.txt`) so searches and exports see inside them
+use crate::config::ArchiveConfig;
+use crate::file_entry::FileEntry;
+use crate::file_type::FileType;
+use crate::index::{FileIndex, LiveIndex};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Ends an archive's path in the paths of its members, before a separator
+pub const MEMBER_MARKER: char = '!';
+
+/// Set in the `file_id` of every member, so members never collide with files on disk
+const MEMBER_ID_BIT: u64 = 1 << 63;
+
+/// Archive formats whose members can be listed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    SevenZip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// Format of an archive by its file name
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if name.ends_with(".7z") {
+            Some(ArchiveFormat::SevenZip)
+        } else if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else {
+            None
+        }
+    }
+
+    /// Format of an indexed file, by its name or else its detected type
+    /// (so a renamed `.zip` is still opened)
+    pub fn of(entry: &FileEntry) -> Option<Self> {
+        if entry.is_directory {
+            return None;
+        }
+        Self::from_name(&entry.name).or_else(|| match entry.file_type? {
+            FileType::Zip => Some(ArchiveFormat::Zip),
+            FileType::SevenZip => Some(ArchiveFormat::SevenZip),
+            FileType::Tar => Some(ArchiveFormat::Tar),
+            _ => None,
+        })
+    }
+}
+
+/// A file or directory inside an archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    /// Path inside the archive, `/`-separated; members of nested archives
+    /// have the nested archive's path and the marker in front (`a.zip!/b.txt`)
+    pub path: String,
+    pub is_directory: bool,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    pub created: Option<DateTime<Utc>>,
+    pub accessed: Option<DateTime<Utc>>,
+}
+
+impl Member {
+    fn directory(path: &str) -> Self {
+        Member {
+            path: path.to_string(),
+            is_directory: true,
+            size: 0,
+            modified: None,
+            created: None,
+            accessed: None,
+        }
+    }
+}
+
+/// A nested archive read into memory, to be listed in turn
+struct Nested {
+    path: String,
+    format: ArchiveFormat,
+    bytes: Vec<u8>,
+}
+
+/// What to open while listing one archive
+struct Lister<'a> {
+    config: &'a ArchiveConfig,
+
+    /// Level of the archive being listed (1 = on disk)
+    depth: usize,
+
+    /// Path of the archive within the outermost one, with the marker (empty on disk)
+    prefix: String,
+}
+
+impl Lister<'_> {
+    /// Whether a member is a nested archive to read
+    fn wants(&self, member: &Member) -> Option<ArchiveFormat> {
+        if member.is_directory || self.depth >= self.config.max_depth || member.size > self.config.max_nested_size() {
+            return None;
+        }
+        ArchiveFormat::from_name(&member.path)
+    }
+
+    /// Add a member, returning the nested archive format if it is to be read
+    fn push(&self, mut member: Member, members: &mut Vec<Member>) -> Option<ArchiveFormat> {
+        let format = self.wants(&member);
+        member.path = format!("{}{}", self.prefix, member.path);
+        members.push(member);
+        format
+    }
+
+    fn list<R: Read + Seek>(&self, reader: R, format: ArchiveFormat, members: &mut Vec<Member>) -> Result<()> {
+        let nested = match format {
+            ArchiveFormat::Zip => self.list_zip(reader, members)?,
+            ArchiveFormat::SevenZip => self.list_7z(reader, members)?,
+            ArchiveFormat::Tar => self.list_tar(reader, members)?,
+            ArchiveFormat::TarGz => self.list_tar(flate2::read::GzDecoder::new(reader), members)?,
+        };
+
+        for archive in nested {
+            let lister = Lister {
+                config: self.config,
+                depth: self.depth + 1,
+                prefix: format!("{}{}{}/", self.prefix, archive.path, MEMBER_MARKER),
+            };
+            // A damaged nested archive stays listed as a plain file
+            let _ = lister.list(Cursor::new(archive.bytes), archive.format, members);
+        }
+        Ok(())
+    }
+
+    fn list_zip<R: Read + Seek>(&self, reader: R, members: &mut Vec<Member>) -> Result<Vec<Nested>> {
+        let mut zip = zip::ZipArchive::new(reader)?;
+        let mut nested = Vec::new();
+        for i in 0..zip.len() {
+            // Raw access reads the headers only, even of encrypted members
+            let file = zip.by_index_raw(i)?;
+            let Some(path) = clean_path(file.name()) else {
+                continue;
+            };
+            let member = Member {
+                path,
+                is_directory: file.is_dir(),
+                size: file.size(),
+                // ZIP stores local time without a zone
+                modified: file
+                    .last_modified()
+                    .and_then(|time| NaiveDateTime::try_from(time).ok())
+                    .map(|time| time.and_utc()),
+                created: None,
+                accessed: None,
+            };
+            drop(file);
+
+            let name = member.path.clone();
+            if let Some(format) = self.push(member, members) {
+                let mut bytes = Vec::new();
+                let read = zip.by_index(i).map(|mut file| file.read_to_end(&mut bytes));
+                if matches!(read, Ok(Ok(_))) {
+                    nested.push(Nested { path: name, format, bytes });
+                }
+            }
+        }
+        Ok(nested)
+    }
+
+    fn list_tar<R: Read>(&self, reader: R, members: &mut Vec<Member>) -> Result<Vec<Nested>> {
+        let mut tar = tar::Archive::new(reader);
+        let mut nested = Vec::new();
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            let Some(path) = clean_path(&entry.path()?.to_string_lossy()) else {
+                continue;
+            };
+            let header = entry.header();
+            let member = Member {
+                path,
+                is_directory: header.entry_type().is_dir(),
+                size: entry.size(),
+                modified: header.mtime().ok().and_then(|secs| DateTime::from_timestamp(secs as i64, 0)),
+                created: None,
+                accessed: None,
+            };
+
+            let name = member.path.clone();
+            if let Some(format) = self.push(member, members) {
+                let mut bytes = Vec::new();
+                if entry.read_to_end(&mut bytes).is_ok() {
+                    nested.push(Nested { path: name, format, bytes });
+                }
+            }
+        }
+        Ok(nested)
+    }
+
+    fn list_7z<R: Read + Seek>(&self, mut reader: R, members: &mut Vec<Member>) -> Result<Vec<Nested>> {
+        let len = reader.seek(SeekFrom::End(0))?;
+        reader.rewind()?;
+        let archive = sevenz_rust::Archive::read(&mut reader, len, &[])?;
+
+        let time = |present: bool, time: sevenz_rust::nt_time::FileTime| {
+            present.then(|| DateTime::from_timestamp(time.to_unix_time(), 0)).flatten()
+        };
+        let mut wanted = HashMap::new();
+        for entry in archive.files.iter().filter(|entry| !entry.is_anti_item()) {
+            let Some(path) = clean_path(entry.name()) else {
+                continue;
+            };
+            let member = Member {
+                path: path.clone(),
+                is_directory: entry.is_directory(),
+                size: entry.size(),
+                modified: time(entry.has_last_modified_date, entry.last_modified_date()),
+                created: time(entry.has_creation_date, entry.creation_date()),
+                accessed: time(entry.has_access_date, entry.access_date()),
+            };
+            if let Some(format) = self.push(member, members) {
+                wanted.insert(path, format);
+            }
+        }
+
+        // Solid archives can only be decoded from the start, so nested
+        // archives cost a pass over everything
+        let mut nested = Vec::new();
+        if !wanted.is_empty() {
+            let mut reader = sevenz_rust::SevenZReader::from_archive(archive, reader, sevenz_rust::Password::empty());
+            reader.for_each_entries(|entry, data| {
+                let path = clean_path(entry.name()).unwrap_or_default();
+                match wanted.get(&path) {
+                    Some(&format) => {
+                        let mut bytes = Vec::new();
+                        data.read_to_end(&mut bytes)?;
+                        nested.push(Nested { path, format, bytes });
+                    }
+                    // Unread data would be taken for the next entry's
+                    None => {
+                        io::copy(data, &mut io::sink())?;
+                    }
+                }
+                Ok(true)
+            })?;
+        }
+        Ok(nested)
+    }
+}
+
+/// A member name as a relative `/`-separated path (None for the archive root)
+fn clean_path(name: &str) -> Option<String> {
+    let path = name.replace('\\', "/");
+    let path = path.trim_start_matches("./").trim_matches('/');
+    (!path.is_empty() && path != ".").then(|| path.to_string())
+}
+
+/// Members of the archive at `path`, including those of nested archives
+/// down to `config.max_depth`
+pub fn list_members(path: &Path, format: ArchiveFormat, config: &ArchiveConfig) -> Result<Vec<Member>> {
+    let file = File::open(path).with_context(|| format!("Failed to open archive: {}", path.display()))?;
+    let lister = Lister {
+        config,
+        depth: 1,
+        prefix: String::new(),
+    };
+    let mut members = Vec::new();
+    lister
+        .list(BufReader::new(file), format, &mut members)
+        .with_context(|| format!("Failed to read archive: {}", path.display()))?;
+    Ok(members)
+}
+
+/// Where the outermost archive's path ends in a member's path
+fn marker_position(path: &str) -> Option<usize> {
+    path.match_indices(MEMBER_MARKER)
+        .map(|(i, _)| i)
+        .find(|&i| path[i + 1..].starts_with(['\\', '/']))
+}
+
+/// Whether an entry is an archive member rather than a file on disk
+///
+/// Members are told apart by their ids: real paths may contain the marker too
+/// (`C:\Music\Yahoo!\a.mp3`).
+pub fn is_member(file_id: u64, path: &str) -> bool {
+    file_id & MEMBER_ID_BIT != 0 && marker_position(path).is_some()
+}
+
+/// The file on disk behind an entry: the outermost archive for members,
+/// the entry's own path otherwise
+pub fn disk_path(entry: &FileEntry) -> &str {
+    match marker_position(&entry.path).filter(|_| entry.is_archive_member()) {
+        Some(end) => &entry.path[..end],
+        None => &entry.path,
+    }
+}
+
+/// Root of the paths of the members of the archive at `path`
+fn members_root(path: &str) -> String {
+    format!("{}{}", path, MEMBER_MARKER)
+}
+
+/// Drop the members of the archive at `path` after it changed or went away,
+/// returning how many there were; the next archive pass lists them again
+///
+/// Any path is checked, not only archive names: archives are also opened by
+/// their detected type (see `ArchiveFormat::of`).
+pub fn forget_members<I: LiveIndex + ?Sized>(index: &mut I, path: &str) -> usize {
+    index.remove_tree(&members_root(path))
+}
+
+/// Move the members of the archive at `from` along with it to `to` (any
+/// path, as for `forget_members`)
+pub fn move_members<I: LiveIndex + ?Sized>(index: &mut I, from: &str, to: &str) -> usize {
+    index.move_path(&members_root(from), &members_root(to))
+}
+
+/// `file_id` of the member at `path`
+fn member_id(path: &str) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    path.hash(&mut hasher);
+    hasher.finish() | MEMBER_ID_BIT
+}
+
+/// Index entries for the members of `archive`
+///
+/// Directories that archives only imply through their members' paths are
+/// added, and of members listed twice (appended TARs) the last one is kept.
+pub fn member_entries(archive: &FileEntry, members: Vec<Member>) -> Vec<FileEntry> {
+    let separator = if archive.path.contains('\\') { "\\" } else { "/" };
+    let full_path = |member: &str| {
+        format!("{}{}{}{}", archive.path, MEMBER_MARKER, separator, member.replace('/', separator))
+    };
+
+    let mut by_path: BTreeMap<String, Member> = members.into_iter().map(|member| (member.path.clone(), member)).collect();
+    let implied: Vec<String> = by_path
+        .keys()
+        .flat_map(|path| path.match_indices('/').map(|(i, _)| &path[..i]))
+        .filter(|parent| !parent.ends_with(MEMBER_MARKER) && !by_path.contains_key(*parent))
+        .map(str::to_string)
+        .collect();
+    for path in implied {
+        by_path.insert(path.clone(), Member::directory(&path));
+    }
+
+    by_path
+        .into_values()
+        .map(|member| {
+            let path = full_path(&member.path);
+            let (name, parent_id) = match member.path.rsplit_once('/') {
+                Some((parent, name)) => {
+                    // Top-level members of a nested archive belong to the nested archive
+                    let parent = parent.strip_suffix(MEMBER_MARKER).unwrap_or(parent);
+                    (name.to_string(), member_id(&full_path(parent)))
+                }
+                None => (member.path.clone(), archive.file_id),
+            };
+            let file_id = member_id(&path);
+            FileEntry::new(
+                name,
+                path,
+                member.is_directory,
+                file_id,
+                parent_id,
+                member.size,
+                member.modified,
+                member.created,
+                member.accessed,
+            )
+        })
+        .collect()
+}
+
+/// What an archive pass did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ArchiveStats {
+    /// Archives whose members were listed
+    pub archives: usize,
+
+    /// Member entries added (files and directories, nested ones included)
+    pub members: usize,
+
+    /// Archives that could not be read
+    pub failed: usize,
+}
+
+impl fmt::Display for ArchiveStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} archives listed, {} members, {} unreadable",
+            self.archives, self.members, self.failed
+        )
+    }
+}
+
+/// List the members of every archive of an index not opened yet and add
+/// them as entries
+///
+/// Members stay until their archive is rescanned, changed (the monitor drops
+/// them, the next pass lists them again), moved or deleted. Archives are
+/// marked as listed even when empty or unreadable, so they are not opened
+/// again until they change.
+pub fn index_archives(index: &mut FileIndex, config: &ArchiveConfig) -> ArchiveStats {
+    // Indexes saved before archives were marked only show it through their members
+    let listed: HashSet<String> = index
+        .entries()
+        .iter()
+        .filter(|entry| entry.is_archive_member())
+        .map(|entry| disk_path(entry).to_string())
+        .collect();
+
+    let listings: Vec<(usize, Result<Vec<Member>>)> = index
+        .entries()
+        .par_iter()
+        .enumerate()
+        .filter(|(_, entry)| !entry.members_listed && !listed.contains(&entry.path) && !entry.is_archive_member())
+        .filter_map(|(i, entry)| {
+            let format = ArchiveFormat::of(entry)?;
+            Some((i, list_members(Path::new(&entry.path), format, config)))
+        })
+        .collect();
+
+    let mut stats = ArchiveStats::default();
+    let mut added = Vec::new();
+    for (i, listing) in listings {
+        index.entries_mut()[i].members_listed = true;
+        match listing {
+            Ok(members) => {
+                let entries = member_entries(&index.entries()[i], members);
+                stats.archives += 1;
+                stats.members += entries.len();
+                added.extend(entries);
+            }
+            Err(_) => stats.failed += 1,
+        }
+    }
+    index.reserve(added.len());
+    for entry in added {
+        index.add_entry(entry);
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_index_archives() {
+        // A TAR with a file, nested in a ZIP whose directory is only implied
+        let mut tar = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mtime(1_700_000_000);
+        header.set_cksum();
+        tar.append_data(&mut header, "deep.txt", &b"hello"[..]).unwrap();
+        let tar = tar.into_inner().unwrap();
+
+        let dir = std::env::temp_dir().join(format!("nothing_archive_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("bundle.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("docs/readme.md", options).unwrap();
+        zip.write_all(b"# Readme").unwrap();
+        zip.start_file("inner.tar", options).unwrap();
+        zip.write_all(&tar).unwrap();
+        zip.finish().unwrap();
+        std::fs::write(dir.join("broken.7z"), b"not an archive").unwrap();
+
+        let mut index = FileIndex::new();
+        for (id, name) in ["bundle.zip", "broken.7z"].into_iter().enumerate() {
+            let path = dir.join(name).to_string_lossy().to_string();
+            index.add_entry(FileEntry::new(name.into(), path, false, id as u64 + 1, 0, 0, None, None, None));
+        }
+
+        let stats = index_archives(&mut index, &ArchiveConfig::default());
+        assert_eq!(stats, ArchiveStats { archives: 1, members: 4, failed: 1 });
+        let zip_path = dir.join("bundle.zip").to_string_lossy().to_string();
+        let find = |member: &str| {
+            let path = format!("{}!/{}", zip_path, member);
+            index.entries().iter().find(|e| e.path == path).cloned().unwrap()
+        };
+        let docs = find("docs");
+        assert!(docs.is_directory && docs.is_archive_member());
+        assert_eq!(docs.parent_id, 1);
+        let readme = find("docs/readme.md");
+        assert_eq!((readme.name.as_str(), readme.size, readme.parent_id), ("readme.md", 8, docs.file_id));
+        let deep = find("inner.tar!/deep.txt");
+        assert_eq!(deep.parent_id, find("inner.tar").file_id);
+        assert_eq!(deep.modified, DateTime::from_timestamp(1_700_000_000, 0));
+        assert_eq!(disk_path(&deep), zip_path);
+
+        // Listed archives are skipped, unreadable ones too, and the depth limit stops at nested ones
+        assert_eq!(index_archives(&mut index, &ArchiveConfig::default()), ArchiveStats::default());
+        let shallow = ArchiveConfig {
+            max_depth: 1,
+            ..Default::default()
+        };
+        let members = list_members(&dir.join("bundle.zip"), ArchiveFormat::Zip, &shallow).unwrap();
+        assert_eq!(members.len(), 2);
+
+        // Members follow their archive, also to a name that is not an archive's (opened by its detected type)
+        let moved = dir.join("moved.bin").to_string_lossy().to_string();
+        assert_eq!(move_members(&mut index, &zip_path, &moved), 4);
+        assert!(index.entries().iter().any(|e| e.path == format!("{}!/inner.tar!/deep.txt", moved)));
+        assert_eq!(forget_members(&mut index, &moved), 4);
+
+        // A folder whose name ends in the marker holds plain files
+        let yahoo = FileEntry::new("a.mp3".into(), "/music/Yahoo!/a.mp3".into(), false, 7, 6, 0, None, None, None);
+        assert!(!yahoo.is_archive_member());
+        assert_eq!(disk_path(&yahoo), "/music/Yahoo!/a.mp3");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,5 +1,8 @@
 use std::path::Path;
+use nothing::api::Request;
+use nothing::ipc::{self, IpcClient};
 use nothing::persistence;
+use serde_json::Value;
 
 fn main() -> anyhow::Result<()> {
     println!("========================================================================");
@@ -7,37 +10,16 @@ fn main() -> anyhow::Result<()> {
     println!("========================================================================");
     println!();
 
-    // Check if a scan is currently running
-    let scan_running = std::process::Command::new("tasklist")
-        .output()
-        .ok()
-        .and_then(|output| {
-            let output_str = String::from_utf8_lossy(&output.stdout);
-            if output_str.contains("nothing.exe") {
-                // Extract memory usage
-                for line in output_str.lines() {
-                    if line.contains("nothing.exe") {
-                        let parts: Vec<&str> = line.split_whitespace().collect();
-                        if parts.len() >= 5 {
-                            let mem_kb = parts[4].replace(",", "");
-                            if let Ok(kb) = mem_kb.parse::<u64>() {
-                                return Some(kb);
-                            }
-                        }
-                    }
-                }
-            }
-            None
-        });
-
-    if let Some(mem_kb) = scan_running {
-        let mem_gb = mem_kb as f64 / 1_048_576.0;
-        println!("[SCAN] ACTIVE SCAN DETECTED:");
-        println!("   Memory usage: {:.2} GB", mem_gb);
-        println!("   Status: Building index in memory (will write when complete)");
-        println!();
+    // A running indexer knows what it is doing; ask it instead of reading the index files
+    if let Ok(mut client) = IpcClient::connect(&ipc::socket_path()?) {
+        let status = client.request(&Request::Status)?;
+        print_indexer_status(&status);
+        return Ok(());
     }
 
+    println!("No indexer is running; showing the index files on disk.");
+    println!();
+
     // Get all available drives (A-Z)
     let all_drives: Vec<char> = ('A'..='Z').collect();
 
@@ -91,15 +73,9 @@ fn main() -> anyhow::Result<()> {
                         }
                     }
                 } else {
-                    let status = if scan_running.is_some() && total_indexed > 0 {
-                        "[SCAN] SCANNING"
-                    } else {
-                        "[WAIT] WAITING"
-                    };
-
                     println!("{:<8} {:<20} {:<15} {:<20} {:<15}",
                              format!("{}:", drive),
-                             status,
+                             "[WAIT] WAITING",
                              "-",
                              "-",
                              "-");
@@ -136,9 +112,74 @@ fn main() -> anyhow::Result<()> {
     if total_indexed < total_drives {
         println!("** {} drive(s) still need indexing!", total_drives - total_indexed);
         println!("   Run: nothing.exe -a -f");
+        println!("   Or keep every drive indexed in the background: nothing.exe -a daemon");
     } else {
         println!("** All drives are indexed!");
     }
 
     Ok(())
 }
+
+/// Volumes and jobs as reported by `nothing daemon` (or `nothing serve`)
+fn print_indexer_status(status: &Value) {
+    println!("{:<12} {:<22} {:<14} {:<14} {:<20}",
+             "Volume", "Status", "Files", "Directories", "Last scan");
+    println!("{}", "-".repeat(84));
+
+    let volumes = status["volumes"].as_array().cloned().unwrap_or_default();
+    for volume in &volumes {
+        let jobs = &volume["jobs"];
+        let state = if let Some(kind) = jobs["running"]["kind"].as_str() {
+            format!("[BUSY] {}", kind.to_uppercase())
+        } else if !jobs["last_error"].is_null() {
+            "[ERR] FAILED".to_string()
+        } else if volume["ready"].as_bool() == Some(true) {
+            "[OK] LIVE".to_string()
+        } else {
+            "[WAIT] WAITING".to_string()
+        };
+
+        println!("{:<12} {:<22} {:<14} {:<14} {:<20}",
+                 volume["name"].as_str().unwrap_or("?"),
+                 state,
+                 count(&volume["files"]),
+                 count(&volume["directories"]),
+                 timestamp(&jobs["last_scan"]));
+        if let Some(error) = jobs["last_error"].as_str() {
+            println!("{:<12} {}", "", error);
+        }
+    }
+
+    println!("{}", "-".repeat(84));
+    println!();
+
+    let monitor = &status["monitor"];
+    println!("== SUMMARY ==");
+    println!("   Volumes: {} ({} live)", volumes.len(),
+             volumes.iter().filter(|v| v["ready"].as_bool() == Some(true)).count());
+    println!("   Total files indexed: {}", count(&status["total_files"]));
+    println!("   Total directories indexed: {}", count(&status["total_directories"]));
+    println!("   Changes applied since start: {} (from {} events)",
+             ["created", "deleted", "renamed", "modified"]
+                 .iter()
+                 .filter_map(|kind| monitor[kind].as_u64())
+                 .sum::<u64>(),
+             count(&monitor["events"]));
+    if let Some(report) = status["last_reconciliation"].as_object() {
+        println!("   Last consistency check: {} (+{} -{} ~{})",
+                 report["root"].as_str().unwrap_or("?"), report["added"], report["removed"], report["updated"]);
+    }
+}
+
+/// A count from the status, or "-" if the volume isn't loaded yet
+fn count(value: &Value) -> String {
+    value.as_u64().map_or_else(|| "-".to_string(), |n| n.to_string())
+}
+
+/// An RFC 3339 time from the status, shortened to local minutes
+fn timestamp(value: &Value) -> String {
+    value
+        .as_str()
+        .and_then(|time| chrono::DateTime::parse_from_rfc3339(time).ok())
+        .map_or_else(|| "-".to_string(), |time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+}
@@ -13,6 +13,13 @@ fn main() -> anyhow::Result<()> {
     println!("   Directories: {}", index.directory_count());
     println!("   Memory usage: ~{:.2} KB\n", index.memory_usage() as f64 / 1024.0);
 
+    let stats = persistence::index_storage_stats(&cache_path)?;
+    println!("💾 Storage:");
+    println!("   Compression: {}", stats.compression.name());
+    println!("   Raw size: {:.2} KB", stats.raw_bytes as f64 / 1024.0);
+    println!("   Stored size: {:.2} KB ({:.1}% of raw)\n",
+             stats.stored_bytes as f64 / 1024.0, stats.ratio() * 100.0);
+
     println!("📁 Sample entries (first 30):");
     println!("{:<40} {:<15} {:<60}", "Name", "Type", "Path");
     println!("{}", "-".repeat(115));
@@ -0,0 +1,301 @@
+// Filesystem change sources: normalized events from a watched tree
+#[cfg(not(target_os = "linux"))]
+use crate::activity_log;
+use anyhow::Result;
+#[cfg(any(not(target_os = "linux"), test))]
+use notify::event::{ModifyKind, RenameMode};
+#[cfg(any(not(target_os = "linux"), test))]
+use notify::{Event, EventKind};
+#[cfg(not(target_os = "linux"))]
+use notify::{RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+#[cfg(not(target_os = "linux"))]
+use std::sync::mpsc::{self, RecvTimeoutError};
+#[cfg(not(target_os = "linux"))]
+use std::thread;
+#[cfg(not(target_os = "linux"))]
+use std::time::Duration;
+
+/// A change to the filesystem, independent of the backend that saw it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeEvent {
+    /// File or directory created (or moved in from outside the watched tree)
+    Created(PathBuf),
+
+    /// File or directory deleted (or moved out of the watched tree)
+    Deleted(PathBuf),
+
+    /// File or directory renamed or moved within the watched tree
+    Renamed { from: PathBuf, to: PathBuf },
+
+    /// Contents or metadata changed
+    Modified(PathBuf),
+
+    /// Events were lost (queue overflow, watcher error) somewhere under this path
+    Overflow(PathBuf),
+}
+
+/// Backend that watches a tree and reports `ChangeEvent`s
+///
+/// Watching stops when the source is dropped; the sender passed to `start`
+/// is dropped with it, which ends the receiving loop.
+pub trait ChangeSource: Send {
+    /// Human-readable description of what is watched
+    fn describe(&self) -> String;
+
+    /// Root of the watched tree
+    fn root(&self) -> &Path;
+
+    /// Start watching and send events to `events`
+    fn start(&mut self, events: Sender<ChangeEvent>) -> Result<()>;
+}
+
+/// Best change source for a path on this platform
+pub fn for_path(path: PathBuf) -> Box<dyn ChangeSource> {
+    #[cfg(target_os = "linux")]
+    {
+        Box::new(crate::inotify_source::InotifySource::new(path))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        Box::new(NotifySource::new(path))
+    }
+}
+
+// Linux uses `InotifySource`, so the `notify` backend below is built on other
+// platforms only (its rename pairing also for tests)
+
+/// How long the first half of a rename waits for its second half
+#[cfg(not(target_os = "linux"))]
+const RENAME_WINDOW: Duration = Duration::from_millis(100);
+
+/// Change source using the `notify` crate (ReadDirectoryChangesW, FSEvents, inotify, ...)
+///
+/// Backends report renames as separate "from" and "to" events (Windows,
+/// macOS); a translator thread pairs them into `ChangeEvent::Renamed`.
+#[cfg(not(target_os = "linux"))]
+pub struct NotifySource {
+    root: PathBuf,
+    watcher: Option<notify::RecommendedWatcher>,
+    translator: Option<thread::JoinHandle<()>>,
+}
+
+#[cfg(not(target_os = "linux"))]
+impl NotifySource {
+    /// Create a source for the tree under `root`
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            watcher: None,
+            translator: None,
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl ChangeSource for NotifySource {
+    fn describe(&self) -> String {
+        self.root.display().to_string()
+    }
+
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn start(&mut self, events: Sender<ChangeEvent>) -> Result<()> {
+        let (raw_tx, raw_rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+            let _ = raw_tx.send(res);
+        })?;
+        watcher.watch(&self.root, RecursiveMode::Recursive)?;
+
+        let root = self.root.clone();
+        let translator = thread::spawn(move || {
+            let mut pairer = RenamePairer::default();
+            loop {
+                let (changes, done) = match raw_rx.recv_timeout(RENAME_WINDOW) {
+                    Ok(Ok(event)) => (pairer.push(event, &root), false),
+                    Ok(Err(e)) => {
+                        activity_log::log(format!("Watch error on {}: {}", root.display(), e));
+                        // Only the subtree the error names (if any) needs a rescan
+                        let affected = e.paths.first().cloned().unwrap_or_else(|| root.clone());
+                        let mut changes = pairer.flush();
+                        changes.push(ChangeEvent::Overflow(affected));
+                        (changes, false)
+                    }
+                    Err(RecvTimeoutError::Timeout) => (pairer.flush(), false),
+                    Err(RecvTimeoutError::Disconnected) => (pairer.flush(), true),
+                };
+                let sent = changes.into_iter().all(|change| events.send(change).is_ok());
+                if done || !sent {
+                    break;
+                }
+            }
+        });
+
+        self.watcher = Some(watcher);
+        self.translator = Some(translator);
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl Drop for NotifySource {
+    fn drop(&mut self) {
+        // Dropping the watcher closes the raw channel, which ends the translator
+        self.watcher.take();
+        if let Some(translator) = self.translator.take() {
+            let _ = translator.join();
+        }
+    }
+}
+
+/// Turns `notify` events into `ChangeEvent`s, pairing the halves of renames
+#[cfg(any(not(target_os = "linux"), test))]
+#[derive(Default)]
+struct RenamePairer {
+    /// "From" half waiting for its "to" half, with its tracker if the backend gives one
+    pending: Option<(PathBuf, Option<usize>)>,
+
+    /// Last rename emitted (inotify also reports a combined event after the halves)
+    last_rename: Option<(PathBuf, PathBuf)>,
+}
+
+#[cfg(any(not(target_os = "linux"), test))]
+impl RenamePairer {
+    /// Translate one event
+    fn push(&mut self, event: Event, root: &Path) -> Vec<ChangeEvent> {
+        if event.need_rescan() {
+            let mut changes = self.flush();
+            changes.push(ChangeEvent::Overflow(root.to_path_buf()));
+            return changes;
+        }
+
+        let tracker = event.tracker();
+        let paths = event.paths;
+        match event.kind {
+            EventKind::Modify(ModifyKind::Name(mode)) => match mode {
+                RenameMode::Both if paths.len() == 2 => {
+                    let mut paths = paths.into_iter();
+                    let from = paths.next().unwrap();
+                    let to = paths.next().unwrap();
+                    let mut changes = self.flush();
+                    if self.last_rename.as_ref() != Some(&(from.clone(), to.clone())) {
+                        changes.push(self.renamed(from, to));
+                    }
+                    changes
+                }
+                RenameMode::From => paths.into_iter().flat_map(|path| self.rename_from(path, tracker)).collect(),
+                RenameMode::To => paths.into_iter().flat_map(|path| self.rename_to(path, tracker)).collect(),
+                // Backend could not tell which half this is (FSEvents): check the disk
+                _ => paths
+                    .into_iter()
+                    .flat_map(|path| {
+                        if path.exists() {
+                            self.rename_to(path, tracker)
+                        } else {
+                            self.rename_from(path, tracker)
+                        }
+                    })
+                    .collect(),
+            },
+            kind => {
+                let mut changes = self.flush();
+                match kind {
+                    EventKind::Create(_) => changes.extend(paths.into_iter().map(ChangeEvent::Created)),
+                    EventKind::Remove(_) => changes.extend(paths.into_iter().map(ChangeEvent::Deleted)),
+                    EventKind::Modify(_) => changes.extend(paths.into_iter().map(ChangeEvent::Modified)),
+                    _ => {}
+                }
+                changes
+            }
+        }
+    }
+
+    /// Give up waiting for the other half: a lone "from" means the path left the tree
+    fn flush(&mut self) -> Vec<ChangeEvent> {
+        self.pending
+            .take()
+            .map(|(path, _)| ChangeEvent::Deleted(path))
+            .into_iter()
+            .collect()
+    }
+
+    fn rename_from(&mut self, path: PathBuf, tracker: Option<usize>) -> Vec<ChangeEvent> {
+        let changes = self.flush();
+        self.pending = Some((path, tracker));
+        changes
+    }
+
+    fn rename_to(&mut self, path: PathBuf, tracker: Option<usize>) -> Vec<ChangeEvent> {
+        match self.pending.take() {
+            Some((from, from_tracker)) if from_tracker.is_none() || tracker.is_none() || from_tracker == tracker => {
+                vec![self.renamed(from, path)]
+            }
+            // Unrelated "from" (left the tree) and a path that arrived from outside
+            Some((from, _)) => vec![ChangeEvent::Deleted(from), ChangeEvent::Created(path)],
+            None => vec![ChangeEvent::Created(path)],
+        }
+    }
+
+    fn renamed(&mut self, from: PathBuf, to: PathBuf) -> ChangeEvent {
+        self.last_rename = Some((from.clone(), to.clone()));
+        ChangeEvent::Renamed { from, to }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, Flag};
+
+    fn rename_half(mode: RenameMode, path: &str) -> Event {
+        Event::new(EventKind::Modify(ModifyKind::Name(mode))).add_path(path.into())
+    }
+
+    #[test]
+    fn test_translate_notify_events() {
+        let root = Path::new("/data");
+        let mut pairer = RenamePairer::default();
+
+        let event = Event::new(EventKind::Create(CreateKind::File)).add_path("/data/a.txt".into());
+        assert_eq!(pairer.push(event, root), vec![ChangeEvent::Created("/data/a.txt".into())]);
+
+        let event = Event::new(EventKind::Other).set_flag(Flag::Rescan);
+        assert_eq!(pairer.push(event, root), vec![ChangeEvent::Overflow("/data".into())]);
+    }
+
+    #[test]
+    fn test_pair_rename_halves() {
+        let root = Path::new("/data");
+        let mut pairer = RenamePairer::default();
+        let renamed = ChangeEvent::Renamed {
+            from: "/data/a.txt".into(),
+            to: "/data/b.txt".into(),
+        };
+
+        // Windows: "from" then "to", no tracker
+        assert!(pairer.push(rename_half(RenameMode::From, "/data/a.txt"), root).is_empty());
+        assert_eq!(pairer.push(rename_half(RenameMode::To, "/data/b.txt"), root), vec![renamed.clone()]);
+
+        // inotify: halves with a tracker, then a combined event that must not repeat the rename
+        let both = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+            .add_path("/data/a.txt".into())
+            .add_path("/data/b.txt".into());
+        assert!(pairer.push(both, root).is_empty());
+
+        // A lone "from" is a move out of the tree
+        assert!(pairer.push(rename_half(RenameMode::From, "/data/c.txt"), root).is_empty());
+        assert_eq!(pairer.flush(), vec![ChangeEvent::Deleted("/data/c.txt".into())]);
+
+        // Halves with different trackers do not belong together
+        let from = rename_half(RenameMode::From, "/data/d.txt").set_tracker(1);
+        let to = rename_half(RenameMode::To, "/data/e.txt").set_tracker(2);
+        assert!(pairer.push(from, root).is_empty());
+        assert_eq!(
+            pairer.push(to, root),
+            vec![ChangeEvent::Deleted("/data/d.txt".into()), ChangeEvent::Created("/data/e.txt".into())]
+        );
+    }
+}
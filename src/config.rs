@@ -0,0 +1,682 @@
+// Configuration: where Nothing keeps its data, and the config.toml file
+use crate::export::ExportFormat;
+use crate::index_format::Compression;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Environment variable that overrides the data directory
+pub const HOME_ENV: &str = "NOTHING_HOME";
+
+/// Name of the config file
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Results shown in the interactive CLI unless configured otherwise
+pub const DEFAULT_RESULT_LIMIT: usize = 50;
+
+/// Results shown in the GUI unless configured otherwise
+pub const DEFAULT_GUI_RESULT_LIMIT: usize = 100;
+
+/// Minutes between consistency checks of monitored volumes unless configured otherwise
+pub const DEFAULT_CHECK_INTERVAL_MINUTES: u64 = 60;
+
+/// Port `nothing serve` listens on unless configured otherwise
+pub const DEFAULT_SERVER_PORT: u16 = 7590;
+
+/// Hours between full rescans in daemon mode unless configured otherwise
+pub const DEFAULT_RESCAN_INTERVAL_HOURS: u64 = 24;
+
+/// Minutes between index saves in daemon mode unless configured otherwise
+pub const DEFAULT_SAVE_INTERVAL_MINUTES: u64 = 15;
+
+/// Drifted entries in one consistency check that make the daemon rescan a volume
+pub const DEFAULT_DRIFT_RESCAN_THRESHOLD: usize = 1000;
+
+/// Largest file (MB) a `content:` search reads unless configured otherwise
+pub const DEFAULT_CONTENT_MAX_FILE_SIZE_MB: u64 = 10;
+
+/// Most files a `content:` search reads unless configured otherwise
+pub const DEFAULT_CONTENT_MAX_FILES: usize = 100_000;
+
+/// Levels of archives opened (1 = only archives on disk) unless configured otherwise
+pub const DEFAULT_ARCHIVE_MAX_DEPTH: usize = 3;
+
+/// Largest nested archive (MB) read into memory unless configured otherwise
+pub const DEFAULT_ARCHIVE_MAX_NESTED_SIZE_MB: u64 = 256;
+
+/// Data directory given with `--data-dir`
+static DATA_DIR_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use `dir` as the data directory for the rest of the process (`--data-dir`)
+pub fn set_data_dir(dir: PathBuf) -> Result<()> {
+    if DATA_DIR_OVERRIDE.set(dir.clone()).is_err() && DATA_DIR_OVERRIDE.get() != Some(&dir) {
+        bail!("Data directory is already set to {}", DATA_DIR_OVERRIDE.get().unwrap().display());
+    }
+    Ok(())
+}
+
+/// Directory for indexes, bookmarks and search history
+///
+/// Resolution order: `--data-dir`, `$NOTHING_HOME`, an existing `~/.nothing`
+/// from older versions, then the platform data directory
+/// (`%LOCALAPPDATA%\nothing`, `$XDG_DATA_HOME/nothing` or
+/// `~/.local/share/nothing`, `~/Library/Application Support/nothing`).
+pub fn data_dir() -> Result<PathBuf> {
+    if let Some(dir) = explicit_dir() {
+        return Ok(dir);
+    }
+
+    if let Some(legacy) = dirs::home_dir().map(|home| home.join(".nothing")) {
+        if legacy.is_dir() {
+            return Ok(legacy);
+        }
+    }
+
+    #[cfg(windows)]
+    let base = dirs::data_local_dir();
+    #[cfg(not(windows))]
+    let base = dirs::data_dir();
+
+    base.map(|dir| dir.join("nothing"))
+        .with_context(|| format!("Could not determine a data directory; set {} or pass --data-dir", HOME_ENV))
+}
+
+/// Path of the config file
+///
+/// Lives in the data directory when that is set explicitly, otherwise in the
+/// platform config directory (`%APPDATA%\nothing`, `~/.config/nothing`, ...).
+pub fn config_path() -> Result<PathBuf> {
+    if let Some(dir) = explicit_dir() {
+        return Ok(dir.join(CONFIG_FILE));
+    }
+
+    match dirs::config_dir() {
+        Some(dir) => Ok(dir.join("nothing").join(CONFIG_FILE)),
+        None => Ok(data_dir()?.join(CONFIG_FILE)),
+    }
+}
+
+/// Data directory from `--data-dir` or `$NOTHING_HOME`
+fn explicit_dir() -> Option<PathBuf> {
+    if let Some(dir) = DATA_DIR_OVERRIDE.get() {
+        return Some(dir.clone());
+    }
+    std::env::var_os(HOME_ENV)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Contents of config.toml; every field is optional
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub scan: ScanConfig,
+    pub search: SearchConfig,
+    pub gui: GuiConfig,
+    pub export: ExportConfig,
+    pub storage: StorageConfig,
+    pub monitor: MonitorConfig,
+    pub server: ServerConfig,
+    pub daemon: DaemonConfig,
+    pub snapshots: SnapshotConfig,
+    pub content: ContentConfig,
+    pub archives: ArchiveConfig,
+}
+
+impl Config {
+    /// Load the config file, or the defaults if there is none
+    pub fn load() -> Result<Self> {
+        let path = config_path()?;
+        if path.exists() {
+            Self::load_from(&path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Load a config file
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("Invalid config file: {}", path.display()))
+    }
+
+    /// Exclusion rules for scanning
+    pub fn exclusions(&self) -> Exclusions {
+        Exclusions::new(&self.scan.exclude)
+    }
+}
+
+/// How volumes are scanned
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanMode {
+    /// Names and paths only (fast MFT read)
+    #[default]
+    Fast,
+
+    /// Sizes and timestamps as well
+    Full,
+}
+
+/// `[scan]` section
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanConfig {
+    /// Fast or full metadata scanning (`-f` always means full)
+    pub mode: ScanMode,
+
+    /// Drives to index when none is given on the command line
+    pub drives: Vec<char>,
+
+    /// Directory trees to index when none is given on the command line
+    pub roots: Vec<PathBuf>,
+
+    /// Names (`*`/`?` wildcards) or path prefixes to leave out of the index
+    pub exclude: Vec<String>,
+
+    /// Always include cloud storage folders
+    pub include_cloud: bool,
+
+    /// Read the first bytes of each file to detect its type (`--detect-types`)
+    pub detect_types: bool,
+}
+
+/// `[search]` section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    /// Results shown in the interactive CLI
+    pub result_limit: usize,
+
+    /// Results shown in the GUI
+    pub gui_result_limit: usize,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            result_limit: DEFAULT_RESULT_LIMIT,
+            gui_result_limit: DEFAULT_GUI_RESULT_LIMIT,
+        }
+    }
+}
+
+/// `[gui]` section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuiConfig {
+    /// "dark" or "light"
+    pub theme: String,
+}
+
+impl Default for GuiConfig {
+    fn default() -> Self {
+        Self {
+            theme: "dark".to_string(),
+        }
+    }
+}
+
+/// `[export]` section
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportConfig {
+    /// Format used by Ctrl+E when no format is picked
+    pub format: ExportFormat,
+
+    /// Where exports are written (default: current directory)
+    pub directory: Option<PathBuf>,
+}
+
+impl ExportConfig {
+    /// Path for an export file named `file_name`
+    pub fn path_for(&self, file_name: &str) -> PathBuf {
+        match &self.directory {
+            Some(dir) => dir.join(file_name),
+            None => PathBuf::from(file_name),
+        }
+    }
+}
+
+/// `[storage]` section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// Index file compression: "zstd", "lz4" or "none"
+    pub compression: Compression,
+
+    /// Compression level (zstd: 1-22)
+    pub compression_level: i32,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            compression: Compression::Zstd,
+            compression_level: 3,
+        }
+    }
+}
+
+/// `[snapshots]` section: how many dated copies of each index file to keep
+///
+/// Every save of a volume's index is copied to the snapshot directory, then
+/// the newest snapshot of each of the last `keep_hourly` hours, `keep_daily`
+/// days and `keep_weekly` weeks is kept. All zero (the default) = no snapshots.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnapshotConfig {
+    pub keep_hourly: usize,
+    pub keep_daily: usize,
+    pub keep_weekly: usize,
+}
+
+impl SnapshotConfig {
+    /// Whether saves take snapshots at all
+    pub fn is_enabled(&self) -> bool {
+        self.keep_hourly + self.keep_daily + self.keep_weekly > 0
+    }
+}
+
+/// `[content]` section: limits of `content:` searches, which read the files
+/// that match the rest of the query, and the folders kept in a full-text index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContentConfig {
+    /// Larger files are not read
+    pub max_file_size_mb: u64,
+
+    /// Most files read per search
+    pub max_files: usize,
+
+    /// Lines shown before and after each matching line
+    pub context_lines: usize,
+
+    /// Matching lines kept per file
+    pub max_matches_per_file: usize,
+
+    /// Folders whose file contents are indexed, so searches there read no files
+    pub indexed_folders: Vec<PathBuf>,
+}
+
+impl Default for ContentConfig {
+    fn default() -> Self {
+        Self {
+            max_file_size_mb: DEFAULT_CONTENT_MAX_FILE_SIZE_MB,
+            max_files: DEFAULT_CONTENT_MAX_FILES,
+            context_lines: 1,
+            max_matches_per_file: 5,
+            indexed_folders: Vec::new(),
+        }
+    }
+}
+
+impl ContentConfig {
+    /// Largest file read, in bytes
+    pub fn max_file_size(&self) -> u64 {
+        self.max_file_size_mb.saturating_mul(1024 * 1024)
+    }
+}
+
+/// `[archives]` section: listing the members of ZIP, 7z and TAR archives
+/// as entries below each archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArchiveConfig {
+    /// List archive members after scanning (`--index-archives`)
+    pub enabled: bool,
+
+    /// Levels of archives opened: 1 = only archives on disk, 2 = archives in those, ...
+    pub max_depth: usize,
+
+    /// Larger nested archives are listed as plain files
+    pub max_nested_size_mb: u64,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_depth: DEFAULT_ARCHIVE_MAX_DEPTH,
+            max_nested_size_mb: DEFAULT_ARCHIVE_MAX_NESTED_SIZE_MB,
+        }
+    }
+}
+
+impl ArchiveConfig {
+    /// Largest nested archive read, in bytes
+    pub fn max_nested_size(&self) -> u64 {
+        self.max_nested_size_mb.saturating_mul(1024 * 1024)
+    }
+}
+
+/// `[monitor]` section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonitorConfig {
+    /// Minutes between full consistency checks of monitored volumes (0 = never)
+    pub check_interval_minutes: u64,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            check_interval_minutes: DEFAULT_CHECK_INTERVAL_MINUTES,
+        }
+    }
+}
+
+impl MonitorConfig {
+    /// Time between consistency checks, if enabled
+    pub fn check_interval(&self) -> Option<Duration> {
+        match self.check_interval_minutes {
+            0 => None,
+            minutes => Some(Duration::from_secs(minutes * 60)),
+        }
+    }
+}
+
+/// `[server]` section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// Port on 127.0.0.1 for `nothing serve`
+    pub port: u16,
+
+    /// Token clients must send (none = no authentication)
+    pub token: Option<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            port: DEFAULT_SERVER_PORT,
+            token: None,
+        }
+    }
+}
+
+/// `[daemon]` section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DaemonConfig {
+    /// Hours between full rescans of each volume (0 = never)
+    pub rescan_interval_hours: u64,
+
+    /// Minutes between saves of changed indexes (0 = only on exit)
+    pub save_interval_minutes: u64,
+
+    /// Entries a single consistency check must correct to trigger a full rescan (0 = never)
+    pub drift_rescan_threshold: usize,
+
+    /// SQLite database kept in sync with the indexes (see `nothing export --sqlite`)
+    pub sqlite: Option<PathBuf>,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            rescan_interval_hours: DEFAULT_RESCAN_INTERVAL_HOURS,
+            save_interval_minutes: DEFAULT_SAVE_INTERVAL_MINUTES,
+            drift_rescan_threshold: DEFAULT_DRIFT_RESCAN_THRESHOLD,
+            sqlite: None,
+        }
+    }
+}
+
+impl DaemonConfig {
+    /// Time between scheduled rescans, if enabled
+    pub fn rescan_interval(&self) -> Option<Duration> {
+        match self.rescan_interval_hours {
+            0 => None,
+            hours => Some(Duration::from_secs(hours * 3600)),
+        }
+    }
+
+    /// Time between periodic saves, if enabled
+    pub fn save_interval(&self) -> Option<Duration> {
+        match self.save_interval_minutes {
+            0 => None,
+            minutes => Some(Duration::from_secs(minutes * 60)),
+        }
+    }
+
+    /// Whether a consistency check that corrected `drift` entries calls for a full rescan
+    pub fn needs_rescan(&self, drift: usize) -> bool {
+        self.drift_rescan_threshold > 0 && drift >= self.drift_rescan_threshold
+    }
+}
+
+/// Exclusion rules from `[scan] exclude`
+///
+/// Patterns containing a path separator exclude everything under that path
+/// (a bare `/` or `\` is ignored rather than excluding everything); other
+/// patterns are matched against names with `*` and `?` wildcards. Matching
+/// ignores ASCII case.
+#[derive(Debug, Clone, Default)]
+pub struct Exclusions {
+    prefixes: Vec<String>,
+    names: Vec<String>,
+}
+
+impl Exclusions {
+    /// Build rules from config patterns
+    pub fn new(patterns: &[String]) -> Self {
+        let mut exclusions = Self::default();
+        for pattern in patterns {
+            let pattern = pattern.trim().to_lowercase();
+            if pattern.is_empty() {
+                continue;
+            }
+            if pattern.contains(['/', '\\']) {
+                let prefix = pattern.trim_end_matches(['/', '\\']);
+                if !prefix.is_empty() {
+                    exclusions.prefixes.push(prefix.to_string());
+                }
+            } else {
+                exclusions.names.push(pattern);
+            }
+        }
+        exclusions
+    }
+
+    /// Check if there are no rules
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty() && self.names.is_empty()
+    }
+
+    /// Whether an entry is excluded, assuming its parent directory is not
+    ///
+    /// Used while walking: excluded directories are not descended into.
+    pub fn excludes_entry(&self, name: &str, path: &str) -> bool {
+        let name = name.to_lowercase();
+        self.names.iter().any(|pattern| wildcard_match(pattern, &name)) || self.excludes_prefix(path)
+    }
+
+    /// Whether a path is excluded, checking every component
+    pub fn excludes(&self, path: &str) -> bool {
+        self.excludes_prefix(path) || self.excludes_component(path)
+    }
+
+    /// Whether a path inside a walked (or watched) `root` is excluded
+    ///
+    /// Like walking from `root`, names are only matched below it.
+    pub fn excludes_below(&self, root: &str, path: &str) -> bool {
+        self.excludes_prefix(path) || self.excludes_component(path.strip_prefix(root).unwrap_or(path))
+    }
+
+    fn excludes_component(&self, path: &str) -> bool {
+        if self.names.is_empty() {
+            return false;
+        }
+        path.to_lowercase()
+            .split(['/', '\\'])
+            .any(|component| self.names.iter().any(|pattern| wildcard_match(pattern, component)))
+    }
+
+    fn excludes_prefix(&self, path: &str) -> bool {
+        if self.prefixes.is_empty() {
+            return false;
+        }
+        let path = path.to_lowercase();
+        self.prefixes.iter().any(|prefix| {
+            path.strip_prefix(prefix.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', '\\']))
+        })
+    }
+}
+
+/// Match `text` against a pattern with `*` (any run) and `?` (any one character)
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            // Let the last `*` swallow one more character
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config: Config = toml::from_str(
+            r#"
+            [scan]
+            mode = "full"
+            drives = ["C", "D"]
+            exclude = ["node_modules", "*.tmp", "/proc"]
+            detect_types = true
+
+            [search]
+            result_limit = 200
+
+            [gui]
+            theme = "light"
+
+            [export]
+            format = "json"
+            directory = "/tmp/exports"
+
+            [storage]
+            compression = "lz4"
+
+            [monitor]
+            check_interval_minutes = 0
+
+            [server]
+            token = "secret"
+
+            [daemon]
+            rescan_interval_hours = 0
+            drift_rescan_threshold = 50
+            sqlite = "/tmp/index.db"
+
+            [snapshots]
+            keep_daily = 7
+
+            [content]
+            max_file_size_mb = 2
+            indexed_folders = ["/home/me/notes"]
+
+            [archives]
+            enabled = true
+            max_depth = 2
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.scan.mode, ScanMode::Full);
+        assert_eq!(config.scan.drives, vec!['C', 'D']);
+        assert!(config.scan.detect_types);
+        assert_eq!(config.search.result_limit, 200);
+        assert_eq!(config.search.gui_result_limit, DEFAULT_GUI_RESULT_LIMIT);
+        assert_eq!(config.gui.theme, "light");
+        assert_eq!(config.export.format, ExportFormat::Json);
+        assert_eq!(config.export.path_for("a.json"), PathBuf::from("/tmp/exports/a.json"));
+        assert_eq!(config.storage.compression, Compression::Lz4);
+        assert_eq!(config.storage.compression_level, 3);
+        assert_eq!(config.monitor.check_interval(), None);
+        assert_eq!(config.server.port, DEFAULT_SERVER_PORT);
+        assert_eq!(config.server.token.as_deref(), Some("secret"));
+        assert_eq!(config.daemon.rescan_interval(), None);
+        assert_eq!(config.daemon.save_interval(), Some(Duration::from_secs(15 * 60)));
+        assert!(config.daemon.needs_rescan(50));
+        assert!(!config.daemon.needs_rescan(49));
+        assert_eq!(config.daemon.sqlite, Some(PathBuf::from("/tmp/index.db")));
+        assert!(config.snapshots.is_enabled());
+        assert_eq!(config.snapshots.keep_hourly, 0);
+        assert_eq!(config.content.max_file_size(), 2 * 1024 * 1024);
+        assert_eq!(config.content.max_files, DEFAULT_CONTENT_MAX_FILES);
+        assert_eq!(config.content.indexed_folders, vec![PathBuf::from("/home/me/notes")]);
+        assert!(config.archives.enabled);
+        assert_eq!(config.archives.max_depth, 2);
+        assert_eq!(config.archives.max_nested_size_mb, DEFAULT_ARCHIVE_MAX_NESTED_SIZE_MB);
+
+        // An empty file is all defaults
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.scan.mode, ScanMode::Fast);
+        assert_eq!(config.search.result_limit, DEFAULT_RESULT_LIMIT);
+        assert_eq!(config.monitor.check_interval(), Some(Duration::from_secs(3600)));
+        assert_eq!(config.daemon.rescan_interval(), Some(Duration::from_secs(24 * 3600)));
+        assert!(!config.snapshots.is_enabled());
+        assert!(!config.archives.enabled);
+    }
+
+    #[test]
+    fn test_exclusions() {
+        let exclusions = Exclusions::new(&[
+            "node_modules".to_string(),
+            "*.TMP".to_string(),
+            "C:\\Windows\\".to_string(),
+            "/proc".to_string(),
+        ]);
+
+        assert!(exclusions.excludes_entry("node_modules", "/src/app/node_modules"));
+        assert!(exclusions.excludes_entry("build.tmp", "/src/build.tmp"));
+        assert!(exclusions.excludes_entry("System32", "c:\\windows\\System32"));
+        assert!(exclusions.excludes_entry("proc", "/proc"));
+        assert!(!exclusions.excludes_entry("procfs", "/procfs"));
+        assert!(!exclusions.excludes_entry("main.rs", "/src/main.rs"));
+
+        // Entries inside an excluded directory
+        assert!(exclusions.excludes("/src/app/node_modules/react/index.js"));
+        assert!(!exclusions.excludes("/src/app/index.js"));
+        assert!(exclusions.excludes_below("/src", "/src/app/node_modules/react"));
+        assert!(!exclusions.excludes_below("/home/node_modules", "/home/node_modules/app.js"));
+
+        // The root alone would be an empty prefix matching every path
+        let root_only = Exclusions::new(&["/".to_string(), "\\".to_string()]);
+        assert!(root_only.is_empty());
+        assert!(!root_only.excludes("/home/me"));
+
+        assert!(wildcard_match("a*b?c", "axxbyc"));
+        assert!(wildcard_match("*", ""));
+        assert!(!wildcard_match("a*b", "axxc"));
+    }
+}
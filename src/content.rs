@@ -0,0 +1,355 @@
+// Content search (`content:` / `grep:`): reads the files that pass the rest
+// of the query and keeps those whose text contains the pattern
+use crate::config::ContentConfig;
+use crate::content_index::{ContentIndex, Hit};
+use crate::file_entry::FileEntry;
+use crate::filters::SearchFilters;
+use crate::index::IndexView;
+use crate::multi_volume::MultiVolumeIndex;
+use crate::search::{SearchEngine, SearchResult};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Bytes looked at to tell text from binary data
+const SNIFF_LEN: usize = 8192;
+
+/// Longest line kept in a match, in characters
+const MAX_LINE_CHARS: usize = 240;
+
+/// A line of a file containing the pattern
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContentMatch {
+    /// 1-based
+    pub line_number: usize,
+    pub line: String,
+
+    /// Context lines, in file order
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+/// Text to look for, case-insensitive unless it has an uppercase letter
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    text: String,
+    case_sensitive: bool,
+}
+
+impl Pattern {
+    pub fn new(text: &str) -> Self {
+        let case_sensitive = text.chars().any(char::is_uppercase);
+        Self {
+            text: if case_sensitive { text.to_string() } else { text.to_lowercase() },
+            case_sensitive,
+        }
+    }
+
+    /// The text looked for (lowercased unless case-sensitive)
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Character offset of the first match in `line`
+    fn find(&self, line: &str) -> Option<usize> {
+        if self.case_sensitive {
+            line.find(&self.text).map(|at| line[..at].chars().count())
+        } else {
+            let lower = line.to_lowercase();
+            lower.find(&self.text).map(|at| lower[..at].chars().count())
+        }
+    }
+}
+
+/// How `content:` searches run: their limits, and the full-text index of
+/// the folders in `indexed_folders` if one is open
+#[derive(Debug, Clone, Default)]
+pub struct ContentSearch {
+    pub config: ContentConfig,
+    pub index: Option<Arc<ContentIndex>>,
+}
+
+impl ContentSearch {
+    pub fn new(config: ContentConfig, index: Option<Arc<ContentIndex>>) -> Self {
+        Self { config, index }
+    }
+}
+
+/// Search the volumes like `MultiVolumeIndex::search`, then, if the filters
+/// have a content pattern, keep the matching files that contain it
+///
+/// Without search terms every file passing the filters is a candidate. At
+/// most `config.max_files` candidates are searched, best matches first.
+/// Files in the indexed folders are looked up in the content index, others
+/// are read; without search terms indexed files come first, most relevant
+/// first. Returns `None` if `cancel` was set before the search finished.
+pub fn search<I: IndexView>(
+    volumes: &MultiVolumeIndex<I>,
+    engine: &mut SearchEngine,
+    terms: &str,
+    limit: usize,
+    filters: &SearchFilters,
+    content: &ContentSearch,
+    cancel: &AtomicBool,
+) -> Option<Vec<SearchResult>> {
+    let Some(pattern) = &filters.content else {
+        return Some(volumes.search(engine, terms, limit, filters));
+    };
+    let config = &content.config;
+
+    let candidates = if terms.is_empty() {
+        let mut candidates = Vec::new();
+        for volume in volumes.volumes() {
+            let index = volume.index.lock().unwrap();
+            candidates.extend(filtered_files(&*index, filters, config.max_files - candidates.len()));
+            if candidates.len() >= config.max_files || cancel.load(Ordering::Relaxed) {
+                break;
+            }
+        }
+        candidates
+    } else {
+        volumes.search(engine, terms, config.max_files, filters)
+    };
+
+    let pattern = Pattern::new(pattern);
+    let indexed = content
+        .index
+        .as_deref()
+        .and_then(|index| Some(IndexedHits { index, hits: index.search(&pattern, config)? }));
+    let mut results = search_candidates(candidates, &pattern, config, indexed.as_ref(), cancel)?;
+    if terms.is_empty() && indexed.is_some() {
+        results.sort_by_key(|result| Reverse(result.score));
+    }
+    results.truncate(limit);
+    Some(results)
+}
+
+/// Matches of a pattern in the content index
+struct IndexedHits<'a> {
+    index: &'a ContentIndex,
+    hits: HashMap<String, Hit>,
+}
+
+/// Files of an index that pass the filters, up to `max`
+fn filtered_files<I: IndexView + ?Sized>(index: &I, filters: &SearchFilters, max: usize) -> Vec<SearchResult> {
+    index
+        .entry_refs()
+        .filter(|entry| !entry.is_directory())
+        .filter_map(|entry| {
+            let entry = entry.to_entry();
+            filters.matches(&entry).then(|| SearchResult { entry, score: 0, content: Vec::new() })
+        })
+        .take(max)
+        .collect()
+}
+
+/// The candidates containing the pattern, in candidate order, with their
+/// matching lines: indexed ones with their index relevance as score, the
+/// others read in parallel
+///
+/// Directories, unreadable, binary and oversized files are skipped.
+fn search_candidates(
+    candidates: Vec<SearchResult>,
+    pattern: &Pattern,
+    config: &ContentConfig,
+    indexed: Option<&IndexedHits>,
+    cancel: &AtomicBool,
+) -> Option<Vec<SearchResult>> {
+    let results: Vec<SearchResult> = candidates
+        .into_par_iter()
+        .filter_map(|mut result| {
+            if cancel.load(Ordering::Relaxed) {
+                return None;
+            }
+            match indexed {
+                // Files the index does not hold (other types, too large) are read
+                Some(indexed) if indexed.index.contains(&result.entry.path) => {
+                    let hit = indexed.hits.get(&result.entry.path)?;
+                    result.score = (hit.score * 1000.0) as u32;
+                    result.content = hit.matches.clone();
+                }
+                _ => {
+                    if !is_searchable(&result.entry, config) {
+                        return None;
+                    }
+                    result.content = grep_file(Path::new(&result.entry.path), pattern, config).ok()?;
+                }
+            }
+            (!result.content.is_empty()).then_some(result)
+        })
+        .collect();
+
+    if cancel.load(Ordering::Relaxed) {
+        return None;
+    }
+    Some(results)
+}
+
+/// Whether an entry is a file on disk small enough to read (sizes from fast scans are 0)
+fn is_searchable(entry: &FileEntry, config: &ContentConfig) -> bool {
+    !entry.is_directory && !entry.is_archive_member() && entry.size <= config.max_file_size()
+}
+
+/// Matching lines of a file (none for binary files)
+pub fn grep_file(path: &Path, pattern: &Pattern, config: &ContentConfig) -> io::Result<Vec<ContentMatch>> {
+    let file = File::open(path)?;
+    if file.metadata()?.len() > config.max_file_size() {
+        return Ok(Vec::new());
+    }
+    let mut bytes = Vec::new();
+    file.take(config.max_file_size()).read_to_end(&mut bytes)?;
+
+    let Some(text) = decode(&bytes) else {
+        return Ok(Vec::new());
+    };
+    Ok(find_matches(&text, pattern, config))
+}
+
+/// The text of a file: UTF-8 or UTF-16LE, by byte order mark or by the
+/// pattern of zero bytes; `None` for binary data
+pub fn decode(bytes: &[u8]) -> Option<String> {
+    if let Some(rest) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+        return Some(String::from_utf8_lossy(rest).into_owned());
+    }
+    if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        return Some(decode_utf16le(rest));
+    }
+
+    let sample = &bytes[..bytes.len().min(SNIFF_LEN)];
+    if !sample.contains(&0) {
+        return Some(String::from_utf8_lossy(bytes).into_owned());
+    }
+
+    // Mostly-ASCII UTF-16LE without a BOM: every odd byte is zero, no even one is
+    let pairs = sample.len() / 2;
+    let odd_zeros = sample.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+    let even_zeros = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+    if pairs > 0 && odd_zeros * 4 >= pairs * 3 && even_zeros * 16 < pairs {
+        Some(decode_utf16le(bytes))
+    } else {
+        None
+    }
+}
+
+fn decode_utf16le(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// Lines of `text` containing the pattern, with context
+pub(crate) fn find_matches(text: &str, pattern: &Pattern, config: &ContentConfig) -> Vec<ContentMatch> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut matches = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let Some(at) = pattern.find(line) else {
+            continue;
+        };
+        let context = |range: std::ops::Range<usize>| lines[range].iter().map(|line| snippet(line, 0)).collect();
+        matches.push(ContentMatch {
+            line_number: i + 1,
+            line: snippet(line, at),
+            before: context(i.saturating_sub(config.context_lines)..i),
+            after: context(i + 1..(i + 1 + config.context_lines).min(lines.len())),
+        });
+        if matches.len() >= config.max_matches_per_file {
+            break;
+        }
+    }
+    matches
+}
+
+/// A line, trimmed, and cut to `MAX_LINE_CHARS` around the character offset `at`
+fn snippet(line: &str, at: usize) -> String {
+    let line = line.trim_end();
+    if line.chars().count() <= MAX_LINE_CHARS {
+        return line.to_string();
+    }
+    let start = at.saturating_sub(MAX_LINE_CHARS / 4);
+    let cut: String = line.chars().skip(start).take(MAX_LINE_CHARS).collect();
+    format!("{}{}…", if start > 0 { "…" } else { "" }, cut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::FileIndex;
+
+    #[test]
+    fn test_content_search() {
+        let dir = std::env::temp_dir().join(format!("nothing_content_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let utf16: Vec<u8> = "first\r\nTODO: fix me\r\n".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let files: [(&str, Vec<u8>); 4] = [
+            ("notes.txt", b"one\ntwo\nremember to fix me\nfour\n".to_vec()),
+            ("wide.txt", utf16),
+            ("blob.bin", b"fix me\0\x01\x02\x03".to_vec()),
+            ("other.txt", b"nothing here\n".to_vec()),
+        ];
+
+        let mut index = FileIndex::new();
+        for (id, (name, bytes)) in files.iter().enumerate() {
+            let path = dir.join(name);
+            std::fs::write(&path, bytes).unwrap();
+            let size = bytes.len() as u64;
+            index.add_entry(FileEntry::new(name.to_string(), path.to_string_lossy().to_string(), false, id as u64 + 1, 0, size, None, None, None));
+        }
+        let volumes = MultiVolumeIndex::new();
+        volumes.add_volume("test", None, index);
+
+        let config = ContentSearch::default();
+        let cancel = AtomicBool::new(false);
+        let query = SearchFilters::parse_filter_string("content:\"fix me\"").unwrap();
+        let mut results = search(&volumes, &mut SearchEngine::new(), "", 10, &query, &config, &cancel).unwrap();
+        results.sort_by(|a, b| a.entry.name.cmp(&b.entry.name));
+
+        let names: Vec<&str> = results.iter().map(|r| r.entry.name.as_str()).collect();
+        assert_eq!(names, vec!["notes.txt", "wide.txt"]);
+        assert_eq!(
+            results[0].content,
+            vec![ContentMatch {
+                line_number: 3,
+                line: "remember to fix me".to_string(),
+                before: vec!["two".to_string()],
+                after: vec!["four".to_string()],
+            }]
+        );
+        assert_eq!(results[1].content[0].line, "TODO: fix me");
+
+        // Uppercase makes the pattern case-sensitive; terms narrow the candidates
+        let query = SearchFilters::parse_filter_string("grep:FIX").unwrap();
+        assert!(search(&volumes, &mut SearchEngine::new(), "notes", 10, &query, &config, &cancel).unwrap().is_empty());
+
+        // Files in an indexed folder that the index does not hold are still read
+        std::fs::write(dir.join("schema.proto"), b"// fix me\n").unwrap();
+        let mut protos = FileIndex::new();
+        let path = dir.join("schema.proto").to_string_lossy().to_string();
+        protos.add_entry(FileEntry::new("schema.proto".to_string(), path, false, 9, 0, 10, None, None, None));
+        volumes.add_volume("protos", None, protos);
+        let indexed = ContentIndex::new(std::slice::from_ref(&dir), Default::default(), &ContentConfig::default());
+        indexed.refresh();
+        let with_index = ContentSearch::new(ContentConfig::default(), Some(Arc::new(indexed)));
+        let query = SearchFilters::parse_filter_string("content:\"fix me\"").unwrap();
+        let results = search(&volumes, &mut SearchEngine::new(), "schema", 10, &query, &with_index, &cancel).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content[0].line, "// fix me");
+
+        // Larger than the limit
+        let tiny = ContentSearch::new(ContentConfig { max_file_size_mb: 0, ..ContentConfig::default() }, None);
+        let query = SearchFilters::parse_filter_string("content:fix").unwrap();
+        assert!(search(&volumes, &mut SearchEngine::new(), "", 10, &query, &tiny, &cancel).unwrap().is_empty());
+
+        cancel.store(true, Ordering::Relaxed);
+        assert!(search(&volumes, &mut SearchEngine::new(), "", 10, &query, &config, &cancel).is_none());
+
+        assert_eq!(snippet(&"x".repeat(1000), 500).chars().count(), MAX_LINE_CHARS + 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,648 @@
+// Full-text index of the folders in `[content] indexed_folders`: the text of
+// each file and an inverted index of its words, kept current by the
+// monitors, so `content:` searches there read no files
+use crate::config::{ContentConfig, Exclusions};
+use crate::content::{self, ContentMatch, Pattern};
+use crate::index::is_within;
+use crate::persistence;
+use anyhow::{bail, Context, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use std::time::SystemTime;
+use walkdir::WalkDir;
+
+/// File in the data directory the index is saved to
+pub const CONTENT_INDEX_FILE: &str = "content_index.bin";
+
+/// Version of the saved file; other versions are rebuilt
+const FORMAT_VERSION: u32 = 2;
+
+/// Longer words are not indexed
+const MAX_WORD_CHARS: usize = 64;
+
+/// BM25 parameters
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+/// How a file's text is extracted and split into words
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Extractor {
+    /// Plain text (UTF-8 or UTF-16LE)
+    Text,
+
+    /// Markdown; words are taken from the text as it reads (see `strip_markdown`)
+    Markdown,
+
+    /// Source code; identifiers are also indexed by their camelCase parts
+    Source,
+}
+
+impl Extractor {
+    /// Extractor for a file, by extension (`None` = not indexed)
+    ///
+    /// Files without an extension (README, Makefile) are read as text.
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let Some(extension) = path.extension() else {
+            return Some(Extractor::Text);
+        };
+        match extension.to_string_lossy().to_lowercase().as_str() {
+            "md" | "markdown" | "mdx" => Some(Extractor::Markdown),
+            "txt" | "text" | "log" | "csv" | "tsv" | "ini" | "cfg" | "conf" | "rst" | "tex" | "srt" | "org" | "adoc" => {
+                Some(Extractor::Text)
+            }
+            "rs" | "py" | "js" | "jsx" | "ts" | "tsx" | "c" | "h" | "cpp" | "cc" | "hpp" | "cs" | "go" | "java" | "kt" | "rb"
+            | "php" | "swift" | "scala" | "lua" | "pl" | "r" | "sh" | "bash" | "ps1" | "bat" | "cmd" | "sql" | "toml"
+            | "yaml" | "yml" | "json" | "xml" | "html" | "htm" | "css" | "scss" | "vue" | "svelte" | "cmake" | "gradle" => {
+                Some(Extractor::Source)
+            }
+            _ => None,
+        }
+    }
+
+    /// The text of a file as it is searched and shown (`None` for binary data)
+    pub fn extract(self, bytes: &[u8]) -> Option<String> {
+        content::decode(bytes)
+    }
+
+    /// Lowercased words of extracted text, with repeats
+    fn words(self, text: &str) -> Vec<String> {
+        let readable;
+        let text = match self {
+            Extractor::Markdown => {
+                readable = text.lines().map(strip_markdown).collect::<Vec<_>>().join("\n");
+                &readable
+            }
+            Extractor::Text | Extractor::Source => text,
+        };
+        let mut words = Vec::new();
+        for word in split_words(text) {
+            if self == Extractor::Source {
+                let parts = camel_case_parts(word);
+                if parts.len() > 1 {
+                    words.extend(parts.iter().map(|part| part.to_lowercase()));
+                }
+            }
+            words.push(word.to_lowercase());
+        }
+        words
+    }
+}
+
+/// Runs of letters and digits, up to `MAX_WORD_CHARS` long
+fn split_words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty() && word.chars().count() <= MAX_WORD_CHARS)
+}
+
+/// "parseHTTPConfig" -> ["parse", "HTTP", "Config"]
+fn camel_case_parts(word: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = word.char_indices().collect();
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for i in 1..chars.len() {
+        let (at, c) = chars[i];
+        let previous = chars[i - 1].1;
+        let next_is_lower = chars.get(i + 1).is_some_and(|(_, next)| next.is_lowercase());
+        if c.is_uppercase() && (previous.is_lowercase() || previous.is_ascii_digit() || (previous.is_uppercase() && next_is_lower)) {
+            parts.push(&word[start..at]);
+            start = at;
+        }
+    }
+    parts.push(&word[start..]);
+    parts
+}
+
+/// A Markdown line as it reads, for its words: no heading or quote marks,
+/// and `[text](url)` -> `text url`, so a link's words follow its text (the
+/// target is still in the file, where searches may look for it)
+///
+/// Emphasis and code marks split words like any other punctuation.
+fn strip_markdown(line: &str) -> String {
+    let line = line.trim_start_matches('#').trim_start_matches('>');
+    let mut text = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(open) = rest.find('[') {
+        let link = &rest[open..];
+        let parsed = link.find("](").and_then(|close| Some((close, link[close..].find(')')? + close)));
+        let Some((close, end)) = parsed else {
+            text.push_str(&rest[..=open]);
+            rest = &rest[open + 1..];
+            continue;
+        };
+        text.push_str(rest[..open].trim_end_matches('!'));
+        text.push_str(&link[1..close]);
+        text.push(' ');
+        text.push_str(&link[close + 2..end]);
+        rest = &link[end + 1..];
+    }
+    text.push_str(rest);
+    text
+}
+
+/// One indexed file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Document {
+    path: String,
+    size: u64,
+    modified: Option<SystemTime>,
+    extractor: u8,
+    text: String,
+}
+
+impl Document {
+    fn extractor(&self) -> Extractor {
+        match self.extractor {
+            1 => Extractor::Markdown,
+            2 => Extractor::Source,
+            _ => Extractor::Text,
+        }
+    }
+}
+
+/// Saved form of the index: the documents only, words are rebuilt on load
+#[derive(Serialize, Deserialize)]
+struct Stored {
+    version: u32,
+    documents: Vec<Document>,
+}
+
+/// Documents and their words
+#[derive(Default)]
+struct Postings {
+    /// Slots of removed documents are `None` until reused
+    documents: Vec<Option<Document>>,
+    free: Vec<u32>,
+    by_path: HashMap<String, u32>,
+
+    /// Word -> (document, occurrences)
+    words: HashMap<String, Vec<(u32, u32)>>,
+
+    /// Words per document, and in all of them (for BM25)
+    lengths: Vec<u32>,
+    total_length: u64,
+}
+
+impl Postings {
+    fn insert(&mut self, document: Document) {
+        self.remove(&document.path);
+
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        let words = document.extractor().words(&document.text);
+        let length = words.len() as u32;
+        for word in words {
+            *counts.entry(word).or_default() += 1;
+        }
+
+        let id = match self.free.pop() {
+            Some(id) => id,
+            None => {
+                self.documents.push(None);
+                self.lengths.push(0);
+                self.documents.len() as u32 - 1
+            }
+        };
+        for (word, count) in counts {
+            self.words.entry(word).or_default().push((id, count));
+        }
+        self.by_path.insert(document.path.clone(), id);
+        self.lengths[id as usize] = length;
+        self.total_length += length as u64;
+        self.documents[id as usize] = Some(document);
+    }
+
+    fn remove(&mut self, path: &str) -> bool {
+        let Some(id) = self.by_path.remove(path) else {
+            return false;
+        };
+        let document = self.documents[id as usize].take().expect("indexed path without a document");
+        for word in document.extractor().words(&document.text).into_iter().collect::<HashSet<_>>() {
+            if let Some(postings) = self.words.get_mut(&word) {
+                postings.retain(|&(doc, _)| doc != id);
+                if postings.is_empty() {
+                    self.words.remove(&word);
+                }
+            }
+        }
+        self.total_length -= self.lengths[id as usize] as u64;
+        self.lengths[id as usize] = 0;
+        self.free.push(id);
+        true
+    }
+
+    fn len(&self) -> usize {
+        self.by_path.len()
+    }
+}
+
+/// What a refresh changed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RefreshStats {
+    pub indexed: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+}
+
+/// A file of the indexed folders that contains a content pattern
+#[derive(Debug, Clone)]
+pub struct Hit {
+    /// BM25 relevance of the pattern's words
+    pub score: f64,
+    pub matches: Vec<ContentMatch>,
+}
+
+/// Full-text index of a set of folders
+///
+/// Shared between searches and the monitors: searches take a read lock,
+/// updates extract the text of changed files first and then lock briefly.
+pub struct ContentIndex {
+    folders: Vec<String>,
+    exclusions: Exclusions,
+    max_file_size: u64,
+    postings: RwLock<Postings>,
+
+    /// Changed since it was loaded or saved
+    dirty: AtomicBool,
+}
+
+impl fmt::Debug for ContentIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContentIndex").field("folders", &self.folders).field("documents", &self.file_count()).finish()
+    }
+}
+
+impl ContentIndex {
+    /// An empty index of `folders`
+    pub fn new(folders: &[PathBuf], exclusions: Exclusions, config: &ContentConfig) -> Self {
+        Self {
+            folders: folders.iter().map(|folder| folder.to_string_lossy().to_string()).collect(),
+            exclusions,
+            max_file_size: config.max_file_size(),
+            postings: RwLock::new(Postings::default()),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    /// Load the saved index of the configured folders, bring it up to date
+    /// with the disk and save it if that changed anything
+    ///
+    /// `None` if no folders are configured.
+    pub fn open(config: &ContentConfig, exclusions: Exclusions) -> Result<Option<(Self, RefreshStats)>> {
+        if config.indexed_folders.is_empty() {
+            return Ok(None);
+        }
+        let index = Self::new(&config.indexed_folders, exclusions, config);
+        let path = persistence::data_file(CONTENT_INDEX_FILE)?;
+        if path.exists() {
+            if let Err(e) = index.load(&path) {
+                eprintln!("⚠️  Rebuilding the content index: {:#}", e);
+            }
+        }
+        let stats = index.refresh();
+        index.save_if_changed(&path)?;
+        Ok(Some((index, stats)))
+    }
+
+    /// Number of indexed files
+    pub fn file_count(&self) -> usize {
+        self.postings.read().unwrap().len()
+    }
+
+    /// Whether the file at `path` is indexed (files of the indexed folders
+    /// that are excluded, too large or of other types are not)
+    pub fn contains(&self, path: &str) -> bool {
+        self.postings.read().unwrap().by_path.contains_key(path)
+    }
+
+    /// Whether `path` is in one of the indexed folders
+    pub fn covers(&self, path: &str) -> bool {
+        self.folders.iter().any(|folder| is_within(path, folder))
+    }
+
+    /// Whether `path` is in an indexed folder and not excluded there
+    fn includes(&self, path: &str) -> bool {
+        self.folders
+            .iter()
+            .any(|folder| is_within(path, folder) && !self.exclusions.excludes_below(folder, path))
+    }
+
+    /// Read every folder and index the files that are new or changed since
+    /// they were indexed, and forget the ones that are gone
+    pub fn refresh(&self) -> RefreshStats {
+        let mut stats = RefreshStats::default();
+        for folder in self.folders.clone() {
+            let folder_stats = self.refresh_under(Path::new(&folder));
+            stats.indexed += folder_stats.indexed;
+            stats.removed += folder_stats.removed;
+            stats.unchanged += folder_stats.unchanged;
+        }
+        stats
+    }
+
+    /// Bring changed paths up to date: files are re-read, folders refreshed,
+    /// and anything missing is removed with everything under it
+    pub fn update_paths(&self, paths: &[PathBuf]) {
+        for path in paths {
+            let text = path.to_string_lossy();
+
+            // A change at or above an indexed folder (a renamed parent, a
+            // consistency check of the volume) can touch any file in it
+            let folders: Vec<&String> = self.folders.iter().filter(|folder| is_within(folder, &text)).collect();
+            if !folders.is_empty() {
+                for folder in folders {
+                    self.refresh_under(Path::new(folder));
+                }
+                continue;
+            }
+
+            if !self.covers(&text) {
+                continue;
+            }
+            if path.is_dir() {
+                self.refresh_under(path);
+            } else if path.is_file() {
+                match self.read_document(path) {
+                    Some(document) => self.postings.write().unwrap().insert(document),
+                    None => {
+                        self.postings.write().unwrap().remove(&text);
+                    }
+                }
+                self.dirty.store(true, Ordering::Relaxed);
+            } else {
+                self.remove_under(&text);
+            }
+        }
+    }
+
+    /// Refresh the files under `root`
+    fn refresh_under(&self, root: &Path) -> RefreshStats {
+        let mut stats = RefreshStats::default();
+        let root_text = root.to_string_lossy().to_string();
+
+        let files: Vec<(PathBuf, fs::Metadata)> = WalkDir::new(root)
+            .into_iter()
+            .filter_entry(|entry| !self.exclusions.excludes_below(&root_text, &entry.path().to_string_lossy()))
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file() && Extractor::for_path(entry.path()).is_some())
+            .filter_map(|entry| Some((entry.path().to_path_buf(), entry.metadata().ok()?)))
+            .collect();
+
+        let seen: HashSet<String> = files.iter().map(|(path, _)| path.to_string_lossy().to_string()).collect();
+        let changed: Vec<PathBuf> = {
+            let postings = self.postings.read().unwrap();
+            files
+                .into_iter()
+                .filter(|(path, metadata)| {
+                    let unchanged = postings
+                        .by_path
+                        .get(path.to_string_lossy().as_ref())
+                        .and_then(|&id| postings.documents[id as usize].as_ref())
+                        .is_some_and(|doc| doc.size == metadata.len() && doc.modified == metadata.modified().ok());
+                    stats.unchanged += unchanged as usize;
+                    !unchanged
+                })
+                .map(|(path, _)| path)
+                .collect()
+        };
+
+        let documents: Vec<Document> = changed.par_iter().filter_map(|path| self.read_document(path)).collect();
+        let mut postings = self.postings.write().unwrap();
+        stats.indexed = documents.len();
+        for document in documents {
+            postings.insert(document);
+        }
+
+        let gone: Vec<String> = postings
+            .by_path
+            .keys()
+            .filter(|path| is_within(path, &root_text) && !seen.contains(*path))
+            .cloned()
+            .collect();
+        for path in &gone {
+            postings.remove(path);
+        }
+        stats.removed = gone.len();
+
+        if stats.indexed + stats.removed > 0 {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+        stats
+    }
+
+    /// Forget `path` and everything under it
+    fn remove_under(&self, path: &str) {
+        let mut postings = self.postings.write().unwrap();
+        let gone: Vec<String> = postings.by_path.keys().filter(|indexed| is_within(indexed, path)).cloned().collect();
+        for indexed in &gone {
+            postings.remove(indexed);
+        }
+        if !gone.is_empty() {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Extract a file (`None` if it is excluded, too large, binary or unreadable)
+    fn read_document(&self, path: &Path) -> Option<Document> {
+        let extractor = Extractor::for_path(path)?;
+        let text = path.to_string_lossy().to_string();
+        if !self.includes(&text) {
+            return None;
+        }
+        let metadata = fs::metadata(path).ok()?;
+        if metadata.len() > self.max_file_size {
+            return None;
+        }
+        let bytes = fs::read(path).ok()?;
+        Some(Document {
+            path: text,
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            extractor: extractor as u8,
+            text: extractor.extract(&bytes)?,
+        })
+    }
+
+    /// Indexed files containing the pattern, with their matching lines, by path
+    ///
+    /// `None` if the pattern has no words to look up (e.g. only punctuation);
+    /// such patterns have to be searched in the files themselves.
+    pub fn search(&self, pattern: &Pattern, config: &ContentConfig) -> Option<HashMap<String, Hit>> {
+        let lowered = pattern.text().to_lowercase();
+        let query: Vec<&str> = split_words(&lowered).collect();
+        if query.is_empty() {
+            return None;
+        }
+
+        let postings = self.postings.read().unwrap();
+        let documents = postings.len().max(1) as f64;
+        let average_length = (postings.total_length as f64 / documents).max(1.0);
+
+        // A pattern can start or end inside a word: "fix me" matches
+        // "prefix media", so its first word is a suffix, its last a prefix
+        // and the ones in between whole words
+        let mut scores: Option<HashMap<u32, f64>> = None;
+        for (i, word) in query.iter().enumerate() {
+            let last = query.len() - 1;
+            let fits = |indexed: &str| match (i == 0, i == last) {
+                (true, true) => indexed.contains(word),
+                (true, false) => indexed.ends_with(word),
+                (false, true) => indexed.starts_with(word),
+                (false, false) => indexed == *word,
+            };
+
+            let mut frequencies: HashMap<u32, u32> = HashMap::new();
+            let exact = postings.words.get(*word).filter(|_| i != 0 && i != last);
+            match exact {
+                Some(list) => frequencies.extend(list.iter().copied()),
+                None => {
+                    for (_, list) in postings.words.iter().filter(|(indexed, _)| fits(indexed)) {
+                        for &(doc, count) in list {
+                            *frequencies.entry(doc).or_default() += count;
+                        }
+                    }
+                }
+            }
+
+            let found = frequencies.len() as f64;
+            let idf = (1.0 + (documents - found + 0.5) / (found + 0.5)).ln();
+            let word_scores = frequencies.into_iter().map(|(doc, count)| {
+                let count = count as f64;
+                let length = postings.lengths[doc as usize] as f64;
+                (doc, idf * count * (K1 + 1.0) / (count + K1 * (1.0 - B + B * length / average_length)))
+            });
+            scores = Some(match scores {
+                None => word_scores.collect(),
+                Some(previous) => {
+                    let current: HashMap<u32, f64> = word_scores.collect();
+                    previous
+                        .into_iter()
+                        .filter_map(|(doc, score)| Some((doc, score + current.get(&doc)?)))
+                        .collect()
+                }
+            });
+        }
+
+        // The words narrow the documents down; the lines must contain the pattern itself
+        let hits = scores
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(doc, score)| {
+                let document = postings.documents[doc as usize].as_ref()?;
+                let matches = content::find_matches(&document.text, pattern, config);
+                (!matches.is_empty()).then(|| (document.path.clone(), Hit { score, matches }))
+            })
+            .collect();
+        Some(hits)
+    }
+
+    /// Save the index if anything changed since it was loaded or saved
+    pub fn save_if_changed(&self, path: &Path) -> Result<()> {
+        if self.dirty.swap(false, Ordering::Relaxed) {
+            if let Err(e) = self.save(path) {
+                self.dirty.store(true, Ordering::Relaxed);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Save to the data directory if anything changed
+    pub fn save_to_data_dir(&self) -> Result<()> {
+        self.save_if_changed(&persistence::data_file(CONTENT_INDEX_FILE)?)
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        let stored = Stored {
+            version: FORMAT_VERSION,
+            documents: self.postings.read().unwrap().documents.iter().flatten().cloned().collect(),
+        };
+        let file = File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut encoder = zstd::stream::write::Encoder::new(BufWriter::new(file), 3)?;
+        bincode::serialize_into(&mut encoder, &stored)?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    /// Add the saved documents that are still in the indexed folders
+    fn load(&self, path: &Path) -> Result<()> {
+        let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let decoder = zstd::stream::read::Decoder::new(BufReader::new(file))?;
+        let stored: Stored = bincode::deserialize_from(decoder).with_context(|| format!("Failed to read {}", path.display()))?;
+        if stored.version != FORMAT_VERSION {
+            bail!("{} has format version {}", path.display(), stored.version);
+        }
+
+        let mut postings = self.postings.write().unwrap();
+        for document in stored.documents {
+            if self.covers(&document.path) {
+                postings.insert(document);
+            } else {
+                self.dirty.store(true, Ordering::Relaxed);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_index() {
+        let dir = std::env::temp_dir().join(format!("nothing_content_index_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("docs")).unwrap();
+        fs::create_dir_all(dir.join("node_modules")).unwrap();
+        fs::write(dir.join("docs/guide.md"), "# Setup\n\nRead the [install notes](http://x) first.\nSet `snake_case` names.\n").unwrap();
+        fs::write(dir.join("docs/notes.txt"), "install install install\nfix me later\n").unwrap();
+        fs::write(dir.join("main.rs"), "fn parseConfigFile() {}\n").unwrap();
+        fs::write(dir.join("image.png"), "install").unwrap();
+        fs::write(dir.join("node_modules/dep.js"), "install").unwrap();
+
+        let config = ContentConfig::default();
+        let index = ContentIndex::new(std::slice::from_ref(&dir), Exclusions::new(&["node_modules".to_string()]), &config);
+        assert_eq!(index.refresh(), RefreshStats { indexed: 3, removed: 0, unchanged: 0 });
+
+        // Ranked by how often the words occur; lines are the file's own
+        let hits = index.search(&Pattern::new("install"), &config).unwrap();
+        let notes = dir.join("docs/notes.txt").to_string_lossy().to_string();
+        let guide = dir.join("docs/guide.md").to_string_lossy().to_string();
+        assert_eq!(hits.len(), 2);
+        assert!(hits[&notes].score > hits[&guide].score);
+        assert_eq!(hits[&guide].matches[0].line, "Read the [install notes](http://x) first.");
+        assert_eq!(hits[&guide].matches[0].line_number, 3);
+        assert_eq!(index.search(&Pattern::new("snake_case"), &config).unwrap().len(), 1);
+        assert_eq!(index.search(&Pattern::new("notes](http"), &config).unwrap().len(), 1);
+        assert!(index.contains(&guide));
+        assert!(!index.contains(&dir.join("image.png").to_string_lossy()));
+
+        // Patterns may start and end inside words
+        assert_eq!(index.search(&Pattern::new("ix me lat"), &config).unwrap().len(), 1);
+        assert_eq!(index.search(&Pattern::new("config"), &config).unwrap().len(), 1);
+        assert!(index.search(&Pattern::new("Install"), &config).unwrap().is_empty());
+        assert!(index.search(&Pattern::new("()"), &config).is_none());
+
+        // Monitor updates, then a reload from disk
+        fs::write(dir.join("docs/notes.txt"), "nothing to see\n").unwrap();
+        fs::remove_file(dir.join("main.rs")).unwrap();
+        index.update_paths(&[dir.join("docs/notes.txt"), dir.join("main.rs")]);
+        assert_eq!(index.search(&Pattern::new("install"), &config).unwrap().len(), 1);
+        assert_eq!(index.file_count(), 2);
+
+        let saved = dir.join(CONTENT_INDEX_FILE);
+        index.save_if_changed(&saved).unwrap();
+        let reloaded = ContentIndex::new(&[dir.join("docs")], Exclusions::default(), &config);
+        reloaded.load(&saved).unwrap();
+        assert_eq!(reloaded.refresh(), RefreshStats { indexed: 0, removed: 0, unchanged: 2 });
+
+        assert_eq!(camel_case_parts("parseHTTPConfig"), vec!["parse", "HTTP", "Config"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,457 @@
+// Background indexer: owns the indexes of all configured volumes, keeps them
+// scanned, live and saved, and answers clients over IPC and HTTP
+use crate::activity_log;
+use crate::archive;
+use crate::api::IndexService;
+use crate::change_source;
+use crate::config::{ArchiveConfig, Config, DaemonConfig, Exclusions};
+use crate::content::ContentSearch;
+use crate::file_type;
+use crate::filters::SearchFilters;
+use crate::index::{is_within, FileIndex};
+use crate::ipc::{self, IpcServer};
+use crate::jobs::{JobKind, JobTracker};
+use crate::monitor::{Monitor, MonitorOptions, MonitorStatus};
+use crate::multi_drive;
+use crate::multi_volume::MultiVolumeIndex;
+use crate::persistence::{self, StorageOptions};
+use crate::reconcile::DriftReport;
+use crate::scanner::{Progress, VolumeSource};
+use crate::server::SearchServer;
+use crate::sqlite_export::SqliteExport;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long the scheduler waits for drift reports before looking for due work
+const TICK: Duration = Duration::from_secs(1);
+
+/// Command-line settings for the daemon
+pub struct DaemonOptions {
+    pub full_metadata: bool,
+    pub detect_types: bool,
+    pub archives: Option<ArchiveConfig>,
+    pub storage: StorageOptions,
+    pub port: u16,
+    pub token: Option<String>,
+}
+
+/// Where a volume is in its life in the daemon
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Not loaded or scanned yet
+    Pending,
+
+    /// In the volume list and monitored
+    Ready,
+
+    /// Could not be loaded or scanned; retried at the next scheduled rescan
+    Failed,
+}
+
+/// A volume the daemon is responsible for
+struct ManagedVolume {
+    source: VolumeSource,
+    name: String,
+    state: State,
+    next_rescan: Option<Instant>,
+}
+
+/// Scheduler state shared by the steps of the main loop
+struct Daemon {
+    volumes: Arc<MultiVolumeIndex>,
+    jobs: Arc<JobTracker>,
+    monitor: Monitor,
+    status: Arc<MonitorStatus>,
+    exclusions: Exclusions,
+    schedule: DaemonConfig,
+    options: DaemonOptions,
+
+    /// SQLite mirror of the indexes, if configured
+    sqlite: Option<SqliteExport>,
+
+    /// Content index, saved with the volumes
+    content: ContentSearch,
+}
+
+/// Run the daemon until Ctrl+C
+///
+/// The servers start first, so clients can watch the initial loads and scans
+/// in the status. Volumes are then loaded from their index files (or scanned
+/// when there is none) one at a time and monitored as soon as they are ready.
+pub fn run(sources: Vec<VolumeSource>, config: &Config, options: DaemonOptions) -> Result<()> {
+    let exclusions = config.exclusions();
+    let volumes = Arc::new(MultiVolumeIndex::new());
+    let jobs = Arc::new(JobTracker::default());
+    // A broken imported file must not keep the daemon from serving the rest
+    let imported = match volumes.load_imported() {
+        Ok(imported) => {
+            for (path, e) in imported.skipped {
+                note(format!("⚠️  Skipping imported index {}: {:#}", path, e));
+            }
+            imported.names
+        }
+        Err(e) => {
+            note(format!("⚠️  Failed to load imported volumes: {:#}", e));
+            Vec::new()
+        }
+    };
+
+    let sqlite = config.daemon.sqlite.as_deref().map(SqliteExport::open).transpose()?;
+    let content = crate::open_content_search(config);
+
+    let (drift_tx, drift_rx) = mpsc::channel();
+    let (changed_tx, changed_rx) = mpsc::channel();
+    let monitor = Monitor::with_options(MonitorOptions {
+        exclusions: exclusions.clone(),
+        check_interval: config.monitor.check_interval(),
+        drift_reports: Some(drift_tx),
+        changed_paths: sqlite.as_ref().map(|_| changed_tx),
+        content_index: content.index.clone(),
+    });
+    let status = monitor.status();
+
+    let mut managed: Vec<ManagedVolume> = sources
+        .into_iter()
+        .map(|source| ManagedVolume {
+            name: source.name(),
+            source,
+            state: State::Pending,
+            next_rescan: None,
+        })
+        .collect();
+    for volume in &managed {
+        jobs.register(&volume.name);
+    }
+
+    let roots = managed.iter().map(|v| (v.name.clone(), v.source.root())).collect();
+    let service = Arc::new(
+        IndexService::new(Arc::clone(&volumes))
+            .monitor(Arc::clone(&status))
+            .rescan_roots(roots, exclusions.clone())
+            .jobs(Arc::clone(&jobs))
+            .content(content.clone()),
+    );
+    let http = SearchServer::bind(Arc::clone(&service), options.port)?.token(options.token.clone());
+    let ipc = IpcServer::bind(service, &ipc::socket_path()?)?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let stops = [http.stop_handle(), ipc.stop_handle(), Arc::clone(&stop)];
+    ctrlc::set_handler(move || {
+        for stop in &stops {
+            stop.store(true, Ordering::Relaxed);
+        }
+    })?;
+
+    println!("🛠️  Indexer daemon for {} volume(s) (Ctrl+C to stop)", managed.len());
+    println!("🌐 HTTP API: http://{}", http.local_addr());
+    if options.token.is_none() {
+        println!("⚠️  No token set: any local program can query the index over HTTP");
+    }
+    println!("🔌 Indexer socket: {}", ipc.path().display());
+    if let Some(log) = activity_log::path() {
+        println!("📝 Activity log: {}", log.display());
+    }
+    if !imported.is_empty() {
+        println!("📥 Imported volumes: {}", imported.join(", "));
+    }
+    if let Some(path) = &config.daemon.sqlite {
+        println!("🗃️  SQLite mirror: {}", path.display());
+    }
+
+    let http_thread = thread::spawn(move || http.run());
+    let ipc_thread = thread::spawn(move || ipc.run());
+
+    let mut daemon = Daemon {
+        volumes,
+        jobs,
+        monitor,
+        status,
+        exclusions,
+        schedule: config.daemon.clone(),
+        options,
+        sqlite,
+        content,
+    };
+    daemon.schedule(&mut managed, &drift_rx, &changed_rx, &stop);
+
+    // Ctrl+C already stopped the servers; wait for them, then for the monitor
+    let http_result = http_thread.join().expect("HTTP server thread panicked");
+    let ipc_result = ipc_thread.join().expect("IPC server thread panicked");
+    let Daemon { volumes, monitor, options, content, .. } = daemon;
+    let stats = monitor.stop();
+    note(format!("🛑 Stopping after {} changes from {} events", stats.changes(), stats.events));
+    crate::save_content_index(&content);
+
+    println!("\nSaving indexes...");
+    for (name, stats) in volumes.save_all(&options.storage)? {
+        print!("{}: ", name);
+        crate::print_storage_stats(&stats);
+    }
+
+    http_result?;
+    ipc_result
+}
+
+impl Daemon {
+    /// Bring volumes up, then rescan and save them on schedule until stopped
+    fn schedule(
+        &mut self,
+        managed: &mut [ManagedVolume],
+        drift: &mpsc::Receiver<DriftReport>,
+        changed: &mpsc::Receiver<PathBuf>,
+        stop: &AtomicBool,
+    ) {
+        let mut saved_changes = self.changes();
+        let mut next_save = self.schedule.save_interval().map(|interval| Instant::now() + interval);
+
+        while !stop.load(Ordering::Relaxed) {
+            // One volume per pass, so Ctrl+C is noticed between long scans
+            if let Some(volume) = managed.iter_mut().find(|v| v.state == State::Pending) {
+                self.bring_up(volume);
+                continue;
+            }
+
+            match drift.recv_timeout(TICK) {
+                Ok(report) if self.schedule.needs_rescan(report.total()) => {
+                    let root = report.root.to_string_lossy();
+                    let volume = managed
+                        .iter_mut()
+                        .filter(|v| v.state == State::Ready)
+                        .find(|v| is_within(&root, &v.source.root().to_string_lossy()));
+                    if let Some(volume) = volume {
+                        note(format!(
+                            "⚠️  {} entries on {} had drifted; rescanning the volume",
+                            report.total(),
+                            volume.name
+                        ));
+                        self.rescan(volume);
+                    }
+                }
+                Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+                // The monitor keeps the sender for as long as the daemon runs
+                Err(RecvTimeoutError::Disconnected) => thread::sleep(TICK),
+            }
+            self.mirror_changes(managed, changed);
+
+            let now = Instant::now();
+            for volume in managed.iter_mut() {
+                if volume.next_rescan.is_some_and(|at| now >= at) {
+                    note(format!("🔁 Scheduled rescan of {}", volume.name));
+                    match volume.state {
+                        State::Failed => {
+                            volume.state = State::Pending;
+                            volume.next_rescan = None;
+                        }
+                        _ => self.rescan(volume),
+                    }
+                }
+            }
+
+            if let (Some(at), Some(interval)) = (next_save, self.schedule.save_interval()) {
+                let changes = self.changes();
+                if now >= at && changes != saved_changes {
+                    self.save_ready(managed);
+                    saved_changes = changes;
+                }
+                if now >= at {
+                    next_save = Some(now + interval);
+                }
+            }
+        }
+    }
+
+    /// Load a volume from its index file (or scan it), add it and start monitoring it
+    fn bring_up(&mut self, volume: &mut ManagedVolume) {
+        let cache_path = match persistence::get_volume_index_path(&volume.name) {
+            Ok(path) => path,
+            Err(e) => return self.fail(volume, JobKind::Load, e),
+        };
+
+        let index = if Path::new(&cache_path).exists() {
+            let Some(mut job) = self.jobs.start(&volume.name, JobKind::Load) else {
+                return;
+            };
+            match persistence::load_index(&cache_path) {
+                Ok(index) => {
+                    note(format!("✅ Loaded {}: {} files, {} directories", volume.name, index.file_count(), index.directory_count()));
+                    index
+                }
+                Err(e) => {
+                    // A broken index file is replaced by a fresh scan
+                    job.fail(format!("{:#}", e));
+                    drop(job);
+                    note(format!("⚠️  Failed to load {}: {:#}; scanning it instead", volume.name, e));
+                    match self.scan(volume) {
+                        Some(index) => index,
+                        None => return self.give_up(volume),
+                    }
+                }
+            }
+        } else {
+            match self.scan(volume) {
+                Some(index) => index,
+                None => return self.give_up(volume),
+            }
+        };
+
+        let index = self.volumes.add_volume(&volume.name, Some(cache_path), index);
+        if let Err(e) = self.monitor.watch(change_source::for_path(volume.source.root()), index) {
+            note(format!("⚠️  Failed to monitor {}: {:#}; it is kept current by rescans only", volume.name, e));
+        }
+        volume.state = State::Ready;
+        volume.next_rescan = self.schedule.rescan_interval().map(|interval| Instant::now() + interval);
+        self.mirror(&volume.name, None);
+    }
+
+    /// Scan a volume into a new index and save it, as a Scan job
+    fn scan(&self, volume: &ManagedVolume) -> Option<FileIndex> {
+        let mut job = self.jobs.start(&volume.name, JobKind::Scan)?;
+        note(format!("🔍 Scanning {}...", volume.name));
+
+        let started = Instant::now();
+        // The walk's own messages go to the activity log; the console gets the notes around it
+        let progress: Progress = Arc::new(|message| activity_log::log(message));
+        let mut index = match multi_drive::scan_source(&volume.source, self.options.full_metadata, &self.exclusions, &progress) {
+            Ok(index) => index,
+            Err(e) => {
+                job.fail(format!("{:#}", e));
+                note(format!("❌ Failed to scan {}: {:#}", volume.name, e));
+                return None;
+            }
+        };
+        note(format!(
+            "✅ Scanned {}: {} files, {} directories in {:.1}s",
+            volume.name,
+            index.file_count(),
+            index.directory_count(),
+            started.elapsed().as_secs_f64()
+        ));
+        if self.options.detect_types {
+            note(format!("🔎 {}: {}", volume.name, file_type::detect_types(&mut index)));
+        }
+        if let Some(archives) = &self.options.archives {
+            note(format!("📦 {}: {}", volume.name, archive::index_archives(&mut index, archives)));
+        }
+        drop(job);
+
+        self.save(&volume.name, &index);
+        Some(index)
+    }
+
+    /// Replace a ready volume's index with a fresh scan
+    ///
+    /// The old index keeps answering queries and taking monitor events while
+    /// the scan runs. Changes made during the scan that it missed are picked
+    /// up by the next consistency check.
+    fn rescan(&mut self, volume: &mut ManagedVolume) {
+        volume.next_rescan = self.schedule.rescan_interval().map(|interval| Instant::now() + interval);
+        let Some(live) = self.volumes.volume(&volume.name) else {
+            return;
+        };
+        if let Some(index) = self.scan(volume) {
+            *live.index.lock().unwrap() = index;
+            self.mirror(&volume.name, None);
+        }
+    }
+
+    /// Mirror the paths the monitors changed since the last tick into SQLite
+    fn mirror_changes(&mut self, managed: &[ManagedVolume], changed: &mpsc::Receiver<PathBuf>) {
+        let mut by_volume: HashMap<&str, Vec<String>> = HashMap::new();
+        for path in changed.try_iter() {
+            let path = path.to_string_lossy().into_owned();
+            let volume = managed
+                .iter()
+                .filter(|v| v.state == State::Ready)
+                .find(|v| is_within(&path, &v.source.root().to_string_lossy()));
+            if let Some(volume) = volume {
+                by_volume.entry(&volume.name).or_default().push(path);
+            }
+        }
+        for (name, paths) in by_volume {
+            self.mirror(name, Some(&paths));
+        }
+    }
+
+    /// Bring a volume's rows in the SQLite mirror up to date, all of them or
+    /// just those at or below `paths`
+    fn mirror(&mut self, name: &str, paths: Option<&[String]>) {
+        let (Some(sqlite), Some(live)) = (&mut self.sqlite, self.volumes.volume(name)) else {
+            return;
+        };
+        let index = live.index.lock().unwrap();
+        let all = SearchFilters::default();
+        let result = match paths {
+            Some(paths) => sqlite.sync_paths(name, &*index, paths, &all),
+            None => sqlite.sync_volume(name, &*index, &all),
+        };
+        match result {
+            Ok(stats) if !stats.is_empty() => activity_log::log(format!("🗃️  Mirrored {} to SQLite: {}", name, stats)),
+            Ok(_) => {}
+            Err(e) => note(format!("❌ Failed to mirror {} to SQLite: {:#}", name, e)),
+        }
+    }
+
+    /// Save every ready volume, each as a Save job, and the content index
+    fn save_ready(&self, managed: &[ManagedVolume]) {
+        for volume in managed.iter().filter(|v| v.state == State::Ready) {
+            if let Some(live) = self.volumes.volume(&volume.name) {
+                let index = live.index.lock().unwrap();
+                self.save(&volume.name, &index);
+            }
+        }
+        if let Some(index) = &self.content.index {
+            if let Err(e) = index.save_to_data_dir() {
+                note(format!("❌ Failed to save the content index: {:#}", e));
+            }
+        }
+    }
+
+    /// Write a volume's index file, unless another job is running on it
+    fn save(&self, name: &str, index: &FileIndex) {
+        let Some(mut job) = self.jobs.start(name, JobKind::Save) else {
+            return;
+        };
+        let result = persistence::get_volume_index_path(name)
+            .and_then(|path| persistence::save_volume_index(index, name, &path, &self.options.storage));
+        match result {
+            Ok(stats) => activity_log::log(format!("💾 Saved {} ({:.1} MB)", name, stats.stored_bytes as f64 / 1_048_576.0)),
+            Err(e) => {
+                job.fail(format!("{:#}", e));
+                note(format!("❌ Failed to save {}: {:#}", name, e));
+            }
+        }
+    }
+
+    /// Record a failure that happened before any job could start
+    fn fail(&self, volume: &mut ManagedVolume, kind: JobKind, error: anyhow::Error) {
+        if let Some(mut job) = self.jobs.start(&volume.name, kind) {
+            job.fail(format!("{:#}", error));
+        }
+        note(format!("❌ {}: {:#}", volume.name, error));
+        self.give_up(volume);
+    }
+
+    /// Leave a volume out until its next scheduled rescan
+    fn give_up(&self, volume: &mut ManagedVolume) {
+        volume.state = State::Failed;
+        volume.next_rescan = self.schedule.rescan_interval().map(|interval| Instant::now() + interval);
+    }
+
+    /// Changes applied to the indexes so far (events and corrected drift)
+    fn changes(&self) -> u64 {
+        let stats = self.status.snapshot();
+        stats.changes() + stats.drift
+    }
+}
+
+/// Print a message and write it to the activity log
+fn note(message: String) {
+    println!("{}", message);
+    activity_log::log(message);
+}
@@ -0,0 +1,510 @@
+// Differences between two indexes of a volume (e.g. yesterday's and today's):
+// what was added, removed, moved, resized or retimestamped
+use crate::export::{escape_csv_field, escape_tsv_field, format_file_size, ExportFormat};
+use crate::file_entry::FileEntry;
+use crate::index::{is_within, rebase_path, FileIndex};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+/// How entries of the two indexes are paired up
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatchBy {
+    /// By file id (MFT record or inode), falling back to the path for
+    /// entries whose id is missing or not unique
+    #[default]
+    FileId,
+
+    /// By path only (for listings whose ids mean nothing, like imports)
+    Path,
+}
+
+/// One way an entry changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Moved,
+    Resized,
+    Retimestamped,
+}
+
+impl ChangeKind {
+    /// Name used in exports
+    pub fn key(&self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Moved => "moved",
+            ChangeKind::Resized => "resized",
+            ChangeKind::Retimestamped => "retimestamped",
+        }
+    }
+
+    /// Marker at the start of a line in the plain output
+    fn marker(&self) -> char {
+        match self {
+            ChangeKind::Added => '+',
+            ChangeKind::Removed => '-',
+            ChangeKind::Moved => '>',
+            ChangeKind::Resized => '~',
+            ChangeKind::Retimestamped => '@',
+        }
+    }
+}
+
+/// An entry that differs between the two indexes
+#[derive(Debug, Clone)]
+pub struct Change {
+    /// What changed, in `ChangeKind` order (an entry can be moved and resized)
+    pub kinds: Vec<ChangeKind>,
+
+    /// The entry in the old index (None if added)
+    pub old: Option<FileEntry>,
+
+    /// The entry in the new index (None if removed)
+    pub new: Option<FileEntry>,
+}
+
+impl Change {
+    /// Current path of the entry (its last path if removed)
+    pub fn path(&self) -> &str {
+        match (&self.new, &self.old) {
+            (Some(entry), _) | (None, Some(entry)) => &entry.path,
+            (None, None) => "",
+        }
+    }
+
+    fn entry(&self) -> &FileEntry {
+        self.new.as_ref().or(self.old.as_ref()).expect("a change has an old or a new entry")
+    }
+
+    fn kinds_text(&self) -> String {
+        self.kinds.iter().map(ChangeKind::key).collect::<Vec<_>>().join("+")
+    }
+}
+
+/// Bytes that appeared and disappeared under one top-level directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirectoryDelta {
+    pub directory: String,
+    pub added: u64,
+    pub removed: u64,
+}
+
+/// Result of comparing two indexes
+#[derive(Debug, Default)]
+pub struct IndexDiff {
+    /// Changed entries, by path
+    pub changes: Vec<Change>,
+
+    /// Entries found unchanged in both
+    pub unchanged: usize,
+
+    /// Unchanged entries (old, new) that moved only because a directory above
+    /// them did; counted in `unchanged` and in `by_directory`, not listed
+    pub carried: Vec<(FileEntry, FileEntry)>,
+
+    /// Directory holding the volume's top-level entries (the common root of
+    /// every path in either index)
+    pub root: String,
+}
+
+impl IndexDiff {
+    /// Number of changes of a kind
+    pub fn count(&self, kind: ChangeKind) -> usize {
+        self.changes.iter().filter(|c| c.kinds.contains(&kind)).count()
+    }
+
+    /// Bytes added and removed per top-level directory (the directories just
+    /// below the volume root), largest change first
+    ///
+    /// Growth of a resized file counts as added, shrinking as removed; a file
+    /// moved between directories (on its own or inside a moved directory) is
+    /// removed from one and added to the other.
+    pub fn by_directory(&self) -> Vec<DirectoryDelta> {
+        let root = &self.root;
+        let mut deltas: BTreeMap<String, DirectoryDelta> = BTreeMap::new();
+        let mut add = |path: &str, added: u64, removed: u64| {
+            let directory = top_directory(path, root);
+            let delta = deltas.entry(directory.clone()).or_insert_with(|| DirectoryDelta {
+                directory,
+                ..Default::default()
+            });
+            delta.added += added;
+            delta.removed += removed;
+        };
+
+        let carried = self.carried.iter().map(|(old, new)| (Some(old), Some(new)));
+        for pair in self.changes.iter().map(|c| (c.old.as_ref(), c.new.as_ref())).chain(carried) {
+            match pair {
+                (None, Some(new)) => add(&new.path, new.size, 0),
+                (Some(old), None) => add(&old.path, 0, old.size),
+                (Some(old), Some(new)) if top_directory(&old.path, root) != top_directory(&new.path, root) => {
+                    add(&old.path, 0, old.size);
+                    add(&new.path, new.size, 0);
+                }
+                (Some(old), Some(new)) => add(&new.path, new.size.saturating_sub(old.size), old.size.saturating_sub(new.size)),
+                (None, None) => {}
+            }
+        }
+
+        let mut deltas: Vec<DirectoryDelta> = deltas.into_values().filter(|d| d.added + d.removed > 0).collect();
+        deltas.sort_by_key(|d| std::cmp::Reverse(d.added + d.removed));
+        deltas
+    }
+}
+
+/// Compare two indexes of the same volume
+///
+/// Entries inside a moved directory that moved along with it are not listed
+/// (the directory's move covers them) unless something else about them changed.
+pub fn diff_indexes(old: &FileIndex, new: &FileIndex, by: MatchBy) -> IndexDiff {
+    let (old_entries, new_entries) = (old.entries(), new.entries());
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    let mut old_matched = vec![false; old_entries.len()];
+    let mut new_matched = vec![false; new_entries.len()];
+
+    if by == MatchBy::FileId {
+        let old_ids = unique_ids(old_entries);
+        let new_ids = unique_ids(new_entries);
+        for (id, &j) in &new_ids {
+            if let Some(&i) = old_ids.get(id) {
+                if same_file(&old_entries[i], &new_entries[j]) {
+                    pairs.push((i, j));
+                    old_matched[i] = true;
+                    new_matched[j] = true;
+                }
+            }
+        }
+    }
+
+    let old_paths: HashMap<&str, usize> = old_entries
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !old_matched[*i])
+        .map(|(i, entry)| (entry.path.as_str(), i))
+        .collect();
+    for (j, entry) in new_entries.iter().enumerate() {
+        if new_matched[j] {
+            continue;
+        }
+        if let Some(&i) = old_paths.get(entry.path.as_str()) {
+            if !old_matched[i] {
+                pairs.push((i, j));
+                old_matched[i] = true;
+                new_matched[j] = true;
+            }
+        }
+    }
+
+    // Directories that moved, old path -> new path
+    let moved_dirs: HashMap<&str, &str> = pairs
+        .iter()
+        .map(|&(i, j)| (&old_entries[i], &new_entries[j]))
+        .filter(|(old, new)| old.is_directory && old.path != new.path)
+        .map(|(old, new)| (old.path.as_str(), new.path.as_str()))
+        .collect();
+
+    let mut diff = IndexDiff {
+        root: common_root(old_entries.iter().chain(new_entries).map(|e| e.path.as_str())),
+        ..Default::default()
+    };
+    for &(i, j) in &pairs {
+        let (old, new) = (&old_entries[i], &new_entries[j]);
+        let mut kinds = Vec::new();
+        if old.path != new.path && !moved_with_parent(old, new, &moved_dirs) {
+            kinds.push(ChangeKind::Moved);
+        }
+        if !new.is_directory && old.size != new.size {
+            kinds.push(ChangeKind::Resized);
+        }
+        if old.modified != new.modified {
+            kinds.push(ChangeKind::Retimestamped);
+        }
+        if kinds.is_empty() {
+            diff.unchanged += 1;
+            if old.path != new.path {
+                diff.carried.push((old.clone(), new.clone()));
+            }
+        } else {
+            diff.changes.push(Change {
+                kinds,
+                old: Some(old.clone()),
+                new: Some(new.clone()),
+            });
+        }
+    }
+    for (entry, _) in new_entries.iter().zip(&new_matched).filter(|(_, matched)| !**matched) {
+        diff.changes.push(Change {
+            kinds: vec![ChangeKind::Added],
+            old: None,
+            new: Some(entry.clone()),
+        });
+    }
+    for (entry, _) in old_entries.iter().zip(&old_matched).filter(|(_, matched)| !**matched) {
+        diff.changes.push(Change {
+            kinds: vec![ChangeKind::Removed],
+            old: Some(entry.clone()),
+            new: None,
+        });
+    }
+
+    diff.changes.sort_by(|a, b| a.path().cmp(b.path()));
+    diff
+}
+
+/// Position of each non-zero file id that occurs once
+fn unique_ids(entries: &[FileEntry]) -> HashMap<u64, usize> {
+    let mut ids: HashMap<u64, Option<usize>> = HashMap::new();
+    for (i, entry) in entries.iter().enumerate().filter(|(_, e)| e.file_id != 0) {
+        ids.entry(entry.file_id).and_modify(|seen| *seen = None).or_insert(Some(i));
+    }
+    ids.into_iter().filter_map(|(id, i)| Some((id, i?))).collect()
+}
+
+/// Whether two entries with the same id are one file: a reused id (inode
+/// numbers are) shows as a different kind of entry or a new creation time
+fn same_file(old: &FileEntry, new: &FileEntry) -> bool {
+    let recreated = matches!((old.created, new.created), (Some(a), Some(b)) if a != b);
+    old.is_directory == new.is_directory && !recreated
+}
+
+/// Whether an entry's new path is just its old one below a moved directory
+fn moved_with_parent(old: &FileEntry, new: &FileEntry, moved_dirs: &HashMap<&str, &str>) -> bool {
+    let Some((parent, _)) = old.path.rsplit_once(['\\', '/']) else {
+        return false;
+    };
+    moved_dirs
+        .get(parent)
+        .and_then(|to| rebase_path(&old.path, parent, to))
+        .is_some_and(|path| path == new.path)
+}
+
+/// Deepest directory that contains every path
+fn common_root<'a>(mut paths: impl Iterator<Item = &'a str>) -> String {
+    let Some(first) = paths.next() else {
+        return String::new();
+    };
+    let mut root = first.rsplit_once(['\\', '/']).map_or("", |(dir, _)| dir).to_string();
+    for path in paths {
+        while !root.is_empty() && !is_within(path, &root) {
+            root = root.rsplit_once(['\\', '/']).map_or("", |(dir, _)| dir).to_string();
+        }
+    }
+    root
+}
+
+/// Directory just below `root` that holds `path` (`root` itself for its own files)
+fn top_directory(path: &str, root: &str) -> String {
+    let rest = path[root.len().min(path.len())..].trim_start_matches(['\\', '/']);
+    match rest.split_once(['\\', '/']) {
+        Some((top, _)) => format!("{}{}{}", root, separator(path), top),
+        None if root.is_empty() => path.to_string(),
+        None => root.to_string(),
+    }
+}
+
+fn separator(path: &str) -> char {
+    if path.contains('\\') {
+        '\\'
+    } else {
+        '/'
+    }
+}
+
+/// Write the changes in an export format
+///
+/// `paths` prints one change per line with a marker (`+` added, `-` removed,
+/// `>` moved, `~` resized, `@` retimestamped); the structured formats carry
+/// both the old and the new path, size and modification time.
+pub fn write_changes(mut out: impl Write, diff: &IndexDiff, format: ExportFormat) -> Result<()> {
+    match format {
+        ExportFormat::Paths => {
+            for change in &diff.changes {
+                writeln!(out, "{}", describe(change))?;
+            }
+        }
+        ExportFormat::Nul => {
+            for change in &diff.changes {
+                out.write_all(change.path().as_bytes())?;
+                out.write_all(b"\0")?;
+            }
+        }
+        ExportFormat::Ndjson => {
+            for change in &diff.changes {
+                writeln!(out, "{}", change_json(change))?;
+            }
+        }
+        ExportFormat::Json => {
+            let changes: Vec<Value> = diff.changes.iter().map(change_json).collect();
+            let document = json!({ "timestamp": Utc::now().to_rfc3339(), "changes": changes });
+            serde_json::to_writer_pretty(&mut out, &document)?;
+            writeln!(out)?;
+        }
+        ExportFormat::Csv | ExportFormat::Tsv => {
+            let (delimiter, end, escape): (&str, &str, fn(&str) -> String) = match format {
+                ExportFormat::Csv => (",", "\r\n", escape_csv_field),
+                _ => ("\t", "\n", escape_tsv_field),
+            };
+            let header = ["Change", "Path", "Old Path", "Type", "Size (bytes)", "Old Size (bytes)", "Modified", "Old Modified"];
+            write!(out, "{}{}", header.join(delimiter), end)?;
+            for change in &diff.changes {
+                let fields = change_fields(change);
+                let fields: Vec<String> = fields.iter().map(|f| escape(f)).collect();
+                write!(out, "{}{}", fields.join(delimiter), end)?;
+            }
+        }
+        ExportFormat::Html | ExportFormat::Markdown | ExportFormat::Bodyfile | ExportFormat::Timeline => {
+            bail!("Diffs export as plain, csv, tsv, json, ndjson or null-separated")
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// One line of the plain output
+fn describe(change: &Change) -> String {
+    let marker = change.kinds[0].marker();
+    let mut line = match (&change.old, &change.new) {
+        (Some(old), Some(new)) if change.kinds.contains(&ChangeKind::Moved) => format!("{} {} -> {}", marker, old.path, new.path),
+        _ => format!("{} {}", marker, change.path()),
+    };
+    if let (Some(old), Some(new)) = (&change.old, &change.new) {
+        if change.kinds.contains(&ChangeKind::Resized) {
+            line.push_str(&format!(" ({} -> {})", format_file_size(old.size), format_file_size(new.size)));
+        }
+        if change.kinds.contains(&ChangeKind::Retimestamped) {
+            line.push_str(&format!(" (modified {} -> {})", time_text(old.modified), time_text(new.modified)));
+        }
+    }
+    line
+}
+
+fn change_json(change: &Change) -> Value {
+    let entry = change.entry();
+    json!({
+        "change": change.kinds.iter().map(ChangeKind::key).collect::<Vec<_>>(),
+        "path": change.path(),
+        "old_path": change.old.as_ref().map(|e| &e.path),
+        "type": if entry.is_directory { "directory" } else { "file" },
+        "size": change.new.as_ref().map(|e| e.size),
+        "old_size": change.old.as_ref().map(|e| e.size),
+        "modified": change.new.as_ref().and_then(|e| e.modified).map(|d| d.to_rfc3339()),
+        "old_modified": change.old.as_ref().and_then(|e| e.modified).map(|d| d.to_rfc3339()),
+        "file_id": entry.file_id,
+    })
+}
+
+fn change_fields(change: &Change) -> [String; 8] {
+    let size = |entry: &Option<FileEntry>| entry.as_ref().map(|e| e.size.to_string()).unwrap_or_default();
+    let modified = |entry: &Option<FileEntry>| entry.as_ref().map(|e| time_text(e.modified)).unwrap_or_default();
+    [
+        change.kinds_text(),
+        change.path().to_string(),
+        change.old.as_ref().map(|e| e.path.clone()).unwrap_or_default(),
+        if change.entry().is_directory { "Directory" } else { "File" }.to_string(),
+        size(&change.new),
+        size(&change.old),
+        modified(&change.new),
+        modified(&change.old),
+    ]
+}
+
+fn time_text(time: Option<DateTime<Utc>>) -> String {
+    time.map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn entry(path: &str, is_directory: bool, file_id: u64, size: u64, modified: i64) -> FileEntry {
+        let name = path.rsplit('/').next().unwrap().to_string();
+        let modified = Utc.timestamp_opt(modified, 0).single();
+        FileEntry::new(name, path.to_string(), is_directory, file_id, 0, size, modified, None, None)
+    }
+
+    fn index(entries: Vec<FileEntry>) -> FileIndex {
+        let mut index = FileIndex::new();
+        for entry in entries {
+            index.add_entry(entry);
+        }
+        index
+    }
+
+    #[test]
+    fn test_diff_indexes() {
+        // gone.txt's id is reused by a new file (its creation time gives it away)
+        let created = |mut entry: FileEntry, secs: i64| {
+            entry.created = Utc.timestamp_opt(secs, 0).single();
+            entry
+        };
+        let old = index(vec![
+            entry("/v/docs", true, 1, 0, 0),
+            entry("/v/docs/a.txt", false, 2, 100, 10),
+            created(entry("/v/docs/gone.txt", false, 3, 50, 10), 1),
+            entry("/v/src", true, 4, 0, 0),
+            entry("/v/src/lib.rs", false, 5, 10, 10),
+            entry("/v/src/old name.rs", false, 6, 10, 10),
+        ]);
+        let new = index(vec![
+            entry("/v/papers", true, 1, 0, 0),
+            entry("/v/papers/a.txt", false, 2, 100, 10),
+            entry("/v/src", true, 4, 0, 0),
+            entry("/v/src/lib.rs", false, 5, 30, 20),
+            entry("/v/src/new name.rs", false, 6, 10, 10),
+            entry("/v/src/added.rs", false, 7, 5, 30),
+            created(entry("/v/src/reused.rs", false, 3, 50, 10), 99),
+        ]);
+
+        let diff = diff_indexes(&old, &new, MatchBy::FileId);
+        let lines: Vec<String> = diff.changes.iter().map(describe).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "- /v/docs/gone.txt",
+                "> /v/docs -> /v/papers",
+                "+ /v/src/added.rs",
+                "~ /v/src/lib.rs (10 B -> 30 B) (modified 1970-01-01 00:00:10 -> 1970-01-01 00:00:20)",
+                "> /v/src/old name.rs -> /v/src/new name.rs",
+                "+ /v/src/reused.rs",
+            ]
+        );
+        assert_eq!(diff.unchanged, 2);
+        assert_eq!(diff.count(ChangeKind::Moved), 2);
+
+        let deltas = diff.by_directory();
+        assert_eq!(
+            deltas,
+            vec![
+                // a.txt moved along with its directory
+                DirectoryDelta { directory: "/v/docs".to_string(), added: 0, removed: 150 },
+                DirectoryDelta { directory: "/v/papers".to_string(), added: 100, removed: 0 },
+                DirectoryDelta { directory: "/v/src".to_string(), added: 75, removed: 0 },
+            ]
+        );
+
+        // By path, the renames are a removal and an addition
+        let diff = diff_indexes(&old, &new, MatchBy::Path);
+        assert_eq!(diff.count(ChangeKind::Moved), 0);
+        assert_eq!(diff.count(ChangeKind::Added), 5);
+
+        let mut csv = Vec::new();
+        write_changes(&mut csv, &diff_indexes(&old, &new, MatchBy::FileId), ExportFormat::Csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.contains("\r\nmoved,/v/papers,/v/docs,Directory,0,0,"));
+
+        // Changes all inside one directory still group under the top-level one
+        let top_level = || vec![entry("/v/docs", true, 1, 0, 0), entry("/v/src", true, 2, 0, 0), entry("/v/src/app", true, 3, 0, 0)];
+        let mut grown = top_level();
+        grown.push(entry("/v/src/app/main.rs", false, 4, 40, 10));
+        grown.push(entry("/v/src/app/lib.rs", false, 5, 2, 10));
+        let diff = diff_indexes(&index(top_level()), &index(grown), MatchBy::FileId);
+        assert_eq!(diff.root, "/v");
+        assert_eq!(diff.by_directory(), vec![DirectoryDelta { directory: "/v/src".to_string(), added: 42, removed: 0 }]);
+    }
+}
@@ -0,0 +1,317 @@
+// Parallel directory walker for non-NTFS volumes (Linux, macOS, network shares)
+use crate::config::Exclusions;
+use crate::file_entry::FileEntry;
+use crate::index::FileIndex;
+use crate::scanner::{Progress, Scanner};
+use crate::security::Security;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use rayon::prelude::*;
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Scanner that walks a directory tree with one task per directory
+///
+/// Uses the inode number as `file_id` and the parent directory's inode as
+/// `parent_id`, so entries link up the same way MFT entries do.
+pub struct DirectoryWalker {
+    root: PathBuf,
+    exclusions: Exclusions,
+    progress: Option<Progress>,
+}
+
+impl DirectoryWalker {
+    /// Create a walker for the tree under `root`
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            exclusions: Exclusions::default(),
+            progress: None,
+        }
+    }
+
+    /// Report the start and end of `scan_into_index` (quiet by default)
+    pub fn progress(mut self, progress: Progress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Leave out entries matching `exclusions` (excluded directories are not descended into)
+    pub fn exclusions(mut self, exclusions: Exclusions) -> Self {
+        self.exclusions = exclusions;
+        self
+    }
+
+    /// Walk the whole tree quietly, returning its entries (the root itself is not included)
+    pub fn entries(&self) -> Result<Vec<FileEntry>> {
+        self.walk_root().map(|(entries, _)| entries)
+    }
+
+    /// Walk from the root, also counting directories that could not be read
+    fn walk_root(&self) -> Result<(Vec<FileEntry>, usize)> {
+        let metadata = fs::metadata(&self.root)
+            .with_context(|| format!("Failed to read directory: {}", self.root.display()))?;
+        if !metadata.is_dir() {
+            bail!("Not a directory: {}", self.root.display());
+        }
+
+        let unreadable = AtomicUsize::new(0);
+        let entries = self.walk(&self.root, file_id(&self.root, &metadata), device_id(&metadata), &unreadable);
+        Ok((entries, unreadable.into_inner()))
+    }
+
+    /// Walk one directory, then its subdirectories in parallel
+    fn walk(&self, dir: &Path, dir_id: u64, root_device: u64, unreadable: &AtomicUsize) -> Vec<FileEntry> {
+        let read_dir = match fs::read_dir(dir) {
+            Ok(read_dir) => read_dir,
+            Err(_) => {
+                unreadable.fetch_add(1, Ordering::Relaxed);
+                return Vec::new();
+            }
+        };
+
+        let mut entries = Vec::new();
+        let mut subdirs = Vec::new();
+
+        for dir_entry in read_dir.flatten() {
+            // DirEntry::metadata does not follow symlinks
+            let metadata = match dir_entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+
+            let path = dir_entry.path();
+            let entry = entry_from_metadata(&path, &metadata, dir_id);
+            if self.exclusions.excludes_entry(&entry.name, &entry.path) {
+                continue;
+            }
+
+            // Stay on the root's filesystem, like `find -xdev`: mount points are indexed, their contents are not
+            if metadata.is_dir() && device_id(&metadata) == root_device {
+                subdirs.push((path, entry.file_id));
+            }
+            entries.push(entry);
+        }
+
+        // Siblings stay together, followed by each subtree in order
+        let subtrees: Vec<Vec<FileEntry>> = subdirs
+            .par_iter()
+            .map(|(path, id)| self.walk(path, *id, root_device, unreadable))
+            .collect();
+        for subtree in subtrees {
+            entries.extend(subtree);
+        }
+
+        entries
+    }
+}
+
+impl Scanner for DirectoryWalker {
+    fn describe(&self) -> String {
+        self.root.display().to_string()
+    }
+
+    fn scan_into_index(&self, index: &mut FileIndex) -> Result<()> {
+        let report = |message: &str| {
+            if let Some(progress) = &self.progress {
+                progress(message);
+            }
+        };
+        report(&format!("Scanning {}...", self.root.display()));
+
+        let start_time = Instant::now();
+
+        let (entries, unreadable) = self.walk_root()?;
+
+        index.reserve(entries.len());
+        for entry in entries {
+            index.add_entry(entry);
+        }
+
+        report(&format!(
+            "Scan complete: {} files, {} directories in {:.2}s",
+            index.file_count(),
+            index.directory_count(),
+            start_time.elapsed().as_secs_f64()
+        ));
+        if unreadable > 0 {
+            report(&format!("⚠️  {} directories could not be read (permission denied?)", unreadable));
+        }
+
+        Ok(())
+    }
+}
+
+/// Build an index entry from a path and its (non-followed) metadata
+pub fn entry_from_metadata(path: &Path, metadata: &Metadata, parent_id: u64) -> FileEntry {
+    let is_directory = metadata.is_dir();
+
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string());
+
+    let mut entry = FileEntry::new(
+        name,
+        path.to_string_lossy().to_string(),
+        is_directory,
+        file_id(path, metadata),
+        parent_id,
+        if is_directory { 0 } else { metadata.len() },
+        metadata.modified().ok().map(DateTime::<Utc>::from),
+        metadata.created().ok().map(DateTime::<Utc>::from),
+        metadata.accessed().ok().map(DateTime::<Utc>::from),
+    );
+    entry.changed = changed_time(metadata);
+    entry.security = security(metadata);
+    entry
+}
+
+/// Build an index entry for a single path, linked to its parent directory
+pub fn entry_for_path(path: &Path) -> std::io::Result<FileEntry> {
+    let metadata = fs::symlink_metadata(path)?;
+    let parent_id = path
+        .parent()
+        .and_then(|parent| fs::symlink_metadata(parent).ok().map(|m| file_id(parent, &m)))
+        .unwrap_or(0);
+    Ok(entry_from_metadata(path, &metadata, parent_id))
+}
+
+/// Stable identifier for a file: the inode number
+#[cfg(unix)]
+fn file_id(_path: &Path, metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+/// Stable identifier for a file: the file index (the MFT reference on NTFS)
+///
+/// Falls back to a hash of the path when the file can't be opened.
+#[cfg(windows)]
+fn file_id(path: &Path, metadata: &Metadata) -> u64 {
+    use std::os::windows::fs::OpenOptionsExt;
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::Storage::FileSystem::{
+        GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION, FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_OPEN_REPARSE_POINT,
+    };
+
+    // No access rights are needed to read the file index; backup semantics
+    // opens directories and the reparse point flag the link itself
+    let file = fs::OpenOptions::new()
+        .access_mode(0)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS.0 | FILE_FLAG_OPEN_REPARSE_POINT.0)
+        .open(path);
+    let mut info = BY_HANDLE_FILE_INFORMATION::default();
+    match file {
+        // SAFETY: the handle stays open while the call runs and `info` is a valid out pointer
+        Ok(file) if unsafe { GetFileInformationByHandle(HANDLE(file.as_raw_handle()), &mut info) }.is_ok() => {
+            (info.nFileIndexHigh as u64) << 32 | info.nFileIndexLow as u64
+        }
+        _ => path_hash(path, metadata),
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn file_id(path: &Path, metadata: &Metadata) -> u64 {
+    path_hash(path, metadata)
+}
+
+/// Identifier for a file where the system offers none: a hash of its path
+#[cfg(not(unix))]
+fn path_hash(path: &Path, _metadata: &Metadata) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    path.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Status change time (ctime)
+#[cfg(unix)]
+fn changed_time(metadata: &Metadata) -> Option<DateTime<Utc>> {
+    use std::os::unix::fs::MetadataExt;
+    DateTime::from_timestamp(metadata.ctime(), metadata.ctime_nsec() as u32)
+}
+
+#[cfg(not(unix))]
+fn changed_time(_metadata: &Metadata) -> Option<DateTime<Utc>> {
+    None
+}
+
+/// Owner, group and permission bits, shared by entries that have the same
+#[cfg(unix)]
+fn security(metadata: &Metadata) -> Option<Arc<Security>> {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::os::unix::fs::MetadataExt;
+
+    thread_local! {
+        static SHARED: RefCell<HashMap<(u32, u32, u32), Arc<Security>>> = RefCell::new(HashMap::new());
+    }
+    let key = (metadata.uid(), metadata.gid(), metadata.mode() & 0o777);
+    let security = SHARED.with(|shared| {
+        Arc::clone(
+            shared
+                .borrow_mut()
+                .entry(key)
+                .or_insert_with(|| Arc::new(Security::from_mode(key.0, key.1, key.2))),
+        )
+    });
+    Some(security)
+}
+
+#[cfg(not(unix))]
+fn security(_metadata: &Metadata) -> Option<Arc<Security>> {
+    None
+}
+
+/// Device the file lives on (for staying on one filesystem)
+#[cfg(unix)]
+fn device_id(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.dev()
+}
+
+#[cfg(not(unix))]
+fn device_id(_metadata: &Metadata) -> u64 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_walk_directory_tree() {
+        let root = std::env::temp_dir().join(format!("nothing_walker_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("docs/2024")).unwrap();
+        fs::write(root.join("docs/report.pdf"), b"12345").unwrap();
+        fs::write(root.join("docs/2024/notes.txt"), b"hi").unwrap();
+        fs::write(root.join("readme.md"), b"").unwrap();
+
+        let mut index = FileIndex::new();
+        DirectoryWalker::new(&root).scan_into_index(&mut index).unwrap();
+
+        assert_eq!(index.file_count(), 3);
+        assert_eq!(index.directory_count(), 2);
+
+        let find = |name: &str| index.entries().iter().find(|e| e.name == name).unwrap().clone();
+        let report = find("report.pdf");
+        assert_eq!(report.size, 5);
+        assert!(report.modified.is_some());
+        assert_eq!(report.path, root.join("docs").join("report.pdf").to_string_lossy());
+
+        // Entries link to their parent directory
+        let docs = find("docs");
+        let year = find("2024");
+        assert_eq!(report.parent_id, docs.file_id);
+        assert_eq!(year.parent_id, docs.file_id);
+        assert_eq!(find("notes.txt").parent_id, year.file_id);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,93 +1,637 @@
+use crate::file_entry::{FileEntry, Timestamps};
+use crate::file_type::FileType;
+use crate::filters::SearchFilters;
+use crate::index::{EntryRef, IndexView};
+use crate::report::{ReportExporter, ReportKind};
 use crate::search::SearchResult;
-use anyhow::Result;
+use crate::security::{parse_acl, Security};
+use crate::timeline::{BodyfileExporter, TimelineExporter};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::fs::File;
-use std::io::Write;
-use serde_json::json;
+use std::io::{BufWriter, Write};
+use std::str::FromStr;
+use std::sync::Arc;
+use serde_json::{json, Map, Value};
 
-/// Export search results to CSV format
-pub fn export_csv(results: &[SearchResult], path: &str) -> Result<()> {
-    let mut file = File::create(path)?;
+/// Export file formats
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
 
-    // Write CSV header
-    writeln!(file, "Name,Path,Type,Size (bytes),Size (formatted),Modified,Created,Accessed,Score")?;
+    /// One JSON object per line
+    Ndjson,
 
-    // Write each result
-    for result in results {
-        let entry = &result.entry;
+    /// Tab-separated, with tabs and line breaks in fields escaped as `\t`, `\n`, `\r`
+    Tsv,
+
+    /// One path per line
+    #[serde(alias = "plain")]
+    Paths,
+
+    /// Paths terminated by NUL, for `xargs -0`
+    #[serde(alias = "null-separated")]
+    Nul,
 
-        let entry_type = if entry.is_directory { "Directory" } else { "File" };
-        let size_formatted = format_file_size(entry.size);
-        let modified = entry.modified.map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_default();
-        let created = entry.created.map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_default();
-        let accessed = entry.accessed.map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_default();
+    /// Self-contained HTML report with a summary and a sortable table
+    Html,
 
-        // Escape CSV fields (handle commas, quotes)
-        let name = escape_csv_field(&entry.name);
-        let path = escape_csv_field(&entry.path);
+    /// Markdown report with a summary and a table
+    #[serde(alias = "md")]
+    Markdown,
 
-        writeln!(
-            file,
-            "{},{},{},{},{},{},{},{},{}",
-            name,
-            path,
-            entry_type,
-            entry.size,
-            size_formatted,
-            modified,
-            created,
-            accessed,
-            result.score
-        )?;
+    /// Sleuth Kit bodyfile with every timestamp, for `mactime`
+    #[serde(alias = "mactime")]
+    Bodyfile,
+
+    /// CSV timeline: one row per timestamp, oldest first, with MACB letters
+    Timeline,
+}
+
+impl ExportFormat {
+    /// File extension for this format
+    pub fn extension(&self) -> &str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Tsv => "tsv",
+            ExportFormat::Paths | ExportFormat::Nul => "txt",
+            ExportFormat::Html => "html",
+            ExportFormat::Markdown => "md",
+            ExportFormat::Bodyfile => "body",
+            ExportFormat::Timeline => "csv",
+        }
     }
 
-    Ok(())
+    /// Columns written unless others are selected: JSON formats carry every
+    /// field, reports what a reader needs, CSV and TSV the columns of the
+    /// original CSV export
+    pub fn default_columns(&self) -> &'static [Column] {
+        match self {
+            ExportFormat::Json | ExportFormat::Ndjson => Column::ALL,
+            ExportFormat::Html | ExportFormat::Markdown => Column::REPORT,
+            _ => Column::DEFAULT,
+        }
+    }
+
+    /// Writer for this format; `query` is what the entries were selected by
+    /// (shown in reports, empty for a whole-index export)
+    pub fn exporter<'a>(&self, out: impl Write + 'a, columns: &[Column], query: &str) -> Box<dyn Exporter + 'a> {
+        let columns = columns.to_vec();
+        match self {
+            ExportFormat::Csv => Box::new(DelimitedExporter::csv(out, columns)),
+            ExportFormat::Tsv => Box::new(DelimitedExporter::tsv(out, columns)),
+            ExportFormat::Json => Box::new(JsonExporter::new(out, columns)),
+            ExportFormat::Ndjson => Box::new(NdjsonExporter::new(out, columns)),
+            ExportFormat::Paths => Box::new(PathExporter::new(out, b'\n')),
+            ExportFormat::Nul => Box::new(PathExporter::new(out, b'\0')),
+            ExportFormat::Html => Box::new(ReportExporter::new(out, ReportKind::Html, columns, query)),
+            ExportFormat::Markdown => Box::new(ReportExporter::new(out, ReportKind::Markdown, columns, query)),
+            ExportFormat::Bodyfile => Box::new(BodyfileExporter::new(out)),
+            ExportFormat::Timeline => Box::new(TimelineExporter::new(out)),
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            "ndjson" | "jsonl" => Ok(ExportFormat::Ndjson),
+            "tsv" => Ok(ExportFormat::Tsv),
+            "paths" | "plain" => Ok(ExportFormat::Paths),
+            "nul" | "null" | "null-separated" | "0" => Ok(ExportFormat::Nul),
+            "html" => Ok(ExportFormat::Html),
+            "markdown" | "md" => Ok(ExportFormat::Markdown),
+            "bodyfile" | "body" | "mactime" => Ok(ExportFormat::Bodyfile),
+            "timeline" => Ok(ExportFormat::Timeline),
+            _ => Err(anyhow!(
+                "Invalid format: {}. Use 'csv', 'json', 'ndjson', 'tsv', 'plain', 'null-separated', 'html', 'markdown', 'bodyfile' or 'timeline'",
+                s
+            )),
+        }
+    }
+}
+
+/// A field of an exported entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Name,
+    Path,
+    Type,
+    Size,
+    SizeFormatted,
+    Modified,
+    Created,
+    Accessed,
+    Score,
+    FileId,
+    ParentId,
+    Changed,
+    FnModified,
+    FnAccessed,
+    FnChanged,
+    FnCreated,
+    Owner,
+    Group,
+    Acl,
+    Mime,
+    Kind,
+}
+
+impl Column {
+    /// The CSV export's columns
+    pub const DEFAULT: &'static [Column] = &[
+        Column::Name,
+        Column::Path,
+        Column::Type,
+        Column::Size,
+        Column::SizeFormatted,
+        Column::Modified,
+        Column::Created,
+        Column::Accessed,
+        Column::Score,
+    ];
+
+    /// Columns of HTML and Markdown reports
+    pub const REPORT: &'static [Column] = &[
+        Column::Name,
+        Column::Path,
+        Column::SizeFormatted,
+        Column::Modified,
+    ];
+
+    /// Every column, in the order `entry_json` writes them
+    pub const ALL: &'static [Column] = &[
+        Column::Name,
+        Column::Path,
+        Column::Type,
+        Column::Size,
+        Column::SizeFormatted,
+        Column::Modified,
+        Column::Created,
+        Column::Accessed,
+        Column::Score,
+        Column::FileId,
+        Column::ParentId,
+        Column::Changed,
+        Column::FnModified,
+        Column::FnAccessed,
+        Column::FnChanged,
+        Column::FnCreated,
+        Column::Owner,
+        Column::Group,
+        Column::Acl,
+        Column::Mime,
+        Column::Kind,
+    ];
+
+    /// Key in JSON output and name on the command line
+    pub fn key(&self) -> &'static str {
+        match self {
+            Column::Name => "name",
+            Column::Path => "path",
+            Column::Type => "type",
+            Column::Size => "size",
+            Column::SizeFormatted => "size_formatted",
+            Column::Modified => "modified",
+            Column::Created => "created",
+            Column::Accessed => "accessed",
+            Column::Score => "score",
+            Column::FileId => "file_id",
+            Column::ParentId => "parent_id",
+            Column::Changed => "changed",
+            Column::FnModified => "fn_modified",
+            Column::FnAccessed => "fn_accessed",
+            Column::FnChanged => "fn_changed",
+            Column::FnCreated => "fn_created",
+            Column::Owner => "owner",
+            Column::Group => "group",
+            Column::Acl => "acl",
+            Column::Mime => "mime",
+            Column::Kind => "kind",
+        }
+    }
+
+    /// Header in CSV and TSV output
+    pub fn label(&self) -> &'static str {
+        match self {
+            Column::Name => "Name",
+            Column::Path => "Path",
+            Column::Type => "Type",
+            Column::Size => "Size (bytes)",
+            Column::SizeFormatted => "Size (formatted)",
+            Column::Modified => "Modified",
+            Column::Created => "Created",
+            Column::Accessed => "Accessed",
+            Column::Score => "Score",
+            Column::FileId => "File ID",
+            Column::ParentId => "Parent ID",
+            Column::Changed => "Changed",
+            Column::FnModified => "FN Modified",
+            Column::FnAccessed => "FN Accessed",
+            Column::FnChanged => "FN Changed",
+            Column::FnCreated => "FN Created",
+            Column::Owner => "Owner",
+            Column::Group => "Group",
+            Column::Acl => "ACL",
+            Column::Mime => "MIME Type",
+            Column::Kind => "Kind",
+        }
+    }
+
+    /// Value as text, for CSV, TSV and reports (empty when missing)
+    pub(crate) fn text(&self, entry: &FileEntry, score: Option<u32>) -> String {
+        let time = |t: Option<DateTime<Utc>>| t.map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string()).unwrap_or_default();
+        match self {
+            Column::Name => entry.name.clone(),
+            Column::Path => entry.path.clone(),
+            Column::Type => if entry.is_directory { "Directory" } else { "File" }.to_string(),
+            Column::Size => entry.size.to_string(),
+            Column::SizeFormatted => format_file_size(entry.size),
+            Column::Modified => time(entry.modified),
+            Column::Created => time(entry.created),
+            Column::Accessed => time(entry.accessed),
+            Column::Score => score.map(|s| s.to_string()).unwrap_or_default(),
+            Column::FileId => entry.file_id.to_string(),
+            Column::ParentId => entry.parent_id.to_string(),
+            Column::Changed => time(entry.changed),
+            Column::FnModified => time(file_name_time(entry, |t| t.modified)),
+            Column::FnAccessed => time(file_name_time(entry, |t| t.accessed)),
+            Column::FnChanged => time(file_name_time(entry, |t| t.changed)),
+            Column::FnCreated => time(file_name_time(entry, |t| t.created)),
+            Column::Owner => entry.security.as_ref().and_then(|s| s.owner.clone()).unwrap_or_default(),
+            Column::Group => entry.security.as_ref().and_then(|s| s.group.clone()).unwrap_or_default(),
+            Column::Acl => entry.security.as_ref().map(|s| s.acl_summary()).unwrap_or_default(),
+            Column::Mime => entry.file_type.map(|t| t.mime().to_string()).unwrap_or_default(),
+            Column::Kind => entry.file_type.map(|t| t.kind().to_string()).unwrap_or_default(),
+        }
+    }
+}
+
+impl FromStr for Column {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let key = s.trim().to_lowercase().replace('-', "_");
+        Column::ALL
+            .iter()
+            .copied()
+            .find(|column| column.key() == key)
+            .ok_or_else(|| anyhow!("Invalid column: {}. Use one of: {}", s, column_keys()))
+    }
+}
+
+/// One of an entry's `$FILE_NAME` times, if it has them
+fn file_name_time(entry: &FileEntry, time: impl Fn(&Timestamps) -> Option<DateTime<Utc>>) -> Option<DateTime<Utc>> {
+    entry.file_name_times.as_deref().and_then(time)
+}
+
+fn column_keys() -> String {
+    Column::ALL.iter().map(Column::key).collect::<Vec<_>>().join(", ")
+}
+
+/// Parse a comma-separated column list such as "name,path,size"
+pub fn parse_columns(list: &str) -> Result<Vec<Column>> {
+    list.split(',').filter(|s| !s.trim().is_empty()).map(str::parse).collect()
+}
+
+/// Streaming writer for exported entries
+///
+/// Entries are written as they come, so exports of a whole index never hold
+/// more than one entry in memory. `finish` must be called once at the end.
+pub trait Exporter {
+    /// Write one entry (`score` is None outside a search)
+    fn write_entry(&mut self, entry: &FileEntry, score: Option<u32>) -> Result<()>;
+
+    /// Write whatever closes the document and flush
+    fn finish(&mut self) -> Result<()>;
+}
+
+/// RFC 4180 CSV, or TSV with tabs and line breaks escaped
+pub struct DelimitedExporter<W> {
+    out: W,
+    columns: Vec<Column>,
+    delimiter: char,
+    header_written: bool,
+}
+
+impl<W: Write> DelimitedExporter<W> {
+    pub fn csv(out: W, columns: Vec<Column>) -> Self {
+        Self { out, columns, delimiter: ',', header_written: false }
+    }
+
+    pub fn tsv(out: W, columns: Vec<Column>) -> Self {
+        Self { out, columns, delimiter: '\t', header_written: false }
+    }
+
+    fn write_row(&mut self, fields: &[String]) -> Result<()> {
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|field| match self.delimiter {
+                '\t' => escape_tsv_field(field),
+                _ => escape_csv_field(field),
+            })
+            .collect();
+        // RFC 4180 ends records with CRLF; TSV readers expect plain newlines
+        let end = if self.delimiter == ',' { "\r\n" } else { "\n" };
+        write!(self.out, "{}{}", fields.join(&self.delimiter.to_string()), end)?;
+        Ok(())
+    }
+
+    fn write_header(&mut self) -> Result<()> {
+        if !self.header_written {
+            self.header_written = true;
+            let labels: Vec<String> = self.columns.iter().map(|column| column.label().to_string()).collect();
+            self.write_row(&labels)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Exporter for DelimitedExporter<W> {
+    fn write_entry(&mut self, entry: &FileEntry, score: Option<u32>) -> Result<()> {
+        self.write_header()?;
+        let values: Vec<String> = self.columns.iter().map(|column| column.text(entry, score)).collect();
+        self.write_row(&values)
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.write_header()?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// One JSON object per line
+pub struct NdjsonExporter<W> {
+    out: W,
+    columns: Vec<Column>,
+}
+
+impl<W: Write> NdjsonExporter<W> {
+    pub fn new(out: W, columns: Vec<Column>) -> Self {
+        Self { out, columns }
+    }
+}
+
+impl<W: Write> Exporter for NdjsonExporter<W> {
+    fn write_entry(&mut self, entry: &FileEntry, score: Option<u32>) -> Result<()> {
+        serde_json::to_writer(&mut self.out, &select_columns(entry, score, &self.columns))?;
+        self.out.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// The `export_json` document, written one result at a time
+pub struct JsonExporter<W> {
+    out: W,
+    columns: Vec<Column>,
+    count: usize,
+}
+
+impl<W: Write> JsonExporter<W> {
+    pub fn new(out: W, columns: Vec<Column>) -> Self {
+        Self { out, columns, count: 0 }
+    }
+
+    fn write_start(&mut self) -> Result<()> {
+        writeln!(self.out, "{{\n  \"timestamp\": {},\n  \"results\": [", json!(Utc::now().to_rfc3339()))?;
+        Ok(())
+    }
+}
+
+impl<W: Write> Exporter for JsonExporter<W> {
+    fn write_entry(&mut self, entry: &FileEntry, score: Option<u32>) -> Result<()> {
+        if self.count == 0 {
+            self.write_start()?;
+        } else {
+            writeln!(self.out, ",")?;
+        }
+        write!(self.out, "    ")?;
+        serde_json::to_writer(&mut self.out, &select_columns(entry, score, &self.columns))?;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if self.count == 0 {
+            self.write_start()?;
+        } else {
+            writeln!(self.out)?;
+        }
+        writeln!(self.out, "  ],\n  \"total_results\": {}\n}}", self.count)?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Bare paths, each followed by a terminator (newline or NUL)
+pub struct PathExporter<W> {
+    out: W,
+    terminator: u8,
+}
+
+impl<W: Write> PathExporter<W> {
+    pub fn new(out: W, terminator: u8) -> Self {
+        Self { out, terminator }
+    }
+}
+
+impl<W: Write> Exporter for PathExporter<W> {
+    fn write_entry(&mut self, entry: &FileEntry, _score: Option<u32>) -> Result<()> {
+        self.out.write_all(entry.path.as_bytes())?;
+        self.out.write_all(&[self.terminator])?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Export the results of `query` in the given format
+pub fn export_results(results: &[SearchResult], path: &str, format: ExportFormat, query: &str) -> Result<()> {
+    let file = File::create(path)?;
+    write_results(BufWriter::new(file), results, format, format.default_columns(), query)
+}
+
+/// Export search results to CSV format
+pub fn export_csv(results: &[SearchResult], path: &str) -> Result<()> {
+    export_results(results, path, ExportFormat::Csv, "")
 }
 
 /// Export search results to JSON format
 pub fn export_json(results: &[SearchResult], path: &str) -> Result<()> {
-    let mut file = File::create(path)?;
+    export_results(results, path, ExportFormat::Json, "")
+}
+
+/// Write the results of `query` to `out` in a format, with the selected columns
+pub fn write_results(
+    out: impl Write,
+    results: &[SearchResult],
+    format: ExportFormat,
+    columns: &[Column],
+    query: &str,
+) -> Result<()> {
+    let mut exporter = format.exporter(out, columns, query);
+    for result in results {
+        exporter.write_entry(&result.entry, Some(result.score))?;
+    }
+    exporter.finish()
+}
+
+/// Write every entry of an index that passes the filters, in index order
+///
+/// Returns the number of entries written.
+pub fn export_index<I: IndexView + ?Sized>(index: &I, filters: &SearchFilters, exporter: &mut dyn Exporter) -> Result<usize> {
+    let mut count = 0;
+    for entry in index.entry_refs() {
+        let mapped;
+        let entry = match entry {
+            EntryRef::Owned(owned) => owned,
+            EntryRef::Mapped(_) => {
+                mapped = entry.to_entry();
+                &mapped
+            }
+        };
+        if filters.matches(entry) {
+            exporter.write_entry(entry, None)?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// An entry's selected fields, keyed as in `entry_json`
+fn select_columns(entry: &FileEntry, score: Option<u32>, columns: &[Column]) -> Value {
+    let all = entry_json(entry, score);
+    let mut selected = Map::new();
+    for column in columns {
+        selected.insert(column.key().to_string(), all[column.key()].clone());
+    }
+    Value::Object(selected)
+}
 
+/// The document `export_json` writes: a timestamp and the results
+pub fn results_json(results: &[SearchResult]) -> Value {
     let json_results: Vec<_> = results
         .iter()
         .map(|result| {
-            json!({
-                "name": result.entry.name,
-                "path": result.entry.path,
-                "type": if result.entry.is_directory { "directory" } else { "file" },
-                "size": result.entry.size,
-                "size_formatted": format_file_size(result.entry.size),
-                "modified": result.entry.modified.map(|d| d.to_rfc3339()),
-                "created": result.entry.created.map(|d| d.to_rfc3339()),
-                "accessed": result.entry.accessed.map(|d| d.to_rfc3339()),
-                "score": result.score,
-                "file_id": result.entry.file_id,
-                "parent_id": result.entry.parent_id,
-            })
+            let mut json = entry_json(&result.entry, Some(result.score));
+            if !result.content.is_empty() {
+                json["matches"] = json!(result.content);
+            }
+            json
         })
         .collect();
 
-    let output = json!({
+    json!({
         "timestamp": chrono::Utc::now().to_rfc3339(),
         "total_results": results.len(),
         "results": json_results
-    });
+    })
+}
+
+/// One entry as it appears in exported results (`score` is null outside a search)
+pub fn entry_json(entry: &FileEntry, score: Option<u32>) -> Value {
+    json!({
+        "name": entry.name,
+        "path": entry.path,
+        "type": if entry.is_directory { "directory" } else { "file" },
+        "size": entry.size,
+        "size_formatted": format_file_size(entry.size),
+        "modified": entry.modified.map(|d| d.to_rfc3339()),
+        "created": entry.created.map(|d| d.to_rfc3339()),
+        "accessed": entry.accessed.map(|d| d.to_rfc3339()),
+        "score": score,
+        "file_id": entry.file_id,
+        "parent_id": entry.parent_id,
+        "changed": entry.changed.map(|d| d.to_rfc3339()),
+        "fn_modified": file_name_time(entry, |t| t.modified).map(|d| d.to_rfc3339()),
+        "fn_accessed": file_name_time(entry, |t| t.accessed).map(|d| d.to_rfc3339()),
+        "fn_changed": file_name_time(entry, |t| t.changed).map(|d| d.to_rfc3339()),
+        "fn_created": file_name_time(entry, |t| t.created).map(|d| d.to_rfc3339()),
+        "owner": entry.security.as_ref().and_then(|s| s.owner.as_deref()),
+        "group": entry.security.as_ref().and_then(|s| s.group.as_deref()),
+        "acl": entry.security.as_ref().map(|s| s.acl_summary()),
+        "mime": entry.file_type.map(|t| t.mime()),
+        "kind": entry.file_type.map(|t| t.kind().name()),
+    })
+}
 
-    writeln!(file, "{}", serde_json::to_string_pretty(&output)?)?;
+/// Read back an entry written by `entry_json` (e.g. received from the indexer)
+pub fn entry_from_json(value: &Value) -> Option<FileEntry> {
+    let text = |key: &str| value.get(key).and_then(Value::as_str);
+    let number = |key: &str| value.get(key).and_then(Value::as_u64);
+    let time = |key: &str| {
+        text(key)
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|d| d.with_timezone(&Utc))
+    };
 
-    Ok(())
+    let mut entry = FileEntry::new(
+        text("name")?.to_string(),
+        text("path")?.to_string(),
+        text("type")? == "directory",
+        number("file_id")?,
+        number("parent_id")?,
+        number("size")?,
+        time("modified"),
+        time("created"),
+        time("accessed"),
+    );
+    entry.changed = time("changed");
+    let file_name_times = Timestamps {
+        modified: time("fn_modified"),
+        accessed: time("fn_accessed"),
+        changed: time("fn_changed"),
+        created: time("fn_created"),
+    };
+    if file_name_times != Timestamps::default() {
+        entry.file_name_times = Some(Box::new(file_name_times));
+    }
+    if let Some(acl) = text("acl") {
+        entry.security = Some(Arc::new(Security {
+            owner: text("owner").map(str::to_string),
+            group: text("group").map(str::to_string),
+            acl: parse_acl(acl).ok()?,
+        }));
+    }
+    entry.file_type = text("mime").and_then(FileType::from_mime);
+    Some(entry)
 }
 
-/// Escape CSV field (handle commas, quotes, newlines)
-fn escape_csv_field(s: &str) -> String {
-    if s.contains(',') || s.contains('"') || s.contains('\n') {
+/// Escape CSV field (RFC 4180: quote fields with commas, quotes or line breaks)
+pub(crate) fn escape_csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
         format!("\"{}\"", s.replace('"', "\"\""))
     } else {
         s.to_string()
     }
 }
 
+/// Escape TSV field (backslashes become `\\`, then tabs and line breaks `\t`, `\n` and `\r`)
+pub(crate) fn escape_tsv_field(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r")
+}
+
 /// Format file size in human-readable format
-fn format_file_size(bytes: u64) -> String {
+pub(crate) fn format_file_size(bytes: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
     let mut size = bytes as f64;
     let mut unit_idx = 0;
@@ -113,6 +657,92 @@ mod tests {
         assert_eq!(escape_csv_field("normal"), "normal");
         assert_eq!(escape_csv_field("has,comma"), "\"has,comma\"");
         assert_eq!(escape_csv_field("has\"quote"), "\"has\"\"quote\"");
+        assert_eq!(escape_csv_field("has\rreturn"), "\"has\rreturn\"");
+        assert_eq!(escape_tsv_field("tab\there\n"), "tab\\there\\n");
+        assert_eq!(escape_tsv_field("C:\\temp\\new.txt"), "C:\\\\temp\\\\new.txt");
+
+        // What the TSV export writes, the importer reads back unchanged
+        let mut index = crate::index::FileIndex::new();
+        for path in ["C:\\temp\\new.txt", "\\\\server\\share"] {
+            index.add_entry(FileEntry::new(String::new(), path.to_string(), false, 1, 0, 0, None, None, None));
+        }
+        let mut out = Vec::new();
+        let mut exporter = ExportFormat::Tsv.exporter(&mut out, &[Column::Path, Column::Size], "");
+        export_index(&index, &SearchFilters::default(), exporter.as_mut()).unwrap();
+        exporter.finish().unwrap();
+        drop(exporter);
+        let imported = crate::import::parse_listing(out.as_slice(), crate::import::ImportFormat::Delimited).unwrap();
+        let paths: Vec<&str> = imported.entries().iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["C:\\temp\\new.txt", "\\\\server\\share"]);
+    }
+
+    #[test]
+    fn test_exporters() {
+        let mut index = crate::index::FileIndex::new();
+        for (id, name, size) in [(1, "a,b.txt", 10), (2, "big.iso", 5000), (3, "tab\tname", 0)] {
+            index.add_entry(FileEntry::new(name.to_string(), format!("/d/{}", name), false, id, 0, size, None, None, None));
+        }
+        let export = |format: ExportFormat, columns: &[Column], filters: &SearchFilters| {
+            let mut out = Vec::new();
+            let mut exporter = format.exporter(&mut out, columns, "");
+            let count = export_index(&index, filters, exporter.as_mut()).unwrap();
+            exporter.finish().unwrap();
+            drop(exporter);
+            (count, String::from_utf8(out).unwrap())
+        };
+        let all = SearchFilters::default();
+        let columns = parse_columns("path, size").unwrap();
+
+        let (count, csv) = export(ExportFormat::Csv, &columns, &all);
+        assert_eq!(count, 3);
+        assert_eq!(csv, "Path,Size (bytes)\r\n\"/d/a,b.txt\",10\r\n/d/big.iso,5000\r\n/d/tab\tname,0\r\n");
+
+        let (_, tsv) = export(ExportFormat::Tsv, &columns, &all);
+        assert_eq!(tsv.lines().nth(3), Some("/d/tab\\tname\t0"));
+
+        let (_, ndjson) = export(ExportFormat::Ndjson, &columns, &SearchFilters::parse_filter_string("size:>1kb").unwrap());
+        assert_eq!(ndjson, "{\"path\":\"/d/big.iso\",\"size\":5000}\n");
+
+        let (_, nul) = export(ExportFormat::Nul, &columns, &all);
+        assert_eq!(nul.split('\0').count(), 4);
+
+        // The streamed JSON document is valid, also when empty
+        let (_, json) = export(ExportFormat::Json, Column::ALL, &all);
+        let json: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["total_results"], 3);
+        assert_eq!(json["results"][1]["file_id"], 2);
+        let (_, json) = export(ExportFormat::Json, Column::ALL, &SearchFilters::parse_filter_string("ext:pdf").unwrap());
+        assert_eq!(serde_json::from_str::<Value>(&json).unwrap()["total_results"], 0);
+
+        assert!(parse_columns("path,colour").is_err());
+        assert_eq!("null-separated".parse::<ExportFormat>().unwrap(), ExportFormat::Nul);
+    }
+
+    #[test]
+    fn test_entry_json_round_trip() {
+        let modified = DateTime::parse_from_rfc3339("2024-05-01T10:30:00.250Z").unwrap().with_timezone(&Utc);
+        let entry = FileEntry::new(
+            "a, b.txt".to_string(),
+            "/data/a, b.txt".to_string(),
+            false,
+            42,
+            7,
+            1234,
+            Some(modified),
+            None,
+            None,
+        );
+
+        let json = entry_json(&entry, Some(90));
+        assert_eq!(json["type"], "file");
+        assert_eq!(json["score"], 90);
+
+        let back = entry_from_json(&json).unwrap();
+        assert_eq!((back.name.as_str(), back.path.as_str()), ("a, b.txt", "/data/a, b.txt"));
+        assert_eq!((back.file_id, back.parent_id, back.size), (42, 7, 1234));
+        assert_eq!(back.modified, Some(modified));
+        assert_eq!(back.created, None);
+        assert!(!back.is_directory);
     }
 
     #[test]
@@ -1,5 +1,9 @@
+use crate::archive;
+use crate::file_type::{extension_of, FileKind, FileType};
+use crate::security::Security;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 /// Represents a file or directory entry from the MFT
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +34,51 @@ pub struct FileEntry {
 
     /// Last accessed timestamp
     pub accessed: Option<DateTime<Utc>>,
+
+    /// Metadata change timestamp (MFT record change on NTFS, ctime elsewhere)
+    #[serde(default)]
+    pub changed: Option<DateTime<Utc>>,
+
+    /// Timestamps of the `$FILE_NAME` attribute (NTFS scans only)
+    #[serde(default)]
+    pub file_name_times: Option<Box<Timestamps>>,
+
+    /// Owner, group and access summary (shared by entries with the same descriptor)
+    #[serde(default)]
+    pub security: Option<Arc<Security>>,
+
+    /// Type detected from the file's first bytes (files only, after a detection pass)
+    #[serde(default)]
+    pub file_type: Option<FileType>,
+
+    /// Whether an archive pass opened this file, whatever it found (cleared
+    /// when the file changes, so the next pass lists it again)
+    #[serde(default)]
+    pub members_listed: bool,
+}
+
+/// The four timestamps NTFS keeps in each of `$STANDARD_INFORMATION` and
+/// `$FILE_NAME`, in MACB order
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timestamps {
+    /// Content last modified (M)
+    pub modified: Option<DateTime<Utc>>,
+
+    /// Last accessed (A)
+    pub accessed: Option<DateTime<Utc>>,
+
+    /// Metadata last changed (C)
+    pub changed: Option<DateTime<Utc>>,
+
+    /// Created, or "born" (B)
+    pub created: Option<DateTime<Utc>>,
+}
+
+impl Timestamps {
+    /// The timestamps in MACB order
+    pub fn macb(&self) -> [Option<DateTime<Utc>>; 4] {
+        [self.modified, self.accessed, self.changed, self.created]
+    }
 }
 
 impl FileEntry {
@@ -56,6 +105,78 @@ impl FileEntry {
             modified,
             created,
             accessed,
+            changed: None,
+            file_name_times: None,
+            security: None,
+            file_type: None,
+            members_listed: false,
+        }
+    }
+
+    /// The `$STANDARD_INFORMATION` timestamps (or the filesystem's, off NTFS)
+    pub fn times(&self) -> Timestamps {
+        Timestamps {
+            modified: self.modified,
+            accessed: self.accessed,
+            changed: self.changed,
+            created: self.created,
+        }
+    }
+
+    /// Whether a `$STANDARD_INFORMATION` timestamp is earlier than its
+    /// `$FILE_NAME` counterpart
+    ///
+    /// Windows only sets `$FILE_NAME` times when a file is created, renamed or
+    /// moved, and tools that backdate files usually rewrite only the standard
+    /// ones, so this is the classic trace of timestomping. Archive extractors
+    /// that restore modification times produce it too.
+    pub fn is_timestomped(&self) -> bool {
+        let Some(file_name) = &self.file_name_times else {
+            return false;
+        };
+        self.times()
+            .macb()
+            .into_iter()
+            .zip(file_name.macb())
+            .any(|pair| matches!(pair, (Some(standard), Some(file_name)) if standard < file_name))
+    }
+
+    /// Lowercase extension of the name
+    pub fn extension(&self) -> Option<String> {
+        extension_of(&self.name)
+    }
+
+    /// The detected type, or for files never sniffed the type their extension implies
+    pub fn type_or_guess(&self) -> Option<FileType> {
+        if self.is_directory {
+            return None;
+        }
+        self.file_type.or_else(|| FileType::from_extension(&self.extension()?))
+    }
+
+    /// Broad kind of the file (see `type_or_guess`)
+    pub fn kind(&self) -> Option<FileKind> {
+        self.type_or_guess().map(FileType::kind)
+    }
+
+    /// Whether the detected type does not fit the extension (a `.txt` that is a ZIP)
+    pub fn has_extension_mismatch(&self) -> bool {
+        match (self.file_type, self.extension()) {
+            (Some(file_type), Some(extension)) => file_type.mismatches(&extension),
+            _ => false,
+        }
+    }
+
+    /// Whether this is a member of an archive (no file of its own on disk)
+    pub fn is_archive_member(&self) -> bool {
+        archive::is_member(self.file_id, &self.path)
+    }
+
+    /// Change the full path, keeping `name` in sync with its last component
+    pub fn set_path(&mut self, path: String) {
+        if let Some(name) = path.rsplit(['\\', '/']).next() {
+            self.name = name.to_string();
         }
+        self.path = path;
     }
 }
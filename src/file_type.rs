@@ -0,0 +1,698 @@
+// File types detected from the first bytes of files ("magic bytes"), so
+// filters can see through wrong or missing extensions
+use crate::file_entry::FileEntry;
+use crate::index::FileIndex;
+use anyhow::{anyhow, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Bytes read from each file (a TAR header ends at 262, ODF's mimetype entry before 100)
+pub const SNIFF_LEN: usize = 1024;
+
+/// What a file is, broadly (`kind:` filter)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileKind {
+    Image,
+    Video,
+    Audio,
+    Document,
+    Archive,
+    Executable,
+    Font,
+    Text,
+    Other,
+}
+
+impl FileKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            FileKind::Image => "image",
+            FileKind::Video => "video",
+            FileKind::Audio => "audio",
+            FileKind::Document => "document",
+            FileKind::Archive => "archive",
+            FileKind::Executable => "executable",
+            FileKind::Font => "font",
+            FileKind::Text => "text",
+            FileKind::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for FileKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for FileKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "image" | "images" | "picture" | "pictures" => Ok(FileKind::Image),
+            "video" | "videos" | "movie" | "movies" => Ok(FileKind::Video),
+            "audio" | "music" | "sound" => Ok(FileKind::Audio),
+            "document" | "documents" | "doc" | "docs" => Ok(FileKind::Document),
+            "archive" | "archives" | "compressed" => Ok(FileKind::Archive),
+            "executable" | "executables" | "exe" | "program" | "binary" => Ok(FileKind::Executable),
+            "font" | "fonts" => Ok(FileKind::Font),
+            "text" => Ok(FileKind::Text),
+            "other" => Ok(FileKind::Other),
+            _ => Err(anyhow!(
+                "Invalid kind: {}. Use image, video, audio, document, archive, executable, font, text or other",
+                s
+            )),
+        }
+    }
+}
+
+/// A detected file type
+///
+/// The numbers are stored in index files and must not change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum FileType {
+    /// Binary data of no known type
+    Unknown = 0,
+    /// No binary data in the first bytes
+    Text = 1,
+    Png = 2,
+    Jpeg = 3,
+    Gif = 4,
+    Bmp = 5,
+    Webp = 6,
+    Tiff = 7,
+    Ico = 8,
+    Heic = 9,
+    Psd = 10,
+    Pdf = 11,
+    /// OLE compound file: Office 97-2003 documents, MSI, Outlook messages
+    Ole = 12,
+    Rtf = 13,
+    /// ZIP with an Office Open XML document (docx, xlsx, pptx)
+    OfficeOpenXml = 14,
+    /// ZIP with an OpenDocument file (odt, ods, odp)
+    OpenDocument = 15,
+    Epub = 16,
+    Zip = 17,
+    Gzip = 18,
+    Bzip2 = 19,
+    Xz = 20,
+    Zstd = 21,
+    SevenZip = 22,
+    Rar = 23,
+    Tar = 24,
+    Cab = 25,
+    /// Windows PE (and DOS) executable
+    Exe = 26,
+    Elf = 27,
+    MachO = 28,
+    Wasm = 29,
+    Mp3 = 30,
+    Flac = 31,
+    Ogg = 32,
+    Wav = 33,
+    Midi = 34,
+    M4a = 35,
+    Mp4 = 36,
+    QuickTime = 37,
+    Matroska = 38,
+    Webm = 39,
+    Avi = 40,
+    Flv = 41,
+    /// Windows Media (wmv, wma)
+    Asf = 42,
+    Sqlite = 43,
+    Woff = 44,
+    Woff2 = 45,
+    TrueType = 46,
+    OpenType = 47,
+}
+
+impl FileType {
+    /// Every type, in code order
+    pub const ALL: [FileType; 48] = [
+        FileType::Unknown,
+        FileType::Text,
+        FileType::Png,
+        FileType::Jpeg,
+        FileType::Gif,
+        FileType::Bmp,
+        FileType::Webp,
+        FileType::Tiff,
+        FileType::Ico,
+        FileType::Heic,
+        FileType::Psd,
+        FileType::Pdf,
+        FileType::Ole,
+        FileType::Rtf,
+        FileType::OfficeOpenXml,
+        FileType::OpenDocument,
+        FileType::Epub,
+        FileType::Zip,
+        FileType::Gzip,
+        FileType::Bzip2,
+        FileType::Xz,
+        FileType::Zstd,
+        FileType::SevenZip,
+        FileType::Rar,
+        FileType::Tar,
+        FileType::Cab,
+        FileType::Exe,
+        FileType::Elf,
+        FileType::MachO,
+        FileType::Wasm,
+        FileType::Mp3,
+        FileType::Flac,
+        FileType::Ogg,
+        FileType::Wav,
+        FileType::Midi,
+        FileType::M4a,
+        FileType::Mp4,
+        FileType::QuickTime,
+        FileType::Matroska,
+        FileType::Webm,
+        FileType::Avi,
+        FileType::Flv,
+        FileType::Asf,
+        FileType::Sqlite,
+        FileType::Woff,
+        FileType::Woff2,
+        FileType::TrueType,
+        FileType::OpenType,
+    ];
+
+    /// Number stored in index files
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        Self::ALL.get(code as usize).copied()
+    }
+
+    /// MIME type, kind and the extensions files of this type usually have
+    fn info(self) -> (&'static str, FileKind, &'static [&'static str]) {
+        use FileKind::*;
+        match self {
+            FileType::Unknown => ("application/octet-stream", Other, &[]),
+            FileType::Text => ("text/plain", Text, &[]),
+            FileType::Png => ("image/png", Image, &["png", "apng"]),
+            FileType::Jpeg => ("image/jpeg", Image, &["jpg", "jpeg", "jpe", "jfif"]),
+            FileType::Gif => ("image/gif", Image, &["gif"]),
+            FileType::Bmp => ("image/bmp", Image, &["bmp", "dib"]),
+            FileType::Webp => ("image/webp", Image, &["webp"]),
+            FileType::Tiff => ("image/tiff", Image, &["tif", "tiff", "dng", "cr2", "nef", "arw", "orf", "rw2", "pef"]),
+            FileType::Ico => ("image/vnd.microsoft.icon", Image, &["ico", "cur"]),
+            FileType::Heic => ("image/heic", Image, &["heic", "heif", "avif"]),
+            FileType::Psd => ("image/vnd.adobe.photoshop", Image, &["psd", "psb"]),
+            FileType::Pdf => ("application/pdf", Document, &["pdf", "ai"]),
+            FileType::Ole => (
+                "application/x-ole-storage",
+                Document,
+                &["doc", "dot", "xls", "xlt", "ppt", "pps", "pot", "msi", "msp", "msg", "vsd", "pub", "mpp", "db"],
+            ),
+            FileType::Rtf => ("application/rtf", Document, &["rtf", "doc"]),
+            FileType::OfficeOpenXml => (
+                "application/vnd.openxmlformats-officedocument",
+                Document,
+                &["docx", "docm", "dotx", "dotm", "xlsx", "xlsm", "xltx", "xltm", "pptx", "pptm", "potx", "ppsx", "vsdx"],
+            ),
+            FileType::OpenDocument => (
+                "application/vnd.oasis.opendocument",
+                Document,
+                &["odt", "ods", "odp", "odg", "odf", "ott", "ots", "otp"],
+            ),
+            FileType::Epub => ("application/epub+zip", Document, &["epub"]),
+            FileType::Zip => (
+                "application/zip",
+                Archive,
+                &[
+                    "zip", "jar", "war", "ear", "apk", "aab", "aar", "ipa", "xpi", "crx", "vsix", "nupkg", "whl", "egg", "appx",
+                    "msix", "kmz", "3mf", "cbz", "docx", "xlsx", "pptx", "odt", "ods", "odp", "epub", "xps", "oxps",
+                ],
+            ),
+            FileType::Gzip => ("application/gzip", Archive, &["gz", "tgz", "svgz"]),
+            FileType::Bzip2 => ("application/x-bzip2", Archive, &["bz2", "tbz", "tbz2"]),
+            FileType::Xz => ("application/x-xz", Archive, &["xz", "txz"]),
+            FileType::Zstd => ("application/zstd", Archive, &["zst", "tzst"]),
+            FileType::SevenZip => ("application/x-7z-compressed", Archive, &["7z"]),
+            FileType::Rar => ("application/vnd.rar", Archive, &["rar", "cbr"]),
+            FileType::Tar => ("application/x-tar", Archive, &["tar"]),
+            FileType::Cab => ("application/vnd.ms-cab-compressed", Archive, &["cab"]),
+            FileType::Exe => (
+                "application/vnd.microsoft.portable-executable",
+                Executable,
+                &["exe", "dll", "sys", "scr", "ocx", "cpl", "efi", "com", "drv", "mui", "ax", "node", "pyd"],
+            ),
+            FileType::Elf => ("application/x-elf", Executable, &["so", "o", "ko", "elf", "bin", "node"]),
+            FileType::MachO => ("application/x-mach-binary", Executable, &["dylib", "bundle", "o", "node"]),
+            FileType::Wasm => ("application/wasm", Executable, &["wasm"]),
+            FileType::Mp3 => ("audio/mpeg", Audio, &["mp3", "mp2"]),
+            FileType::Flac => ("audio/flac", Audio, &["flac"]),
+            FileType::Ogg => ("audio/ogg", Audio, &["ogg", "oga", "opus", "ogv", "spx"]),
+            FileType::Wav => ("audio/wav", Audio, &["wav"]),
+            FileType::Midi => ("audio/midi", Audio, &["mid", "midi"]),
+            FileType::M4a => ("audio/mp4", Audio, &["m4a", "m4b", "m4r"]),
+            FileType::Mp4 => ("video/mp4", Video, &["mp4", "m4v", "3gp", "3g2", "mov", "f4v"]),
+            FileType::QuickTime => ("video/quicktime", Video, &["mov", "qt"]),
+            FileType::Matroska => ("video/x-matroska", Video, &["mkv", "mka", "mk3d", "webm"]),
+            FileType::Webm => ("video/webm", Video, &["webm", "weba"]),
+            FileType::Avi => ("video/x-msvideo", Video, &["avi"]),
+            FileType::Flv => ("video/x-flv", Video, &["flv"]),
+            FileType::Asf => ("video/x-ms-asf", Video, &["wmv", "wma", "asf"]),
+            FileType::Sqlite => ("application/vnd.sqlite3", Other, &["sqlite", "sqlite3", "db", "db3", "sdb"]),
+            FileType::Woff => ("font/woff", Font, &["woff"]),
+            FileType::Woff2 => ("font/woff2", Font, &["woff2"]),
+            FileType::TrueType => ("font/ttf", Font, &["ttf", "ttc", "tte"]),
+            FileType::OpenType => ("font/otf", Font, &["otf"]),
+        }
+    }
+
+    pub fn mime(self) -> &'static str {
+        self.info().0
+    }
+
+    pub fn kind(self) -> FileKind {
+        self.info().1
+    }
+
+    /// Type usually meant by `extension` (lowercase)
+    ///
+    /// Extensions several types use (`doc`, `db`, `mov`, `webm`, `o`, ...)
+    /// map to the one type they most often are.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        let file_type = match extension {
+            "png" | "apng" => FileType::Png,
+            "jpg" | "jpeg" | "jpe" | "jfif" => FileType::Jpeg,
+            "gif" => FileType::Gif,
+            "bmp" | "dib" => FileType::Bmp,
+            "webp" => FileType::Webp,
+            "tif" | "tiff" | "dng" | "cr2" | "nef" | "arw" | "orf" | "rw2" | "pef" => FileType::Tiff,
+            "ico" | "cur" => FileType::Ico,
+            "heic" | "heif" | "avif" => FileType::Heic,
+            "psd" | "psb" => FileType::Psd,
+            "pdf" | "ai" => FileType::Pdf,
+            "doc" | "dot" | "xls" | "xlt" | "ppt" | "pps" | "pot" | "msi" | "msp" | "msg" | "vsd" | "pub" | "mpp" => FileType::Ole,
+            "rtf" => FileType::Rtf,
+            "docx" | "docm" | "dotx" | "dotm" | "xlsx" | "xlsm" | "xltx" | "xltm" | "pptx" | "pptm" | "potx"
+            | "ppsx" | "vsdx" => FileType::OfficeOpenXml,
+            "odt" | "ods" | "odp" | "odg" | "odf" | "ott" | "ots" | "otp" => FileType::OpenDocument,
+            "epub" => FileType::Epub,
+            "zip" | "jar" | "war" | "ear" | "apk" | "aab" | "aar" | "ipa" | "xpi" | "crx" | "vsix" | "nupkg"
+            | "whl" | "egg" | "appx" | "msix" | "kmz" | "3mf" | "cbz" | "xps" | "oxps" => FileType::Zip,
+            "gz" | "tgz" | "svgz" => FileType::Gzip,
+            "bz2" | "tbz" | "tbz2" => FileType::Bzip2,
+            "xz" | "txz" => FileType::Xz,
+            "zst" | "tzst" => FileType::Zstd,
+            "7z" => FileType::SevenZip,
+            "rar" | "cbr" => FileType::Rar,
+            "tar" => FileType::Tar,
+            "cab" => FileType::Cab,
+            "exe" | "dll" | "sys" | "scr" | "ocx" | "cpl" | "efi" | "com" | "drv" | "mui" | "ax" | "node" | "pyd" => FileType::Exe,
+            "so" | "o" | "ko" | "elf" | "bin" => FileType::Elf,
+            "dylib" | "bundle" => FileType::MachO,
+            "wasm" => FileType::Wasm,
+            "mp3" | "mp2" => FileType::Mp3,
+            "flac" => FileType::Flac,
+            "ogg" | "oga" | "opus" | "ogv" | "spx" => FileType::Ogg,
+            "wav" => FileType::Wav,
+            "mid" | "midi" => FileType::Midi,
+            "m4a" | "m4b" | "m4r" => FileType::M4a,
+            "mp4" | "m4v" | "3gp" | "3g2" | "f4v" => FileType::Mp4,
+            "mov" | "qt" => FileType::QuickTime,
+            "mkv" | "mka" | "mk3d" => FileType::Matroska,
+            "webm" | "weba" => FileType::Webm,
+            "avi" => FileType::Avi,
+            "flv" => FileType::Flv,
+            "wmv" | "wma" | "asf" => FileType::Asf,
+            "sqlite" | "sqlite3" | "db" | "db3" | "sdb" => FileType::Sqlite,
+            "woff" => FileType::Woff,
+            "woff2" => FileType::Woff2,
+            "ttf" | "ttc" | "tte" => FileType::TrueType,
+            "otf" => FileType::OpenType,
+            _ => return None,
+        };
+        Some(file_type)
+    }
+
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.mime().eq_ignore_ascii_case(mime))
+    }
+
+    /// Whether a file of this type is unexpected under `extension` (lowercase)
+    ///
+    /// Only recognized types are compared. Text only counts as a mismatch
+    /// under an extension of a binary type (an `.exe` that is text).
+    pub fn mismatches(self, extension: &str) -> bool {
+        match self {
+            FileType::Unknown => false,
+            FileType::Text => Self::from_extension(extension).is_some(),
+            _ => !self.info().2.contains(&extension),
+        }
+    }
+
+    /// Type of a file, from its first bytes (at least `SNIFF_LEN` of them if
+    /// the file is that long); `None` for empty files
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.is_empty() {
+            return None;
+        }
+        let starts = |magic: &[u8]| bytes.starts_with(magic);
+        let at = |offset: usize, magic: &[u8]| bytes.get(offset..offset + magic.len()) == Some(magic);
+
+        let detected = if starts(b"\x89PNG\r\n\x1a\n") {
+            FileType::Png
+        } else if starts(b"\xFF\xD8\xFF") {
+            FileType::Jpeg
+        } else if starts(b"GIF87a") || starts(b"GIF89a") {
+            FileType::Gif
+        } else if starts(b"BM") && bytes.len() >= 26 && at(6, b"\0\0\0\0") {
+            FileType::Bmp
+        } else if starts(b"RIFF") && at(8, b"WEBP") {
+            FileType::Webp
+        } else if starts(b"RIFF") && at(8, b"WAVE") {
+            FileType::Wav
+        } else if starts(b"RIFF") && at(8, b"AVI ") {
+            FileType::Avi
+        } else if starts(b"II*\0") || starts(b"MM\0*") {
+            FileType::Tiff
+        } else if starts(b"\0\0\x01\0") || starts(b"\0\0\x02\0") {
+            FileType::Ico
+        } else if starts(b"8BPS") {
+            FileType::Psd
+        } else if starts(b"%PDF-") {
+            FileType::Pdf
+        } else if starts(b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1") {
+            FileType::Ole
+        } else if starts(b"{\\rtf") {
+            FileType::Rtf
+        } else if starts(b"PK\x03\x04") {
+            zip_type(bytes)
+        } else if starts(b"PK\x05\x06") || starts(b"PK\x07\x08") {
+            FileType::Zip
+        } else if starts(b"\x1F\x8B") {
+            FileType::Gzip
+        } else if starts(b"BZh") {
+            FileType::Bzip2
+        } else if starts(b"\xFD7zXZ\0") {
+            FileType::Xz
+        } else if starts(b"\x28\xB5\x2F\xFD") {
+            FileType::Zstd
+        } else if starts(b"7z\xBC\xAF\x27\x1C") {
+            FileType::SevenZip
+        } else if starts(b"Rar!\x1A\x07") {
+            FileType::Rar
+        } else if at(257, b"ustar") {
+            FileType::Tar
+        } else if starts(b"MSCF") {
+            FileType::Cab
+        } else if starts(b"MZ") {
+            FileType::Exe
+        } else if starts(b"\x7FELF") {
+            FileType::Elf
+        } else if [b"\xFE\xED\xFA\xCE", b"\xFE\xED\xFA\xCF", b"\xCE\xFA\xED\xFE", b"\xCF\xFA\xED\xFE", b"\xCA\xFE\xBA\xBE"]
+            .iter()
+            .any(|magic| starts(*magic))
+        {
+            FileType::MachO
+        } else if starts(b"\0asm") {
+            FileType::Wasm
+        } else if starts(b"ID3") || (bytes.len() >= 2 && bytes[0] == 0xFF && matches!(bytes[1], 0xFB | 0xF3 | 0xF2)) {
+            FileType::Mp3
+        } else if starts(b"fLaC") {
+            FileType::Flac
+        } else if starts(b"OggS") {
+            FileType::Ogg
+        } else if starts(b"MThd") {
+            FileType::Midi
+        } else if at(4, b"ftyp") {
+            iso_media_type(bytes.get(8..12).unwrap_or_default())
+        } else if at(4, b"moov") || at(4, b"mdat") || at(4, b"wide") {
+            FileType::QuickTime
+        } else if starts(b"\x1A\x45\xDF\xA3") {
+            if contains(bytes, b"webm") {
+                FileType::Webm
+            } else {
+                FileType::Matroska
+            }
+        } else if starts(b"FLV\x01") {
+            FileType::Flv
+        } else if starts(b"\x30\x26\xB2\x75\x8E\x66\xCF\x11") {
+            FileType::Asf
+        } else if starts(b"SQLite format 3\0") {
+            FileType::Sqlite
+        } else if starts(b"wOFF") {
+            FileType::Woff
+        } else if starts(b"wOF2") {
+            FileType::Woff2
+        } else if ((starts(b"\0\x01\0\0") || starts(b"true")) && is_sfnt(bytes))
+            || (starts(b"ttcf") && (at(4, b"\0\x01\0\0") || at(4, b"\0\x02\0\0")))
+        {
+            FileType::TrueType
+        } else if starts(b"OTTO") && is_sfnt(bytes) {
+            FileType::OpenType
+        } else if is_text(bytes) {
+            FileType::Text
+        } else {
+            FileType::Unknown
+        };
+        Some(detected)
+    }
+
+    /// Type of the file at `path`, from its first `SNIFF_LEN` bytes
+    pub fn sniff(path: &Path) -> io::Result<Option<Self>> {
+        let mut bytes = Vec::with_capacity(SNIFF_LEN);
+        File::open(path)?.take(SNIFF_LEN as u64).read_to_end(&mut bytes)?;
+        Ok(Self::detect(&bytes))
+    }
+}
+
+impl fmt::Display for FileType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.mime())
+    }
+}
+
+/// A ZIP file by the name (and, for ODF and EPUB, the content) of its first member
+fn zip_type(bytes: &[u8]) -> FileType {
+    let name_len = bytes.get(26..28).map_or(0, |len| u16::from_le_bytes([len[0], len[1]]) as usize);
+    let Some(name) = bytes.get(30..30 + name_len) else {
+        return FileType::Zip;
+    };
+    match name {
+        b"[Content_Types].xml" | b"_rels/.rels" => FileType::OfficeOpenXml,
+        name if name.starts_with(b"word/") || name.starts_with(b"xl/") || name.starts_with(b"ppt/") => FileType::OfficeOpenXml,
+        b"mimetype" => {
+            // Stored uncompressed right after the header, as ODF and EPUB require
+            let content = &bytes[(30 + name_len).min(bytes.len())..];
+            if content.starts_with(b"application/epub+zip") {
+                FileType::Epub
+            } else if content.starts_with(b"application/vnd.oasis.opendocument") {
+                FileType::OpenDocument
+            } else {
+                FileType::Zip
+            }
+        }
+        _ => FileType::Zip,
+    }
+}
+
+/// An ISO base media file (MP4 family) by its major brand
+fn iso_media_type(brand: &[u8]) -> FileType {
+    match brand {
+        b"M4A " | b"M4B " | b"M4P " | b"F4A " => FileType::M4a,
+        b"qt  " => FileType::QuickTime,
+        b"heic" | b"heix" | b"hevc" | b"heim" | b"heis" | b"mif1" | b"msf1" | b"avif" => FileType::Heic,
+        _ => FileType::Mp4,
+    }
+}
+
+/// Whether the bytes start with a plausible sfnt font header: a table count
+/// the search range agrees with, and a table directory of printable tags
+fn is_sfnt(bytes: &[u8]) -> bool {
+    let u16_at = |offset: usize| bytes.get(offset..offset + 2).map(|b| u16::from_be_bytes([b[0], b[1]]));
+    let (Some(tables), Some(search_range)) = (u16_at(4), u16_at(6)) else {
+        return false;
+    };
+    if tables == 0 || tables > 256 || search_range as u32 != 16 << tables.ilog2() {
+        return false;
+    }
+    bytes
+        .get(12..)
+        .unwrap_or_default()
+        .chunks_exact(16)
+        .take(tables as usize)
+        .all(|record| record[..4].iter().all(|&b| b == b' ' || b.is_ascii_alphanumeric() || b == b'/'))
+}
+
+fn contains(bytes: &[u8], needle: &[u8]) -> bool {
+    bytes.windows(needle.len()).any(|window| window == needle)
+}
+
+/// Whether the bytes read like text: a UTF-16 byte order mark, or no zero
+/// bytes and hardly any other control characters
+fn is_text(bytes: &[u8]) -> bool {
+    if bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]) {
+        return true;
+    }
+    if bytes.contains(&0) {
+        return false;
+    }
+    let control = bytes
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B))
+        .count();
+    control * 100 <= bytes.len()
+}
+
+/// Lowercase extension of a file name (`None` without one, or for dot files like `.bashrc`)
+pub fn extension_of(name: &str) -> Option<String> {
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() && !extension.is_empty() => Some(extension.to_lowercase()),
+        _ => None,
+    }
+}
+
+/// What a type detection pass did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DetectStats {
+    /// Files whose type was detected
+    pub detected: usize,
+
+    /// Detected files whose extension does not fit their type
+    pub mismatched: usize,
+
+    /// Files that could not be read (or were empty)
+    pub failed: usize,
+}
+
+impl fmt::Display for DetectStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} files typed, {} with a mismatched extension, {} unreadable or empty",
+            self.detected, self.mismatched, self.failed
+        )
+    }
+}
+
+/// Read the first bytes of every file of an index without a detected type
+/// and record its type
+///
+/// Files keep their type until they are rescanned; files the monitor adds
+/// have none until the next pass.
+pub fn detect_types(index: &mut FileIndex) -> DetectStats {
+    let detected: Vec<(usize, Option<FileType>)> = index
+        .entries()
+        .par_iter()
+        .enumerate()
+        .filter(|(_, entry)| !entry.is_directory && entry.file_type.is_none() && !entry.is_archive_member())
+        .map(|(i, entry)| (i, FileType::sniff(Path::new(&entry.path)).ok().flatten()))
+        .collect();
+
+    let mut stats = DetectStats::default();
+    let entries = index.entries_mut();
+    for (i, file_type) in detected {
+        let Some(file_type) = file_type else {
+            stats.failed += 1;
+            continue;
+        };
+        let entry: &mut FileEntry = &mut entries[i];
+        entry.file_type = Some(file_type);
+        stats.detected += 1;
+        stats.mismatched += entry.has_extension_mismatch() as usize;
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let mut tar = vec![0u8; 512];
+        tar[..8].copy_from_slice(b"file.txt");
+        tar[257..262].copy_from_slice(b"ustar");
+        let mut docx = b"PK\x03\x04".to_vec();
+        docx.extend_from_slice(&[0; 22]);
+        docx.extend_from_slice(&19u16.to_le_bytes());
+        docx.extend_from_slice(&[0, 0]);
+        docx.extend_from_slice(b"[Content_Types].xml");
+
+        let mut ttf = b"true\0\x02\0\x20\0\x01\0\0".to_vec();
+        for tag in [b"cmap", b"glyf"] {
+            ttf.extend_from_slice(tag);
+            ttf.extend_from_slice(&[0; 12]);
+        }
+
+        let cases: [(&[u8], FileType); 12] = [
+            (b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", FileType::Png),
+            (b"%PDF-1.7\n%\xE2\xE3", FileType::Pdf),
+            (b"PK\x03\x04\x14\0\0\0\x08\0", FileType::Zip),
+            (&docx, FileType::OfficeOpenXml),
+            (&tar, FileType::Tar),
+            (b"\0\0\0\x20ftypisom\0\0\x02\0", FileType::Mp4),
+            (b"MZ\x90\0\x03\0\0\0", FileType::Exe),
+            (b"# Notes\n\nPlain text, caf\xC3\xA9.\n", FileType::Text),
+            (b"\x01\x02\x03\0\xFF\xEE", FileType::Unknown),
+            (&ttf, FileType::TrueType),
+            (b"true or false, said the notes", FileType::Text),
+            (b"true\0\x01\0\x20", FileType::Unknown),
+        ];
+        for (bytes, expected) in cases {
+            assert_eq!(FileType::detect(bytes), Some(expected));
+        }
+        assert_eq!(FileType::detect(b""), None);
+
+        assert_eq!(FileType::Png.kind(), FileKind::Image);
+        assert_eq!(FileType::from_mime("application/PDF"), Some(FileType::Pdf));
+        assert_eq!(FileType::from_code(FileType::OpenType.code()), Some(FileType::OpenType));
+        assert!(FileType::Zip.mismatches("txt"));
+        assert!(!FileType::Zip.mismatches("docx"));
+        assert!(FileType::Text.mismatches("jpg"));
+        assert!(!FileType::Text.mismatches("md"));
+        assert!(!FileType::Unknown.mismatches("jpg"));
+        for (extension, expected) in [("mp4", FileType::Mp4), ("m4a", FileType::M4a), ("mov", FileType::QuickTime), ("docx", FileType::OfficeOpenXml), ("db", FileType::Sqlite)] {
+            assert_eq!(FileType::from_extension(extension), Some(expected));
+        }
+        assert!(FileType::M4a.mismatches("mp4"));
+        for file_type in FileType::ALL {
+            for extension in file_type.info().2 {
+                let canonical = FileType::from_extension(extension).unwrap();
+                assert!(!canonical.mismatches(extension), "{} maps to {:?}", extension, canonical);
+            }
+        }
+        assert_eq!(extension_of("archive.TAR.gz").as_deref(), Some("gz"));
+        assert_eq!(extension_of(".bashrc"), None);
+        assert_eq!("pictures".parse::<FileKind>().unwrap(), FileKind::Image);
+        assert!("blob".parse::<FileKind>().is_err());
+
+        // A pass over an index types each file once
+        let dir = std::env::temp_dir().join(format!("nothing_file_type_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut index = FileIndex::new();
+        for (id, (name, bytes)) in [("photo.jpg", &b"\xFF\xD8\xFF\xE0"[..]), ("notes.txt", b"PK\x05\x06"), ("empty.txt", b"")]
+            .into_iter()
+            .enumerate()
+        {
+            let path = dir.join(name);
+            std::fs::write(&path, bytes).unwrap();
+            index.add_entry(FileEntry::new(name.into(), path.to_string_lossy().into(), false, id as u64 + 1, 0, 0, None, None, None));
+        }
+        let stats = detect_types(&mut index);
+        assert_eq!(stats, DetectStats { detected: 2, mismatched: 1, failed: 1 });
+        assert_eq!(index.entries()[0].kind(), Some(FileKind::Image));
+        assert!(index.entries()[1].has_extension_mismatch());
+        assert_eq!(detect_types(&mut index), DetectStats { detected: 0, mismatched: 0, failed: 1 });
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,5 +1,7 @@
 use chrono::{DateTime, Duration, Utc};
 use crate::file_entry::FileEntry;
+use crate::file_type::FileKind;
+use crate::security::{trustee_name, trustee_id, AclFilter};
 use anyhow::{Result, anyhow};
 
 /// Search filters for advanced file searching
@@ -28,6 +30,32 @@ pub struct SearchFilters {
 
     /// Filter by type: Some(true) = dirs only, Some(false) = files only, None = both
     pub is_directory: Option<bool>,
+
+    /// Some(true) = only entries with a standard timestamp earlier than its
+    /// `$FILE_NAME` counterpart, Some(false) = only entries without one
+    pub timestomped: Option<bool>,
+
+    /// Owner SID (or uid) the entry must have
+    pub owner: Option<String>,
+
+    /// Rights a trustee must have on the entry (e.g. "everyone-write")
+    pub acl: Option<AclFilter>,
+
+    /// Kind of file, detected or (for files never sniffed) implied by the extension
+    pub kind: Option<FileKind>,
+
+    /// MIME type ("application/pdf"), or its top-level type ("image", "image/*")
+    pub mime: Option<String>,
+
+    /// Some(true) = only files whose detected type does not fit their
+    /// extension, Some(false) = only files where it does (or is unknown)
+    pub ext_mismatch: Option<bool>,
+
+    /// Text the file contents must contain (`content:` / `grep:`)
+    ///
+    /// Not checked by `matches`: the contents are read afterwards, only for
+    /// the entries that pass everything else (see `content::search`).
+    pub content: Option<String>,
 }
 
 impl SearchFilters {
@@ -39,8 +67,8 @@ impl SearchFilters {
     pub fn parse_filter_string(filter_str: &str) -> Result<Self> {
         let mut filters = Self::new();
 
-        // Split by spaces to get individual filters
-        for part in filter_str.split_whitespace() {
+        // Split by spaces (outside quotes) to get individual filters
+        for part in query_parts(filter_str) {
             if !part.contains(':') {
                 continue; // Not a filter, skip
             }
@@ -51,7 +79,7 @@ impl SearchFilters {
             }
 
             let key = parts[0].to_lowercase();
-            let value = parts[1];
+            let value = unquote(parts[1]);
 
             match key.as_str() {
                 "size" => {
@@ -69,6 +97,30 @@ impl SearchFilters {
                 "type" => {
                     filters.parse_type_filter(value)?;
                 }
+                "timestomp" | "timestomped" => {
+                    filters.parse_timestomp_filter(value)?;
+                }
+                "owner" => {
+                    filters.owner = Some(trustee_id(value));
+                }
+                "acl" => {
+                    filters.acl = Some(value.parse()?);
+                }
+                "kind" => {
+                    filters.kind = Some(value.parse()?);
+                }
+                "mime" => {
+                    filters.mime = Some(value.trim_end_matches("/*").to_lowercase());
+                }
+                "ext-mismatch" | "extmismatch" => {
+                    filters.ext_mismatch = Some(parse_yes_no("ext-mismatch", value)?);
+                }
+                "content" | "grep" => {
+                    if value.is_empty() {
+                        return Err(anyhow!("Empty content search. Use content:\"some text\""));
+                    }
+                    filters.content = Some(value.to_string());
+                }
                 _ => {} // Ignore unknown filters
             }
         }
@@ -169,6 +221,12 @@ impl SearchFilters {
         Ok(())
     }
 
+    /// Parse timestomp filter: "yes", "no"
+    fn parse_timestomp_filter(&mut self, value: &str) -> Result<()> {
+        self.timestomped = Some(parse_yes_no("timestomp", value)?);
+        Ok(())
+    }
+
     /// Check if a file entry matches all filters
     pub fn matches(&self, entry: &FileEntry) -> bool {
         // Size filters
@@ -246,6 +304,54 @@ impl SearchFilters {
             }
         }
 
+        if let Some(timestomped) = self.timestomped {
+            if entry.is_timestomped() != timestomped {
+                return false;
+            }
+        }
+
+        // Owner and ACL filters (entries without security information never match)
+        if let Some(owner) = &self.owner {
+            let entry_owner = entry.security.as_ref().and_then(|s| s.owner.as_deref());
+            if !entry_owner.is_some_and(|o| o.eq_ignore_ascii_case(owner)) {
+                return false;
+            }
+        }
+
+        if let Some(acl) = &self.acl {
+            if !entry.security.as_ref().is_some_and(|s| acl.matches(s)) {
+                return false;
+            }
+        }
+
+        // File type filters (directories and files of no known type never match)
+        if let Some(kind) = self.kind {
+            if entry.kind() != Some(kind) {
+                return false;
+            }
+        }
+
+        if let Some(mime) = &self.mime {
+            let Some(file_type) = entry.type_or_guess() else {
+                return false;
+            };
+            let entry_mime = file_type.mime();
+            let matches = if mime.contains('/') {
+                entry_mime.eq_ignore_ascii_case(mime)
+            } else {
+                entry_mime.split('/').next() == Some(mime.as_str())
+            };
+            if !matches {
+                return false;
+            }
+        }
+
+        if let Some(mismatch) = self.ext_mismatch {
+            if entry.is_directory || entry.has_extension_mismatch() != mismatch {
+                return false;
+            }
+        }
+
         true
     }
 
@@ -259,6 +365,13 @@ impl SearchFilters {
             && self.created_before.is_none()
             && self.extensions.is_empty()
             && self.is_directory.is_none()
+            && self.timestomped.is_none()
+            && self.owner.is_none()
+            && self.acl.is_none()
+            && self.kind.is_none()
+            && self.mime.is_none()
+            && self.ext_mismatch.is_none()
+            && self.content.is_none()
     }
 
     /// Get a human-readable description of active filters
@@ -289,6 +402,34 @@ impl SearchFilters {
             parts.push(if is_dir { "directories only" } else { "files only" }.to_string());
         }
 
+        if let Some(timestomped) = self.timestomped {
+            parts.push(if timestomped { "timestomped" } else { "not timestomped" }.to_string());
+        }
+
+        if let Some(owner) = &self.owner {
+            parts.push(format!("owner: {}", trustee_name(owner)));
+        }
+
+        if let Some(acl) = &self.acl {
+            parts.push(format!("acl: {}", acl));
+        }
+
+        if let Some(kind) = self.kind {
+            parts.push(format!("kind: {}", kind));
+        }
+
+        if let Some(mime) = &self.mime {
+            parts.push(format!("mime: {}", mime));
+        }
+
+        if let Some(mismatch) = self.ext_mismatch {
+            parts.push(if mismatch { "extension mismatch" } else { "extension matches" }.to_string());
+        }
+
+        if let Some(content) = &self.content {
+            parts.push(format!("containing \"{}\"", content));
+        }
+
         if parts.is_empty() {
             "no filters".to_string()
         } else {
@@ -297,6 +438,56 @@ impl SearchFilters {
     }
 }
 
+/// A yes/no filter value (an empty one means yes)
+fn parse_yes_no(filter: &str, value: &str) -> Result<bool> {
+    match value.to_lowercase().as_str() {
+        "yes" | "true" | "1" | "" => Ok(true),
+        "no" | "false" | "0" => Ok(false),
+        _ => Err(anyhow!("Invalid {} filter: {}. Use 'yes' or 'no'", filter, value)),
+    }
+}
+
+/// Extract search query without filter syntax
+pub fn extract_search_query(query: &str) -> String {
+    query_parts(query)
+        .into_iter()
+        .filter(|part| !part.contains(':'))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Split a query on whitespace, keeping double-quoted text (`content:"fix me"`)
+/// in one part
+pub fn query_parts(query: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = None;
+    let mut quoted = false;
+    for (i, c) in query.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        if c.is_whitespace() && !quoted {
+            if let Some(begin) = start.take() {
+                parts.push(&query[begin..i]);
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+    if let Some(begin) = start {
+        parts.push(&query[begin..]);
+    }
+    parts
+}
+
+/// A filter value without its surrounding double quotes
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .map(|rest| rest.strip_suffix('"').unwrap_or(rest))
+        .unwrap_or(value)
+}
+
 /// Parse size string like "100kb", "1.5gb", "500mb"
 fn parse_size(s: &str) -> Result<u64> {
     let s = s.trim().to_lowercase();
@@ -369,5 +560,30 @@ mod tests {
         assert_eq!(filters.min_size, Some(104857600));
         assert_eq!(filters.extensions, vec!["rs", "md"]);
         assert_eq!(filters.is_directory, Some(false));
+
+        let filters = SearchFilters::parse_filter_string("setup timestomp:yes").unwrap();
+        assert_eq!(filters.timestomped, Some(true));
+        assert!(SearchFilters::parse_filter_string("timestomp:maybe").is_err());
+
+        let filters = SearchFilters::parse_filter_string("owner:administrators acl:everyone-write").unwrap();
+        assert_eq!(filters.owner.as_deref(), Some("S-1-5-32-544"));
+        assert!(filters.acl.is_some());
+
+        let filters = SearchFilters::parse_filter_string("kind:images mime:image/* ext-mismatch:yes").unwrap();
+        assert_eq!(filters.kind, Some(FileKind::Image));
+        assert_eq!(filters.mime.as_deref(), Some("image"));
+        assert_eq!(filters.ext_mismatch, Some(true));
+        let mut entry = FileEntry::new("cat.txt".into(), "/tmp/cat.txt".into(), false, 1, 0, 0, None, None, None);
+        assert!(!filters.matches(&entry));
+        entry.file_type = Some(crate::file_type::FileType::Png);
+        assert!(filters.matches(&entry));
+        assert!(SearchFilters::parse_filter_string("kind:blob").is_err());
+
+        let query = "main ext:rs content:\"fix me\"  lib";
+        let filters = SearchFilters::parse_filter_string(query).unwrap();
+        assert_eq!(filters.content.as_deref(), Some("fix me"));
+        assert_eq!(extract_search_query(query), "main lib");
+        assert_eq!(SearchFilters::parse_filter_string("grep:TODO").unwrap().content.as_deref(), Some("TODO"));
+        assert!(SearchFilters::parse_filter_string("content:").is_err());
     }
 }
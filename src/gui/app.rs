@@ -1,12 +1,19 @@
-use crate::filters::SearchFilters;
+use crate::filters::{extract_search_query, SearchFilters};
 use crate::gui::theme::AppTheme;
-use crate::index::FileIndex;
+use crate::archive;
+use crate::config::Config;
+use crate::content::{self, ContentSearch};
+use crate::export::ExportFormat;
+use crate::file_type::FileKind;
+use crate::monitor::MonitorStatus;
+use crate::multi_volume::MultiVolumeIndex;
 use crate::search::{SearchEngine, SearchResult};
 use iced::{
-    widget::{button, column, container, row, scrollable, text, text_input, Column, Space},
+    widget::{button, checkbox, column, container, row, scrollable, text, text_input, Column, Space},
     Alignment, Element, Length, Task, Theme,
 };
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Instant;
 
 /// Application loading state
@@ -20,8 +27,17 @@ enum LoadingState {
 
 /// Main application state
 pub struct NothingGui {
-    /// File index (shared with monitoring threads)
-    index: Arc<Mutex<FileIndex>>,
+    /// Per-volume indexes (shared with monitoring threads)
+    index: Arc<MultiVolumeIndex>,
+
+    /// Settings from the config file
+    config: Config,
+
+    /// Live update counters from the filesystem monitor
+    monitor: Arc<MonitorStatus>,
+
+    /// Limits and content index of `content:` searches
+    content: ContentSearch,
 
     /// Search engine
     search_engine: SearchEngine,
@@ -44,6 +60,9 @@ pub struct NothingGui {
     /// Current filters
     filters: SearchFilters,
 
+    /// Whether the category buttons filter by detected file kind instead of extension
+    match_detected_kind: bool,
+
     /// Search timing
     last_search_time: Option<std::time::Duration>,
 
@@ -63,6 +82,12 @@ pub struct NothingGui {
     /// Search ID for canceling outdated searches
     search_id: u64,
 
+    /// Set to stop the running content search
+    cancel: Arc<AtomicBool>,
+
+    /// Whether the last content search was cancelled
+    cancelled: bool,
+
     /// Loading state
     loading_state: LoadingState,
 }
@@ -88,6 +113,12 @@ pub enum Message {
     /// Search completed with results and search ID
     SearchComplete(u64, Vec<SearchResult>, std::time::Duration),
 
+    /// Stop the running content search
+    CancelSearch,
+
+    /// A content search stopped before finishing
+    SearchCancelled(u64),
+
     /// Theme toggled
     ToggleTheme,
 
@@ -109,11 +140,8 @@ pub enum Message {
     /// Open containing folder
     OpenFolder,
 
-    /// Export results to CSV
-    ExportCSV,
-
-    /// Export results to JSON
-    ExportJSON,
+    /// Export results in a format
+    Export(ExportFormat),
 
     /// Copy path to clipboard
     CopyPath,
@@ -178,6 +206,9 @@ pub enum Message {
     /// Filter: Presentation extensions
     FilterExtPresentations,
 
+    /// Category buttons use the detected file kind
+    ToggleDetectedKind(bool),
+
     /// Index loading progress update
     LoadingProgress(usize, usize),
 
@@ -192,16 +223,20 @@ pub enum Message {
 }
 
 impl NothingGui {
-    fn new(index: Arc<Mutex<FileIndex>>) -> Self {
+    fn new(index: Arc<MultiVolumeIndex>, config: Config, monitor: Arc<MonitorStatus>, content: ContentSearch) -> Self {
         Self {
             index,
+            monitor,
+            content,
             search_engine: SearchEngine::new(),
             query: String::new(),
             results: Vec::new(),
-            theme: AppTheme::default(),
+            theme: AppTheme::from_name(&config.gui.theme),
+            config,
             show_filters: true,
             show_stats: false,
             filters: SearchFilters::default(),
+            match_detected_kind: false,
             last_search_time: None,
             sort_by: SortColumn::Name,
             sort_ascending: true,
@@ -209,6 +244,8 @@ impl NothingGui {
             last_click: None,
             searching: false,
             search_id: 0,
+            cancel: Arc::new(AtomicBool::new(false)),
+            cancelled: false,
             loading_state: LoadingState::Ready, // Start as Ready since main.rs loads indexes
         }
     }
@@ -221,6 +258,8 @@ impl NothingGui {
         match message {
             Message::SearchChanged(query) => {
                 self.query = query;
+                // A content search for the old query is no longer wanted
+                self.cancel.store(true, Ordering::Relaxed);
                 // Increment search ID to invalidate previous searches
                 self.search_id = self.search_id.wrapping_add(1);
                 let search_id = self.search_id;
@@ -245,27 +284,36 @@ impl NothingGui {
 
                 // Spawn async search to avoid blocking UI
                 self.searching = true;
+                self.cancelled = false;
+                self.cancel.store(true, Ordering::Relaxed);
+                self.cancel = Arc::new(AtomicBool::new(false));
+                let cancel = Arc::clone(&self.cancel);
                 let index = Arc::clone(&self.index);
-                let query = self.query.clone();
-                let filters = self.filters.clone();
+                let (query, filters) = self.search_query();
+                let content = self.content.clone();
+                let limit = self.config.search.gui_result_limit;
 
                 return Task::perform(
                     async move {
                         let start = Instant::now();
-                        let index = index.lock().unwrap();
                         let mut search_engine = SearchEngine::new();
 
-                        let results = search_engine.search_with_filters(
+                        let results = content::search(
                             &index,
+                            &mut search_engine,
                             &query,
-                            100,
+                            limit,
                             &filters,
+                            &content,
+                            &cancel,
                         );
 
-                        drop(index);
                         (search_id, results, start.elapsed())
                     },
-                    |(search_id, results, elapsed)| Message::SearchComplete(search_id, results, elapsed),
+                    |(search_id, results, elapsed)| match results {
+                        Some(results) => Message::SearchComplete(search_id, results, elapsed),
+                        None => Message::SearchCancelled(search_id),
+                    },
                 );
             }
 
@@ -280,6 +328,19 @@ impl NothingGui {
                 // Otherwise ignore these stale results
             }
 
+            Message::CancelSearch => {
+                self.cancel.store(true, Ordering::Relaxed);
+            }
+
+            Message::SearchCancelled(search_id) => {
+                if search_id == self.search_id {
+                    self.results.clear();
+                    self.selected_index = None;
+                    self.searching = false;
+                    self.cancelled = true;
+                }
+            }
+
             Message::ToggleTheme => {
                 self.theme.toggle();
             }
@@ -316,7 +377,7 @@ impl NothingGui {
                     // Double-click detected - open the file
                     self.last_click = None;
                     if let Some(result) = self.results.get(index) {
-                        let _ = open::that(&result.entry.path);
+                        let _ = open::that(archive::disk_path(&result.entry));
                     }
                 } else {
                     // Single click - just select
@@ -328,7 +389,7 @@ impl NothingGui {
             Message::OpenResult => {
                 if let Some(index) = self.selected_index {
                     if let Some(result) = self.results.get(index) {
-                        let _ = open::that(&result.entry.path);
+                        let _ = open::that(archive::disk_path(&result.entry));
                     }
                 }
             }
@@ -336,7 +397,7 @@ impl NothingGui {
             Message::OpenFolder => {
                 if let Some(index) = self.selected_index {
                     if let Some(result) = self.results.get(index) {
-                        let path = std::path::Path::new(&result.entry.path);
+                        let path = std::path::Path::new(archive::disk_path(&result.entry));
                         if let Some(parent) = path.parent() {
                             let _ = open::that(parent);
                         }
@@ -344,31 +405,17 @@ impl NothingGui {
                 }
             }
 
-            Message::ExportCSV => {
+            Message::Export(format) => {
                 if !self.results.is_empty() {
                     // Use file dialog to select save location
-                    let file_dialog = rfd::FileDialog::new()
-                        .add_filter("CSV", &["csv"])
-                        .set_file_name("search_results.csv");
+                    let extension = format.extension();
+                    let file_dialog = self.export_dialog()
+                        .add_filter(extension.to_uppercase(), &[extension])
+                        .set_file_name(format!("search_results.{}", extension));
 
                     if let Some(path) = file_dialog.save_file() {
                         use crate::export;
-                        if let Err(e) = export::export_csv(&self.results, path.to_str().unwrap()) {
-                            eprintln!("Export failed: {}", e);
-                        }
-                    }
-                }
-            }
-
-            Message::ExportJSON => {
-                if !self.results.is_empty() {
-                    let file_dialog = rfd::FileDialog::new()
-                        .add_filter("JSON", &["json"])
-                        .set_file_name("search_results.json");
-
-                    if let Some(path) = file_dialog.save_file() {
-                        use crate::export;
-                        if let Err(e) = export::export_json(&self.results, path.to_str().unwrap()) {
+                        if let Err(e) = export::export_results(&self.results, path.to_str().unwrap(), format, &self.query) {
                             eprintln!("Export failed: {}", e);
                         }
                     }
@@ -393,163 +440,97 @@ impl NothingGui {
 
             Message::FilterChanged(filters) => {
                 self.filters = filters;
-                self.perform_search();
+                return self.perform_search();
             }
 
             Message::ClearFilters => {
                 self.filters = SearchFilters::default();
-                self.perform_search();
+                return self.perform_search();
             }
 
             Message::FilterModifiedLast7Days => {
                 use chrono::{Utc, Duration};
                 self.filters.modified_after = Some(Utc::now() - Duration::days(7));
                 self.filters.modified_before = None;
-                self.perform_search();
+                return self.perform_search();
             }
 
             Message::FilterModifiedLast30Days => {
                 use chrono::{Utc, Duration};
                 self.filters.modified_after = Some(Utc::now() - Duration::days(30));
                 self.filters.modified_before = None;
-                self.perform_search();
+                return self.perform_search();
             }
 
             Message::FilterModifiedLastYear => {
                 use chrono::{Utc, Duration};
                 self.filters.modified_after = Some(Utc::now() - Duration::days(365));
                 self.filters.modified_before = None;
-                self.perform_search();
+                return self.perform_search();
             }
 
             Message::FilterSizeSmall => {
                 self.filters.min_size = None;
                 self.filters.max_size = Some(1024 * 1024); // 1 MB
-                self.perform_search();
+                return self.perform_search();
             }
 
             Message::FilterSizeMedium => {
                 self.filters.min_size = Some(1024 * 1024); // 1 MB
                 self.filters.max_size = Some(100 * 1024 * 1024); // 100 MB
-                self.perform_search();
+                return self.perform_search();
             }
 
             Message::FilterSizeLarge => {
                 self.filters.min_size = Some(100 * 1024 * 1024); // 100 MB
                 self.filters.max_size = None;
-                self.perform_search();
+                return self.perform_search();
             }
 
             Message::FilterTypeFiles => {
                 self.filters.is_directory = Some(false); // false = files only
-                self.perform_search();
+                return self.perform_search();
             }
 
             Message::FilterTypeDirs => {
                 self.filters.is_directory = Some(true); // true = directories only
-                self.perform_search();
+                return self.perform_search();
             }
 
             Message::FilterExtDocuments => {
-                self.filters.extensions = vec![
-                    "pdf".to_string(),
-                    "doc".to_string(),
-                    "docx".to_string(),
-                    "txt".to_string(),
-                    "odt".to_string(),
-                    "rtf".to_string(),
-                ];
-                self.perform_search();
+                return self.filter_category(Some(FileKind::Document), &["pdf", "doc", "docx", "txt", "odt", "rtf"]);
             }
 
             Message::FilterExtImages => {
-                self.filters.extensions = vec![
-                    "jpg".to_string(),
-                    "jpeg".to_string(),
-                    "png".to_string(),
-                    "gif".to_string(),
-                    "bmp".to_string(),
-                    "svg".to_string(),
-                ];
-                self.perform_search();
+                return self.filter_category(Some(FileKind::Image), &["jpg", "jpeg", "png", "gif", "bmp", "svg"]);
             }
 
             Message::FilterExtVideos => {
-                self.filters.extensions = vec![
-                    "mp4".to_string(),
-                    "avi".to_string(),
-                    "mkv".to_string(),
-                    "mov".to_string(),
-                    "wmv".to_string(),
-                    "flv".to_string(),
-                    "webm".to_string(),
-                    "m4v".to_string(),
-                ];
-                self.perform_search();
+                return self.filter_category(Some(FileKind::Video), &["mp4", "avi", "mkv", "mov", "wmv", "flv", "webm", "m4v"]);
             }
 
             Message::FilterExtAudio => {
-                self.filters.extensions = vec![
-                    "mp3".to_string(),
-                    "wav".to_string(),
-                    "flac".to_string(),
-                    "aac".to_string(),
-                    "ogg".to_string(),
-                    "wma".to_string(),
-                    "m4a".to_string(),
-                ];
-                self.perform_search();
+                return self.filter_category(Some(FileKind::Audio), &["mp3", "wav", "flac", "aac", "ogg", "wma", "m4a"]);
             }
 
             Message::FilterExtCode => {
-                self.filters.extensions = vec![
-                    "rs".to_string(),
-                    "py".to_string(),
-                    "js".to_string(),
-                    "ts".to_string(),
-                    "java".to_string(),
-                    "cpp".to_string(),
-                    "c".to_string(),
-                    "h".to_string(),
-                    "cs".to_string(),
-                    "go".to_string(),
-                    "rb".to_string(),
-                    "php".to_string(),
-                ];
-                self.perform_search();
+                return self.filter_category(None, &["rs", "py", "js", "ts", "java", "cpp", "c", "h", "cs", "go", "rb", "php"]);
             }
 
             Message::FilterExtArchives => {
-                self.filters.extensions = vec![
-                    "zip".to_string(),
-                    "rar".to_string(),
-                    "7z".to_string(),
-                    "tar".to_string(),
-                    "gz".to_string(),
-                    "bz2".to_string(),
-                    "xz".to_string(),
-                ];
-                self.perform_search();
+                return self.filter_category(Some(FileKind::Archive), &["zip", "rar", "7z", "tar", "gz", "bz2", "xz"]);
             }
 
             Message::FilterExtSpreadsheets => {
-                self.filters.extensions = vec![
-                    "xlsx".to_string(),
-                    "xls".to_string(),
-                    "csv".to_string(),
-                    "ods".to_string(),
-                ];
-                self.perform_search();
+                return self.filter_category(None, &["xlsx", "xls", "csv", "ods"]);
             }
 
             Message::FilterExtPresentations => {
-                self.filters.extensions = vec![
-                    "pptx".to_string(),
-                    "ppt".to_string(),
-                    "odp".to_string(),
-                    "key".to_string(),
-                ];
-                self.perform_search();
+                return self.filter_category(None, &["pptx", "ppt", "odp", "key"]);
+            }
+
+            Message::ToggleDetectedKind(enabled) => {
+                self.match_detected_kind = enabled;
             }
 
             Message::LoadingProgress(loaded, total) => {
@@ -588,6 +569,9 @@ impl NothingGui {
                 use iced::keyboard::{Key, key::Named};
 
                 match key.as_ref() {
+                    Key::Named(Named::Escape) if self.searching && self.search_query().1.content.is_some() => {
+                        self.cancel.store(true, Ordering::Relaxed);
+                    }
                     Key::Named(Named::Escape) => {
                         self.query.clear();
                         self.results.clear();
@@ -596,7 +580,7 @@ impl NothingGui {
                     Key::Named(Named::Enter) => {
                         if let Some(index) = self.selected_index {
                             if let Some(result) = self.results.get(index) {
-                                let _ = open::that(&result.entry.path);
+                                let _ = open::that(archive::disk_path(&result.entry));
                             }
                         }
                     }
@@ -619,7 +603,7 @@ impl NothingGui {
                     Key::Character(c) if c == "o" && modifiers.control() => {
                         if let Some(index) = self.selected_index {
                             if let Some(result) = self.results.get(index) {
-                                let path = std::path::Path::new(&result.entry.path);
+                                let path = std::path::Path::new(archive::disk_path(&result.entry));
                                 if let Some(parent) = path.parent() {
                                     let _ = open::that(parent);
                                 }
@@ -638,9 +622,10 @@ impl NothingGui {
                         }
                     }
                     Key::Character(c) if c == "e" && modifiers.control() => {
-                        // Export to CSV
+                        // Export in the configured default format
                         if !self.results.is_empty() {
-                            return Task::perform(async {}, |_| Message::ExportCSV);
+                            let message = Message::Export(self.config.export.format);
+                            return Task::perform(async {}, move |_| message.clone());
                         }
                     }
                     _ => {}
@@ -711,27 +696,72 @@ impl NothingGui {
 }
 
 impl NothingGui {
+    /// Save dialog starting in the configured export directory
+    fn export_dialog(&self) -> rfd::FileDialog {
+        let dialog = rfd::FileDialog::new();
+        match &self.config.export.directory {
+            Some(dir) => dialog.set_directory(dir),
+            None => dialog,
+        }
+    }
+
+    /// Search terms and filters: the filter panel's, plus a `content:` (or
+    /// `grep:`) pattern typed into the query
+    fn search_query(&self) -> (String, SearchFilters) {
+        let mut filters = self.filters.clone();
+        filters.content = SearchFilters::parse_filter_string(&self.query).ok().and_then(|typed| typed.content);
+        let terms = match filters.content {
+            Some(_) => extract_search_query(&self.query),
+            None => self.query.clone(),
+        };
+        (terms, filters)
+    }
+
+    /// Filter by a category: its detected kind if the toggle is on and it
+    /// has one, otherwise its extensions
+    fn filter_category(&mut self, kind: Option<FileKind>, extensions: &[&str]) -> Task<Message> {
+        match kind.filter(|_| self.match_detected_kind) {
+            Some(kind) => {
+                self.filters.kind = Some(kind);
+                self.filters.extensions.clear();
+            }
+            None => {
+                self.filters.kind = None;
+                self.filters.extensions = extensions.iter().map(|ext| ext.to_string()).collect();
+            }
+        }
+        self.perform_search()
+    }
+
     /// Perform search with current query and filters
-    fn perform_search(&mut self) {
+    ///
+    /// Content searches read files, so they run in the background like typed searches.
+    fn perform_search(&mut self) -> Task<Message> {
         if self.query.is_empty() {
             self.results.clear();
             self.last_search_time = None;
-            return;
+            return Task::none();
+        }
+
+        let (query, filters) = self.search_query();
+        if filters.content.is_some() {
+            self.search_id = self.search_id.wrapping_add(1);
+            let search_id = self.search_id;
+            return Task::perform(async move { search_id }, Message::PerformSearch);
         }
 
         let start = Instant::now();
-        let index = self.index.lock().unwrap();
 
-        self.results = self.search_engine.search_with_filters(
-            &index,
-            &self.query,
-            100, // Show top 100 results in GUI
-            &self.filters,
+        self.results = self.index.search(
+            &mut self.search_engine,
+            &query,
+            self.config.search.gui_result_limit,
+            &filters,
         );
 
-        drop(index);
         self.last_search_time = Some(start.elapsed());
         self.sort_results();
+        Task::none()
     }
 
     /// Sort results based on current sort column
@@ -788,11 +818,19 @@ impl NothingGui {
         .padding(8);
 
         let export_csv_button = button(text("📄 Export CSV").size(14))
-            .on_press(Message::ExportCSV)
+            .on_press(Message::Export(ExportFormat::Csv))
             .padding(8);
 
         let export_json_button = button(text("📋 Export JSON").size(14))
-            .on_press(Message::ExportJSON)
+            .on_press(Message::Export(ExportFormat::Json))
+            .padding(8);
+
+        let export_report_button = button(text("📊 HTML Report").size(14))
+            .on_press(Message::Export(ExportFormat::Html))
+            .padding(8);
+
+        let export_markdown_button = button(text("📝 Markdown").size(14))
+            .on_press(Message::Export(ExportFormat::Markdown))
             .padding(8);
 
         let title_row = row![
@@ -801,6 +839,8 @@ impl NothingGui {
                 .width(Length::Fill),
             export_csv_button,
             export_json_button,
+            export_report_button,
+            export_markdown_button,
             filters_button,
             stats_button,
             theme_button,
@@ -934,6 +974,10 @@ impl NothingGui {
         let ext_presentations = button(text("Presentations").size(12))
             .on_press(Message::FilterExtPresentations)
             .padding(6);
+        let ext_detected = checkbox("Match detected type", self.match_detected_kind)
+            .on_toggle(Message::ToggleDetectedKind)
+            .size(14)
+            .text_size(12);
 
         let clear_button = button(text("Clear All Filters").size(14))
             .on_press(Message::ClearFilters)
@@ -962,6 +1006,7 @@ impl NothingGui {
             Space::with_height(15),
 
             ext_label,
+            ext_detected,
             ext_docs,
             ext_images,
             ext_videos,
@@ -1014,6 +1059,29 @@ impl NothingGui {
         }
 
         // Show searching indicator
+        if self.searching && self.search_query().1.content.is_some() {
+            let searching_message = column![
+                Space::with_height(Length::Fill),
+                text("🔍 Searching file contents...")
+                    .size(20)
+                    .color(iced::Color::from_rgb(0.7, 0.7, 0.9))
+                    .width(Length::Fill)
+                    .align_x(iced::alignment::Horizontal::Center),
+                container(button(text("Cancel").size(14)).on_press(Message::CancelSearch).padding(8))
+                    .width(Length::Fill)
+                    .align_x(iced::alignment::Horizontal::Center),
+                Space::with_height(Length::Fill),
+            ]
+            .spacing(10)
+            .width(Length::Fill)
+            .height(Length::Fill);
+
+            return container(searching_message)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into();
+        }
+
         if self.searching {
             let searching_message = column![
                 Space::with_height(Length::Fill),
@@ -1036,7 +1104,7 @@ impl NothingGui {
         if self.results.is_empty() {
             let no_results = column![
                 Space::with_height(Length::Fill),
-                text("No matches found")
+                text(if self.cancelled { "Content search cancelled" } else { "No matches found" })
                     .size(20)
                     .width(Length::Fill)
                     .align_x(iced::alignment::Horizontal::Center),
@@ -1125,6 +1193,25 @@ impl NothingGui {
             .spacing(5)
             .padding(8);
 
+            // Matching lines of a content search under the row
+            let row_content: Element<Message> = if result.content.is_empty() {
+                row_content.into()
+            } else {
+                let lines: Vec<String> = result
+                    .content
+                    .iter()
+                    .map(|found| format!("{:>6}: {}", found.line_number, found.line))
+                    .collect();
+                column![
+                    row_content,
+                    text(lines.join("\n"))
+                        .size(11)
+                        .font(iced::Font::MONOSPACE)
+                        .color(iced::Color::from_rgb(0.8, 0.8, 0.6)),
+                ]
+                .into()
+            };
+
             let row_button = button(row_content)
                 .on_press(Message::SelectResult(index))
                 .width(Length::Fill)
@@ -1162,22 +1249,22 @@ impl NothingGui {
 
     /// View status bar
     fn view_status_bar(&self) -> Element<Message> {
-        let index = self.index.lock().unwrap();
-        let file_count = index.file_count();
-        let dir_count = index.directory_count();
-        drop(index);
+        let file_count = self.index.file_count();
+        let dir_count = self.index.directory_count();
 
         let search_time = self
             .last_search_time
             .map(|d| format!("{}ms", d.as_millis()))
             .unwrap_or_else(|| "—".to_string());
 
+        let live = self.monitor.snapshot();
         let status_text = format!(
-            "📊 {} files, {} directories • Results: {} • Search: {}",
+            "📊 {} files, {} directories • Results: {} • Search: {} • 📡 {} live changes",
             file_count,
             dir_count,
             self.results.len(),
-            search_time
+            search_time,
+            live.changes()
         );
 
         let status_row = row![text(status_text).size(12),]
@@ -1228,7 +1315,7 @@ fn format_file_size(bytes: u64) -> String {
 }
 
 /// Run the GUI application
-pub fn run(index: Arc<Mutex<FileIndex>>) -> iced::Result {
+pub fn run(index: Arc<MultiVolumeIndex>, config: Config, monitor: Arc<MonitorStatus>, content: ContentSearch) -> iced::Result {
     iced::application(
         NothingGui::title,
         NothingGui::update,
@@ -1243,7 +1330,7 @@ pub fn run(index: Arc<Mutex<FileIndex>>) -> iced::Result {
         ..Default::default()
     })
     .run_with(move || {
-        let app = NothingGui::new(index.clone());
+        let app = NothingGui::new(index.clone(), config.clone(), monitor.clone(), content.clone());
         (app, Task::none())
     })
 }
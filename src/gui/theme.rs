@@ -42,6 +42,16 @@ impl AppTheme {
     }
 }
 
+impl AppTheme {
+    /// Theme from its config name ("dark" or "light"); unknown names use the default
+    pub fn from_name(name: &str) -> Self {
+        match name.to_lowercase().as_str() {
+            "light" => AppTheme::Light,
+            _ => AppTheme::Dark,
+        }
+    }
+}
+
 impl Default for AppTheme {
     fn default() -> Self {
         AppTheme::Dark
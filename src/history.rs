@@ -1,3 +1,4 @@
+use crate::config;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
@@ -133,13 +134,7 @@ impl SearchHistory {
 
     /// Get the history file path
     fn get_history_path() -> Result<PathBuf> {
-        let username = std::env::var("USERNAME")
-            .or_else(|_| std::env::var("USER"))
-            .unwrap_or_else(|_| "default".to_string());
-
-        let path = PathBuf::from(format!("C:\\Users\\{}\\. nothing\\history.json", username));
-
-        Ok(path)
+        Ok(config::data_dir()?.join("history.json"))
     }
 
     /// Get all queries (for display)
@@ -0,0 +1,621 @@
+// Importers for file listings made by other tools (Everything, find, dir /s)
+// or by our own exports, so offline machines can be searched as volumes
+use crate::export::entry_from_json;
+use crate::file_entry::FileEntry;
+use crate::index::FileIndex;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Seconds between 1601-01-01 (FILETIME epoch) and 1970-01-01
+const FILETIME_UNIX_OFFSET: i64 = 11_644_473_600;
+
+/// Windows FILE_ATTRIBUTE_DIRECTORY, as found in EFU listings
+const ATTRIBUTE_DIRECTORY: u64 = 0x10;
+
+/// Layouts of the listings that can be imported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// CSV or TSV with a header row: Everything's EFU and CSV exports, our CSV and TSV exports
+    Delimited,
+
+    /// Our JSON export (`{"timestamp": ..., "results": [...]}`) or a JSON array of entries
+    Json,
+
+    /// One JSON entry per line, as written by the NDJSON export
+    Ndjson,
+
+    /// `find -printf '%y\t%s\t%T@\t%p\n'` output, or bare paths from plain `find`
+    Find,
+
+    /// Windows `dir /s` output
+    Dir,
+}
+
+impl FromStr for ImportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "csv" | "tsv" | "efu" => Ok(ImportFormat::Delimited),
+            "json" => Ok(ImportFormat::Json),
+            "ndjson" | "jsonl" => Ok(ImportFormat::Ndjson),
+            "find" => Ok(ImportFormat::Find),
+            "dir" => Ok(ImportFormat::Dir),
+            _ => Err(anyhow!("Invalid listing format: {}. Use 'csv', 'efu', 'json', 'ndjson', 'find' or 'dir'", s)),
+        }
+    }
+}
+
+impl ImportFormat {
+    /// Guess the format from the start of a listing
+    pub fn detect(head: &str) -> ImportFormat {
+        let mut lines = head.lines().map(str::trim).filter(|line| !line.is_empty());
+        let first = lines.next().unwrap_or_default();
+        if first == "{" || first.starts_with('[') {
+            return ImportFormat::Json;
+        }
+        if first.starts_with('{') {
+            return ImportFormat::Ndjson;
+        }
+        if head.lines().take(10).any(|line| line.starts_with(" Volume in drive") || line.starts_with(" Directory of ")) {
+            return ImportFormat::Dir;
+        }
+        let header = split_header(first.trim_start_matches('\u{feff}'));
+        if header.iter().any(|column| matches!(column_role(column), Some(Role::Path))) {
+            return ImportFormat::Delimited;
+        }
+        ImportFormat::Find
+    }
+}
+
+/// Read a listing file into an index (`format` is detected when not given)
+///
+/// Listings rarely carry file ids, so entries without one are numbered in
+/// order and linked to their parent directory by path.
+pub fn read_listing(path: &Path, format: Option<ImportFormat>) -> Result<FileIndex> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let format = match format {
+        Some(format) => format,
+        None if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("efu")) => ImportFormat::Delimited,
+        None => ImportFormat::detect(&String::from_utf8_lossy(reader.fill_buf()?)),
+    };
+    parse_listing(reader, format).with_context(|| format!("Failed to import {}", path.display()))
+}
+
+/// Parse a listing in the given format into an index
+pub fn parse_listing(reader: impl BufRead, format: ImportFormat) -> Result<FileIndex> {
+    let entries = match format {
+        ImportFormat::Delimited => parse_delimited(reader)?,
+        ImportFormat::Json => parse_json(reader)?,
+        ImportFormat::Ndjson => parse_ndjson(reader)?,
+        ImportFormat::Find => parse_find(reader)?,
+        ImportFormat::Dir => parse_dir(reader)?,
+    };
+    Ok(link_entries(entries))
+}
+
+/// What a column of a delimited listing holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    /// Full path (EFU's "Filename"), or the folder when there is also a Name column (Everything's CSV)
+    Path,
+    Name,
+    Type,
+    Size,
+    Modified,
+    Created,
+    Accessed,
+    Attributes,
+    FileId,
+    ParentId,
+}
+
+fn column_role(header: &str) -> Option<Role> {
+    let header = header.trim().trim_matches('"').to_lowercase().replace('_', " ");
+    let role = match header.as_str() {
+        "filename" | "path" | "full path" => Role::Path,
+        "name" => Role::Name,
+        "type" => Role::Type,
+        "size" | "size (bytes)" => Role::Size,
+        "date modified" | "modified" => Role::Modified,
+        "date created" | "created" => Role::Created,
+        "date accessed" | "accessed" => Role::Accessed,
+        "attributes" => Role::Attributes,
+        "file id" => Role::FileId,
+        "parent id" => Role::ParentId,
+        _ => return None,
+    };
+    Some(role)
+}
+
+/// Header fields, split on tabs if there are any, else on commas
+fn split_header(line: &str) -> Vec<&str> {
+    if line.contains('\t') {
+        line.split('\t').collect()
+    } else {
+        line.split(',').collect()
+    }
+}
+
+fn parse_delimited(mut reader: impl BufRead) -> Result<Vec<FileEntry>> {
+    let mut header = String::new();
+    reader.read_line(&mut header)?;
+    let header = header.trim_start_matches('\u{feff}').trim_end_matches(['\r', '\n']);
+    let tabs = header.contains('\t');
+    let roles: Vec<Option<Role>> = split_header(header).into_iter().map(column_role).collect();
+    if !roles.contains(&Some(Role::Path)) {
+        bail!("No path column in the header: {}", header);
+    }
+
+    let mut entries = Vec::new();
+    let mut records = Records { reader, tabs };
+    while let Some(record) = records.next_record()? {
+        let field = |role: Role| -> Option<&str> {
+            let i = roles.iter().position(|r| *r == Some(role))?;
+            record.get(i).map(String::as_str).filter(|s| !s.is_empty())
+        };
+        let Some(mut path) = field(Role::Path).map(str::to_string) else {
+            continue;
+        };
+        // Everything's CSV has the folder in Path and the file in Name; our
+        // export has the full path in Path, which then ends in a separator and the name
+        if let Some(name) = field(Role::Name) {
+            let separator = if path.contains('\\') { '\\' } else { '/' };
+            if path != name && !path.ends_with(&format!("{}{}", separator, name)) {
+                if !path.ends_with(separator) {
+                    path.push(separator);
+                }
+                path.push_str(name);
+            }
+        }
+
+        let attributes = field(Role::Attributes).and_then(|a| a.parse::<u64>().ok());
+        let is_directory = match field(Role::Type) {
+            Some(kind) => kind.eq_ignore_ascii_case("directory") || kind.eq_ignore_ascii_case("folder"),
+            None => attributes.is_some_and(|a| a & ATTRIBUTE_DIRECTORY != 0),
+        };
+        let mut entry = new_entry(path, is_directory);
+        entry.size = field(Role::Size).and_then(parse_size).unwrap_or(0);
+        entry.modified = field(Role::Modified).and_then(parse_time);
+        entry.created = field(Role::Created).and_then(parse_time);
+        entry.accessed = field(Role::Accessed).and_then(parse_time);
+        entry.file_id = field(Role::FileId).and_then(|id| id.parse().ok()).unwrap_or(0);
+        entry.parent_id = field(Role::ParentId).and_then(|id| id.parse().ok()).unwrap_or(0);
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Record reader for CSV (RFC 4180 quoting, fields may span lines) and TSV
+/// (our export's `\t`, `\n` and `\r` escapes)
+struct Records<R> {
+    reader: R,
+    tabs: bool,
+}
+
+impl<R: BufRead> Records<R> {
+    fn next_record(&mut self) -> Result<Option<Vec<String>>> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if self.tabs {
+            return Ok(Some(line.split('\t').map(unescape_tsv).collect()));
+        }
+
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars: Vec<char> = line.chars().collect();
+        let mut i = 0;
+        loop {
+            if i == chars.len() {
+                if !quoted {
+                    break;
+                }
+                // A quoted field with a line break: the record goes on
+                let mut next = String::new();
+                if self.reader.read_line(&mut next)? == 0 {
+                    bail!("Unterminated quoted field");
+                }
+                field.push('\n');
+                chars = next.trim_end_matches(['\r', '\n']).chars().collect();
+                i = 0;
+                continue;
+            }
+            match (chars[i], quoted) {
+                ('"', true) if chars.get(i + 1) == Some(&'"') => {
+                    field.push('"');
+                    i += 1;
+                }
+                ('"', true) => quoted = false,
+                ('"', false) if field.is_empty() => quoted = true,
+                (',', false) => fields.push(std::mem::take(&mut field)),
+                (c, _) => field.push(c),
+            }
+            i += 1;
+        }
+        fields.push(field);
+        Ok(Some(fields))
+    }
+}
+
+fn unescape_tsv(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('\\') => out.push('\\'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn parse_json(mut reader: impl Read) -> Result<Vec<FileEntry>> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let document: Value = serde_json::from_str(&text).context("Invalid JSON")?;
+    let results = match &document {
+        Value::Array(results) => results,
+        Value::Object(object) => object
+            .get("results")
+            .and_then(Value::as_array)
+            .ok_or_else(|| anyhow!("No \"results\" array in the JSON document"))?,
+        _ => bail!("Expected a JSON object or array"),
+    };
+    results.iter().map(entry_from_value).collect()
+}
+
+fn parse_ndjson(reader: impl BufRead) -> Result<Vec<FileEntry>> {
+    let mut entries = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: Value = serde_json::from_str(&line).with_context(|| format!("Invalid JSON on line {}", number + 1))?;
+        entries.push(entry_from_value(&value)?);
+    }
+    Ok(entries)
+}
+
+/// Entry from an exported JSON object, which may hold only some columns
+fn entry_from_value(value: &Value) -> Result<FileEntry> {
+    if let Some(entry) = entry_from_json(value) {
+        return Ok(entry);
+    }
+    let path = value
+        .get("path")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("Entry without a path: {}", value))?;
+    let is_directory = value.get("type").and_then(Value::as_str) == Some("directory");
+    let mut entry = new_entry(path.to_string(), is_directory);
+    let number = |key: &str| value.get(key).and_then(Value::as_u64).unwrap_or(0);
+    let time = |key: &str| value.get(key).and_then(Value::as_str).and_then(parse_time);
+    entry.size = number("size");
+    entry.file_id = number("file_id");
+    entry.parent_id = number("parent_id");
+    entry.modified = time("modified");
+    entry.created = time("created");
+    entry.accessed = time("accessed");
+    Ok(entry)
+}
+
+fn parse_find(reader: impl BufRead) -> Result<Vec<FileEntry>> {
+    let mut entries = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        // `%y\t%s\t%T@\t%p`: type letter, size, mtime in seconds, path
+        let fields: Vec<&str> = line.splitn(4, '\t').collect();
+        let entry = match fields[..] {
+            [kind, size, mtime, path] if kind.len() == 1 && size.parse::<u64>().is_ok() => {
+                let mut entry = new_entry(path.to_string(), kind == "d");
+                entry.size = if kind == "d" { 0 } else { size.parse()? };
+                entry.modified = parse_time(mtime);
+                entry
+            }
+            _ => new_entry(line, false),
+        };
+        entries.push(entry);
+    }
+
+    // Bare paths say nothing about type: anything with children is a directory
+    let mut parents: Vec<String> = entries.iter().filter_map(|e| parent_path(&e.path).map(str::to_string)).collect();
+    parents.sort();
+    parents.dedup();
+    for entry in &mut entries {
+        if parents.binary_search(&entry.path).is_ok() {
+            entry.is_directory = true;
+            entry.size = 0;
+        }
+    }
+    Ok(entries)
+}
+
+fn parse_dir(reader: impl BufRead) -> Result<Vec<FileEntry>> {
+    let mut entries = Vec::new();
+    let mut directory: Option<String> = None;
+    for line in reader.lines() {
+        let line = line?;
+        if let Some(path) = line.strip_prefix(" Directory of ") {
+            let path = path.trim().to_string();
+            entries.push(new_entry(path.clone(), true));
+            directory = Some(path);
+            continue;
+        }
+        let Some(directory) = &directory else {
+            continue;
+        };
+        let Some((modified, rest)) = parse_dir_time(&line) else {
+            continue;
+        };
+        let Some((kind, name)) = rest.trim_start().split_once(char::is_whitespace) else {
+            continue;
+        };
+        let name = name.trim_start();
+        if name == "." || name == ".." {
+            continue;
+        }
+
+        let (is_directory, size, name) = match kind {
+            "<DIR>" => (true, 0, name),
+            // Links show their target: "name [target]"
+            "<JUNCTION>" | "<SYMLINKD>" => (true, 0, name.rsplit_once(" [").map_or(name, |(name, _)| name)),
+            "<SYMLINK>" => (false, 0, name.rsplit_once(" [").map_or(name, |(name, _)| name)),
+            _ => match parse_size(kind) {
+                Some(size) => (false, size, name),
+                None => continue,
+            },
+        };
+        let separator = if directory.ends_with('\\') { "" } else { "\\" };
+        let mut entry = new_entry(format!("{}{}{}", directory, separator, name), is_directory);
+        entry.size = size;
+        entry.modified = Some(modified);
+        entries.push(entry);
+    }
+
+    // Directories appear in their parent's listing and as " Directory of"
+    let mut seen = std::collections::HashSet::new();
+    entries.retain(|entry| seen.insert(entry.path.clone()));
+    Ok(entries)
+}
+
+/// Date and time at the start of a `dir` line, and the rest of the line
+fn parse_dir_time(line: &str) -> Option<(DateTime<Utc>, &str)> {
+    let mut fields = line.split_whitespace();
+    let (date, time) = (fields.next()?, fields.next()?);
+    let meridiem = fields.next().filter(|f| f.eq_ignore_ascii_case("AM") || f.eq_ignore_ascii_case("PM"));
+    let (stamp, formats): (String, &[&str]) = match meridiem {
+        Some(meridiem) => (format!("{} {} {}", date, time, meridiem), &["%m/%d/%Y %I:%M %p", "%d/%m/%Y %I:%M %p"]),
+        None => (format!("{} {}", date, time), &["%d/%m/%Y %H:%M", "%d.%m.%Y %H:%M", "%Y-%m-%d %H:%M", "%m/%d/%Y %H:%M"]),
+    };
+    let time = formats.iter().find_map(|format| NaiveDateTime::parse_from_str(&stamp, format).ok())?;
+    let rest = skip_fields(line, if meridiem.is_some() { 3 } else { 2 });
+    Some((Utc.from_utc_datetime(&time), rest))
+}
+
+/// What follows the first `n` whitespace-separated fields of a line
+fn skip_fields(line: &str, n: usize) -> &str {
+    let mut rest = line;
+    for _ in 0..n {
+        rest = rest.trim_start();
+        rest = &rest[rest.find(char::is_whitespace).unwrap_or(rest.len())..];
+    }
+    rest
+}
+
+/// Size with thousands separators ("12,345", "12.345", "12 345")
+fn parse_size(text: &str) -> Option<u64> {
+    let digits: String = text.chars().filter(|c| !matches!(c, ',' | '.' | '\'' | ' ' | '\u{a0}')).collect();
+    digits.parse().ok()
+}
+
+/// Timestamp in any of the forms listings use: RFC 3339, "YYYY-MM-DD HH:MM[:SS]"
+/// (read as UTC), Windows FILETIME ticks (EFU) or Unix seconds (`find`'s `%T@`)
+fn parse_time(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(time.with_timezone(&Utc));
+    }
+    if let Ok(ticks) = text.parse::<i64>() {
+        // FILETIME counts 100 ns ticks; Unix seconds are far smaller
+        return if ticks > 100_000_000_000 {
+            DateTime::from_timestamp(ticks / 10_000_000 - FILETIME_UNIX_OFFSET, (ticks % 10_000_000) as u32 * 100)
+        } else {
+            DateTime::from_timestamp(ticks, 0)
+        };
+    }
+    if let Ok(seconds) = text.parse::<f64>() {
+        return DateTime::from_timestamp(seconds.trunc() as i64, (seconds.fract() * 1e9) as u32);
+    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y/%m/%d %H:%M:%S", "%Y/%m/%d %H:%M", "%m/%d/%Y %I:%M %p"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .map(|time| Utc.from_utc_datetime(&time))
+}
+
+fn new_entry(path: String, is_directory: bool) -> FileEntry {
+    let mut entry = FileEntry::new(String::new(), String::new(), is_directory, 0, 0, 0, None, None, None);
+    entry.set_path(path);
+    entry
+}
+
+/// Parent directory of a path ("C:\a" for "C:\a\b", "/a" for "/a/b")
+fn parent_path(path: &str) -> Option<&str> {
+    let trimmed = path.trim_end_matches(['\\', '/']);
+    let i = trimmed.rfind(['\\', '/'])?;
+    match &trimmed[..i] {
+        "" => Some(&path[..1]),
+        parent if parent.ends_with(':') => Some(&path[..=i]),
+        parent => Some(parent),
+    }
+}
+
+/// Number entries without a file id after the highest id in the listing,
+/// and point entries without a parent id at their parent directory
+fn link_entries(mut entries: Vec<FileEntry>) -> FileIndex {
+    if entries.iter().any(|entry| entry.file_id == 0) {
+        let mut next_id = entries.iter().map(|entry| entry.file_id).max().unwrap_or(0);
+        let mut ids = HashMap::new();
+        for entry in &mut entries {
+            if entry.file_id == 0 {
+                next_id += 1;
+                entry.file_id = next_id;
+            }
+            if entry.is_directory {
+                ids.insert(entry.path.trim_end_matches(['\\', '/']).to_string(), entry.file_id);
+            }
+        }
+        for entry in entries.iter_mut().filter(|entry| entry.parent_id == 0) {
+            entry.parent_id = parent_path(&entry.path)
+                .and_then(|parent| ids.get(parent.trim_end_matches(['\\', '/'])))
+                .copied()
+                .unwrap_or(0);
+        }
+    }
+
+    let mut index = FileIndex::with_capacity(entries.len());
+    for entry in entries {
+        index.add_entry(entry);
+    }
+    index
+}
+
+/// Check a name for an imported volume: letters, digits, '-' and '_', at
+/// least two characters so it can't be mistaken for a drive letter
+pub fn check_volume_name(name: &str) -> Result<()> {
+    if name.len() < 2 || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        bail!("Invalid volume name: {:?}. Use at least two letters, digits, '-' or '_'", name);
+    }
+    Ok(())
+}
+
+/// Volume name for a listing file, from its file name
+pub fn volume_name_for(path: &Path) -> String {
+    let stem = path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    stem.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '-' }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn import(listing: &str) -> Vec<FileEntry> {
+        let format = ImportFormat::detect(listing);
+        parse_listing(listing.as_bytes(), format).unwrap().entries().to_vec()
+    }
+
+    #[test]
+    fn test_import_listings() {
+        // Everything EFU: FILETIME dates, directory attribute
+        let efu = "Filename,Size,Date Modified,Date Created,Attributes\r\n\
+                   \"C:\\Data\",0,133500000000000000,,16\r\n\
+                   \"C:\\Data\\a, b.txt\",42,133500000000000000,,32\r\n";
+        let entries = import(efu);
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].is_directory);
+        assert_eq!(entries[1].name, "a, b.txt");
+        assert_eq!(entries[1].size, 42);
+        assert_eq!(entries[1].parent_id, entries[0].file_id);
+        assert_eq!(entries[1].modified.unwrap().timestamp(), 13_350_000_000 - FILETIME_UNIX_OFFSET);
+
+        // Our own CSV and NDJSON exports round-trip, ids included
+        let csv = "Name,Path,Type,Size (bytes),Modified,File ID,Parent ID\r\nx.txt,/d/x.txt,File,5,2024-01-15 10:22:00,7,3\r\n";
+        let entries = import(csv);
+        assert_eq!((entries[0].path.as_str(), entries[0].file_id, entries[0].parent_id), ("/d/x.txt", 7, 3));
+
+        // Everything's CSV: a folder whose name ends like the file's is still joined
+        let csv = "Name,Path,Size\r\ndocs,C:\\Mydocs,0\r\nreport.txt,C:\\Work,5\r\n";
+        let paths: Vec<String> = import(csv).into_iter().map(|e| e.path).collect();
+        assert_eq!(paths, vec!["C:\\Mydocs\\docs", "C:\\Work\\report.txt"]);
+
+        // Listings mixing ids and no ids keep the given ids and number the rest after them
+        let csv = "Path,Type,File ID,Parent ID\r\n/d,Directory,,\r\n/d/a.txt,File,7,0\r\n/d/b.txt,File,,\r\n";
+        let entries = import(csv);
+        let ids: Vec<(u64, u64)> = entries.iter().map(|e| (e.file_id, e.parent_id)).collect();
+        assert_eq!(ids, vec![(8, 0), (7, 8), (9, 8)]);
+        let ndjson = "{\"path\":\"/d/y.txt\",\"size\":9}\n";
+        assert_eq!(import(ndjson)[0].size, 9);
+
+        // find -printf and bare find output
+        let find = "d\t4096\t1700000000.5\t/srv\nf\t10\t1700000000.0\t/srv/notes.md\n";
+        let entries = import(find);
+        assert!(entries[0].is_directory && entries[0].size == 0);
+        assert_eq!(entries[1].parent_id, entries[0].file_id);
+        let entries = import("/srv\n/srv/notes.md\n");
+        assert!(entries[0].is_directory && !entries[1].is_directory);
+
+        // dir /s
+        let dir = " Volume in drive C is OS\r\n\r\n Directory of C:\\Users\r\n\r\n\
+                   01/15/2024  10:22 AM    <DIR>          .\r\n\
+                   01/14/2024  09:01 PM            12,345 my notes.txt\r\n\
+                   01/14/2024  09:01 PM    <JUNCTION>     Docs [C:\\Elsewhere]\r\n\
+                                  1 File(s)         12,345 bytes\r\n";
+        let entries = import(dir);
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["C:\\Users", "C:\\Users\\my notes.txt", "C:\\Users\\Docs"]);
+        assert_eq!(entries[1].size, 12_345);
+        assert_eq!(entries[1].modified.unwrap().to_rfc3339(), "2024-01-14T21:01:00+00:00");
+        assert!(entries[2].is_directory);
+
+        let index = parse_listing(dir.as_bytes(), ImportFormat::Dir).unwrap();
+        assert_eq!(index.directory_count(), 2);
+
+        assert!(check_volume_name("laptop-2019").is_ok());
+        assert!(check_volume_name("C").is_err());
+    }
+
+    #[test]
+    fn test_tsv_export_round_trip() {
+        use crate::export::{export_index, Column, ExportFormat};
+        use crate::filters::SearchFilters;
+
+        // Backslashes, tabs and line breaks in what the TSV export writes decode back to the same entries
+        let mut index = FileIndex::new();
+        index.add_entry(FileEntry::new("share".to_string(), "\\\\server\\share".to_string(), true, 1, 0, 0, None, None, None));
+        index.add_entry(FileEntry::new("new\tfile.txt".to_string(), "\\\\server\\share\\new\tfile.txt".to_string(), false, 2, 1, 42, None, None, None));
+        index.add_entry(FileEntry::new("notes\r\n.md".to_string(), "C:\\temp\\notes\r\n.md".to_string(), false, 3, 0, 7, None, None, None));
+        let columns = [Column::Name, Column::Path, Column::Type, Column::Size, Column::FileId, Column::ParentId];
+        let mut out = Vec::new();
+        let mut exporter = ExportFormat::Tsv.exporter(&mut out, &columns, "");
+        export_index(&index, &SearchFilters::default(), exporter.as_mut()).unwrap();
+        exporter.finish().unwrap();
+        drop(exporter);
+
+        let listing = String::from_utf8(out).unwrap();
+        let entries = import(&listing);
+        let fields: Vec<(&str, &str, bool, u64, u64, u64)> =
+            entries.iter().map(|e| (e.name.as_str(), e.path.as_str(), e.is_directory, e.size, e.file_id, e.parent_id)).collect();
+        assert_eq!(fields, vec![
+            ("share", "\\\\server\\share", true, 0, 1, 0),
+            ("new\tfile.txt", "\\\\server\\share\\new\tfile.txt", false, 42, 2, 1),
+            ("notes\r\n.md", "C:\\temp\\notes\r\n.md", false, 7, 3, 0),
+        ]);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::file_entry::FileEntry;
+use crate::mapped_index::MappedRecord;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::mem;
@@ -96,6 +97,12 @@ impl FileIndex {
         }
     }
 
+    /// Keep only the entries for which `keep` returns true
+    pub fn retain(&mut self, mut keep: impl FnMut(&FileEntry) -> bool) {
+        self.entries.retain(|entry| keep(entry));
+        self.directory_count = self.entries.iter().filter(|e| e.is_directory).count();
+    }
+
     /// Remove file from index by path
     pub fn remove_by_path(&mut self, path: &str) {
         if let Some(pos) = self.entries.iter().position(|e| e.path == path) {
@@ -109,12 +116,31 @@ impl FileIndex {
     /// Update file path (for renames/moves)
     pub fn update_path(&mut self, file_id: u64, new_path: String) {
         if let Some(entry) = self.entries.iter_mut().find(|e| e.file_id == file_id) {
-            entry.path = new_path.clone();
-            // Extract name from path
-            if let Some(name) = new_path.split('\\').last() {
-                entry.name = name.to_string();
+            entry.set_path(new_path);
+        }
+    }
+
+    /// Move the entry at `from` and everything below it to `to`
+    ///
+    /// Returns the number of entries updated.
+    pub fn move_path(&mut self, from: &str, to: &str) -> usize {
+        let mut moved = 0;
+        for entry in &mut self.entries {
+            if let Some(new_path) = rebase_path(&entry.path, from, to) {
+                entry.set_path(new_path);
+                moved += 1;
             }
         }
+        moved
+    }
+
+    /// Remove the entry at `path` and everything below it
+    ///
+    /// Returns the number of entries removed.
+    pub fn remove_tree(&mut self, path: &str) -> usize {
+        let before = self.entries.len();
+        self.retain(|entry| !is_within(&entry.path, path));
+        before - self.entries.len()
     }
 
     /// Update modified timestamp
@@ -132,13 +158,14 @@ impl FileIndex {
     }
 
     /// Update metadata by path (for cloud monitoring)
+    ///
+    /// A changed archive's members are listed again by the next archive pass.
     pub fn update_metadata_by_path(&mut self, path: &str, size: u64, modified: Option<std::time::SystemTime>) {
         if let Some(entry) = self.entries.iter_mut().find(|e| e.path == path) {
             entry.size = size;
+            entry.members_listed = false;
             if let Some(sys_time) = modified {
-                if let Ok(duration) = sys_time.duration_since(std::time::UNIX_EPOCH) {
-                    entry.modified = DateTime::from_timestamp(duration.as_secs() as i64, 0);
-                }
+                entry.modified = Some(DateTime::<Utc>::from(sys_time));
             }
         }
     }
@@ -149,3 +176,173 @@ impl Default for FileIndex {
         Self::new()
     }
 }
+
+/// Borrowed view of a single entry, so searches can score names and paths
+/// without cloning every entry they look at
+#[derive(Clone, Copy)]
+pub enum EntryRef<'a> {
+    /// Entry held in memory (a `FileIndex` or a mapped index overlay)
+    Owned(&'a FileEntry),
+
+    /// Record read straight out of a memory-mapped index file
+    Mapped(MappedRecord<'a>),
+}
+
+impl<'a> EntryRef<'a> {
+    /// File name (without path)
+    pub fn name(&self) -> &'a str {
+        match self {
+            EntryRef::Owned(entry) => &entry.name,
+            EntryRef::Mapped(record) => record.name(),
+        }
+    }
+
+    /// Full path to the file
+    pub fn path(&self) -> &'a str {
+        match self {
+            EntryRef::Owned(entry) => &entry.path,
+            EntryRef::Mapped(record) => record.path(),
+        }
+    }
+
+    /// Whether this is a directory
+    pub fn is_directory(&self) -> bool {
+        match self {
+            EntryRef::Owned(entry) => entry.is_directory,
+            EntryRef::Mapped(record) => record.is_directory(),
+        }
+    }
+
+    /// Unique file identifier (MFT record number or inode)
+    pub fn file_id(&self) -> u64 {
+        match self {
+            EntryRef::Owned(entry) => entry.file_id,
+            EntryRef::Mapped(record) => record.file_id(),
+        }
+    }
+
+    /// File identifier of the parent directory
+    pub fn parent_id(&self) -> u64 {
+        match self {
+            EntryRef::Owned(entry) => entry.parent_id,
+            EntryRef::Mapped(record) => record.parent_id(),
+        }
+    }
+
+    /// File size in bytes (0 for directories)
+    pub fn size(&self) -> u64 {
+        match self {
+            EntryRef::Owned(entry) => entry.size,
+            EntryRef::Mapped(record) => record.size(),
+        }
+    }
+
+    /// Last modified timestamp
+    pub fn modified(&self) -> Option<DateTime<Utc>> {
+        match self {
+            EntryRef::Owned(entry) => entry.modified,
+            EntryRef::Mapped(record) => record.modified(),
+        }
+    }
+
+    /// Materialize an owned copy of the entry
+    pub fn to_entry(self) -> FileEntry {
+        match self {
+            EntryRef::Owned(entry) => (*entry).clone(),
+            EntryRef::Mapped(record) => record.to_entry(),
+        }
+    }
+}
+
+/// Read access shared by the in-memory and memory-mapped indexes
+pub trait IndexView {
+    /// Iterate over every live entry
+    fn entry_refs(&self) -> Box<dyn Iterator<Item = EntryRef<'_>> + '_>;
+
+    /// Total number of entries (files + directories)
+    fn len(&self) -> usize;
+
+    /// Number of directories
+    fn directory_count(&self) -> usize;
+
+    /// Check if the index is empty
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of files (non-directories)
+    fn file_count(&self) -> usize {
+        self.len() - self.directory_count()
+    }
+}
+
+/// Whether `path` is `root` itself or lies below it
+pub fn is_within(path: &str, root: &str) -> bool {
+    match path.strip_prefix(root) {
+        Some(rest) => rest.is_empty() || rest.starts_with(['\\', '/']) || root.ends_with(['\\', '/']),
+        None => false,
+    }
+}
+
+/// New path of `path` after `from` moved to `to` (None if `path` is not within `from`)
+pub fn rebase_path(path: &str, from: &str, to: &str) -> Option<String> {
+    if is_within(path, from) {
+        Some(format!("{}{}", to, &path[from.len()..]))
+    } else {
+        None
+    }
+}
+
+/// Mutations the filesystem monitors apply to a live index
+pub trait LiveIndex {
+    /// Add a newly created entry
+    fn add_entry(&mut self, entry: FileEntry);
+
+    /// Remove the entry at `path`
+    fn remove_by_path(&mut self, path: &str);
+
+    /// Remove the entry at `path` and all its descendants
+    fn remove_tree(&mut self, path: &str) -> usize;
+
+    /// Move the entry at `from` and all its descendants to `to`
+    fn move_path(&mut self, from: &str, to: &str) -> usize;
+
+    /// Refresh size and modified time of the entry at `path`
+    fn update_metadata_by_path(&mut self, path: &str, size: u64, modified: Option<std::time::SystemTime>);
+}
+
+impl IndexView for FileIndex {
+    fn entry_refs(&self) -> Box<dyn Iterator<Item = EntryRef<'_>> + '_> {
+        Box::new(self.entries.iter().map(EntryRef::Owned))
+    }
+
+    fn len(&self) -> usize {
+        FileIndex::len(self)
+    }
+
+    fn directory_count(&self) -> usize {
+        FileIndex::directory_count(self)
+    }
+}
+
+impl LiveIndex for FileIndex {
+    fn add_entry(&mut self, entry: FileEntry) {
+        FileIndex::add_entry(self, entry)
+    }
+
+    fn remove_by_path(&mut self, path: &str) {
+        FileIndex::remove_by_path(self, path)
+    }
+
+    fn remove_tree(&mut self, path: &str) -> usize {
+        FileIndex::remove_tree(self, path)
+    }
+
+    fn move_path(&mut self, from: &str, to: &str) -> usize {
+        FileIndex::move_path(self, from, to)
+    }
+
+    fn update_metadata_by_path(&mut self, path: &str, size: u64, modified: Option<std::time::SystemTime>) {
+        FileIndex::update_metadata_by_path(self, path, size, modified)
+    }
+}
@@ -0,0 +1,533 @@
+// Compact on-disk encoding for index files
+//
+// Paths are front-coded: each entry stores only the bytes that differ from
+// the previous entry's path. Scans emit siblings next to each other, so most
+// entries share a long prefix with their predecessor. The name is usually
+// the tail of the path and is then not stored at all. Integers are varints.
+// The encoded stream is written through a streaming compressor.
+use crate::file_entry::{FileEntry, Timestamps};
+use crate::file_type::FileType;
+use crate::index::FileIndex;
+use crate::security::{Ace, Security};
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Magic bytes at the start of a compressed index file
+pub const MAGIC: &[u8; 8] = b"NOTHIDX2";
+
+/// Version of the entry encoding (2 added the change and `$FILE_NAME` times,
+/// 3 owners and ACLs, 4 a second flags byte and detected file types)
+pub const FORMAT_VERSION: u16 = 4;
+
+/// Size of the uncompressed file header
+pub const HEADER_SIZE: usize = 40;
+
+const FLAG_DIRECTORY: u8 = 1;
+const FLAG_NAME_IS_TAIL: u8 = 1 << 1;
+const FLAG_MODIFIED: u8 = 1 << 2;
+const FLAG_CREATED: u8 = 1 << 3;
+const FLAG_ACCESSED: u8 = 1 << 4;
+const FLAG_CHANGED: u8 = 1 << 5;
+const FLAG_FILE_NAME_TIMES: u8 = 1 << 6;
+const FLAG_SECURITY: u8 = 1 << 7;
+
+/// Bits of the second flags byte (version 4 on)
+const FLAG2_FILE_TYPE: u8 = 1;
+const FLAG2_MEMBERS_LISTED: u8 = 1 << 1;
+
+/// Bit of an encoded ACL entry's rights byte marking a deny entry
+const ACE_DENY: u8 = 1 << 7;
+
+/// Most entries reserved up front when decoding; counts and lengths in a
+/// file are untrusted, so larger indexes grow as their entries are read
+const MAX_PREALLOCATED_ENTRIES: u64 = 1 << 20;
+
+/// Compression applied to the encoded entry stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    None,
+    Zstd,
+    Lz4,
+}
+
+impl Compression {
+    fn to_byte(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Zstd => 1,
+            Compression::Lz4 => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Result<Self> {
+        match byte {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Zstd),
+            2 => Ok(Compression::Lz4),
+            _ => Err(anyhow!("Unknown compression codec: {}", byte)),
+        }
+    }
+
+    /// Name for display
+    pub fn name(&self) -> &str {
+        match self {
+            Compression::None => "none",
+            Compression::Zstd => "zstd",
+            Compression::Lz4 => "lz4",
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "none" | "off" => Ok(Compression::None),
+            "zstd" => Ok(Compression::Zstd),
+            "lz4" => Ok(Compression::Lz4),
+            _ => Err(anyhow!("Invalid compression: {}. Use 'zstd', 'lz4' or 'none'", s)),
+        }
+    }
+}
+
+/// Fixed-size header written before the compressed stream
+#[derive(Debug, Clone, Copy)]
+pub struct Header {
+    pub version: u16,
+    pub compression: Compression,
+    pub level: i32,
+    pub entry_count: u64,
+    pub directory_count: u64,
+
+    /// Size the index would take in the legacy verbatim (bincode) format
+    pub raw_bytes: u64,
+}
+
+impl Header {
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut buf = [0u8; HEADER_SIZE];
+        buf[..8].copy_from_slice(MAGIC);
+        buf[8..10].copy_from_slice(&self.version.to_le_bytes());
+        buf[10] = self.compression.to_byte();
+        // buf[11] reserved
+        buf[12..16].copy_from_slice(&self.level.to_le_bytes());
+        buf[16..24].copy_from_slice(&self.entry_count.to_le_bytes());
+        buf[24..32].copy_from_slice(&self.directory_count.to_le_bytes());
+        buf[32..40].copy_from_slice(&self.raw_bytes.to_le_bytes());
+        writer.write_all(&buf)
+    }
+
+    /// Parse a header; returns `None` if the bytes are not a compressed index header
+    pub fn parse(buf: &[u8]) -> Result<Option<Self>> {
+        if buf.len() < HEADER_SIZE || &buf[..8] != MAGIC {
+            return Ok(None);
+        }
+
+        let version = u16::from_le_bytes([buf[8], buf[9]]);
+        if version > FORMAT_VERSION {
+            bail!("Index format version {} is newer than this build supports ({})", version, FORMAT_VERSION);
+        }
+
+        let u64_at = |offset: usize| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&buf[offset..offset + 8]);
+            u64::from_le_bytes(bytes)
+        };
+
+        Ok(Some(Self {
+            version,
+            compression: Compression::from_byte(buf[10])?,
+            level: i32::from_le_bytes([buf[12], buf[13], buf[14], buf[15]]),
+            entry_count: u64_at(16),
+            directory_count: u64_at(24),
+            raw_bytes: u64_at(32),
+        }))
+    }
+}
+
+/// Encode all entries of an index into `writer`
+///
+/// Security descriptors are shared by many entries, so each is written once
+/// and later entries refer to it by number.
+pub fn encode_entries<W: Write>(index: &FileIndex, writer: &mut W) -> io::Result<()> {
+    let mut previous_path: &[u8] = &[];
+    let mut descriptors: HashMap<&Security, u64> = HashMap::new();
+
+    for entry in index.entries() {
+        let path = entry.path.as_bytes();
+        let shared = common_prefix_len(previous_path, path);
+
+        let mut flags = 0u8;
+        if entry.is_directory {
+            flags |= FLAG_DIRECTORY;
+        }
+        let name_is_tail = entry.path.ends_with(&entry.name);
+        if name_is_tail {
+            flags |= FLAG_NAME_IS_TAIL;
+        }
+        // Times nanoseconds cannot represent (before 1677 or after 2262) are left out
+        let times = [entry.modified, entry.created, entry.accessed, entry.changed].map(|t| t.and_then(nanos));
+        for (time, flag) in times.iter().zip([FLAG_MODIFIED, FLAG_CREATED, FLAG_ACCESSED, FLAG_CHANGED]) {
+            if time.is_some() {
+                flags |= flag;
+            }
+        }
+        if entry.file_name_times.is_some() {
+            flags |= FLAG_FILE_NAME_TIMES;
+        }
+        if entry.security.is_some() {
+            flags |= FLAG_SECURITY;
+        }
+        let mut flags2 = 0u8;
+        if entry.file_type.is_some() {
+            flags2 |= FLAG2_FILE_TYPE;
+        }
+        if entry.members_listed {
+            flags2 |= FLAG2_MEMBERS_LISTED;
+        }
+
+        writer.write_all(&[flags, flags2])?;
+        write_varint(writer, shared as u64)?;
+        write_bytes(writer, &path[shared..])?;
+        if name_is_tail {
+            write_varint(writer, entry.name.len() as u64)?;
+        } else {
+            write_bytes(writer, entry.name.as_bytes())?;
+        }
+        write_varint(writer, entry.file_id)?;
+        write_varint(writer, entry.parent_id)?;
+        write_varint(writer, entry.size)?;
+        for timestamp in times.into_iter().flatten() {
+            write_varint(writer, zigzag(timestamp))?;
+        }
+        if let Some(file_name_times) = &entry.file_name_times {
+            // A byte of which times are present, then each as an offset from
+            // the created time: they are usually all equal to it
+            let file_name_times = file_name_times.macb().map(|t| t.and_then(nanos));
+            let present = file_name_times.iter().enumerate().fold(0u8, |bits, (i, t)| bits | ((t.is_some() as u8) << i));
+            writer.write_all(&[present])?;
+            let base = times[1].unwrap_or(0);
+            for timestamp in file_name_times.into_iter().flatten() {
+                write_varint(writer, zigzag(timestamp.wrapping_sub(base)))?;
+            }
+        }
+        if let Some(security) = &entry.security {
+            // The descriptor's number, followed by the descriptor the first time
+            let next = descriptors.len() as u64;
+            let number = *descriptors.entry(security).or_insert(next);
+            write_varint(writer, number)?;
+            if number == next {
+                write_security(writer, security)?;
+            }
+        }
+        if let Some(file_type) = entry.file_type {
+            writer.write_all(&[file_type.code()])?;
+        }
+
+        previous_path = path;
+    }
+
+    Ok(())
+}
+
+/// Decode `count` entries written in encoding `version` from `reader` into a new index
+pub fn decode_entries<R: Read>(reader: &mut R, count: u64, version: u16) -> Result<FileIndex> {
+    let mut index = FileIndex::with_capacity(count.min(MAX_PREALLOCATED_ENTRIES) as usize);
+    let mut previous_path: Vec<u8> = Vec::new();
+    let mut descriptors: Vec<Arc<Security>> = Vec::new();
+
+    for _ in 0..count {
+        let mut flags = [0u8; 1];
+        reader.read_exact(&mut flags)?;
+        let flags = flags[0];
+        let mut flags2 = [0u8; 1];
+        if version >= 4 {
+            reader.read_exact(&mut flags2)?;
+        }
+        let flags2 = flags2[0];
+
+        let shared = read_varint(reader)? as usize;
+        if shared > previous_path.len() {
+            bail!("Corrupt index: shared prefix longer than previous path");
+        }
+        let mut path_bytes = previous_path[..shared].to_vec();
+        path_bytes.extend_from_slice(&read_bytes(reader)?);
+        let path = String::from_utf8(path_bytes)
+            .map_err(|_| anyhow!("Corrupt index: path is not valid UTF-8"))?;
+
+        let name = if flags & FLAG_NAME_IS_TAIL != 0 {
+            let len = read_varint(reader)? as usize;
+            path.get(path.len().saturating_sub(len)..)
+                .ok_or_else(|| anyhow!("Corrupt index: invalid name length"))?
+                .to_string()
+        } else {
+            String::from_utf8(read_bytes(reader)?)
+                .map_err(|_| anyhow!("Corrupt index: name is not valid UTF-8"))?
+        };
+
+        let file_id = read_varint(reader)?;
+        let parent_id = read_varint(reader)?;
+        let size = read_varint(reader)?;
+        let mut read_timestamp = |flag: u8| -> Result<Option<DateTime<Utc>>> {
+            if flags & flag == 0 {
+                return Ok(None);
+            }
+            Ok(Some(DateTime::from_timestamp_nanos(unzigzag(read_varint(reader)?))))
+        };
+        let modified = read_timestamp(FLAG_MODIFIED)?;
+        let created = read_timestamp(FLAG_CREATED)?;
+        let accessed = read_timestamp(FLAG_ACCESSED)?;
+        let changed = read_timestamp(FLAG_CHANGED)?;
+        let file_name_times = if flags & FLAG_FILE_NAME_TIMES != 0 {
+            let mut present = [0u8; 1];
+            reader.read_exact(&mut present)?;
+            let base = created.and_then(nanos).unwrap_or(0);
+            let mut times = [None; 4];
+            for (i, time) in times.iter_mut().enumerate() {
+                if present[0] & (1 << i) != 0 {
+                    *time = Some(DateTime::from_timestamp_nanos(unzigzag(read_varint(reader)?).wrapping_add(base)));
+                }
+            }
+            let [modified, accessed, changed, created] = times;
+            Some(Box::new(Timestamps { modified, accessed, changed, created }))
+        } else {
+            None
+        };
+        let security = if flags & FLAG_SECURITY != 0 {
+            let number = read_varint(reader)? as usize;
+            if number == descriptors.len() {
+                descriptors.push(Arc::new(read_security(reader)?));
+            }
+            Some(Arc::clone(
+                descriptors.get(number).ok_or_else(|| anyhow!("Corrupt index: unknown security descriptor"))?,
+            ))
+        } else {
+            None
+        };
+        let file_type = if flags2 & FLAG2_FILE_TYPE != 0 {
+            let mut code = [0u8; 1];
+            reader.read_exact(&mut code)?;
+            // Types added by newer versions read as unknown binary data
+            Some(FileType::from_code(code[0]).unwrap_or(FileType::Unknown))
+        } else {
+            None
+        };
+
+        previous_path.clear();
+        previous_path.extend_from_slice(path.as_bytes());
+
+        let mut entry = FileEntry::new(
+            name,
+            path,
+            flags & FLAG_DIRECTORY != 0,
+            file_id,
+            parent_id,
+            size,
+            modified,
+            created,
+            accessed,
+        );
+        entry.changed = changed;
+        entry.file_name_times = file_name_times;
+        entry.security = security;
+        entry.file_type = file_type;
+        entry.members_listed = flags2 & FLAG2_MEMBERS_LISTED != 0;
+        index.add_entry(entry);
+    }
+
+    Ok(index)
+}
+
+/// Owner and group (empty when missing), then the ACL entries
+pub(crate) fn write_security<W: Write>(writer: &mut W, security: &Security) -> io::Result<()> {
+    write_bytes(writer, security.owner.as_deref().unwrap_or("").as_bytes())?;
+    write_bytes(writer, security.group.as_deref().unwrap_or("").as_bytes())?;
+    write_varint(writer, security.acl.len() as u64)?;
+    for ace in &security.acl {
+        writer.write_all(&[ace.rights | if ace.allow { 0 } else { ACE_DENY }])?;
+        write_bytes(writer, ace.trustee.as_bytes())?;
+    }
+    Ok(())
+}
+
+pub(crate) fn read_security<R: Read>(reader: &mut R) -> Result<Security> {
+    let owner = Some(read_sid(reader)?).filter(|s| !s.is_empty());
+    let group = Some(read_sid(reader)?).filter(|s| !s.is_empty());
+    let count = read_varint(reader)?;
+    let mut acl = Vec::new();
+    for _ in 0..count {
+        let mut rights = [0u8; 1];
+        reader.read_exact(&mut rights)?;
+        acl.push(Ace {
+            allow: rights[0] & ACE_DENY == 0,
+            trustee: read_sid(reader)?,
+            rights: rights[0] & !ACE_DENY,
+        });
+    }
+    Ok(Security { owner, group, acl })
+}
+
+fn read_sid<R: Read>(reader: &mut R) -> Result<String> {
+    String::from_utf8(read_bytes(reader)?).map_err(|_| anyhow!("Corrupt index: SID is not valid UTF-8"))
+}
+
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> io::Result<()> {
+    let mut buf = [0u8; 10];
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf[len] = byte;
+            len += 1;
+            break;
+        }
+        buf[len] = byte | 0x80;
+        len += 1;
+    }
+    writer.write_all(&buf[..len])
+}
+
+fn read_varint<R: Read>(reader: &mut R) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8; 1];
+        reader.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(anyhow!("Corrupt index: varint too long"))
+}
+
+fn write_bytes<W: Write>(writer: &mut W, bytes: &[u8]) -> io::Result<()> {
+    write_varint(writer, bytes.len() as u64)?;
+    writer.write_all(bytes)
+}
+
+/// Read a length-prefixed byte string, allocating only as much as the input holds
+fn read_bytes<R: Read>(reader: &mut R) -> Result<Vec<u8>> {
+    let len = read_varint(reader)?;
+    let mut bytes = Vec::new();
+    reader.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        bail!("Corrupt index: string runs past the end of the file");
+    }
+    Ok(bytes)
+}
+
+fn nanos(timestamp: DateTime<Utc>) -> Option<i64> {
+    timestamp.timestamp_nanos_opt()
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint_round_trip() {
+        for value in [0u64, 1, 127, 128, 300, u32::MAX as u64, u64::MAX] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, value).unwrap();
+            assert_eq!(read_varint(&mut buf.as_slice()).unwrap(), value);
+        }
+        for value in [0i64, -1, 1, i64::MIN, i64::MAX] {
+            assert_eq!(unzigzag(zigzag(value)), value);
+        }
+    }
+
+    #[test]
+    fn test_entries_round_trip() {
+        let mut index = FileIndex::new();
+        let now = DateTime::from_timestamp(1_700_000_000, 42);
+        index.add_entry(FileEntry::new("src".into(), "C:\\Apps\\src".into(), true, 40, 5, 0, None, None, None));
+        index.add_entry(FileEntry::new("main.rs".into(), "C:\\Apps\\src\\main.rs".into(), false, 41, 40, 1234, now, now, None));
+        index.add_entry(FileEntry::new("lib.rs".into(), "C:\\Apps\\src\\lib.rs".into(), false, 42, 40, 99, None, now, None));
+        let mut stomped = FileEntry::new("tool.exe".into(), "C:\\Apps\\src\\tool.exe".into(), false, 43, 40, 7, now, now, now);
+        stomped.changed = DateTime::from_timestamp(1_700_000_100, 0);
+        stomped.file_name_times = Some(Box::new(Timestamps {
+            modified: DateTime::from_timestamp(1_700_000_050, 0),
+            accessed: None,
+            changed: now,
+            created: now,
+        }));
+        index.add_entry(stomped);
+        let security = Arc::new(Security::from_mode(1000, 100, 0o640));
+        for entry in index.entries_mut().iter_mut().take(2) {
+            entry.security = Some(Arc::clone(&security));
+        }
+        index.entries_mut()[1].file_type = Some(FileType::Text);
+        index.entries_mut()[3].file_type = Some(FileType::Exe);
+        index.entries_mut()[3].members_listed = true;
+        // Name that is not the tail of its path (cloud entries, renamed roots)
+        index.add_entry(FileEntry::new("OneDrive".into(), "C:\\Users\\me\\OneDrive - Personal".into(), true, 0, 0, 0, None, None, None));
+
+        let mut buf = Vec::new();
+        encode_entries(&index, &mut buf).unwrap();
+        let decoded = decode_entries(&mut buf.as_slice(), index.len() as u64, FORMAT_VERSION).unwrap();
+
+        assert_eq!(decoded.len(), 5);
+        assert_eq!(decoded.directory_count(), 2);
+        for (a, b) in index.entries().iter().zip(decoded.entries()) {
+            assert_eq!(a.name, b.name);
+            assert_eq!(a.path, b.path);
+            assert_eq!(a.file_id, b.file_id);
+            assert_eq!(a.size, b.size);
+            assert_eq!(a.modified, b.modified);
+            assert_eq!(a.created, b.created);
+            assert_eq!(a.accessed, b.accessed);
+            assert_eq!(a.changed, b.changed);
+            assert_eq!(a.file_name_times, b.file_name_times);
+            assert_eq!(a.security, b.security);
+            assert_eq!(a.file_type, b.file_type);
+            assert_eq!(a.members_listed, b.members_listed);
+        }
+        assert!(Arc::ptr_eq(decoded.entries()[0].security.as_ref().unwrap(), decoded.entries()[1].security.as_ref().unwrap()));
+        assert!(decoded.entries()[3].is_timestomped());
+        assert!(!decoded.entries()[1].is_timestomped());
+    }
+
+    #[test]
+    fn test_untrusted_input() {
+        // Times nanoseconds cannot hold are stored as missing, not as the epoch
+        let mut index = FileIndex::new();
+        let ancient = DateTime::parse_from_rfc3339("1601-01-01T00:00:00Z").ok().map(|t| t.to_utc());
+        let now = DateTime::from_timestamp(1_700_000_000, 0);
+        let mut entry = FileEntry::new("old.txt".into(), "C:\\old.txt".into(), false, 1, 0, 1, now, ancient, None);
+        entry.file_name_times = Some(Box::new(Timestamps { modified: ancient, created: now, ..Default::default() }));
+        index.add_entry(entry);
+        let mut buf = Vec::new();
+        encode_entries(&index, &mut buf).unwrap();
+        let decoded = decode_entries(&mut buf.as_slice(), 1, FORMAT_VERSION).unwrap();
+        let entry = &decoded.entries()[0];
+        assert_eq!((entry.modified, entry.created), (now, None));
+        assert_eq!(entry.file_name_times.as_deref().map(|t| (t.modified, t.created)), Some((None, now)));
+
+        // A huge entry count or string length fails on the missing data instead of allocating it
+        assert!(decode_entries(&mut buf.as_slice(), u64::MAX, FORMAT_VERSION).is_err());
+        let mut huge = Vec::new();
+        write_varint(&mut huge, u64::MAX >> 1).unwrap();
+        huge.extend_from_slice(b"abc");
+        assert!(read_bytes(&mut huge.as_slice()).is_err());
+    }
+}
@@ -0,0 +1,391 @@
+// Linux change source: recursive inotify watches with rename pairing
+use crate::activity_log;
+use crate::change_source::{ChangeEvent, ChangeSource};
+use anyhow::{Context, Result};
+use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// How long the reader sleeps when the queue is empty
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Events requested on every watched directory
+fn watch_mask() -> WatchMask {
+    WatchMask::CREATE
+        | WatchMask::DELETE
+        | WatchMask::MOVED_FROM
+        | WatchMask::MOVED_TO
+        | WatchMask::MODIFY
+        | WatchMask::ATTRIB
+        | WatchMask::ONLYDIR
+        | WatchMask::DONT_FOLLOW
+        | WatchMask::EXCL_UNLINK
+}
+
+/// Change source using inotify directly
+///
+/// inotify watches single directories, so every directory in the tree gets
+/// its own watch; new directories are watched as they appear. The
+/// MOVED_FROM/MOVED_TO halves of a rename are paired by cookie, and a queue
+/// overflow is reported as `ChangeEvent::Overflow` for the whole root.
+pub struct InotifySource {
+    root: PathBuf,
+    stop: Arc<AtomicBool>,
+    reader: Option<thread::JoinHandle<()>>,
+}
+
+impl InotifySource {
+    /// Create a source for the tree under `root`
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            stop: Arc::new(AtomicBool::new(false)),
+            reader: None,
+        }
+    }
+}
+
+impl ChangeSource for InotifySource {
+    fn describe(&self) -> String {
+        self.root.display().to_string()
+    }
+
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn start(&mut self, events: Sender<ChangeEvent>) -> Result<()> {
+        let inotify = Inotify::init().context("Failed to initialize inotify")?;
+        let mut watcher = TreeWatcher {
+            inotify,
+            root: self.root.clone(),
+            watches: HashMap::new(),
+            pending_moves: Vec::new(),
+            events,
+        };
+
+        // The root must be watchable; failures below it are only reported
+        let wd = watcher
+            .inotify
+            .add_watch(&self.root, watch_mask())
+            .with_context(|| format!("Failed to watch {}", self.root.display()))?;
+        watcher.watches.insert(wd, self.root.clone());
+        watcher.watch_children(&self.root.clone(), false);
+
+        let stop = Arc::clone(&self.stop);
+        self.reader = Some(thread::spawn(move || watcher.run(&stop)));
+        Ok(())
+    }
+}
+
+impl Drop for InotifySource {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+/// Event read from the queue, detached from the read buffer
+struct RawEvent {
+    wd: WatchDescriptor,
+    mask: EventMask,
+    cookie: u32,
+    name: Option<OsString>,
+}
+
+/// Reader state: the inotify instance and the directory behind each watch
+struct TreeWatcher {
+    inotify: Inotify,
+    root: PathBuf,
+    watches: HashMap<WatchDescriptor, PathBuf>,
+
+    /// MOVED_FROM halves waiting for their MOVED_TO: cookie, path, is directory
+    pending_moves: Vec<(u32, PathBuf, bool)>,
+
+    events: Sender<ChangeEvent>,
+}
+
+impl TreeWatcher {
+    /// Read and translate events until stopped or the receiver goes away
+    fn run(mut self, stop: &AtomicBool) {
+        let mut buffer = vec![0u8; 64 * 1024];
+
+        while !stop.load(Ordering::Relaxed) {
+            let batch: Vec<RawEvent> = match self.inotify.read_events(&mut buffer) {
+                Ok(events) => events
+                    .map(|e| RawEvent {
+                        wd: e.wd,
+                        mask: e.mask,
+                        cookie: e.cookie,
+                        name: e.name.map(|n| n.to_os_string()),
+                    })
+                    .collect(),
+                Err(e) => {
+                    activity_log::log(format!("inotify read error on {}: {}", self.root.display(), e));
+                    let _ = self.events.send(ChangeEvent::Overflow(self.root.clone()));
+                    return;
+                }
+            };
+
+            if batch.is_empty() {
+                // Queue is idle: unpaired MOVED_FROMs will not get their other half
+                if !self.flush_moves() {
+                    return;
+                }
+                thread::sleep(POLL_INTERVAL);
+                continue;
+            }
+
+            if !self.process_batch(batch) {
+                return;
+            }
+        }
+    }
+
+    /// Translate one read's worth of events; false once the receiver is gone
+    ///
+    /// The halves of a rename can be split across reads, so MOVED_FROMs stay
+    /// pending until the queue goes idle.
+    fn process_batch(&mut self, batch: Vec<RawEvent>) -> bool {
+        let mut changes = Vec::new();
+
+        for event in batch {
+            if event.mask.contains(EventMask::Q_OVERFLOW) {
+                changes.push(ChangeEvent::Overflow(self.root.clone()));
+                continue;
+            }
+            if event.mask.contains(EventMask::IGNORED) {
+                // Watched directory was deleted or unmounted
+                self.watches.remove(&event.wd);
+                continue;
+            }
+
+            let dir = match self.watches.get(&event.wd) {
+                Some(dir) => dir.clone(),
+                None => continue,
+            };
+            let path = match &event.name {
+                Some(name) => dir.join(name),
+                None => dir,
+            };
+            let is_dir = event.mask.contains(EventMask::ISDIR);
+
+            if event.mask.contains(EventMask::MOVED_FROM) {
+                self.pending_moves.push((event.cookie, path, is_dir));
+            } else if event.mask.contains(EventMask::MOVED_TO) {
+                let from = self
+                    .pending_moves
+                    .iter()
+                    .position(|(cookie, _, _)| *cookie == event.cookie)
+                    .map(|i| self.pending_moves.remove(i).1);
+                match from {
+                    Some(from) => {
+                        let mut found = Vec::new();
+                        if is_dir {
+                            self.rename_watches(&from, &path);
+                            // Renamed before its CREATE was handled: nothing watches it yet
+                            if !self.watches.values().any(|dir| *dir == path) {
+                                found = self.watch_new_dir(&path);
+                            }
+                        }
+                        changes.push(ChangeEvent::Renamed { from, to: path });
+                        changes.extend(found);
+                    }
+                    None => {
+                        // Moved in from outside the tree
+                        changes.push(ChangeEvent::Created(path.clone()));
+                        if is_dir {
+                            changes.extend(self.watch_new_dir(&path));
+                        }
+                    }
+                }
+            } else if event.mask.contains(EventMask::CREATE) {
+                changes.push(ChangeEvent::Created(path.clone()));
+                if is_dir {
+                    changes.extend(self.watch_new_dir(&path));
+                }
+            } else if event.mask.contains(EventMask::DELETE) {
+                changes.push(ChangeEvent::Deleted(path));
+            } else if event.mask.intersects(EventMask::MODIFY | EventMask::ATTRIB) {
+                changes.push(ChangeEvent::Modified(path));
+            }
+        }
+
+        changes.into_iter().all(|change| self.events.send(change).is_ok())
+    }
+
+    /// Report unpaired MOVED_FROMs: moved out of the tree, so gone as far as the index is concerned
+    fn flush_moves(&mut self) -> bool {
+        let mut changes = Vec::new();
+        for (_, from, is_dir) in mem::take(&mut self.pending_moves) {
+            if is_dir {
+                self.unwatch_tree(&from);
+            }
+            changes.push(ChangeEvent::Deleted(from));
+        }
+        changes.into_iter().all(|change| self.events.send(change).is_ok())
+    }
+
+    /// Watch a directory that just appeared, returning its existing contents
+    ///
+    /// Entries created before the watch was in place produce no events of
+    /// their own, so they are reported here.
+    fn watch_new_dir(&mut self, dir: &Path) -> Vec<ChangeEvent> {
+        match self.inotify.add_watch(dir, watch_mask()) {
+            Ok(wd) => {
+                self.watches.insert(wd, dir.to_path_buf());
+            }
+            Err(e) => {
+                activity_log::log(format!("Failed to watch {}: {}", dir.display(), e));
+                return Vec::new();
+            }
+        }
+        self.watch_children(dir, true)
+    }
+
+    /// Add watches below `dir`, optionally collecting Created events for what is found
+    fn watch_children(&mut self, dir: &Path, report: bool) -> Vec<ChangeEvent> {
+        let mut changes = Vec::new();
+        let read_dir = match fs::read_dir(dir) {
+            Ok(read_dir) => read_dir,
+            Err(_) => return changes,
+        };
+
+        for dir_entry in read_dir.flatten() {
+            let path = dir_entry.path();
+            // file_type does not follow symlinks
+            let is_dir = dir_entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+
+            if report {
+                changes.push(ChangeEvent::Created(path.clone()));
+            }
+            if !is_dir {
+                continue;
+            }
+
+            match self.inotify.add_watch(&path, watch_mask()) {
+                Ok(wd) => {
+                    self.watches.insert(wd, path.clone());
+                    changes.extend(self.watch_children(&path, report));
+                }
+                Err(e) => {
+                    // Usually ENOSPC: fs.inotify.max_user_watches is too low
+                    activity_log::log(format!("⚠️  Failed to watch {}: {}", path.display(), e));
+                }
+            }
+        }
+
+        changes
+    }
+
+    /// Point the watches of a renamed directory tree at their new paths
+    fn rename_watches(&mut self, from: &Path, to: &Path) {
+        for path in self.watches.values_mut() {
+            if let Ok(rest) = path.strip_prefix(from) {
+                *path = to.join(rest);
+            }
+        }
+    }
+
+    /// Drop the watches of a directory tree that left the watched root
+    fn unwatch_tree(&mut self, dir: &Path) {
+        let gone: Vec<WatchDescriptor> = self
+            .watches
+            .iter()
+            .filter(|(_, path)| path.starts_with(dir))
+            .map(|(wd, _)| wd.clone())
+            .collect();
+        for wd in gone {
+            self.watches.remove(&wd);
+            let _ = self.inotify.rm_watch(wd);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::{self, Receiver};
+    use std::time::Instant;
+
+    /// Collect events until `expected` have all been seen (or give up after a few seconds)
+    fn wait_for(rx: &Receiver<ChangeEvent>, expected: &[ChangeEvent]) -> Vec<ChangeEvent> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut seen = Vec::new();
+        while !expected.iter().all(|e| seen.contains(e)) && Instant::now() < deadline {
+            if let Ok(event) = rx.recv_timeout(Duration::from_millis(100)) {
+                seen.push(event);
+            }
+        }
+        seen
+    }
+
+    #[test]
+    fn test_inotify_events() {
+        let root = std::env::temp_dir().join(format!("nothing_inotify_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("existing")).unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let mut source = InotifySource::new(&root);
+        source.start(tx).unwrap();
+
+        // Create, rename and delete a file
+        fs::write(root.join("a.txt"), b"hello").unwrap();
+        fs::rename(root.join("a.txt"), root.join("b.txt")).unwrap();
+        fs::remove_file(root.join("b.txt")).unwrap();
+        let expected = [
+            ChangeEvent::Created(root.join("a.txt")),
+            ChangeEvent::Renamed {
+                from: root.join("a.txt"),
+                to: root.join("b.txt"),
+            },
+            ChangeEvent::Deleted(root.join("b.txt")),
+        ];
+        let seen = wait_for(&rx, &expected);
+        for event in &expected {
+            assert!(seen.contains(event), "missing {:?} in {:?}", event, seen);
+        }
+
+        // New directories are watched too, including after a rename
+        fs::create_dir(root.join("new")).unwrap();
+        fs::rename(root.join("new"), root.join("renamed")).unwrap();
+        fs::write(root.join("renamed/inner.txt"), b"").unwrap();
+        fs::write(root.join("existing/deep.txt"), b"").unwrap();
+        let expected = [
+            ChangeEvent::Created(root.join("new")),
+            ChangeEvent::Renamed {
+                from: root.join("new"),
+                to: root.join("renamed"),
+            },
+            ChangeEvent::Created(root.join("renamed/inner.txt")),
+            ChangeEvent::Created(root.join("existing/deep.txt")),
+        ];
+        let seen = wait_for(&rx, &expected);
+        for event in &expected {
+            assert!(seen.contains(event), "missing {:?} in {:?}", event, seen);
+        }
+
+        // A directory moved out of the tree is reported as deleted
+        let outside = std::env::temp_dir().join(format!("nothing_inotify_out_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&outside);
+        fs::rename(root.join("renamed"), &outside).unwrap();
+        let expected = [ChangeEvent::Deleted(root.join("renamed"))];
+        let seen = wait_for(&rx, &expected);
+        assert!(seen.contains(&expected[0]), "missing {:?} in {:?}", expected[0], seen);
+
+        drop(source);
+        fs::remove_dir_all(&outside).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -1,9 +1,13 @@
-use crate::export;
-use crate::filters::SearchFilters;
+use crate::config::{self, Config, ExportConfig};
+use crate::content::{self, ContentSearch};
+use crate::export::{self, ExportFormat};
+use crate::filters::{extract_search_query, query_parts, SearchFilters};
 use crate::history::SearchHistory;
-use crate::index::FileIndex;
+use crate::index::{FileIndex, IndexView};
+use crate::ipc::IpcClient;
 use crate::metrics::SearchMetrics;
-use crate::search::SearchEngine;
+use crate::multi_volume::MultiVolumeIndex;
+use crate::search::{SearchEngine, SearchResult};
 use anyhow::Result;
 use crossterm::{
     cursor,
@@ -13,10 +17,10 @@ use crossterm::{
     terminal::{self, ClearType},
 };
 use std::io::{stdout, Write};
-use std::sync::{Arc, Mutex};
-use std::time::Instant;
-
-const RESULT_LIMIT: usize = 50;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 /// Format file size in human-readable format
 fn format_file_size(bytes: u64) -> String {
@@ -36,16 +40,64 @@ fn format_file_size(bytes: u64) -> String {
     }
 }
 
-/// Run interactive search mode with Arc<Mutex<>> index (for monitoring)
-pub fn run_interactive_search_with_arc(index: &Arc<Mutex<FileIndex>>) -> Result<()> {
+/// Where the interactive search gets its results
+pub trait SearchBackend {
+    /// Up to `limit` results for a query in the prompt's syntax (terms and filters)
+    ///
+    /// `None` if `cancel` was set before a content search finished.
+    fn search(&mut self, query: &str, limit: usize, cancel: &AtomicBool) -> Result<Option<Vec<SearchResult>>>;
+}
+
+/// Volumes loaded in this process
+struct LocalVolumes<'a, I> {
+    engine: SearchEngine,
+    volumes: &'a MultiVolumeIndex<I>,
+    content: ContentSearch,
+}
+
+impl<I: IndexView> SearchBackend for LocalVolumes<'_, I> {
+    fn search(&mut self, query: &str, limit: usize, cancel: &AtomicBool) -> Result<Option<Vec<SearchResult>>> {
+        // Volumes are locked one at a time while searching
+        perform_volume_search(&mut self.engine, self.volumes, query, limit, &self.content, cancel)
+    }
+}
+
+/// A running indexer, over IPC (content searches run in the indexer and
+/// are waited for)
+impl SearchBackend for IpcClient {
+    fn search(&mut self, query: &str, limit: usize, _cancel: &AtomicBool) -> Result<Option<Vec<SearchResult>>> {
+        IpcClient::search(self, query, limit).map(Some)
+    }
+}
+
+/// Run interactive search mode over shared volumes (for monitoring)
+pub fn run_interactive_search_with_arc<I: IndexView + Send>(
+    volumes: &Arc<MultiVolumeIndex<I>>,
+    config: &Config,
+    content: ContentSearch,
+) -> Result<()> {
+    let mut backend = LocalVolumes {
+        engine: SearchEngine::new(),
+        volumes: volumes.as_ref(),
+        content,
+    };
+    run_interactive_search_with(&mut backend, config)
+}
+
+/// Run interactive search mode with results from `backend` (local volumes or a running indexer)
+pub fn run_interactive_search_with(backend: &mut (dyn SearchBackend + Send), config: &Config) -> Result<()> {
+    let limit = config.search.result_limit;
     let mut stdout = stdout();
-    let mut search_engine = SearchEngine::new();
     let mut query = String::new();
     let mut history = SearchHistory::new()?;
     let mut metrics = SearchMetrics::new();
     let mut show_help = false;
     let mut show_stats = false;
     let mut last_results = Vec::new();
+    let mut last_duration = Duration::ZERO;
+
+    // A key pressed during a content search cancels it, then is handled
+    let mut pending_key = None;
 
     // Enable raw mode for character-by-character input
     terminal::enable_raw_mode()?;
@@ -55,56 +107,59 @@ pub fn run_interactive_search_with_arc(index: &Arc<Mutex<FileIndex>>) -> Result<
 
     loop {
         // Read keyboard events
-        if event::poll(std::time::Duration::from_millis(100))? {
-            if let Event::Key(key_event) = event::read()? {
-                match handle_key_event(key_event, &mut query, &mut history, &mut show_help, &mut show_stats)? {
-                    KeyAction::Exit => break,
-                    KeyAction::UpdateSearch => {
-                        // Lock index for searching
-                        let index_guard = index.lock().unwrap();
-                        let start = Instant::now();
-                        let results = perform_search(&mut search_engine, &*index_guard, &query)?;
-                        let duration = start.elapsed();
-                        metrics.record_search(duration, results.len());
-                        last_results = results.clone();
-                        drop(index_guard); // Release lock
-
-                        display_search_results(&mut stdout, &results, &query, duration, show_help, show_stats, &metrics)?;
-                    }
-                    KeyAction::Export => {
-                        // Export current results
-                        if !last_results.is_empty() {
-                            export_results(&mut stdout, &last_results)?;
-                            // Redisplay after export
-                            let index_guard = index.lock().unwrap();
-                            let start = Instant::now();
-                            let results = perform_search(&mut search_engine, &*index_guard, &query)?;
-                            let duration = start.elapsed();
-                            drop(index_guard);
-                            display_search_results(&mut stdout, &results, &query, duration, show_help, show_stats, &metrics)?;
-                        }
-                    }
-                    KeyAction::ToggleHelp => {
-                        show_help = !show_help;
-                        let index_guard = index.lock().unwrap();
-                        let start = Instant::now();
-                        let results = perform_search(&mut search_engine, &*index_guard, &query)?;
-                        let duration = start.elapsed();
-                        drop(index_guard);
-                        display_search_results(&mut stdout, &results, &query, duration, show_help, show_stats, &metrics)?;
-                    }
-                    KeyAction::ToggleStats => {
-                        show_stats = !show_stats;
-                        let index_guard = index.lock().unwrap();
-                        let start = Instant::now();
-                        let results = perform_search(&mut search_engine, &*index_guard, &query)?;
-                        let duration = start.elapsed();
-                        drop(index_guard);
-                        display_search_results(&mut stdout, &results, &query, duration, show_help, show_stats, &metrics)?;
+        let key_event = match pending_key.take() {
+            Some(key_event) => key_event,
+            None => {
+                if !event::poll(Duration::from_millis(100))? {
+                    continue;
+                }
+                let Event::Key(key_event) = event::read()? else {
+                    continue;
+                };
+                key_event
+            }
+        };
+
+        match handle_key_event(key_event, &mut query, &mut history, &mut show_help, &mut show_stats)? {
+            KeyAction::Exit => break,
+            KeyAction::UpdateSearch => {
+                let start = Instant::now();
+                let Some(results) = search_until_key(&mut stdout, backend, &query, limit, &mut pending_key)? else {
+                    if pending_key.is_none() {
+                        last_results.clear();
+                        display_search_results(&mut stdout, &last_results, &query, start.elapsed(), show_help, show_stats, &metrics)?;
+                        execute!(
+                            stdout,
+                            SetForegroundColor(Color::Yellow),
+                            Print("Content search cancelled (Enter to run it again)\n"),
+                            ResetColor,
+                        )?;
                     }
-                    KeyAction::None => {}
+                    continue;
+                };
+                last_duration = start.elapsed();
+                metrics.record_search(last_duration, results.len());
+                last_results = results;
+
+                display_search_results(&mut stdout, &last_results, &query, last_duration, show_help, show_stats, &metrics)?;
+            }
+            KeyAction::Export => {
+                // Export current results
+                if !last_results.is_empty() {
+                    export_results(&mut stdout, &last_results, &query, &config.export)?;
+                    // Redisplay after export
+                    display_search_results(&mut stdout, &last_results, &query, last_duration, show_help, show_stats, &metrics)?;
                 }
             }
+            KeyAction::ToggleHelp => {
+                show_help = !show_help;
+                display_search_results(&mut stdout, &last_results, &query, last_duration, show_help, show_stats, &metrics)?;
+            }
+            KeyAction::ToggleStats => {
+                show_stats = !show_stats;
+                display_search_results(&mut stdout, &last_results, &query, last_duration, show_help, show_stats, &metrics)?;
+            }
+            KeyAction::None => {}
         }
     }
 
@@ -148,7 +203,7 @@ pub fn run_interactive_search(index: &FileIndex) -> Result<()> {
                     KeyAction::Exit => break,
                     KeyAction::UpdateSearch => {
                         let start = Instant::now();
-                        let results = perform_search(&mut search_engine, index, &query)?;
+                        let results = perform_search(&mut search_engine, index, &query, config::DEFAULT_RESULT_LIMIT)?;
                         let duration = start.elapsed();
                         metrics.record_search(duration, results.len());
                         last_results = results.clone();
@@ -157,10 +212,10 @@ pub fn run_interactive_search(index: &FileIndex) -> Result<()> {
                     KeyAction::Export => {
                         // Export current results
                         if !last_results.is_empty() {
-                            export_results(&mut stdout, &last_results)?;
+                            export_results(&mut stdout, &last_results, &query, &ExportConfig::default())?;
                             // Redisplay after export
                             let start = Instant::now();
-                            let results = perform_search(&mut search_engine, index, &query)?;
+                            let results = perform_search(&mut search_engine, index, &query, config::DEFAULT_RESULT_LIMIT)?;
                             let duration = start.elapsed();
                             display_search_results(&mut stdout, &results, &query, duration, show_help, show_stats, &metrics)?;
                         }
@@ -168,14 +223,14 @@ pub fn run_interactive_search(index: &FileIndex) -> Result<()> {
                     KeyAction::ToggleHelp => {
                         show_help = !show_help;
                         let start = Instant::now();
-                        let results = perform_search(&mut search_engine, index, &query)?;
+                        let results = perform_search(&mut search_engine, index, &query, config::DEFAULT_RESULT_LIMIT)?;
                         let duration = start.elapsed();
                         display_search_results(&mut stdout, &results, &query, duration, show_help, show_stats, &metrics)?;
                     }
                     KeyAction::ToggleStats => {
                         show_stats = !show_stats;
                         let start = Instant::now();
-                        let results = perform_search(&mut search_engine, index, &query)?;
+                        let results = perform_search(&mut search_engine, index, &query, config::DEFAULT_RESULT_LIMIT)?;
                         let duration = start.elapsed();
                         display_search_results(&mut stdout, &results, &query, duration, show_help, show_stats, &metrics)?;
                     }
@@ -286,10 +341,11 @@ fn handle_key_event(
 }
 
 /// Perform search with filter parsing
-fn perform_search(
+fn perform_search<I: IndexView + ?Sized>(
     search_engine: &mut SearchEngine,
-    index: &FileIndex,
+    index: &I,
     query_str: &str,
+    limit: usize,
 ) -> Result<Vec<crate::search::SearchResult>> {
     // Parse filters from query
     let filters = SearchFilters::parse_filter_string(query_str).unwrap_or_default();
@@ -301,19 +357,75 @@ fn perform_search(
     let results = if search_query.is_empty() {
         Vec::new()
     } else {
-        search_engine.search_with_filters(index, &search_query, RESULT_LIMIT, &filters)
+        search_engine.search_with_filters(index, &search_query, limit, &filters)
     };
 
     Ok(results)
 }
 
-/// Extract search query without filter syntax
-fn extract_search_query(query: &str) -> String {
-    query
-        .split_whitespace()
-        .filter(|part| !part.contains(':'))
-        .collect::<Vec<_>>()
-        .join(" ")
+/// Perform search with filter parsing across all volumes
+fn perform_volume_search<I: IndexView>(
+    search_engine: &mut SearchEngine,
+    volumes: &MultiVolumeIndex<I>,
+    query_str: &str,
+    limit: usize,
+    content: &ContentSearch,
+    cancel: &AtomicBool,
+) -> Result<Option<Vec<crate::search::SearchResult>>> {
+    let filters = SearchFilters::parse_filter_string(query_str).unwrap_or_default();
+    let search_query = extract_search_query(query_str);
+
+    if search_query.is_empty() && filters.content.is_none() {
+        return Ok(Some(Vec::new()));
+    }
+    Ok(content::search(volumes, search_engine, &search_query, limit, &filters, content, cancel))
+}
+
+/// Search on another thread while watching the keyboard, so any key cancels
+/// a slow content search (and is left in `pending_key` to be handled)
+fn search_until_key(
+    stdout: &mut std::io::Stdout,
+    backend: &mut (dyn SearchBackend + Send),
+    query: &str,
+    limit: usize,
+    pending_key: &mut Option<KeyEvent>,
+) -> Result<Option<Vec<SearchResult>>> {
+    let cancel = AtomicBool::new(false);
+    let filters = SearchFilters::parse_filter_string(query).unwrap_or_default();
+    if filters.content.is_none() {
+        return backend.search(query, limit, &cancel);
+    }
+
+    execute!(
+        stdout,
+        Print("\n"),
+        SetForegroundColor(Color::DarkGrey),
+        Print("🔍 Searching file contents... (Esc or any key to cancel)"),
+        ResetColor,
+    )?;
+    stdout.flush()?;
+
+    thread::scope(|scope| {
+        let search = scope.spawn(|| backend.search(query, limit, &cancel));
+        while !search.is_finished() {
+            let pressed = event::poll(Duration::from_millis(50)).and_then(|ready| if ready { event::read().map(Some) } else { Ok(None) });
+            match pressed {
+                Ok(Some(Event::Key(key))) => {
+                    cancel.store(true, Ordering::Relaxed);
+                    if key.code != KeyCode::Esc {
+                        *pending_key = Some(key);
+                    }
+                    break;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    cancel.store(true, Ordering::Relaxed);
+                    return Err(e.into());
+                }
+            }
+        }
+        search.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    })
 }
 
 /// Display header
@@ -409,7 +521,8 @@ fn display_search_results(
     )?;
 
     // Show filter parts in different color
-    let filter_parts: Vec<&str> = query.split_whitespace()
+    let filter_parts: Vec<&str> = query_parts(query)
+        .into_iter()
         .filter(|p| p.contains(':'))
         .collect();
     if !filter_parts.is_empty() {
@@ -424,13 +537,13 @@ fn display_search_results(
 
     execute!(stdout, Print("\n\n"))?;
 
-    if search_query.is_empty() {
+    if search_query.is_empty() && filters.content.is_none() {
         stdout.flush()?;
         return Ok(());
     }
 
     // Count total matches (including those beyond limit)
-    let total_matches = results.len(); // This is limited to RESULT_LIMIT in the search
+    let total_matches = results.len(); // This is limited to the result limit in the search
 
     // Show match count
     if total_matches == 0 {
@@ -446,7 +559,7 @@ fn display_search_results(
             SetForegroundColor(Color::Green),
             Print(format!(
                 "Found matches (showing top {})\n\n",
-                RESULT_LIMIT.min(total_matches)
+                total_matches
             )),
             ResetColor,
         )?;
@@ -500,6 +613,18 @@ fn display_search_results(
                 ResetColor,
                 Print(format!(" (score: {})\n", result.score)),
             )?;
+
+            // Matching lines of a content search, grep-style
+            for found in &result.content {
+                let first = found.line_number - found.before.len();
+                for (i, line) in found.before.iter().enumerate() {
+                    display_content_line(stdout, first + i, '-', line, Color::DarkGrey)?;
+                }
+                display_content_line(stdout, found.line_number, ':', &found.line, Color::White)?;
+                for (i, line) in found.after.iter().enumerate() {
+                    display_content_line(stdout, found.line_number + 1 + i, '-', line, Color::DarkGrey)?;
+                }
+            }
         }
     }
 
@@ -507,6 +632,20 @@ fn display_search_results(
     Ok(())
 }
 
+/// One line of a file under a content search result
+fn display_content_line(stdout: &mut std::io::Stdout, number: usize, separator: char, line: &str, color: Color) -> Result<()> {
+    execute!(
+        stdout,
+        SetForegroundColor(Color::Yellow),
+        Print(format!("      {:>5}{} ", number, separator)),
+        SetForegroundColor(color),
+        Print(line),
+        ResetColor,
+        Print("\n"),
+    )?;
+    Ok(())
+}
+
 /// Display help text
 fn display_help(stdout: &mut std::io::Stdout) -> Result<()> {
     execute!(
@@ -526,11 +665,12 @@ fn display_help(stdout: &mut std::io::Stdout) -> Result<()> {
         Print("║   modified:7d        Modified in last 7 days                     ║\n"),
         Print("║   modified:>2024-01-01  Modified after Jan 1, 2024               ║\n"),
         Print("║   type:file          Files only (use type:dir for dirs)          ║\n"),
+        Print("║   content:\"TODO\"     Files containing TODO (any key cancels)     ║\n"),
         SetForegroundColor(Color::Yellow),
         Print("║ Keyboard Shortcuts:                                               ║\n"),
         ResetColor,
         Print("║   ↑/↓                Navigate search history                     ║\n"),
-        Print("║   Ctrl+E             Export results to CSV/JSON                  ║\n"),
+        Print("║   Ctrl+E             Export results or a report                  ║\n"),
         Print("║   F1                 Toggle this help                            ║\n"),
         Print("║   F2                 Toggle statistics                           ║\n"),
         Print("║   Ctrl+C             Exit                                        ║\n"),
@@ -586,7 +726,12 @@ fn display_stats(stdout: &mut std::io::Stdout, metrics: &SearchMetrics) -> Resul
 }
 
 /// Export results with user prompt
-fn export_results(stdout: &mut std::io::Stdout, results: &[crate::search::SearchResult]) -> Result<()> {
+fn export_results(
+    stdout: &mut std::io::Stdout,
+    results: &[crate::search::SearchResult],
+    query: &str,
+    export_config: &ExportConfig,
+) -> Result<()> {
     // Clear screen and show export prompt
     execute!(
         stdout,
@@ -599,6 +744,11 @@ fn export_results(stdout: &mut std::io::Stdout, results: &[crate::search::Search
         Print("Choose format:\n"),
         Print("  1. CSV\n"),
         Print("  2. JSON\n"),
+        Print("  3. NDJSON\n"),
+        Print("  4. TSV\n"),
+        Print("  5. HTML report\n"),
+        Print("  6. Markdown report\n"),
+        Print(format!("  Enter for default ({})\n", export_config.format.extension().to_uppercase())),
         Print("  ESC to cancel\n\n"),
         Print("Choice: "),
     )?;
@@ -608,42 +758,36 @@ fn export_results(stdout: &mut std::io::Stdout, results: &[crate::search::Search
     loop {
         if event::poll(std::time::Duration::from_millis(100))? {
             if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('1') => {
-                        let path = format!("search_results_{}.csv", chrono::Utc::now().format("%Y%m%d_%H%M%S"));
-                        export::export_csv(results, &path)?;
-                        execute!(
-                            stdout,
-                            Print("\n\n"),
-                            SetForegroundColor(Color::Green),
-                            Print(format!("✓ Exported to {}\n", path)),
-                            ResetColor,
-                            Print("\nPress any key to continue..."),
-                        )?;
-                        stdout.flush()?;
-                        event::read()?;
-                        break;
-                    }
-                    KeyCode::Char('2') => {
-                        let path = format!("search_results_{}.json", chrono::Utc::now().format("%Y%m%d_%H%M%S"));
-                        export::export_json(results, &path)?;
-                        execute!(
-                            stdout,
-                            Print("\n\n"),
-                            SetForegroundColor(Color::Green),
-                            Print(format!("✓ Exported to {}\n", path)),
-                            ResetColor,
-                            Print("\nPress any key to continue..."),
-                        )?;
-                        stdout.flush()?;
-                        event::read()?;
-                        break;
-                    }
-                    KeyCode::Esc => {
-                        break;
-                    }
-                    _ => {}
-                }
+                let format = match key.code {
+                    KeyCode::Char('1') => ExportFormat::Csv,
+                    KeyCode::Char('2') => ExportFormat::Json,
+                    KeyCode::Char('3') => ExportFormat::Ndjson,
+                    KeyCode::Char('4') => ExportFormat::Tsv,
+                    KeyCode::Char('5') => ExportFormat::Html,
+                    KeyCode::Char('6') => ExportFormat::Markdown,
+                    KeyCode::Enter => export_config.format,
+                    KeyCode::Esc => break,
+                    _ => continue,
+                };
+
+                let file_name = format!(
+                    "search_results_{}.{}",
+                    chrono::Utc::now().format("%Y%m%d_%H%M%S"),
+                    format.extension()
+                );
+                let path = export_config.path_for(&file_name).to_string_lossy().to_string();
+                export::export_results(results, &path, format, query)?;
+                execute!(
+                    stdout,
+                    Print("\n\n"),
+                    SetForegroundColor(Color::Green),
+                    Print(format!("✓ Exported to {}\n", path)),
+                    ResetColor,
+                    Print("\nPress any key to continue..."),
+                )?;
+                stdout.flush()?;
+                event::read()?;
+                break;
             }
         }
     }
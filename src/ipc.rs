@@ -0,0 +1,280 @@
+// Local IPC: length-prefixed JSON over a Unix domain socket (a named pipe on
+// Windows), so the CLI, GUI and editor plugins can share one indexer process
+use crate::api::{IndexService, Request};
+use crate::export::entry_from_json;
+use crate::filters::{extract_search_query, SearchFilters};
+use crate::index::{IndexView, LiveIndex};
+use crate::search::{SearchEngine, SearchResult};
+use anyhow::{anyhow, bail, Context, Result};
+use interprocess::local_socket::prelude::*;
+use interprocess::local_socket::{GenericFilePath, Listener, ListenerNonblockingMode, ListenerOptions, Name, Stream};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Socket file in the data directory (Unix)
+pub const SOCKET_FILE: &str = "nothing.sock";
+
+/// Named pipe the indexer listens on (Windows)
+pub const PIPE_NAME: &str = r"\\.\pipe\nothing";
+
+/// Largest message accepted, so a bad length prefix can't exhaust memory
+const MAX_MESSAGE: usize = 256 * 1024 * 1024;
+
+/// How often the accept loop checks whether it should stop
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Where the indexer listens
+pub fn socket_path() -> Result<PathBuf> {
+    if cfg!(windows) {
+        Ok(PathBuf::from(PIPE_NAME))
+    } else {
+        crate::persistence::data_file(SOCKET_FILE)
+    }
+}
+
+fn socket_name(path: &Path) -> Result<Name<'_>> {
+    path.as_os_str()
+        .to_fs_name::<GenericFilePath>()
+        .with_context(|| format!("Invalid socket path: {}", path.display()))
+}
+
+/// Write one message: a 4-byte little-endian length, then that many bytes of JSON
+pub fn write_message(writer: &mut impl Write, message: &impl Serialize) -> Result<()> {
+    let bytes = serde_json::to_vec(message)?;
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(&bytes)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read one message, or `None` if the other side closed the connection
+pub fn read_message<T: DeserializeOwned>(reader: &mut impl Read) -> Result<Option<T>> {
+    let mut length = [0u8; 4];
+    match reader.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+
+    let length = u32::from_le_bytes(length) as usize;
+    if length > MAX_MESSAGE {
+        bail!("Message too large: {} bytes", length);
+    }
+    let mut bytes = vec![0u8; length];
+    reader.read_exact(&mut bytes)?;
+    Ok(Some(serde_json::from_slice(&bytes)?))
+}
+
+/// Answers `api::Request` messages on the local socket
+///
+/// Each connection gets its own thread and search engine and may send any
+/// number of requests. Replies are the JSON the HTTP API returns; failures
+/// are `{"error": message, "status": code}`.
+pub struct IpcServer<I> {
+    listener: Listener,
+    path: PathBuf,
+    service: Arc<IndexService<I>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl<I: IndexView + LiveIndex + Send + 'static> IpcServer<I> {
+    /// Listen on `path`, replacing a socket file left behind by an indexer that is gone
+    pub fn bind(service: Arc<IndexService<I>>, path: &Path) -> Result<Self> {
+        if Stream::connect(socket_name(path)?).is_ok() {
+            bail!("Another indexer is already listening on {}", path.display());
+        }
+
+        let listener = ListenerOptions::new()
+            .name(socket_name(path)?)
+            .nonblocking(ListenerNonblockingMode::Accept)
+            .try_overwrite(true)
+            .create_sync()
+            .with_context(|| format!("Failed to listen on {}", path.display()))?;
+
+        Ok(Self {
+            listener,
+            path: path.to_path_buf(),
+            service,
+            stop: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Socket path (or pipe name) the server is listening on
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Flag that makes `run` return when set
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    /// Accept connections until the stop flag is set
+    pub fn run(&self) -> Result<()> {
+        while !self.stop.load(Ordering::Relaxed) {
+            match self.listener.accept() {
+                Ok(stream) => {
+                    let service = Arc::clone(&self.service);
+                    thread::spawn(move || serve_connection(&service, stream));
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+                Err(e) => return Err(e).with_context(|| format!("Failed to accept on {}", self.path.display())),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Answer requests on one connection until the client hangs up
+fn serve_connection<I: IndexView + LiveIndex + Send + 'static>(service: &IndexService<I>, mut stream: Stream) {
+    let mut engine = SearchEngine::new();
+    loop {
+        let reply = match read_message::<Value>(&mut stream) {
+            Ok(None) => return,
+            Ok(Some(message)) => match serde_json::from_value::<Request>(message) {
+                Ok(request) => service
+                    .handle(&mut engine, &request)
+                    .unwrap_or_else(|e| json!({ "error": e.message, "status": e.status })),
+                Err(e) => json!({ "error": format!("Invalid request: {}", e), "status": 400 }),
+            },
+            // The stream is out of step or gone; nothing more can be read from it
+            Err(_) => return,
+        };
+        if write_message(&mut stream, &reply).is_err() {
+            return;
+        }
+    }
+}
+
+/// Connection to a running indexer
+pub struct IpcClient {
+    stream: Stream,
+}
+
+impl IpcClient {
+    /// Connect to the indexer listening on `path`
+    pub fn connect(path: &Path) -> Result<Self> {
+        let stream = Stream::connect(socket_name(path)?)
+            .with_context(|| format!("No indexer is listening on {}", path.display()))?;
+        Ok(Self { stream })
+    }
+
+    /// Send a request and wait for the reply
+    pub fn request(&mut self, request: &Request) -> Result<Value> {
+        write_message(&mut self.stream, request)?;
+        let reply: Value = read_message(&mut self.stream)?.ok_or_else(|| anyhow!("The indexer closed the connection"))?;
+        match reply.get("error").and_then(Value::as_str) {
+            Some(message) => bail!("{}", message),
+            None => Ok(reply),
+        }
+    }
+
+    /// Search like the interactive prompt does: invalid filters are ignored
+    /// and a query without search terms (or a `content:` pattern) matches nothing
+    pub fn search(&mut self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let terms = extract_search_query(query);
+        let filters = SearchFilters::parse_filter_string(query);
+        let has_content = filters.as_ref().is_ok_and(|filters| filters.content.is_some());
+        if terms.is_empty() && !has_content {
+            return Ok(Vec::new());
+        }
+        let query = if filters.is_ok() { query.to_string() } else { terms };
+
+        let reply = self.request(&Request::Search { query, limit, offset: 0 })?;
+        let results = reply["results"].as_array().cloned().unwrap_or_default();
+        Ok(results
+            .iter()
+            .filter_map(|result| {
+                let entry = entry_from_json(result)?;
+                let score = result["score"].as_u64().unwrap_or(0) as u32;
+                let content = serde_json::from_value(result["matches"].clone()).unwrap_or_default();
+                Some(SearchResult { entry, score, content })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_entry::FileEntry;
+    use crate::index::FileIndex;
+    use crate::multi_volume::MultiVolumeIndex;
+
+    #[test]
+    fn test_message_framing() {
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &Request::Status).unwrap();
+        write_message(&mut buffer, &json!({"count": 3})).unwrap();
+        assert_eq!(&buffer[..4], &20u32.to_le_bytes());
+
+        let mut reader = buffer.as_slice();
+        assert_eq!(read_message::<Request>(&mut reader).unwrap(), Some(Request::Status));
+        assert_eq!(read_message::<Value>(&mut reader).unwrap(), Some(json!({"count": 3})));
+        assert_eq!(read_message::<Value>(&mut reader).unwrap(), None);
+
+        // A length prefix beyond the limit is refused before allocating
+        let huge = (u32::MAX).to_le_bytes();
+        assert!(read_message::<Value>(&mut huge.as_slice()).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_client_server() {
+        let dir = std::env::temp_dir().join(format!("nothing_ipc_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(SOCKET_FILE);
+
+        let mut index = FileIndex::new();
+        for (id, name) in [(1, "report.pdf"), (2, "report.txt"), (3, "notes.md")] {
+            index.add_entry(FileEntry::new(
+                name.to_string(),
+                format!("/data/{}", name),
+                false,
+                id,
+                0,
+                id * 100,
+                None,
+                None,
+                None,
+            ));
+        }
+        let volumes = Arc::new(MultiVolumeIndex::new());
+        volumes.add_volume("data", None, index);
+
+        let server = IpcServer::bind(Arc::new(IndexService::new(volumes)), &path).unwrap();
+        let stop = server.stop_handle();
+        let handle = thread::spawn(move || server.run().unwrap());
+
+        // A second indexer can't take over the socket
+        let service = Arc::new(IndexService::new(Arc::new(MultiVolumeIndex::<FileIndex>::new())));
+        assert!(IpcServer::bind(service, &path).is_err());
+
+        let mut client = IpcClient::connect(&path).unwrap();
+        let results = client.search("report ext:pdf", 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].entry.path, "/data/report.pdf");
+        assert_eq!(results[0].entry.size, 100);
+
+        // Bad filters are dropped, as in the local prompt; no terms, no results
+        assert_eq!(client.search("report size:huge", 10).unwrap().len(), 2);
+        assert!(client.search("ext:pdf", 10).unwrap().is_empty());
+
+        let status = client.request(&Request::Status).unwrap();
+        assert_eq!(status["total_files"], 3);
+        let error = client.request(&Request::Entry { file_id: 9, volume: None }).unwrap_err();
+        assert!(error.to_string().contains("No entry"), "{}", error);
+
+        stop.store(true, Ordering::Relaxed);
+        handle.join().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
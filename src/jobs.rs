@@ -0,0 +1,153 @@
+// Background work on volumes (loads, scans, reconciliations, saves), tracked
+// so clients can see what the indexer is doing
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+
+/// Kind of work running on a volume
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobKind {
+    /// Reading the volume's index file
+    Load,
+
+    /// Full scan (initial, scheduled, or after large drift)
+    Scan,
+
+    /// Comparing the index with the disk and applying the difference
+    Reconcile,
+
+    /// Writing the volume's index file
+    Save,
+}
+
+/// Work in progress on a volume
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub kind: JobKind,
+    pub started: DateTime<Utc>,
+}
+
+/// A volume's background work: what is running and when things last finished
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VolumeJobs {
+    /// Job in progress (one at a time per volume)
+    pub running: Option<Job>,
+
+    /// When the last full scan finished
+    pub last_scan: Option<DateTime<Utc>>,
+
+    /// When the last reconciliation finished
+    pub last_reconcile: Option<DateTime<Utc>>,
+
+    /// When the index file was last written
+    pub last_save: Option<DateTime<Utc>>,
+
+    /// Why the most recent job failed (cleared by the next success)
+    pub last_error: Option<String>,
+}
+
+/// Per-volume job state, shared between the workers and the status query
+#[derive(Debug, Default)]
+pub struct JobTracker {
+    volumes: Mutex<BTreeMap<String, VolumeJobs>>,
+}
+
+impl JobTracker {
+    /// List a volume before any work has started on it
+    pub fn register(&self, volume: &str) {
+        self.volumes.lock().unwrap().entry(volume.to_string()).or_default();
+    }
+
+    /// Start a job, unless one is already running on the volume
+    pub fn start(self: &Arc<Self>, volume: &str, kind: JobKind) -> Option<JobGuard> {
+        let mut volumes = self.volumes.lock().unwrap();
+        let state = volumes.entry(volume.to_string()).or_default();
+        if state.running.is_some() {
+            return None;
+        }
+        state.running = Some(Job {
+            kind,
+            started: Utc::now(),
+        });
+
+        Some(JobGuard {
+            tracker: Arc::clone(self),
+            volume: volume.to_string(),
+            kind,
+            error: None,
+        })
+    }
+
+    /// Current state of every volume
+    pub fn snapshot(&self) -> BTreeMap<String, VolumeJobs> {
+        self.volumes.lock().unwrap().clone()
+    }
+}
+
+/// A running job; it finishes when dropped
+pub struct JobGuard {
+    tracker: Arc<JobTracker>,
+    volume: String,
+    kind: JobKind,
+    error: Option<String>,
+}
+
+impl JobGuard {
+    /// Record that the job failed
+    pub fn fail(&mut self, error: impl Display) {
+        self.error = Some(error.to_string());
+    }
+}
+
+impl Drop for JobGuard {
+    fn drop(&mut self) {
+        let mut volumes = self.tracker.volumes.lock().unwrap();
+        let state = volumes.entry(self.volume.clone()).or_default();
+        state.running = None;
+
+        if let Some(error) = self.error.take() {
+            state.last_error = Some(error);
+            return;
+        }
+        state.last_error = None;
+        let now = Some(Utc::now());
+        match self.kind {
+            JobKind::Load => {}
+            JobKind::Scan => state.last_scan = now,
+            JobKind::Reconcile => state.last_reconcile = now,
+            JobKind::Save => state.last_save = now,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_job_per_volume() {
+        let tracker = Arc::new(JobTracker::default());
+        tracker.register("D");
+
+        let scan = tracker.start("C", JobKind::Scan).unwrap();
+        assert!(tracker.start("C", JobKind::Save).is_none());
+        assert_eq!(tracker.snapshot()["C"].running.as_ref().unwrap().kind, JobKind::Scan);
+        drop(scan);
+
+        let state = &tracker.snapshot()["C"];
+        assert!(state.running.is_none());
+        assert!(state.last_scan.is_some());
+
+        let mut save = tracker.start("C", JobKind::Save).unwrap();
+        save.fail("disk full");
+        drop(save);
+        let state = &tracker.snapshot()["C"];
+        assert_eq!(state.last_error.as_deref(), Some("disk full"));
+        assert!(state.last_save.is_none());
+
+        assert!(tracker.snapshot()["D"].running.is_none());
+    }
+}
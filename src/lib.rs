@@ -1,7 +1,34 @@
 // Library exports for use by binaries and tests
+pub mod activity_log;
+pub mod api;
+pub mod archive;
+pub mod change_source;
+pub mod config;
+pub mod content;
+pub mod content_index;
+pub mod diff;
+pub mod dir_walker;
 pub mod export;
 pub mod file_entry;
+pub mod file_type;
 pub mod filters;
+pub mod import;
 pub mod index;
+pub mod index_format;
+#[cfg(target_os = "linux")]
+pub mod inotify_source;
+pub mod ipc;
+pub mod jobs;
+pub mod mapped_index;
+pub mod monitor;
+pub mod multi_volume;
 pub mod persistence;
+pub mod reconcile;
+pub mod report;
+pub mod scanner;
 pub mod search;
+pub mod security;
+pub mod server;
+pub mod snapshots;
+pub mod sqlite_export;
+pub mod timeline;
@@ -1,38 +1,77 @@
-mod cloud_monitor;
+mod activity_log;
+mod api;
+mod archive;
+mod change_source;
+mod config;
+mod content;
+mod content_index;
+mod daemon;
+mod diff;
+mod dir_walker;
 mod error;
 mod export;
 mod file_entry;
+mod file_type;
 mod filters;
 mod gui;
 mod history;
+mod import;
 mod index;
+mod index_format;
+#[cfg(target_os = "linux")]
+mod inotify_source;
 mod interactive;
+mod ipc;
+mod jobs;
+mod mapped_index;
 mod metrics;
+#[cfg(windows)]
 mod mft_reader;
+#[cfg(windows)]
 mod mft_reader_ntfs;
+mod monitor;
 mod multi_drive;
+mod multi_volume;
 mod persistence;
+mod reconcile;
+mod report;
+mod scanner;
 mod search;
+mod search_cli;
+mod security;
+mod server;
+mod snapshots;
+#[cfg(windows)]
 mod sector_aligned_reader;
-mod usn_monitor;
+mod sqlite_export;
+mod timeline;
+#[cfg(windows)]
 mod volume_test;
 
-use anyhow::Result;
-use clap::Parser;
-use index::FileIndex;
-use mft_reader::MftReader;
-use mft_reader_ntfs::MftReaderNtfs;
-use std::sync::{Arc, Mutex};
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use config::{ArchiveConfig, Config, ScanMode};
+use content::ContentSearch;
+use content_index::ContentIndex;
+use index::{FileIndex, IndexView};
+use mapped_index::MappedIndex;
+use multi_volume::MultiVolumeIndex;
+use scanner::VolumeSource;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
 
 #[derive(Parser, Debug)]
 #[command(name = "nothing")]
 #[command(author = "Nothing Team")]
 #[command(version = "0.6.1")]
-#[command(about = "Fast Windows file search tool - reads NTFS MFT directly", long_about = None)]
+#[command(about = "Fast file search tool - reads the NTFS MFT directly on Windows, walks directories elsewhere", long_about = None)]
 struct Args {
-    /// Drive letter to scan (e.g., C, D, E)
-    #[arg(default_value = "C")]
-    drive: char,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Drive letter to scan (e.g., C, D, E; default: drives from the config file, or C)
+    drive: Option<char>,
 
     /// Start interactive search mode after scanning
     #[arg(short, long)]
@@ -42,6 +81,14 @@ struct Args {
     #[arg(short = 'f', long)]
     full_metadata: bool,
 
+    /// Read the first bytes of each file to detect its type (for kind:, mime: and ext-mismatch:)
+    #[arg(long)]
+    detect_types: bool,
+
+    /// List the members of ZIP, 7z and TAR archives as entries below each archive
+    #[arg(long)]
+    index_archives: bool,
+
     /// Run volume access tests
     #[arg(long)]
     test_volume: bool,
@@ -57,101 +104,406 @@ struct Args {
     /// Launch GUI mode instead of CLI
     #[arg(short = 'g', long)]
     gui: bool,
+
+    /// Index a directory tree instead of a drive (repeatable; default on non-Windows: /)
+    #[arg(short = 'r', long = "root")]
+    roots: Vec<PathBuf>,
+
+    /// Index file compression: zstd, lz4 or none (default: from the config file, or zstd)
+    #[arg(long)]
+    compression: Option<index_format::Compression>,
+
+    /// Compression level (zstd: 1-22; default: from the config file, or 3)
+    #[arg(long)]
+    compression_level: Option<i32>,
+
+    /// Directory for indexes, history and the config file (overrides NOTHING_HOME)
+    #[arg(long)]
+    data_dir: Option<PathBuf>,
+
+    /// Memory-map the cached indexes and search them in place (interactive CLI)
+    #[arg(long)]
+    mmap: bool,
+
+    /// Load the indexes in this process even when an indexer (`nothing serve` or `nothing daemon`) is running
+    #[arg(long)]
+    standalone: bool,
+
+    /// Snapshot retention, from the config file
+    #[arg(skip)]
+    snapshots: config::SnapshotConfig,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Keep the indexes loaded and live, and answer queries over a local HTTP/JSON API
+    Serve {
+        /// Port on 127.0.0.1 (default: from the config file, or 7590)
+        #[arg(long)]
+        port: Option<u16>,
+
+        /// Token clients must send as `Authorization: Bearer <token>` (default: from the config file)
+        #[arg(long)]
+        token: Option<String>,
+    },
+
+    /// Run the background indexer: scan, monitor, rescan and save all configured volumes on a schedule
+    Daemon {
+        /// Port on 127.0.0.1 for the HTTP API (default: from the config file, or 7590)
+        #[arg(long)]
+        port: Option<u16>,
+
+        /// Token HTTP clients must send (default: from the config file)
+        #[arg(long)]
+        token: Option<String>,
+    },
+
+    /// Run one query against the cached indexes, print the matches and exit (1 if nothing matched)
+    Search {
+        /// Search terms and filters, as typed in the interactive prompt (e.g. "report ext:pdf size:>1mb")
+        query: String,
+
+        /// Most results to print (default: from the config file, or 50)
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+
+        /// Output: plain, json, csv, tsv, ndjson or null-separated
+        #[arg(long, default_value = "plain")]
+        format: export::ExportFormat,
+
+        /// Comma-separated columns for csv, tsv, json and ndjson (e.g. "path,size,modified")
+        #[arg(long)]
+        columns: Option<String>,
+
+        /// Order: score, name, path, size (largest first) or modified (newest first)
+        #[arg(long, default_value = "score")]
+        sort: search_cli::SortKey,
+
+        /// Search the snapshots as of this time instead (e.g. 2024-06-11, tuesday, 2d)
+        #[arg(long)]
+        at: Option<String>,
+    },
+
+    /// Export every entry of the cached indexes that passes a filter, streamed without a result limit
+    Export {
+        /// Filters such as "ext:pdf size:>1mb" (search terms limit the export to their matches)
+        #[arg(default_value = "")]
+        query: String,
+
+        /// Output: ndjson, csv, tsv, json, plain, null-separated, html, markdown, bodyfile or timeline
+        #[arg(long, default_value = "ndjson")]
+        format: export::ExportFormat,
+
+        /// Comma-separated columns (default: all for json and ndjson, the CSV export's for csv and tsv)
+        #[arg(long)]
+        columns: Option<String>,
+
+        /// File to write (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Write to (or update) this SQLite database instead; takes filters only
+        #[arg(long, conflicts_with_all = ["columns", "output"])]
+        sqlite: Option<PathBuf>,
+
+        /// Export the snapshots as of this time instead (e.g. 2024-06-11, tuesday, 2d)
+        #[arg(long)]
+        at: Option<String>,
+    },
+
+    /// List the dated snapshots of each volume's index
+    Snapshots,
+
+    /// Compare two index files of a volume and list what changed (exit code 1 if anything did)
+    Diff {
+        /// Older index file (e.g. yesterday's index_C.bin)
+        old: PathBuf,
+
+        /// Newer index file
+        new: PathBuf,
+
+        /// Output: plain, csv, tsv, json, ndjson or null-separated
+        #[arg(long, default_value = "plain")]
+        format: export::ExportFormat,
+
+        /// Match entries by path only, not by file id (for imported listings)
+        #[arg(long)]
+        by_path: bool,
+
+        /// File to write (default: stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Import a file listing from another tool or machine as a read-only volume (no arguments: list them)
+    Import {
+        /// Listing: Everything EFU/CSV, our CSV/TSV/JSON/NDJSON export, find or dir /s output
+        listing: Option<PathBuf>,
+
+        /// Listing format: csv, efu, json, ndjson, find or dir (default: detected)
+        #[arg(long)]
+        format: Option<import::ImportFormat>,
+
+        /// Volume name (default: the listing's file name)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Remove an imported volume
+        #[arg(long, conflicts_with_all = ["listing", "format", "name"])]
+        remove: Option<String>,
+    },
+}
+
+impl Args {
+    /// Fill in everything not given on the command line from the config file
+    fn apply_config(&mut self, config: &Config) {
+        self.full_metadata |= config.scan.mode == ScanMode::Full;
+        self.include_cloud |= config.scan.include_cloud;
+        self.detect_types |= config.scan.detect_types;
+        self.index_archives |= config.archives.enabled;
+        self.compression.get_or_insert(config.storage.compression);
+        self.compression_level.get_or_insert(config.storage.compression_level);
+        self.snapshots = config.snapshots;
+    }
+
+    /// Volumes to scan and monitor
+    fn volume_sources(&self, config: &Config) -> Vec<VolumeSource> {
+        let mut sources: Vec<VolumeSource> = self.roots.iter().cloned().map(VolumeSource::Directory).collect();
+        if let Some(drive) = self.drive {
+            sources.push(VolumeSource::Drive(drive));
+        } else if self.all_drives {
+            sources.extend(multi_drive::get_all_drives().into_iter().map(VolumeSource::Drive));
+        }
+        if !sources.is_empty() {
+            return sources;
+        }
+
+        // Nothing on the command line: use the config file
+        sources.extend(config.scan.drives.iter().copied().map(VolumeSource::Drive));
+        sources.extend(config.scan.roots.iter().cloned().map(VolumeSource::Directory));
+        if !sources.is_empty() {
+            return sources;
+        }
+
+        if cfg!(windows) {
+            vec![VolumeSource::Drive('C')]
+        } else {
+            vec![VolumeSource::Directory(PathBuf::from("/"))]
+        }
+    }
+
+    /// How index files should be written
+    fn storage_options(&self) -> persistence::StorageOptions {
+        let defaults = persistence::StorageOptions::default();
+        persistence::StorageOptions {
+            compression: self.compression.unwrap_or(defaults.compression),
+            level: self.compression_level.unwrap_or(defaults.level),
+            snapshots: self.snapshots,
+        }
+    }
 }
 
 fn main() -> Result<()> {
-    let args = Args::parse();
+    let mut args = Args::parse();
+
+    // The data directory decides where the config file is, so it comes first
+    if let Some(dir) = &args.data_dir {
+        config::set_data_dir(dir.clone())?;
+    }
+    let config = Config::load()?;
+    args.apply_config(&config);
 
     // Run volume tests if requested
     if args.test_volume {
-        volume_test::run_all_tests(args.drive);
+        #[cfg(windows)]
+        volume_test::run_all_tests(args.drive.unwrap_or('C'));
+        #[cfg(not(windows))]
+        eprintln!("Volume tests read NTFS volumes and only run on Windows");
         return Ok(());
     }
 
-    // Determine which drives to monitor
-    let drives: Vec<char> = if args.all_drives {
-        multi_drive::get_all_drives()
-    } else {
-        vec![args.drive]
-    };
+    // Search a memory-mapped index without loading it
+    if args.mmap {
+        return run_mapped(&args, &config);
+    }
+
+    // One-shot search: results on stdout, grep-style exit code
+    if let Some(Command::Search { query, limit, format, columns, sort, at }) = &args.command {
+        let limit = limit.unwrap_or(config.search.result_limit);
+        let matched = columns.as_deref().map(export::parse_columns).transpose().and_then(|columns| {
+            let at = at.as_deref().map(snapshots::parse_when).transpose()?;
+            let volumes = load_cached_volumes(&args.volume_sources(&config), at)?;
+            search_cli::run(&volumes, query, limit, *format, *sort, columns.as_deref(), &open_content_search(&config))
+        });
+        match matched {
+            Ok(true) => return Ok(()),
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    // Streamed export of whole indexes
+    if let Some(Command::Export { query, format, columns, output, sqlite, at }) = &args.command {
+        let columns = columns.as_deref().map(export::parse_columns).transpose()?;
+        let at = at.as_deref().map(snapshots::parse_when).transpose()?;
+        let volumes = load_cached_volumes(&args.volume_sources(&config), at)?;
+        if let Some(path) = sqlite {
+            return search_cli::export_sqlite(&volumes, query, path);
+        }
+        let content = open_content_search(&config);
+        let count = search_cli::export(&volumes, query, *format, columns.as_deref(), output.as_deref(), &content)?;
+        if let Some(path) = output {
+            eprintln!("✅ Exported {} entries to {}", count, path.display());
+        }
+        return Ok(());
+    }
+
+    // Changes between two index files, grep-style exit code like `nothing search`
+    if let Some(Command::Diff { old, new, format, by_path, output }) = &args.command {
+        let by = if *by_path { diff::MatchBy::Path } else { diff::MatchBy::FileId };
+        match diff_index_files(old, new, *format, by, output.as_deref()) {
+            Ok(false) => return Ok(()),
+            Ok(true) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("Error: {:#}", e);
+                std::process::exit(2);
+            }
+        }
+    }
 
-    // Try to load cached indexes
-    let mut index = FileIndex::new();
+    if let Some(Command::Snapshots) = &args.command {
+        return list_snapshots(&args.volume_sources(&config), &config.snapshots);
+    }
 
-    // Load indexes only for requested drives (respects command line argument)
-    let drives_to_load: Vec<char> = if args.gui {
+    // Listings from other machines, kept as read-only volumes
+    if let Some(Command::Import { listing, format, name, remove }) = &args.command {
+        return match (listing, remove) {
+            (Some(listing), _) => import_listing(listing, *format, name.as_deref(), &args.storage_options()),
+            (None, Some(name)) => remove_imported(name),
+            (None, None) => list_imported(),
+        };
+    }
+
+    // The daemon loads, scans and saves the volumes itself
+    if let Some(Command::Daemon { port, token }) = &args.command {
+        init_activity_log();
+        let options = daemon::DaemonOptions {
+            full_metadata: args.full_metadata,
+            detect_types: args.detect_types,
+            archives: args.index_archives.then(|| config.archives.clone()),
+            storage: args.storage_options(),
+            port: port.unwrap_or(config.server.port),
+            token: token.clone().or_else(|| config.server.token.clone()),
+        };
+        return daemon::run(args.volume_sources(&config), &config, options);
+    }
+
+    // Search through a running indexer (`nothing serve` or `nothing daemon`) instead of loading the indexes again
+    if args.interactive && !args.gui && !args.standalone && args.command.is_none() {
+        let path = ipc::socket_path()?;
+        if let Ok(client) = ipc::IpcClient::connect(&path) {
+            return run_with_indexer(client, &path, &config);
+        }
+    }
+
+    let storage = args.storage_options();
+    let exclusions = config.exclusions();
+
+    // Determine which volumes to monitor
+    let sources = args.volume_sources(&config);
+
+    // Each drive or root directory is its own volume with its own index file
+    let volumes = Arc::new(MultiVolumeIndex::new());
+
+    // Load indexes only for requested volumes (respects command line argument)
+    let sources_to_load: Vec<VolumeSource> = if args.gui && args.drive.is_none() && args.roots.is_empty() && cfg!(windows) {
         // GUI mode: load all available drives
-        multi_drive::get_all_drives()
+        multi_drive::get_all_drives().into_iter().map(VolumeSource::Drive).collect()
     } else {
-        // CLI mode: only load the requested drive(s)
-        drives.clone()
+        // CLI mode: only load the requested volume(s)
+        sources.clone()
     };
 
-    let mut loaded_count = 0;
-    let mut total_files = 0;
-    let mut total_dirs = 0;
-
-    for drive in &drives_to_load {
-        if let Ok(cache_path) = persistence::get_index_path(*drive) {
+    for source in &sources_to_load {
+        let name = source.name();
+        if let Ok(cache_path) = persistence::get_volume_index_path(&name) {
             if std::path::Path::new(&cache_path).exists() {
-                match persistence::load_index(&cache_path) {
-                    Ok(drive_index) => {
-                        let files = drive_index.file_count();
-                        let dirs = drive_index.directory_count();
-                        println!("✅ Loaded {} drive: {} files, {} directories", drive, files, dirs);
-
-                        // Merge this drive's index into the main index
-                        for entry in drive_index.entries() {
-                            index.add_entry(entry.clone());
-                        }
-
-                        loaded_count += 1;
-                        total_files += files;
-                        total_dirs += dirs;
+                match volumes.load_volume(&name, &cache_path) {
+                    Ok(volume_index) => {
+                        let volume_index = volume_index.lock().unwrap();
+                        println!("✅ Loaded {}: {} files, {} directories",
+                                 name, volume_index.file_count(), volume_index.directory_count());
                     }
                     Err(e) => {
-                        eprintln!("⚠️  Failed to load {} drive index: {}", drive, e);
+                        eprintln!("⚠️  Failed to load {} index: {}", name, e);
                     }
                 }
             }
         }
     }
 
-    if loaded_count > 0 {
-        println!("\n📊 Combined Index: {} drives, {} total files, {} directories",
-                 loaded_count, total_files, total_dirs);
+    match volumes.load_imported() {
+        Ok(imported) => {
+            warn_skipped_imports(&imported.skipped);
+            for name in imported.names {
+                if let Some(volume) = volumes.volume(&name) {
+                    let index = volume.index.lock().unwrap();
+                    println!("✅ Loaded {} (imported): {} files, {} directories",
+                             name, index.file_count(), index.directory_count());
+                }
+            }
+        }
+        Err(e) => eprintln!("⚠️  Failed to load imported volumes: {}", e),
+    }
+
+    if volumes.volume_count() > 0 {
+        println!("\n📊 Combined Index: {} volumes, {} total files, {} directories",
+                 volumes.volume_count(), volumes.file_count(), volumes.directory_count());
     } else {
         println!("No cached indexes found.");
     }
 
-    // If index is empty, scan drives
-    if index.is_empty() {
-        if args.all_drives {
-            println!("Scanning all fixed drives...\n");
-            multi_drive::scan_all_fixed_drives(&mut index, args.full_metadata)?;
-        } else {
-            // Single drive mode
+    // Scan requested volumes that have no cached index
+    if args.all_drives && args.drive.is_none() && cfg!(windows) {
+        println!("Scanning all fixed drives...\n");
+        multi_drive::scan_all_fixed_drives(&volumes, args.full_metadata, &storage, &exclusions, &scanner::print_progress())?;
+    }
+    for source in &sources {
+        if volumes.volume(&source.name()).is_some() {
+            continue;
+        }
+        // With -a, drives that are not fixed are monitored but never scanned
+        if args.all_drives && args.drive.is_none() && matches!(source, VolumeSource::Drive(_)) {
+            continue;
+        }
+
+        if let VolumeSource::Drive(_) = source {
             if args.full_metadata {
                 println!("Using full metadata mode (includes sizes and timestamps)");
-                let reader = MftReaderNtfs::new(args.drive)?;
-                reader.scan_into_index(&mut index)?;
             } else {
                 println!("Using fast mode (names and paths only)");
-                let reader = MftReader::new(args.drive)?;
-                reader.scan_into_index(&mut index)?;
             }
         }
 
-        // Save index for next time
-        if !drives.is_empty() {
-            println!("\nSaving index to disk...");
-            let cache_path = persistence::get_index_path(drives[0])?;
-            persistence::save_index(&index, &cache_path)?;
-            println!("✅ Index saved");
-        }
+        let stats = multi_drive::scan_volume(&volumes, source, args.full_metadata, &storage, &exclusions, &scanner::print_progress())?;
+        print_storage_stats(&stats);
+    }
+
+    // Types of files not sniffed yet, saved with the volumes
+    if args.detect_types {
+        detect_volume_types(&volumes, &sources, &storage)?;
     }
 
-    // Add cloud storage if requested
+    // Members of archives not listed yet, saved with the volumes
+    if args.index_archives {
+        index_volume_archives(&volumes, &sources, &config.archives, &storage)?;
+    }
+
+    // Add cloud storage if requested (rebuilt every run, never saved)
+    let mut watched_folders = Vec::new();
     if args.include_cloud {
         println!("\n=== Scanning Cloud Storage ===");
         let cloud_folders = multi_drive::find_cloud_storage_folders();
@@ -160,59 +512,468 @@ fn main() -> Result<()> {
             println!("No cloud storage folders found.");
         } else {
             for (provider, path) in cloud_folders {
-                match multi_drive::index_cloud_storage_folder(&mut index, provider, &path) {
-                    Ok(count) => println!("Added {} files from {:?}", count, provider),
+                let mut index = FileIndex::new();
+                match multi_drive::index_cloud_storage_folder(&mut index, provider, &path, &scanner::print_progress()) {
+                    Ok(count) => {
+                        println!("Added {} files from {:?}", count, provider);
+                        let name = cloud_volume_name(&volumes, provider);
+                        let index = volumes.add_volume(&name, None, index);
+                        watched_folders.push((name, path, index));
+                    }
                     Err(e) => eprintln!("Failed to index {:?}: {}", provider, e),
                 }
             }
         }
     }
 
-    // Enter interactive mode (CLI or GUI) with monitoring if requested
-    if args.interactive || args.gui {
-        // Wrap index in Arc<Mutex<>> for thread-safe access
-        let index_arc = Arc::new(Mutex::new(index));
-        let index_clone = Arc::clone(&index_arc);
+    // Enter interactive mode (CLI or GUI) or serve, with monitoring
+    if args.interactive || args.gui || args.command.is_some() {
+        // Start monitoring, each volume updating its own index
+        let mut roots: Vec<(String, PathBuf)> = sources.iter().map(|s| (s.name(), s.root())).collect();
+        let mut watched = monitored_volumes(&volumes, &sources);
+        for (name, path, index) in watched_folders {
+            roots.push((name, path.clone()));
+            watched.push((change_source::for_path(path), index));
+        }
+        let content = open_content_search(&config);
+        let monitor = start_monitor(watched, &config, &content)?;
+
+        // Run interactive search (GUI or CLI), or the API server
+        if let Some(Command::Serve { port, token }) = &args.command {
+            let service = api::IndexService::new(Arc::clone(&volumes))
+                .monitor(monitor.status())
+                .rescan_roots(roots, exclusions.clone())
+                .content(content.clone());
+            let port = port.unwrap_or(config.server.port);
+            let token = token.clone().or_else(|| config.server.token.clone());
+            serve(Arc::new(service), port, token)?;
+        } else if args.gui {
+            println!("\nLaunching GUI...\n");
+            gui::run(Arc::clone(&volumes), config.clone(), monitor.status(), content.clone())?;
+        } else {
+            println!("\nEntering interactive search mode with real-time monitoring...\n");
+            interactive::run_interactive_search_with_arc(&volumes, &config, content.clone())?;
+        }
+
+        // Stop monitoring
+        print_monitor_stats(&monitor.stop());
+        save_content_index(&content);
+
+        // Save updated indexes, each to its own file
+        println!("\nSaving updated indexes...");
+        for (name, stats) in volumes.save_all(&storage)? {
+            print!("{}: ", name);
+            print_storage_stats(&stats);
+        }
+    }
+
+    Ok(())
+}
 
-        // Start USN monitoring for NTFS drives
-        let monitor = usn_monitor::UsnMonitor::new(drives.clone(), Arc::clone(&index_arc))?;
+/// Answer HTTP and IPC requests until Ctrl+C
+fn serve(service: Arc<api::IndexService<FileIndex>>, port: u16, token: Option<String>) -> Result<()> {
+    let no_token = token.is_none();
+    let http = server::SearchServer::bind(Arc::clone(&service), port)?.token(token);
+    let ipc = ipc::IpcServer::bind(service, &ipc::socket_path()?)?;
 
-        // Start cloud monitoring if requested
-        let _cloud_monitor = if args.include_cloud {
-            let cloud_folders: Vec<std::path::PathBuf> = multi_drive::find_cloud_storage_folders()
-                .into_iter()
-                .map(|(_, path)| path)
-                .collect();
+    let stops = [http.stop_handle(), ipc.stop_handle()];
+    ctrlc::set_handler(move || {
+        for stop in &stops {
+            stop.store(true, Ordering::Relaxed);
+        }
+    })?;
 
-            if !cloud_folders.is_empty() {
-                Some(cloud_monitor::CloudMonitor::new(cloud_folders, Arc::clone(&index_arc))?)
-            } else {
-                None
+    println!("\n🌐 Serving on http://{} (Ctrl+C to stop)", http.local_addr());
+    if no_token {
+        println!("⚠️  No token set: any local program can query the index over HTTP");
+    }
+    println!("🔌 Indexer socket: {}", ipc.path().display());
+
+    let ipc_thread = std::thread::spawn(move || ipc.run());
+    http.run()?;
+    ipc_thread.join().expect("IPC server thread panicked")
+}
+
+/// Load the cached index of every volume that has one, without printing anything
+///
+/// With `at`, each volume is loaded from its newest snapshot taken by then
+/// instead (volumes without one are left out).
+fn load_cached_volumes(sources: &[VolumeSource], at: Option<chrono::DateTime<chrono::Utc>>) -> Result<MultiVolumeIndex> {
+    let volumes = MultiVolumeIndex::new();
+    for source in sources {
+        let name = source.name();
+        let cache_path = match at {
+            Some(when) => match snapshots::at(&name, when)? {
+                Some(snapshot) => {
+                    eprintln!("📸 {} as of {}", snapshot.volume, snapshot.taken.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"));
+                    snapshot.path.to_string_lossy().to_string()
+                }
+                None => continue,
+            },
+            None => persistence::get_volume_index_path(&name)?,
+        };
+        if std::path::Path::new(&cache_path).exists() {
+            volumes.load_volume(&name, &cache_path)?;
+        }
+    }
+    warn_skipped_imports(&volumes.load_imported()?.skipped);
+
+    if volumes.volume_count() == 0 {
+        let names: Vec<String> = sources.iter().map(VolumeSource::name).collect();
+        match at {
+            Some(_) => anyhow::bail!("No snapshot of {} from that time; see nothing snapshots", names.join(", ")),
+            None => anyhow::bail!("No cached index for {}; run nothing once to scan", names.join(", ")),
+        }
+    }
+    Ok(volumes)
+}
+
+/// Report imported index files that failed to load and were left out
+fn warn_skipped_imports(skipped: &[(String, anyhow::Error)]) {
+    for (path, e) in skipped {
+        eprintln!("⚠️  Skipping imported index {}: {:#}", path, e);
+    }
+}
+
+/// Print each volume's snapshots, newest first
+fn list_snapshots(sources: &[VolumeSource], policy: &config::SnapshotConfig) -> Result<()> {
+    if !policy.is_enabled() {
+        println!("Snapshots are off; set keep_hourly, keep_daily or keep_weekly in [snapshots] to take them");
+    }
+    for source in sources {
+        let name = source.name();
+        let snapshots = snapshots::list(&name)?;
+        println!("{}: {} snapshot(s)", name, snapshots.len());
+        for snapshot in snapshots.iter().rev() {
+            let stats = persistence::index_storage_stats(&snapshot.path.to_string_lossy())?;
+            println!(
+                "   {}  {} entries, {:.1} MB  {}",
+                snapshot.taken.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S"),
+                stats.entries,
+                stats.stored_bytes as f64 / 1_048_576.0,
+                snapshot.path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Write the changes between two index files and a summary (on stderr);
+/// returns whether anything changed
+fn diff_index_files(
+    old: &std::path::Path,
+    new: &std::path::Path,
+    format: export::ExportFormat,
+    by: diff::MatchBy,
+    output: Option<&std::path::Path>,
+) -> Result<bool> {
+    let load = |path: &std::path::Path| {
+        persistence::load_index(&path.to_string_lossy()).with_context(|| format!("Failed to load {}", path.display()))
+    };
+    let diff = diff::diff_indexes(&load(old)?, &load(new)?, by);
+
+    let out: Box<dyn std::io::Write> = match output {
+        Some(path) => Box::new(std::io::BufWriter::new(
+            std::fs::File::create(path).with_context(|| format!("Failed to create {}", path.display()))?,
+        )),
+        None => Box::new(std::io::BufWriter::new(std::io::stdout().lock())),
+    };
+    diff::write_changes(out, &diff, format)?;
+
+    use diff::ChangeKind::*;
+    eprintln!(
+        "\n📊 {} added, {} removed, {} moved, {} resized, {} retimestamped, {} unchanged",
+        diff.count(Added),
+        diff.count(Removed),
+        diff.count(Moved),
+        diff.count(Resized),
+        diff.count(Retimestamped),
+        diff.unchanged
+    );
+    for delta in diff.by_directory() {
+        eprintln!(
+            "   {}: +{} -{}",
+            delta.directory,
+            export::format_file_size(delta.added),
+            export::format_file_size(delta.removed)
+        );
+    }
+    Ok(!diff.changes.is_empty())
+}
+
+/// Parse a listing and save it as an imported volume
+fn import_listing(
+    listing: &std::path::Path,
+    format: Option<import::ImportFormat>,
+    name: Option<&str>,
+    storage: &persistence::StorageOptions,
+) -> Result<()> {
+    let name = name.map(str::to_string).unwrap_or_else(|| import::volume_name_for(listing));
+    import::check_volume_name(&name)?;
+    let index = import::read_listing(listing, format)?;
+    let path = persistence::get_imported_index_path(&name)?;
+    let stats = persistence::save_index_with(&index, &path, storage)?;
+    println!("✅ Imported {}: {} files, {} directories", name, index.file_count(), index.directory_count());
+    print_storage_stats(&stats);
+    Ok(())
+}
+
+fn remove_imported(name: &str) -> Result<()> {
+    let path = persistence::get_imported_index_path(name)?;
+    if !std::path::Path::new(&path).exists() {
+        anyhow::bail!("No imported volume named {}", name);
+    }
+    std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path))?;
+    println!("🗑️  Removed imported volume {}", name);
+    Ok(())
+}
+
+fn list_imported() -> Result<()> {
+    let volumes = persistence::imported_volumes()?;
+    if volumes.is_empty() {
+        println!("No imported volumes. Add one with: nothing import <listing>");
+    }
+    for (name, path) in volumes {
+        let stats = persistence::index_storage_stats(&path)?;
+        println!("{}: {} entries ({})", name, stats.entries, path);
+    }
+    Ok(())
+}
+
+/// Interactive search against a running indexer instead of loading the indexes here
+fn run_with_indexer(mut client: ipc::IpcClient, path: &std::path::Path, config: &Config) -> Result<()> {
+    let status = client.request(&api::Request::Status)?;
+    println!(
+        "🔌 Using the running indexer at {} ({} volumes, {} files)",
+        path.display(),
+        status["volumes"].as_array().map_or(0, Vec::len),
+        status["total_files"]
+    );
+
+    println!("\nEntering interactive search mode...\n");
+    interactive::run_interactive_search_with(&mut client, config)
+}
+
+/// Change sources for the loaded volumes, each paired with its index
+fn monitored_volumes<I>(volumes: &MultiVolumeIndex<I>, sources: &[VolumeSource]) -> Vec<monitor::WatchedVolume<I>> {
+    sources
+        .iter()
+        .filter_map(|source| {
+            let volume = volumes.volume(&source.name())?;
+            Some((change_source::for_path(source.root()), volume.index))
+        })
+        .collect()
+}
+
+/// Send background activity to the log file in the data directory
+fn init_activity_log() {
+    match persistence::data_file(activity_log::LOG_FILE) {
+        Ok(path) => {
+            if let Err(e) = activity_log::init(&path) {
+                eprintln!("⚠️  {:#}; monitor messages go to stderr", e);
             }
-        } else {
+        }
+        Err(e) => eprintln!("⚠️  {:#}; monitor messages go to stderr", e),
+    }
+}
+
+/// Start monitoring, with activity going to the log file in the data directory
+fn start_monitor<I: IndexView + index::LiveIndex + Send + 'static>(
+    watched: Vec<monitor::WatchedVolume<I>>,
+    config: &Config,
+    content: &ContentSearch,
+) -> Result<monitor::Monitor> {
+    init_activity_log();
+    let options = monitor::MonitorOptions {
+        exclusions: config.exclusions(),
+        check_interval: config.monitor.check_interval(),
+        content_index: content.index.clone(),
+        ..Default::default()
+    };
+    monitor::Monitor::new(watched, options)
+}
+
+/// Settings of `content:` searches, with the content index of the
+/// configured folders loaded and brought up to date
+///
+/// Without the index (none configured, or it failed to open) content
+/// searches read every file.
+fn open_content_search(config: &Config) -> ContentSearch {
+    let index = match ContentIndex::open(&config.content, config.exclusions()) {
+        Ok(Some((index, stats))) => {
+            eprintln!(
+                "📚 Content index: {} files ({} updated, {} removed)",
+                index.file_count(),
+                stats.indexed,
+                stats.removed
+            );
+            Some(Arc::new(index))
+        }
+        Ok(None) => None,
+        Err(e) => {
+            eprintln!("⚠️  Content index unavailable, searching files directly: {:#}", e);
             None
+        }
+    };
+    ContentSearch::new(config.content.clone(), index)
+}
+
+/// Save the content index if the monitor changed it
+fn save_content_index(content: &ContentSearch) {
+    if let Some(index) = &content.index {
+        if let Err(e) = index.save_to_data_dir() {
+            eprintln!("⚠️  Failed to save the content index: {:#}", e);
+        }
+    }
+}
+
+/// Print what the monitor did while the search UI was open
+fn print_monitor_stats(stats: &monitor::MonitorStats) {
+    println!(
+        "📡 Applied {} changes from {} events in {} batches ({} created, {} removed, {} renamed, {} modified)",
+        stats.changes(),
+        stats.events,
+        stats.batches,
+        stats.created,
+        stats.deleted,
+        stats.renamed,
+        stats.modified
+    );
+    if stats.overflows > 0 {
+        println!("⚠️  Events were lost {} times and the affected folders were rescanned", stats.overflows);
+    }
+    if stats.drift > 0 {
+        println!(
+            "🔍 {} consistency checks corrected {} entries; see the activity log",
+            stats.reconciliations, stats.drift
+        );
+    }
+}
+
+/// Pick an unused volume name for a cloud folder
+fn cloud_volume_name(volumes: &MultiVolumeIndex, provider: multi_drive::CloudProvider) -> String {
+    let base = format!("cloud-{:?}", provider);
+    let mut name = base.clone();
+    let mut n = 2;
+    while volumes.volume(&name).is_some() {
+        name = format!("{}-{}", base, n);
+        n += 1;
+    }
+    name
+}
+
+/// Detect the types of the files of each volume that have none yet, and
+/// save the volumes where any were found
+fn detect_volume_types(volumes: &MultiVolumeIndex, sources: &[VolumeSource], storage: &persistence::StorageOptions) -> Result<()> {
+    for source in sources {
+        let Some(volume) = volumes.volume(&source.name()) else {
+            continue;
         };
+        let mut index = volume.index.lock().unwrap();
+        println!("🔎 Detecting file types on {}...", volume.name);
+        let stats = file_type::detect_types(&mut index);
+        println!("✅ {}: {}", volume.name, stats);
+        if let (true, Some(cache_path)) = (stats.detected > 0, &volume.cache_path) {
+            print_storage_stats(&persistence::save_volume_index(&index, &volume.name, cache_path, storage)?);
+        }
+    }
+    Ok(())
+}
 
-        // Run interactive search (GUI or CLI)
-        if args.gui {
-            println!("\nLaunching GUI...\n");
-            let gui_index = Arc::clone(&index_clone);
-            gui::run(gui_index)?;
-        } else {
-            println!("\nEntering interactive search mode with real-time monitoring...\n");
-            interactive::run_interactive_search_with_arc(&index_clone)?;
+/// List the members of the archives of each volume that have none yet,
+/// and save the volumes where any were listed
+fn index_volume_archives(
+    volumes: &MultiVolumeIndex,
+    sources: &[VolumeSource],
+    config: &ArchiveConfig,
+    storage: &persistence::StorageOptions,
+) -> Result<()> {
+    for source in sources {
+        let Some(volume) = volumes.volume(&source.name()) else {
+            continue;
+        };
+        let mut index = volume.index.lock().unwrap();
+        println!("📦 Listing archive members on {}...", volume.name);
+        let stats = archive::index_archives(&mut index, config);
+        println!("✅ {}: {}", volume.name, stats);
+        // Save even without members, so empty and unreadable archives stay marked as listed
+        if let (true, Some(cache_path)) = (stats.archives + stats.failed > 0, &volume.cache_path) {
+            print_storage_stats(&persistence::save_volume_index(&index, &volume.name, cache_path, storage)?);
         }
+    }
+    Ok(())
+}
 
-        // Stop monitoring
-        monitor.stop();
-
-        // Save updated index
-        if !drives.is_empty() {
-            println!("\nSaving updated index...");
-            let final_index = index_clone.lock().unwrap();
-            let cache_path = persistence::get_index_path(drives[0])?;
-            persistence::save_index(&*final_index, &cache_path)?;
-            println!("✅ Index saved");
+/// Print the result of saving an index
+fn print_storage_stats(stats: &persistence::StorageStats) {
+    println!(
+        "✅ Index saved ({:.1} MB, {} compressed to {:.0}% of {:.1} MB raw)",
+        stats.stored_bytes as f64 / 1_048_576.0,
+        stats.compression.name(),
+        stats.ratio() * 100.0,
+        stats.raw_bytes as f64 / 1_048_576.0
+    );
+}
+
+/// Interactive search over memory-mapped indexes
+///
+/// Index files are searched in place, so the prompt is usable as soon as
+/// they are mapped. Monitor changes go into each mapped index's overlay and
+/// are folded back into its file on exit.
+fn run_mapped(args: &Args, config: &Config) -> Result<()> {
+    let sources = args.volume_sources(config);
+
+    let volumes: Arc<MultiVolumeIndex<MappedIndex>> = Arc::new(MultiVolumeIndex::new());
+    for source in &sources {
+        let name = source.name();
+        let map_path = persistence::get_mapped_index_path(&name)?;
+        let cache_path = persistence::get_volume_index_path(&name)?;
+
+        // A cache saved after the map (a scan without --mmap) has changes the map lacks
+        let modified = |path: &str| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        if !mapped_index::MappedIndex::is_current(&map_path) || modified(&cache_path) > modified(&map_path) {
+            // Build the mapped file from the regular cache, or from a fresh scan
+            // (also replacing a file of an older layout)
+            let index = if std::path::Path::new(&cache_path).exists() {
+                persistence::load_index(&cache_path)?
+            } else {
+                let index = multi_drive::scan_source(source, args.full_metadata, &config.exclusions(), &scanner::print_progress())?;
+                persistence::save_volume_index(&index, &name, &cache_path, &args.storage_options())?;
+                index
+            };
+
+            println!("Writing mapped index for {}...", name);
+            persistence::save_mapped_index(&index, &map_path)?;
+        }
+
+        let mapped = volumes.map_volume(&name, &map_path)?;
+        let mapped = mapped.lock().unwrap();
+        println!("✅ Mapped {}: {} files, {} directories",
+                 name, mapped.file_count(), mapped.directory_count());
+    }
+
+    let content = open_content_search(config);
+    let monitor = start_monitor(monitored_volumes(&volumes, &sources), config, &content)?;
+
+    println!("\nEntering interactive search mode with real-time monitoring...\n");
+    interactive::run_interactive_search_with_arc(&volumes, config, content.clone())?;
+
+    // Stopping the monitor releases its references to the indexes
+    print_monitor_stats(&monitor.stop());
+    save_content_index(&content);
+
+    for name in volumes.volume_names() {
+        let volume = match volumes.remove_volume(&name) {
+            Some(volume) => volume,
+            None => continue,
+        };
+        if let (Some(map_path), Ok(mutex)) = (volume.cache_path, Arc::try_unwrap(volume.index)) {
+            let mapped = mutex.into_inner().unwrap();
+            if mapped.overlay_len() > 0 {
+                println!("\nSaving {} changed entries on {}...", mapped.overlay_len(), name);
+                mapped.compact(&map_path)?;
+                println!("✅ Index saved");
+            }
         }
     }
 
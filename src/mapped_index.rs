@@ -0,0 +1,661 @@
+use crate::file_entry::{FileEntry, Timestamps};
+use crate::file_type::FileType;
+use crate::index::{is_within, rebase_path, EntryRef, IndexView, LiveIndex};
+use crate::index_format::{read_security, write_security};
+use crate::security::Security;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use memmap2::Mmap;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::sync::Arc;
+
+/// Magic bytes at the start of a memory-mapped index file (includes format version)
+const MAGIC: &[u8; 8] = b"NOTHMAP2";
+
+/// Header: magic, record count, directory count, arena length, security table length
+const HEADER_SIZE: usize = 40;
+
+/// Fixed size of one record in the record table
+///
+/// Layout (little-endian):
+///   0  name offset (u64)     8  path offset (u64)
+///   16 name length (u32)     20 path length (u32)
+///   24 file_id (u64)         32 parent_id (u64)
+///   40 size (u64)            48 modified (i64 ns)
+///   56 created (i64 ns)      64 accessed (i64 ns)
+///   72 flags (u32)           76 security length (u32)
+///   80 changed (i64 ns)      88 security offset (u64)
+///   96 $FILE_NAME modified, accessed, changed, created (i64 ns each)
+///
+/// The flags hold the detected file type's code plus one in bits 8-15 (0
+/// when none was detected).
+const RECORD_SIZE: usize = 128;
+
+/// Timestamp value stored when the entry has no timestamp
+const NO_TIMESTAMP: i64 = i64::MIN;
+
+/// Record flag: entry is a directory
+const FLAG_DIRECTORY: u32 = 1;
+
+/// Record flag: the `$FILE_NAME` timestamps are present
+const FLAG_FILE_NAME_TIMES: u32 = 2;
+
+/// Read-only index backed by a memory-mapped file
+///
+/// The file holds a fixed-size record table followed by a string arena and
+/// a table of security descriptors (each written once), so opening it only
+/// maps the file - names and paths are read in place when a
+/// search touches them. Changes from the monitors go into a copy-on-write
+/// overlay and never touch the mapped file.
+pub struct MappedIndex {
+    /// The mapped file
+    mmap: Mmap,
+
+    /// Number of records in the mapped file
+    record_count: usize,
+
+    /// Number of directories (including overlay changes)
+    directory_count: usize,
+
+    /// Mapped records hidden by a removal
+    removed: HashSet<usize>,
+
+    /// Mapped records shadowed by an updated in-memory copy
+    replaced: HashMap<usize, FileEntry>,
+
+    /// Entries created since the file was mapped
+    added: Vec<FileEntry>,
+}
+
+/// A single record inside a mapped index file
+#[derive(Clone, Copy)]
+pub struct MappedRecord<'a> {
+    record: &'a [u8],
+    arena: &'a [u8],
+    security: &'a [u8],
+}
+
+/// Where an entry lives inside a mapped index
+enum Slot {
+    Mapped(usize),
+    Added(usize),
+}
+
+impl MappedIndex {
+    /// Map an index file written by [`MappedIndex::write`]
+    pub fn open(path: &str) -> Result<Self> {
+        let file = fs::File::open(path)
+            .with_context(|| format!("Failed to open mapped index: {}", path))?;
+
+        // SAFETY: index files are only produced by `write`, which writes a
+        // temporary file and renames it into place, so a mapped file is never
+        // truncated or rewritten underneath us.
+        let mmap = unsafe { Mmap::map(&file) }
+            .with_context(|| format!("Failed to map index file: {}", path))?;
+
+        if mmap.len() < HEADER_SIZE || &mmap[..8] != MAGIC {
+            bail!("Not a mapped index file of this version: {}", path);
+        }
+
+        let record_count = read_u64(&mmap, 8) as usize;
+        let directory_count = read_u64(&mmap, 16) as usize;
+        let arena_len = read_u64(&mmap, 24) as usize;
+        let security_len = read_u64(&mmap, 32) as usize;
+
+        let expected_len = record_count
+            .checked_mul(RECORD_SIZE)
+            .and_then(|table| table.checked_add(HEADER_SIZE))
+            .and_then(|len| len.checked_add(arena_len))
+            .and_then(|len| len.checked_add(security_len));
+        if expected_len != Some(mmap.len()) {
+            bail!("Mapped index file is truncated or corrupt: {}", path);
+        }
+
+        Ok(Self {
+            mmap,
+            record_count,
+            directory_count,
+            removed: HashSet::new(),
+            replaced: HashMap::new(),
+            added: Vec::new(),
+        })
+    }
+
+    /// Whether `path` holds a mapped index file of this version
+    pub fn is_current(path: &str) -> bool {
+        let mut magic = [0u8; 8];
+        fs::File::open(path).and_then(|mut file| file.read_exact(&mut magic)).is_ok() && &magic == MAGIC
+    }
+
+    /// Write any index to `path` in the mapped layout
+    ///
+    /// The file is written next to `path` and renamed into place. On Windows
+    /// a file cannot be replaced while it is mapped, so drop any
+    /// `MappedIndex` of the same file first (see [`MappedIndex::compact`]).
+    pub fn write<I: IndexView + ?Sized>(index: &I, path: &str) -> Result<()> {
+        let tmp_path = format!("{}.tmp", path);
+        let file = fs::File::create(&tmp_path)
+            .with_context(|| format!("Failed to create index file: {}", tmp_path))?;
+        let mut writer = BufWriter::new(file);
+
+        // Pass 1: record table. The arena layout is fully determined by the
+        // string lengths, so offsets can be computed before any string is written.
+        let mut arena_len = 0u64;
+        let mut record_count = 0u64;
+        let mut directory_count = 0u64;
+        let mut records = Vec::with_capacity(RECORD_SIZE);
+        let mut descriptors: HashMap<Arc<Security>, (u64, u32)> = HashMap::new();
+        let mut security_table = Vec::new();
+
+        writer.write_all(&[0u8; HEADER_SIZE])?;
+        for entry in index.entry_refs() {
+            let entry = entry.to_entry();
+            let path_offset = arena_len;
+            let (name_offset, name_bytes) = if entry.path.ends_with(&entry.name) {
+                // Share the name with the tail of the path
+                (path_offset + (entry.path.len() - entry.name.len()) as u64, 0)
+            } else {
+                (path_offset + entry.path.len() as u64, entry.name.len() as u64)
+            };
+            arena_len += entry.path.len() as u64 + name_bytes;
+
+            records.clear();
+            records.extend_from_slice(&name_offset.to_le_bytes());
+            records.extend_from_slice(&path_offset.to_le_bytes());
+            records.extend_from_slice(&(entry.name.len() as u32).to_le_bytes());
+            records.extend_from_slice(&(entry.path.len() as u32).to_le_bytes());
+            records.extend_from_slice(&entry.file_id.to_le_bytes());
+            records.extend_from_slice(&entry.parent_id.to_le_bytes());
+            records.extend_from_slice(&entry.size.to_le_bytes());
+            records.extend_from_slice(&encode_timestamp(entry.modified).to_le_bytes());
+            records.extend_from_slice(&encode_timestamp(entry.created).to_le_bytes());
+            records.extend_from_slice(&encode_timestamp(entry.accessed).to_le_bytes());
+            let mut flags = if entry.is_directory { FLAG_DIRECTORY } else { 0 };
+            if entry.file_name_times.is_some() {
+                flags |= FLAG_FILE_NAME_TIMES;
+            }
+            flags |= entry.file_type.map_or(0, |t| t.code() as u32 + 1) << 8;
+            records.extend_from_slice(&flags.to_le_bytes());
+            let (security_offset, security_len) = match &entry.security {
+                Some(security) => *descriptors.entry(Arc::clone(security)).or_insert_with(|| {
+                    let offset = security_table.len();
+                    write_security(&mut security_table, security).expect("writing to a Vec cannot fail");
+                    (offset as u64, (security_table.len() - offset) as u32)
+                }),
+                None => (0, 0),
+            };
+            records.extend_from_slice(&security_len.to_le_bytes());
+            records.extend_from_slice(&encode_timestamp(entry.changed).to_le_bytes());
+            records.extend_from_slice(&security_offset.to_le_bytes());
+            for time in entry.file_name_times.as_deref().copied().unwrap_or_default().macb() {
+                records.extend_from_slice(&encode_timestamp(time).to_le_bytes());
+            }
+            writer.write_all(&records)?;
+
+            record_count += 1;
+            if entry.is_directory {
+                directory_count += 1;
+            }
+        }
+
+        // Pass 2: string arena, in the same order
+        for entry in index.entry_refs() {
+            let (name, path) = (entry.name(), entry.path());
+            writer.write_all(path.as_bytes())?;
+            if !path.ends_with(name) {
+                writer.write_all(name.as_bytes())?;
+            }
+        }
+        writer.write_all(&security_table)?;
+
+        // Header last, once the counts are known
+        let mut file = writer.into_inner().map_err(|e| e.into_error())?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(MAGIC)?;
+        file.write_all(&record_count.to_le_bytes())?;
+        file.write_all(&directory_count.to_le_bytes())?;
+        file.write_all(&arena_len.to_le_bytes())?;
+        file.write_all(&(security_table.len() as u64).to_le_bytes())?;
+        file.sync_all()?;
+        drop(file);
+
+        fs::rename(&tmp_path, path)
+            .with_context(|| format!("Failed to replace index file: {}", path))?;
+        Ok(())
+    }
+
+    /// Fold the overlay into a fresh file at `path` and map it again
+    pub fn compact(self, path: &str) -> Result<Self> {
+        let tmp_path = format!("{}.compact", path);
+        Self::write(&self, &tmp_path)?;
+        drop(self);
+        fs::rename(&tmp_path, path)
+            .with_context(|| format!("Failed to replace index file: {}", path))?;
+        Self::open(path)
+    }
+
+    /// Number of entries changed since the file was mapped
+    pub fn overlay_len(&self) -> usize {
+        self.removed.len() + self.replaced.len() + self.added.len()
+    }
+
+    /// Get a record from the mapped file, ignoring the overlay
+    pub fn record(&self, position: usize) -> Option<MappedRecord<'_>> {
+        if position >= self.record_count {
+            return None;
+        }
+        let start = HEADER_SIZE + position * RECORD_SIZE;
+        let arena_start = HEADER_SIZE + self.record_count * RECORD_SIZE;
+        let security_start = arena_start + read_u64(&self.mmap, 24) as usize;
+        Some(MappedRecord {
+            record: &self.mmap[start..start + RECORD_SIZE],
+            arena: &self.mmap[arena_start..security_start],
+            security: &self.mmap[security_start..],
+        })
+    }
+
+    /// Live entries at or below `path`, added entries last and in reverse
+    /// order so they can be removed one by one
+    fn slots_within(&self, path: &str) -> Vec<Slot> {
+        let mut slots: Vec<Slot> = (0..self.record_count)
+            .filter(|pos| self.live_record(*pos).is_some_and(|e| is_within(e.path(), path)))
+            .map(Slot::Mapped)
+            .collect();
+        slots.extend(
+            self.added
+                .iter()
+                .enumerate()
+                .rev()
+                .filter(|(_, e)| is_within(&e.path, path))
+                .map(|(pos, _)| Slot::Added(pos)),
+        );
+        slots
+    }
+
+    /// Find the first live entry matching `predicate`
+    fn find(&self, predicate: impl Fn(EntryRef<'_>) -> bool) -> Option<Slot> {
+        if let Some(pos) = self.added.iter().position(|e| predicate(EntryRef::Owned(e))) {
+            return Some(Slot::Added(pos));
+        }
+        (0..self.record_count)
+            .find(|pos| match self.live_record(*pos) {
+                Some(entry) => predicate(entry),
+                None => false,
+            })
+            .map(Slot::Mapped)
+    }
+
+    /// Mapped record at `position`, as seen through the overlay
+    fn live_record(&self, position: usize) -> Option<EntryRef<'_>> {
+        if self.removed.contains(&position) {
+            None
+        } else if let Some(entry) = self.replaced.get(&position) {
+            Some(EntryRef::Owned(entry))
+        } else {
+            self.record(position).map(EntryRef::Mapped)
+        }
+    }
+
+    /// Get a writable copy of an entry, copying mapped records into the overlay
+    fn entry_mut(&mut self, slot: Slot) -> &mut FileEntry {
+        match slot {
+            Slot::Added(pos) => &mut self.added[pos],
+            Slot::Mapped(pos) => {
+                if !self.replaced.contains_key(&pos) {
+                    let entry = self.record(pos).map(|r| r.to_entry()).expect("valid record position");
+                    self.replaced.insert(pos, entry);
+                }
+                self.replaced.get_mut(&pos).expect("entry was just inserted")
+            }
+        }
+    }
+
+    fn remove_slot(&mut self, slot: Slot) {
+        let is_directory = match slot {
+            Slot::Added(pos) => self.added.remove(pos).is_directory,
+            Slot::Mapped(pos) => {
+                let is_directory = self.live_record(pos).map(|e| e.is_directory()).unwrap_or(false);
+                self.replaced.remove(&pos);
+                self.removed.insert(pos);
+                is_directory
+            }
+        };
+        if is_directory {
+            self.directory_count = self.directory_count.saturating_sub(1);
+        }
+    }
+}
+
+impl IndexView for MappedIndex {
+    fn entry_refs(&self) -> Box<dyn Iterator<Item = EntryRef<'_>> + '_> {
+        let mapped = (0..self.record_count).filter_map(move |pos| self.live_record(pos));
+        Box::new(mapped.chain(self.added.iter().map(EntryRef::Owned)))
+    }
+
+    fn len(&self) -> usize {
+        self.record_count - self.removed.len() + self.added.len()
+    }
+
+    fn directory_count(&self) -> usize {
+        self.directory_count
+    }
+}
+
+impl LiveIndex for MappedIndex {
+    fn add_entry(&mut self, entry: FileEntry) {
+        if entry.is_directory {
+            self.directory_count += 1;
+        }
+        self.added.push(entry);
+    }
+
+    fn remove_by_path(&mut self, path: &str) {
+        if let Some(slot) = self.find(|entry| entry.path() == path) {
+            self.remove_slot(slot);
+        }
+    }
+
+    fn remove_tree(&mut self, path: &str) -> usize {
+        let slots = self.slots_within(path);
+        let removed = slots.len();
+        for slot in slots {
+            self.remove_slot(slot);
+        }
+        removed
+    }
+
+    fn move_path(&mut self, from: &str, to: &str) -> usize {
+        let slots = self.slots_within(from);
+        let moved = slots.len();
+        for slot in slots {
+            let entry = self.entry_mut(slot);
+            if let Some(new_path) = rebase_path(&entry.path, from, to) {
+                entry.set_path(new_path);
+            }
+        }
+        moved
+    }
+
+    fn update_metadata_by_path(&mut self, path: &str, size: u64, modified: Option<std::time::SystemTime>) {
+        if let Some(slot) = self.find(|entry| entry.path() == path) {
+            let entry = self.entry_mut(slot);
+            entry.size = size;
+            if let Some(sys_time) = modified {
+                entry.modified = Some(DateTime::<Utc>::from(sys_time));
+            }
+        }
+    }
+}
+
+impl<'a> MappedRecord<'a> {
+    /// File name (without path)
+    pub fn name(&self) -> &'a str {
+        self.string(read_u64(self.record, 0), read_u32(self.record, 16))
+    }
+
+    /// Full path to the file
+    pub fn path(&self) -> &'a str {
+        self.string(read_u64(self.record, 8), read_u32(self.record, 20))
+    }
+
+    /// MFT file reference number
+    pub fn file_id(&self) -> u64 {
+        read_u64(self.record, 24)
+    }
+
+    /// Parent file reference number
+    pub fn parent_id(&self) -> u64 {
+        read_u64(self.record, 32)
+    }
+
+    /// File size in bytes
+    pub fn size(&self) -> u64 {
+        read_u64(self.record, 40)
+    }
+
+    /// Last modified timestamp
+    pub fn modified(&self) -> Option<DateTime<Utc>> {
+        self.timestamp(48)
+    }
+
+    /// Whether this is a directory
+    pub fn is_directory(&self) -> bool {
+        read_u32(self.record, 72) & FLAG_DIRECTORY != 0
+    }
+
+    /// Materialize an owned `FileEntry`
+    pub fn to_entry(self) -> FileEntry {
+        let mut entry = FileEntry::new(
+            self.name().to_string(),
+            self.path().to_string(),
+            self.is_directory(),
+            self.file_id(),
+            self.parent_id(),
+            self.size(),
+            self.modified(),
+            self.timestamp(56),
+            self.timestamp(64),
+        );
+        let flags = read_u32(self.record, 72);
+        entry.changed = self.timestamp(80);
+        if flags & FLAG_FILE_NAME_TIMES != 0 {
+            entry.file_name_times = Some(Box::new(Timestamps {
+                modified: self.timestamp(96),
+                accessed: self.timestamp(104),
+                changed: self.timestamp(112),
+                created: self.timestamp(120),
+            }));
+        }
+        entry.security = self.security().map(Arc::new);
+        entry.file_type = (flags >> 8 & 0xFF).checked_sub(1).and_then(|code| FileType::from_code(code as u8));
+        entry
+    }
+
+    /// Security descriptor, read out of the security table (`None` if absent or corrupt)
+    fn security(&self) -> Option<Security> {
+        let len = read_u32(self.record, 76) as usize;
+        if len == 0 {
+            return None;
+        }
+        let start = read_u64(self.record, 88) as usize;
+        let mut bytes = self.security.get(start..start.saturating_add(len))?;
+        read_security(&mut bytes).ok()
+    }
+
+    fn timestamp(&self, offset: usize) -> Option<DateTime<Utc>> {
+        decode_timestamp(read_u64(self.record, offset) as i64)
+    }
+
+    /// Read a string out of the arena (empty if out of range or not UTF-8)
+    fn string(&self, offset: u64, len: u32) -> &'a str {
+        let start = offset as usize;
+        self.arena
+            .get(start..start.saturating_add(len as usize))
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+            .unwrap_or("")
+    }
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(buf)
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    let mut buf = [0u8; 4];
+    buf.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(buf)
+}
+
+fn encode_timestamp(timestamp: Option<DateTime<Utc>>) -> i64 {
+    timestamp
+        .and_then(|t| t.timestamp_nanos_opt())
+        .unwrap_or(NO_TIMESTAMP)
+}
+
+fn decode_timestamp(nanos: i64) -> Option<DateTime<Utc>> {
+    if nanos == NO_TIMESTAMP {
+        None
+    } else {
+        Some(DateTime::from_timestamp_nanos(nanos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::FileIndex;
+
+    fn sample_index() -> FileIndex {
+        let mut index = FileIndex::new();
+        index.add_entry(FileEntry::new(
+            "Docs".to_string(),
+            "C:\\Docs".to_string(),
+            true,
+            10,
+            5,
+            0,
+            None,
+            None,
+            None,
+        ));
+        index.add_entry(FileEntry::new(
+            "report.pdf".to_string(),
+            "C:\\Docs\\report.pdf".to_string(),
+            false,
+            11,
+            10,
+            4096,
+            DateTime::from_timestamp(1_700_000_000, 123),
+            None,
+            None,
+        ));
+        index
+    }
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("nothing_{}_{}.map", name, std::process::id()))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = temp_path("round_trip");
+        MappedIndex::write(&sample_index(), &path).unwrap();
+
+        let mapped = MappedIndex::open(&path).unwrap();
+        assert_eq!(IndexView::len(&mapped), 2);
+        assert_eq!(IndexView::directory_count(&mapped), 1);
+
+        let record = mapped.record(1).unwrap();
+        assert_eq!(record.name(), "report.pdf");
+        assert_eq!(record.path(), "C:\\Docs\\report.pdf");
+        assert_eq!(record.size(), 4096);
+        assert_eq!(record.to_entry().modified, DateTime::from_timestamp(1_700_000_000, 123));
+        assert!(record.to_entry().security.is_none());
+
+        drop(mapped);
+        let _ = fs::remove_file(&path);
+
+        // Forensic times, security descriptors and detected types come back too
+        let mut index = sample_index();
+        let security = Arc::new(Security::from_mode(1000, 100, 0o640));
+        for entry in index.entries_mut() {
+            entry.changed = DateTime::from_timestamp(1_600_000_000, 0);
+            entry.security = Some(Arc::clone(&security));
+        }
+        let times = Timestamps { modified: DateTime::from_timestamp(1_500_000_000, 7), ..Default::default() };
+        index.entries_mut()[1].file_name_times = Some(Box::new(times));
+        index.entries_mut()[1].file_type = Some(FileType::Pdf);
+        MappedIndex::write(&index, &path).unwrap();
+        assert!(MappedIndex::is_current(&path));
+
+        let mapped = MappedIndex::open(&path).unwrap();
+        for (a, b) in index.entries().iter().zip(mapped.entry_refs().map(|e| e.to_entry())) {
+            assert_eq!(a.changed, b.changed);
+            assert_eq!(a.file_name_times, b.file_name_times);
+            assert_eq!(a.security, b.security);
+            assert_eq!(a.file_type, b.file_type);
+        }
+        // The shared descriptor is stored once
+        assert_eq!(read_u64(&mapped.mmap, 32), read_u32(mapped.record(0).unwrap().record, 76) as u64);
+
+        drop(mapped);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_overlay() {
+        let path = temp_path("overlay");
+        MappedIndex::write(&sample_index(), &path).unwrap();
+
+        let mut mapped = MappedIndex::open(&path).unwrap();
+        mapped.remove_by_path("C:\\Docs\\report.pdf");
+        assert_eq!(mapped.move_path("C:\\Docs", "C:\\Papers"), 1);
+        mapped.add_entry(FileEntry::new(
+            "notes.txt".to_string(),
+            "C:\\Docs\\notes.txt".to_string(),
+            false,
+            12,
+            10,
+            10,
+            None,
+            None,
+            None,
+        ));
+
+        let paths: Vec<&str> = mapped.entry_refs().map(|e| e.path()).collect();
+        assert_eq!(paths, vec!["C:\\Papers", "C:\\Docs\\notes.txt"]);
+
+        // The overlay survives a compaction
+        let mapped = mapped.compact(&path).unwrap();
+        assert_eq!(mapped.overlay_len(), 0);
+        assert_eq!(mapped.record(0).unwrap().name(), "Papers");
+        assert_eq!(IndexView::len(&mapped), 2);
+
+        drop(mapped);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_move_and_remove_tree() {
+        let path = temp_path("move_tree");
+        MappedIndex::write(&sample_index(), &path).unwrap();
+
+        let mut mapped = MappedIndex::open(&path).unwrap();
+        mapped.add_entry(FileEntry::new(
+            "notes.txt".to_string(),
+            "C:\\Docs\\notes.txt".to_string(),
+            false,
+            12,
+            10,
+            10,
+            None,
+            None,
+            None,
+        ));
+
+        // A directory move carries mapped and added descendants along
+        assert_eq!(mapped.move_path("C:\\Docs", "C:\\Archive\\Docs"), 3);
+        let paths: Vec<&str> = mapped.entry_refs().map(|e| e.path()).collect();
+        assert_eq!(
+            paths,
+            vec!["C:\\Archive\\Docs", "C:\\Archive\\Docs\\report.pdf", "C:\\Archive\\Docs\\notes.txt"]
+        );
+        assert_eq!(mapped.entry_refs().nth(1).unwrap().name(), "report.pdf");
+
+        // Sibling names sharing a prefix are left alone
+        assert_eq!(mapped.remove_tree("C:\\Archive\\Doc"), 0);
+        assert_eq!(mapped.remove_tree("C:\\Archive\\Docs"), 3);
+        assert!(IndexView::is_empty(&mapped));
+        assert_eq!(IndexView::directory_count(&mapped), 0);
+
+        drop(mapped);
+        let _ = fs::remove_file(path);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::file_entry::FileEntry;
 use crate::index::FileIndex;
+use crate::scanner::Scanner;
 use anyhow::{Context, Result};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -255,6 +256,16 @@ impl MftReader {
     }
 }
 
+impl Scanner for MftReader {
+    fn describe(&self) -> String {
+        format!("{}:", self.drive_letter)
+    }
+
+    fn scan_into_index(&self, index: &mut FileIndex) -> Result<()> {
+        MftReader::scan_into_index(self, index)
+    }
+}
+
 /// Format a size in bytes to human-readable format
 fn format_size(bytes: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB", "PB"];
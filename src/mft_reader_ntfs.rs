@@ -1,5 +1,7 @@
-use crate::file_entry::FileEntry;
+use crate::file_entry::{FileEntry, Timestamps};
 use crate::index::FileIndex;
+use crate::scanner::Scanner;
+use crate::security::{self, Security};
 use crate::sector_aligned_reader::SectorAlignedReader;
 use anyhow::{Context, Result};
 use chrono::{DateTime, TimeZone, Utc};
@@ -56,6 +58,15 @@ impl MftReaderNtfs {
         // Get root directory (verify it exists)
         let _root_dir = ntfs.root_directory(&mut file)?;
 
+        // Owners and ACLs, which $STANDARD_INFORMATION refers to by security id
+        let descriptors = match self.read_security_descriptors(&mut file, &ntfs) {
+            Ok(descriptors) => descriptors,
+            Err(e) => {
+                eprintln!("Warning: Failed to read security descriptors: {}", e);
+                HashMap::new()
+            }
+        };
+
         // Track parent directories for path building
         let mut parent_map: HashMap<u64, Arc<str>> = HashMap::new();
 
@@ -191,6 +202,7 @@ impl MftReaderNtfs {
                         &ntfs_file,
                         index,
                         &parent_map,
+                        &descriptors,
                     ) {
                         if pass2_count < 1000 {
                             eprintln!("Warning: Failed to process record {}: {}", record_number, e);
@@ -288,6 +300,7 @@ impl MftReaderNtfs {
         ntfs_file: &NtfsFile<'a>,
         index: &mut FileIndex,
         parent_map: &HashMap<u64, Arc<str>>,
+        descriptors: &HashMap<u32, Arc<Security>>,
     ) -> Result<()>
     where
         T: std::io::Read + std::io::Seek,
@@ -312,24 +325,33 @@ impl MftReaderNtfs {
             self.get_file_size(fs, ntfs_file).unwrap_or(0)
         };
 
-        // Get timestamps
-        let (created, modified, accessed) = self.get_timestamps(fs, ntfs_file)?;
+        // Get timestamps: all four of $STANDARD_INFORMATION and of $FILE_NAME
+        let (times, security_id) = self.get_standard_information(fs, ntfs_file)?;
+        let file_name_times = Timestamps {
+            modified: filetime_to_datetime(file_name.modification_time().nt_timestamp()),
+            accessed: filetime_to_datetime(file_name.access_time().nt_timestamp()),
+            changed: filetime_to_datetime(file_name.mft_record_modification_time().nt_timestamp()),
+            created: filetime_to_datetime(file_name.creation_time().nt_timestamp()),
+        };
 
         // Build full path using complete parent_map from Pass 1
         let path = self.build_path_arc(&name, parent_id, parent_map);
 
         // Create file entry
-        let file_entry = FileEntry::new(
+        let mut file_entry = FileEntry::new(
             name,
             path.to_string(),
             is_directory,
             file_id,
             parent_id,
             size,
-            modified,
-            created,
-            accessed,
+            times.modified,
+            times.created,
+            times.accessed,
         );
+        file_entry.changed = times.changed;
+        file_entry.file_name_times = Some(Box::new(file_name_times));
+        file_entry.security = security_id.and_then(|id| descriptors.get(&id)).cloned();
 
         index.add_entry(file_entry);
 
@@ -389,12 +411,8 @@ impl MftReaderNtfs {
         Ok(0)
     }
 
-    /// Get timestamps from standard information attribute
-    fn get_timestamps<T>(
-        &self,
-        fs: &mut T,
-        ntfs_file: &NtfsFile,
-    ) -> Result<(Option<DateTime<Utc>>, Option<DateTime<Utc>>, Option<DateTime<Utc>>)>
+    /// Get timestamps and security id from standard information attribute
+    fn get_standard_information<T>(&self, fs: &mut T, ntfs_file: &NtfsFile) -> Result<(Timestamps, Option<u32>)>
     where
         T: std::io::Read + std::io::Seek,
     {
@@ -408,14 +426,43 @@ impl MftReaderNtfs {
             let attr = attr_item.to_attribute()?;
             if attr.ty()? == NtfsAttributeType::StandardInformation {
                 let std_info: NtfsStandardInformation = attr.structured_value(fs)?;
-                let created = filetime_to_datetime(std_info.creation_time().nt_timestamp());
-                let modified = filetime_to_datetime(std_info.modification_time().nt_timestamp());
-                let accessed = filetime_to_datetime(std_info.access_time().nt_timestamp());
-                return Ok((created, modified, accessed));
+                let times = Timestamps {
+                    modified: filetime_to_datetime(std_info.modification_time().nt_timestamp()),
+                    accessed: filetime_to_datetime(std_info.access_time().nt_timestamp()),
+                    changed: filetime_to_datetime(std_info.mft_record_modification_time().nt_timestamp()),
+                    created: filetime_to_datetime(std_info.creation_time().nt_timestamp()),
+                };
+                // Only NTFS 3.0+ records carry a security id
+                return Ok((times, std_info.security_id()));
             }
         }
 
-        Ok((None, None, None))
+        Ok((Timestamps::default(), None))
+    }
+
+    /// Read every security descriptor from the $SDS stream of $Secure
+    fn read_security_descriptors<T>(&self, fs: &mut T, ntfs: &Ntfs) -> Result<HashMap<u32, Arc<Security>>>
+    where
+        T: std::io::Read + std::io::Seek,
+    {
+        use ntfs::{KnownNtfsFileRecordNumber, NtfsReadSeek};
+
+        let secure = ntfs.file(fs, KnownNtfsFileRecordNumber::Secure as u64)?;
+        let sds = secure
+            .data(fs, "$SDS")
+            .ok_or_else(|| anyhow::anyhow!("$Secure has no $SDS stream"))??;
+        let mut value = sds.to_attribute()?.value(fs)?;
+
+        let mut bytes = Vec::with_capacity(value.len() as usize);
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let read = value.read(fs, &mut buf)?;
+            if read == 0 {
+                break;
+            }
+            bytes.extend_from_slice(&buf[..read]);
+        }
+        Ok(security::parse_sds(&bytes))
     }
 
     /// Build full path using Arc for efficiency
@@ -443,6 +490,16 @@ impl MftReaderNtfs {
     }
 }
 
+impl Scanner for MftReaderNtfs {
+    fn describe(&self) -> String {
+        format!("{}:", self.drive_letter)
+    }
+
+    fn scan_into_index(&self, index: &mut FileIndex) -> Result<()> {
+        MftReaderNtfs::scan_into_index(self, index)
+    }
+}
+
 /// Convert Windows FILETIME to DateTime
 fn filetime_to_datetime(nt_timestamp: u64) -> Option<DateTime<Utc>> {
     // NT timestamps are 100-nanosecond intervals since January 1, 1601
@@ -0,0 +1,717 @@
+// Live index updates: apply change events from any ChangeSource to its volume
+use crate::activity_log;
+use crate::archive;
+use crate::change_source::{ChangeEvent, ChangeSource};
+use crate::config::Exclusions;
+use crate::content_index::ContentIndex;
+use crate::dir_walker::entry_for_path;
+use crate::file_entry::FileEntry;
+use crate::index::{IndexView, LiveIndex};
+use crate::reconcile::{reconcile, DriftReport};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How long a handler keeps collecting events after the first one of a batch
+const DEBOUNCE_WINDOW: Duration = Duration::from_millis(250);
+
+/// Most events applied under one lock, so a burst can't starve searches
+const MAX_BATCH: usize = 10_000;
+
+/// A change source and the volume index it updates
+pub type WatchedVolume<I> = (Box<dyn ChangeSource>, Arc<Mutex<I>>);
+
+/// How a monitor treats the volumes it watches
+#[derive(Debug, Clone, Default)]
+pub struct MonitorOptions {
+    /// Paths kept out of the index (the same rules as scanning)
+    pub exclusions: Exclusions,
+
+    /// Time between full consistency checks of each volume (None = never)
+    pub check_interval: Option<Duration>,
+
+    /// Where to send reports of checks that found drift (the daemon rescans on large drift)
+    pub drift_reports: Option<mpsc::Sender<DriftReport>>,
+
+    /// Where to send paths whose entries (and descendants) changed, once the
+    /// change is in the index (the daemon mirrors them into SQLite)
+    pub changed_paths: Option<mpsc::Sender<PathBuf>>,
+
+    /// Full-text index to keep up to date with the files that change
+    pub content_index: Option<Arc<ContentIndex>>,
+}
+
+/// Counters shared between the handler threads and whoever asks for status
+#[derive(Default)]
+pub struct MonitorStatus {
+    events: AtomicU64,
+    batches: AtomicU64,
+    created: AtomicU64,
+    deleted: AtomicU64,
+    renamed: AtomicU64,
+    modified: AtomicU64,
+    overflows: AtomicU64,
+    reconciliations: AtomicU64,
+    drift: AtomicU64,
+    last_batch_millis: AtomicI64,
+    last_report: Mutex<Option<DriftReport>>,
+}
+
+/// Point-in-time copy of the monitor counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct MonitorStats {
+    /// Events received from the change sources
+    pub events: u64,
+
+    /// Batches applied (one index lock each)
+    pub batches: u64,
+
+    /// Entries added
+    pub created: u64,
+
+    /// Removals (a directory and its contents count once)
+    pub deleted: u64,
+
+    /// Renames and moves
+    pub renamed: u64,
+
+    /// Metadata updates
+    pub modified: u64,
+
+    /// Times events were lost
+    pub overflows: u64,
+
+    /// Reconciliation scans run (after lost events, or periodic checks)
+    pub reconciliations: u64,
+
+    /// Entries those scans found out of date
+    pub drift: u64,
+
+    /// When the last batch was applied
+    pub last_batch: Option<DateTime<Utc>>,
+}
+
+impl MonitorStats {
+    /// Index changes applied (events after coalescing)
+    pub fn changes(&self) -> u64 {
+        self.created + self.deleted + self.renamed + self.modified
+    }
+}
+
+impl MonitorStatus {
+    /// Current counter values
+    pub fn snapshot(&self) -> MonitorStats {
+        let last_batch_millis = self.last_batch_millis.load(Ordering::Relaxed);
+        MonitorStats {
+            events: self.events.load(Ordering::Relaxed),
+            batches: self.batches.load(Ordering::Relaxed),
+            created: self.created.load(Ordering::Relaxed),
+            deleted: self.deleted.load(Ordering::Relaxed),
+            renamed: self.renamed.load(Ordering::Relaxed),
+            modified: self.modified.load(Ordering::Relaxed),
+            overflows: self.overflows.load(Ordering::Relaxed),
+            reconciliations: self.reconciliations.load(Ordering::Relaxed),
+            drift: self.drift.load(Ordering::Relaxed),
+            last_batch: if last_batch_millis == 0 {
+                None
+            } else {
+                DateTime::from_timestamp_millis(last_batch_millis)
+            },
+        }
+    }
+
+    /// Report of the most recent reconciliation scan
+    pub fn last_report(&self) -> Option<DriftReport> {
+        self.last_report.lock().unwrap().clone()
+    }
+
+    fn add(counter: &AtomicU64, n: u64) {
+        counter.fetch_add(n, Ordering::Relaxed);
+    }
+}
+
+/// Keeps volume indexes up to date from their change sources
+///
+/// Each source gets a handler thread that collects events for a short
+/// window, coalesces them and applies the batch under a single index lock.
+/// When events were lost, the affected subtree is rescanned and compared
+/// with the index; every volume also gets a periodic consistency check.
+/// Messages go to the activity log, not stdout.
+pub struct Monitor {
+    sources: Vec<Box<dyn ChangeSource>>,
+
+    /// Event handler threads (one per source)
+    handlers: Vec<thread::JoinHandle<()>>,
+
+    status: Arc<MonitorStatus>,
+    options: MonitorOptions,
+}
+
+impl Monitor {
+    /// Start every source, each updating its own volume index
+    ///
+    /// Sources that fail to start are reported and skipped.
+    pub fn new<I: IndexView + LiveIndex + Send + 'static>(
+        sources: Vec<WatchedVolume<I>>,
+        options: MonitorOptions,
+    ) -> Result<Self> {
+        let mut monitor = Self::with_options(options);
+        for (source, index) in sources {
+            let description = source.describe();
+            if let Err(e) = monitor.watch(source, index) {
+                eprintln!("Failed to watch {}: {:#}", description, e);
+            }
+        }
+
+        if monitor.sources.is_empty() {
+            eprintln!("⚠️  No volumes could be monitored");
+        } else {
+            match activity_log::path() {
+                Some(log) => eprintln!("📡 Monitoring {} volume(s), activity log: {}", monitor.sources.len(), log.display()),
+                None => eprintln!("📡 Monitoring {} volume(s)", monitor.sources.len()),
+            }
+        }
+
+        Ok(monitor)
+    }
+
+    /// A monitor with no sources yet; volumes are added with `watch` as they become ready
+    pub fn with_options(options: MonitorOptions) -> Self {
+        Self {
+            sources: Vec::new(),
+            handlers: Vec::new(),
+            status: Arc::new(MonitorStatus::default()),
+            options,
+        }
+    }
+
+    /// Start one source, updating `index`
+    pub fn watch<I: IndexView + LiveIndex + Send + 'static>(
+        &mut self,
+        mut source: Box<dyn ChangeSource>,
+        index: Arc<Mutex<I>>,
+    ) -> Result<()> {
+        let (tx, rx) = mpsc::channel();
+        let description = source.describe();
+        source.start(tx)?;
+        activity_log::log(format!("📡 Monitoring {}", description));
+
+        let status = Arc::clone(&self.status);
+        let options = self.options.clone();
+        let root = source.root().to_path_buf();
+        let handler = thread::spawn(move || {
+            let mut next_check = options.check_interval.map(|interval| Instant::now() + interval);
+            loop {
+                let wait = next_check.map(|at| at.saturating_duration_since(Instant::now()));
+                match next_batch(&rx, wait) {
+                    Received::Batch(batch) => {
+                        MonitorStatus::add(&status.events, batch.len() as u64);
+                        let batch = without_excluded(coalesce(batch), &root, &options.exclusions);
+                        let touched = touched_paths(&options, &batch);
+                        for subtree in apply_batch(batch, &index, &status) {
+                            let report = check_subtree(&index, &subtree, &options.exclusions, &status, "Rescan after lost events");
+                            send_drift(&options, report);
+                        }
+                        send_changes(&options, touched);
+                    }
+                    Received::Idle => {}
+                    Received::Closed => break,
+                }
+
+                if let (Some(at), Some(interval)) = (next_check, options.check_interval) {
+                    if Instant::now() >= at {
+                        let report = check_subtree(&index, &root, &options.exclusions, &status, "Consistency check");
+                        send_drift(&options, report);
+                        next_check = Some(Instant::now() + interval);
+                    }
+                }
+            }
+            activity_log::log(format!("🛑 Stopped monitoring {}", description));
+        });
+
+        self.sources.push(source);
+        self.handlers.push(handler);
+        Ok(())
+    }
+
+    /// Shared counters, updated as batches are applied
+    pub fn status(&self) -> Arc<MonitorStatus> {
+        Arc::clone(&self.status)
+    }
+
+    /// Stop monitoring and wait for the event handlers to finish
+    ///
+    /// Pending events are applied first. Once this returns, no handler
+    /// thread holds a reference to an index.
+    pub fn stop(self) -> MonitorStats {
+        // Dropping the sources closes their channels, which ends the handler loops
+        drop(self.sources);
+        for handler in self.handlers {
+            let _ = handler.join();
+        }
+        self.status.snapshot()
+    }
+}
+
+/// What waiting on a source produced
+enum Received {
+    Batch(Vec<ChangeEvent>),
+
+    /// Nothing arrived within the wait
+    Idle,
+
+    /// The source is gone and everything was delivered
+    Closed,
+}
+
+/// Wait (up to `wait`, or indefinitely) for an event, then collect more until the window closes
+fn next_batch(rx: &mpsc::Receiver<ChangeEvent>, wait: Option<Duration>) -> Received {
+    let first = match wait {
+        Some(wait) => match rx.recv_timeout(wait) {
+            Ok(event) => event,
+            Err(mpsc::RecvTimeoutError::Timeout) => return Received::Idle,
+            Err(mpsc::RecvTimeoutError::Disconnected) => return Received::Closed,
+        },
+        None => match rx.recv() {
+            Ok(event) => event,
+            Err(_) => return Received::Closed,
+        },
+    };
+    let deadline = Instant::now() + DEBOUNCE_WINDOW;
+    let mut batch = vec![first];
+
+    while batch.len() < MAX_BATCH {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(remaining) {
+            Ok(event) => batch.push(event),
+            Err(_) => break,
+        }
+    }
+    Received::Batch(batch)
+}
+
+/// Leave out changes to excluded paths below `root`
+///
+/// A rename into an excluded path is the source disappearing from the index.
+fn without_excluded(batch: Vec<ChangeEvent>, root: &Path, exclusions: &Exclusions) -> Vec<ChangeEvent> {
+    if exclusions.is_empty() {
+        return batch;
+    }
+    let root = root.to_string_lossy();
+    let excluded = |path: &Path| exclusions.excludes_below(&root, &path.to_string_lossy());
+
+    batch
+        .into_iter()
+        .filter_map(|event| match event {
+            ChangeEvent::Created(path) | ChangeEvent::Modified(path) if excluded(&path) => None,
+            ChangeEvent::Renamed { from, to } if excluded(&to) => Some(ChangeEvent::Deleted(from)),
+            event => Some(event),
+        })
+        .collect()
+}
+
+/// Pass a report that found drift on to whoever asked for them
+fn send_drift(options: &MonitorOptions, report: Option<DriftReport>) {
+    let Some(report) = report.filter(|report| !report.is_clean()) else {
+        return;
+    };
+    send_changes(options, vec![report.root.clone()]);
+    if let Some(tx) = &options.drift_reports {
+        let _ = tx.send(report);
+    }
+}
+
+/// Paths a batch changes, if anyone listens for them
+fn touched_paths(options: &MonitorOptions, batch: &[ChangeEvent]) -> Vec<PathBuf> {
+    if options.changed_paths.is_none() && options.content_index.is_none() {
+        return Vec::new();
+    }
+    batch
+        .iter()
+        .flat_map(|event| match event {
+            ChangeEvent::Created(path) | ChangeEvent::Deleted(path) | ChangeEvent::Modified(path) => vec![path.clone()],
+            ChangeEvent::Renamed { from, to } => vec![from.clone(), to.clone()],
+            // Reported with the reconciliation that follows
+            ChangeEvent::Overflow(_) => Vec::new(),
+        })
+        .collect()
+}
+
+fn send_changes(options: &MonitorOptions, paths: Vec<PathBuf>) {
+    if let Some(index) = &options.content_index {
+        index.update_paths(&paths);
+    }
+    if let Some(tx) = &options.changed_paths {
+        for path in paths {
+            let _ = tx.send(path);
+        }
+    }
+}
+
+/// Reconcile a subtree with the disk, logging the drift report
+///
+/// Returns the report, or `None` if the subtree could not be read.
+pub fn check_subtree<I: IndexView + LiveIndex>(
+    index: &Mutex<I>,
+    subtree: &Path,
+    exclusions: &Exclusions,
+    status: &MonitorStatus,
+    reason: &str,
+) -> Option<DriftReport> {
+    activity_log::log(format!("🔍 {}: {}", reason, subtree.display()));
+    match reconcile(index, subtree, exclusions) {
+        Ok(report) => {
+            MonitorStatus::add(&status.reconciliations, 1);
+            MonitorStatus::add(&status.drift, report.total() as u64);
+            if report.is_clean() {
+                activity_log::log(format!("✅ No drift: {}", report));
+            } else {
+                activity_log::log(format!("⚠️  Drift corrected: {}", report));
+                for example in &report.examples {
+                    activity_log::log(format!("   {}", example));
+                }
+            }
+            *status.last_report.lock().unwrap() = Some(report.clone());
+            Some(report)
+        }
+        Err(e) => {
+            activity_log::log(format!("Reconciliation of {} failed: {:#}", subtree.display(), e));
+            None
+        }
+    }
+}
+
+/// Drop events made redundant by later ones in the same batch
+///
+/// A modification is dropped when a later event reads the same path from
+/// disk anyway, and repeated overflows collapse into the last one.
+pub fn coalesce(batch: Vec<ChangeEvent>) -> Vec<ChangeEvent> {
+    let mut refreshed: HashSet<PathBuf> = HashSet::new();
+    let mut overflowed: HashSet<PathBuf> = HashSet::new();
+    let mut kept = Vec::with_capacity(batch.len());
+
+    for event in batch.into_iter().rev() {
+        let keep = match &event {
+            ChangeEvent::Modified(path) => refreshed.insert(path.clone()),
+            ChangeEvent::Created(path) => {
+                refreshed.insert(path.clone());
+                true
+            }
+            ChangeEvent::Renamed { to, .. } => {
+                refreshed.insert(to.clone());
+                true
+            }
+            // Before a delete, the path's earlier state no longer matters to later events
+            ChangeEvent::Deleted(path) => {
+                refreshed.remove(path);
+                true
+            }
+            ChangeEvent::Overflow(path) => overflowed.insert(path.clone()),
+        };
+        if keep {
+            kept.push(event);
+        }
+    }
+
+    kept.reverse();
+    kept
+}
+
+/// A change with everything read from disk, ready to apply under the lock
+enum PreparedChange {
+    Upsert(FileEntry),
+    RemoveTree(String),
+    Move {
+        from: String,
+        to: String,
+        fresh: Option<FileEntry>,
+    },
+    Update {
+        path: String,
+        size: u64,
+        modified: Option<SystemTime>,
+    },
+}
+
+/// Read what a change needs from disk (outside the index lock)
+fn prepare(event: &ChangeEvent) -> Option<PreparedChange> {
+    match event {
+        // Gone again before we got to it: nothing to add
+        ChangeEvent::Created(path) => entry_for_path(path).ok().map(PreparedChange::Upsert),
+        ChangeEvent::Deleted(path) => Some(PreparedChange::RemoveTree(path.to_string_lossy().to_string())),
+        ChangeEvent::Renamed { from, to } => Some(PreparedChange::Move {
+            from: from.to_string_lossy().to_string(),
+            to: to.to_string_lossy().to_string(),
+            fresh: entry_for_path(to).ok(),
+        }),
+        ChangeEvent::Modified(path) => std::fs::metadata(path).ok().map(|metadata| PreparedChange::Update {
+            path: path.to_string_lossy().to_string(),
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        }),
+        ChangeEvent::Overflow(_) => None,
+    }
+}
+
+/// Apply a batch of change events to a volume index under one lock
+///
+/// Returns the subtrees where events were lost, which need reconciling.
+pub fn apply_batch<I: LiveIndex>(batch: Vec<ChangeEvent>, index: &Mutex<I>, status: &MonitorStatus) -> Vec<PathBuf> {
+    let mut lost = Vec::new();
+    let prepared: Vec<(ChangeEvent, PreparedChange)> = batch
+        .into_iter()
+        .filter_map(|event| match prepare(&event) {
+            Some(change) => Some((event, change)),
+            None => {
+                if let ChangeEvent::Overflow(path) = event {
+                    MonitorStatus::add(&status.overflows, 1);
+                    activity_log::log(format!("⚠️  Change events were lost under {}", path.display()));
+                    lost.push(path);
+                }
+                None
+            }
+        })
+        .collect();
+
+    if prepared.is_empty() {
+        return lost;
+    }
+
+    let mut messages = Vec::with_capacity(prepared.len());
+    {
+        let mut index = index.lock().unwrap();
+        for (event, change) in &prepared {
+            messages.push(apply_prepared(event, change, &mut *index, status));
+        }
+    }
+
+    MonitorStatus::add(&status.batches, 1);
+    status
+        .last_batch_millis
+        .store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+    for message in messages {
+        activity_log::log(message);
+    }
+    lost
+}
+
+/// Apply one prepared change, returning the log message
+fn apply_prepared<I: LiveIndex>(event: &ChangeEvent, change: &PreparedChange, index: &mut I, status: &MonitorStatus) -> String {
+    match (event, change) {
+        (ChangeEvent::Created(path), PreparedChange::Upsert(entry)) => {
+            // Replace rather than duplicate if the path is already indexed
+            index.remove_by_path(&entry.path);
+            archive::forget_members(index, &entry.path);
+            index.add_entry(entry.clone());
+            MonitorStatus::add(&status.created, 1);
+            format!("➕ Created: {}", path.display())
+        }
+        (ChangeEvent::Deleted(path), PreparedChange::RemoveTree(path_str)) => {
+            // A deleted (or moved-away) directory takes its descendants with it
+            index.remove_tree(path_str);
+            archive::forget_members(index, path_str);
+            MonitorStatus::add(&status.deleted, 1);
+            format!("➖ Removed: {}", path.display())
+        }
+        (ChangeEvent::Renamed { from, to }, PreparedChange::Move { from: from_str, to: to_str, fresh }) => {
+            // Anything already at the destination was replaced by the move
+            index.remove_tree(to_str);
+            archive::forget_members(index, to_str);
+            let moved = index.move_path(from_str, to_str) + archive::move_members(index, from_str, to_str);
+
+            // Refresh the moved entry itself (new parent, ctime); add it if it was never indexed
+            if let Some(entry) = fresh {
+                index.remove_by_path(&entry.path);
+                index.add_entry(entry.clone());
+            }
+            MonitorStatus::add(&status.renamed, 1);
+            if moved > 1 {
+                format!("🔀 Moved: {} → {} ({} entries)", from.display(), to.display(), moved)
+            } else {
+                format!("🔀 Renamed: {} → {}", from.display(), to.display())
+            }
+        }
+        (_, PreparedChange::Update { path, size, modified }) => {
+            index.update_metadata_by_path(path, *size, *modified);
+            archive::forget_members(index, path);
+            MonitorStatus::add(&status.modified, 1);
+            format!("✏️  Modified: {}", path)
+        }
+        _ => unreachable!("change prepared from a different event"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::change_source;
+    use crate::index::FileIndex;
+    use std::fs;
+    use std::time::{Duration, Instant};
+
+    /// Wait until `condition` holds for the index (or give up after a few seconds)
+    fn wait_until(index: &Mutex<FileIndex>, condition: impl Fn(&FileIndex) -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if condition(&index.lock().unwrap()) {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        false
+    }
+
+    #[test]
+    fn test_monitor_updates_index() {
+        let root = std::env::temp_dir().join(format!("nothing_monitor_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        let index = Arc::new(Mutex::new(FileIndex::new()));
+        let sources = vec![(change_source::for_path(root.clone()), Arc::clone(&index))];
+        let monitor = Monitor::new(sources, MonitorOptions::default()).unwrap();
+        assert_eq!(monitor.sources.len(), 1);
+
+        let has = |name: &'static str| move |index: &FileIndex| index.entries().iter().any(|e| e.name == name);
+
+        fs::write(root.join("draft.txt"), b"hello").unwrap();
+        assert!(wait_until(&index, has("draft.txt")));
+
+        fs::rename(root.join("draft.txt"), root.join("final.txt")).unwrap();
+        assert!(wait_until(&index, |i| has("final.txt")(i) && !has("draft.txt")(i)));
+        assert_eq!(index.lock().unwrap().len(), 1);
+
+        fs::remove_file(root.join("final.txt")).unwrap();
+        assert!(wait_until(&index, |i| i.is_empty()));
+
+        // Moving a directory carries its children along
+        fs::create_dir_all(root.join("projects/alpha")).unwrap();
+        fs::write(root.join("projects/alpha/plan.md"), b"").unwrap();
+        assert!(wait_until(&index, has("plan.md")));
+        fs::create_dir(root.join("archive")).unwrap();
+        fs::rename(root.join("projects/alpha"), root.join("archive/alpha")).unwrap();
+        let moved = root.join("archive/alpha/plan.md").to_string_lossy().to_string();
+        assert!(wait_until(&index, |i| i.entries().iter().any(|e| e.path == moved)));
+        {
+            let index = index.lock().unwrap();
+            assert_eq!(index.len(), 4);
+            let alpha = index.entries().iter().find(|e| e.name == "alpha").unwrap();
+            let archive = index.entries().iter().find(|e| e.name == "archive").unwrap();
+            assert_eq!(alpha.parent_id, archive.file_id);
+        }
+
+        // Removing a moved-away directory drops its children
+        let outside = std::env::temp_dir().join(format!("nothing_monitor_out_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&outside);
+        fs::rename(root.join("archive"), &outside).unwrap();
+        assert!(wait_until(&index, |i| i.len() == 1));
+        fs::remove_dir_all(&outside).unwrap();
+
+        let stats = monitor.stop();
+        assert_eq!(Arc::strong_count(&index), 1);
+        assert!(stats.events >= stats.changes());
+        assert!(stats.renamed >= 1, "{:?}", stats);
+        assert!(stats.batches >= 1 && stats.batches <= stats.changes());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_coalesce_batch() {
+        let path = |name: &str| PathBuf::from(format!("/data/{}", name));
+        let batch = vec![
+            ChangeEvent::Created(path("a.txt")),
+            ChangeEvent::Modified(path("a.txt")),
+            ChangeEvent::Modified(path("b.txt")),
+            ChangeEvent::Overflow(path("")),
+            ChangeEvent::Modified(path("b.txt")),
+            ChangeEvent::Modified(path("c.txt")),
+            ChangeEvent::Deleted(path("c.txt")),
+            ChangeEvent::Overflow(path("")),
+        ];
+
+        assert_eq!(
+            coalesce(batch),
+            vec![
+                ChangeEvent::Created(path("a.txt")),
+                ChangeEvent::Modified(path("a.txt")),
+                ChangeEvent::Modified(path("b.txt")),
+                ChangeEvent::Modified(path("c.txt")),
+                ChangeEvent::Deleted(path("c.txt")),
+                ChangeEvent::Overflow(path("")),
+            ]
+        );
+    }
+
+    /// Source that replays a fixed list of events, then goes away
+    struct ScriptedSource {
+        root: PathBuf,
+        events: Vec<ChangeEvent>,
+    }
+
+    impl ChangeSource for ScriptedSource {
+        fn describe(&self) -> String {
+            format!("scripted {}", self.root.display())
+        }
+
+        fn root(&self) -> &Path {
+            &self.root
+        }
+
+        fn start(&mut self, events: mpsc::Sender<ChangeEvent>) -> Result<()> {
+            for event in self.events.drain(..) {
+                events.send(event)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_overflow_triggers_reconciliation() {
+        let root = std::env::temp_dir().join(format!("nothing_overflow_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("lost")).unwrap();
+        fs::write(root.join("lost/missed.txt"), b"").unwrap();
+        fs::write(root.join("lost/skip.tmp"), b"").unwrap();
+
+        // The events for these files never arrived, only the overflow did
+        let source = ScriptedSource {
+            root: root.clone(),
+            events: vec![ChangeEvent::Overflow(root.join("lost"))],
+        };
+        let index = Arc::new(Mutex::new(FileIndex::new()));
+        let (drift_tx, drift_rx) = mpsc::channel();
+        let (changed_tx, changed_rx) = mpsc::channel();
+        let options = MonitorOptions {
+            exclusions: Exclusions::new(&["*.tmp".to_string()]),
+            check_interval: None,
+            drift_reports: Some(drift_tx),
+            changed_paths: Some(changed_tx),
+            ..Default::default()
+        };
+        let monitor = Monitor::new(vec![(Box::new(source) as Box<dyn ChangeSource>, Arc::clone(&index))], options).unwrap();
+        let status = monitor.status();
+        let stats = monitor.stop();
+
+        // The drift was reported to the listener too
+        let reported: Vec<DriftReport> = drift_rx.try_iter().collect();
+        assert_eq!(reported.len(), 1);
+        assert_eq!(reported[0].root, root.join("lost"));
+        assert_eq!(changed_rx.try_iter().collect::<Vec<_>>(), vec![root.join("lost")]);
+
+        assert_eq!(stats.overflows, 1);
+        assert_eq!(stats.reconciliations, 1);
+        assert_eq!(stats.drift, 1);
+        assert_eq!(status.last_report().unwrap().added, 1);
+        let index = index.lock().unwrap();
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.entries()[0].name, "missed.txt");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
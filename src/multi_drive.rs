@@ -1,12 +1,21 @@
 // Multi-drive scanning support
+use crate::config::Exclusions;
+use crate::dir_walker::DirectoryWalker;
 use crate::file_entry::FileEntry;
 use crate::index::FileIndex;
+#[cfg(windows)]
 use crate::mft_reader::MftReader;
+#[cfg(windows)]
 use crate::mft_reader_ntfs::MftReaderNtfs;
-use anyhow::Result;
+use crate::multi_volume::MultiVolumeIndex;
+use crate::persistence::{self, StorageOptions, StorageStats};
+use crate::scanner::{Progress, Scanner, VolumeSource};
+use anyhow::{Context, Result};
 use std::path::PathBuf;
+use std::sync::Arc;
 
 /// Get all available drives on Windows
+#[cfg(windows)]
 pub fn get_all_drives() -> Vec<char> {
     let mut drives = Vec::new();
 
@@ -24,7 +33,14 @@ pub fn get_all_drives() -> Vec<char> {
     drives
 }
 
+/// Drive letters only exist on Windows
+#[cfg(not(windows))]
+pub fn get_all_drives() -> Vec<char> {
+    Vec::new()
+}
+
 /// Get drive type (Fixed, Removable, Network, etc.)
+#[cfg(windows)]
 pub fn get_drive_type(drive: char) -> DriveType {
     use std::ffi::OsStr;
     use std::os::windows::ffi::OsStrExt;
@@ -49,6 +65,11 @@ pub fn get_drive_type(drive: char) -> DriveType {
     }
 }
 
+#[cfg(not(windows))]
+pub fn get_drive_type(_drive: char) -> DriveType {
+    DriveType::Unknown
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DriveType {
     Fixed,
@@ -81,31 +102,98 @@ pub enum CloudProvider {
     OneDrive,
 }
 
+/// Create the scanner for a volume
+///
+/// Directory walks report through `progress`; the MFT readers print their own.
+pub fn create_scanner(
+    source: &VolumeSource,
+    use_full_metadata: bool,
+    exclusions: &Exclusions,
+    progress: &Progress,
+) -> Result<Box<dyn Scanner>> {
+    match source {
+        #[cfg(windows)]
+        VolumeSource::Drive(drive) => {
+            if use_full_metadata {
+                Ok(Box::new(MftReaderNtfs::new(*drive)?))
+            } else {
+                Ok(Box::new(MftReader::new(*drive)?))
+            }
+        }
+        #[cfg(not(windows))]
+        VolumeSource::Drive(drive) => {
+            let _ = (use_full_metadata, progress);
+            anyhow::bail!("Drive {}: can only be scanned on Windows (use --root to index a directory)", drive)
+        }
+        VolumeSource::Directory(root) => {
+            Ok(Box::new(DirectoryWalker::new(root).exclusions(exclusions.clone()).progress(Arc::clone(progress))))
+        }
+    }
+}
+
+/// Scan a volume into a new index, leaving out excluded entries
+pub fn scan_source(
+    source: &VolumeSource,
+    use_full_metadata: bool,
+    exclusions: &Exclusions,
+    progress: &Progress,
+) -> Result<FileIndex> {
+    let mut index = FileIndex::new();
+    let scanner = create_scanner(source, use_full_metadata, exclusions, progress)?;
+    scanner
+        .scan_into_index(&mut index)
+        .with_context(|| format!("Failed to scan {}", scanner.describe()))?;
+
+    // The walker skips excluded entries itself; MFT scans are filtered afterwards
+    if matches!(source, VolumeSource::Drive(_)) && !exclusions.is_empty() {
+        index.retain(|entry| !exclusions.excludes(&entry.path));
+    }
+
+    Ok(index)
+}
+
+/// Scan a volume, save it to its own index file and add it to the volumes
+pub fn scan_volume(
+    volumes: &MultiVolumeIndex,
+    source: &VolumeSource,
+    use_full_metadata: bool,
+    storage: &StorageOptions,
+    exclusions: &Exclusions,
+    progress: &Progress,
+) -> Result<StorageStats> {
+    let index = scan_source(source, use_full_metadata, exclusions, progress)?;
+
+    let name = source.name();
+    let cache_path = persistence::get_volume_index_path(&name)?;
+    let stats = persistence::save_volume_index(&index, &name, &cache_path, storage)?;
+    volumes.add_volume(&name, Some(cache_path), index);
+    Ok(stats)
+}
+
 /// Scan all fixed drives
-pub fn scan_all_fixed_drives(index: &mut FileIndex, use_full_metadata: bool) -> Result<()> {
+pub fn scan_all_fixed_drives(
+    volumes: &MultiVolumeIndex,
+    use_full_metadata: bool,
+    storage: &StorageOptions,
+    exclusions: &Exclusions,
+    progress: &Progress,
+) -> Result<()> {
     let drives = get_all_drives();
 
-    println!("Found {} drive(s)", drives.len());
+    progress(&format!("Found {} drive(s)", drives.len()));
 
     for drive in drives {
         let drive_type = get_drive_type(drive);
-        println!("  {}: - {:?}", drive, drive_type);
+        progress(&format!("  {}: - {:?}", drive, drive_type));
 
-        // Only scan fixed drives (skip removable, network, etc.)
-        if drive_type == DriveType::Fixed {
-            println!("\nScanning drive {}:...", drive);
-
-            let result = if use_full_metadata {
-                let reader = MftReaderNtfs::new(drive)?;
-                reader.scan_into_index(index)
-            } else {
-                let reader = MftReader::new(drive)?;
-                reader.scan_into_index(index)
-            };
+        // Only scan fixed drives (skip removable, network, etc.) that have no cached index
+        if drive_type == DriveType::Fixed && volumes.volume(&drive.to_string()).is_none() {
+            progress(&format!("Scanning drive {}:...", drive));
 
-            match result {
-                Ok(_) => println!("✅ Drive {}: scanned successfully", drive),
-                Err(e) => eprintln!("⚠️  Drive {}: scan failed: {}", drive, e),
+            // Each drive is saved to its own index file
+            match scan_volume(volumes, &VolumeSource::Drive(drive), use_full_metadata, storage, exclusions, progress) {
+                Ok(_) => progress(&format!("✅ Drive {}: scanned successfully", drive)),
+                Err(e) => progress(&format!("⚠️  Drive {}: scan failed: {}", drive, e)),
             }
         }
     }
@@ -171,11 +259,12 @@ pub fn index_cloud_storage_folder(
     index: &mut FileIndex,
     provider: CloudProvider,
     path: &PathBuf,
+    progress: &Progress,
 ) -> Result<usize> {
     use std::fs;
     use walkdir::WalkDir;
 
-    println!("\nIndexing {:?} folder: {:?}", provider, path);
+    progress(&format!("Indexing {:?} folder: {:?}", provider, path));
 
     let mut count = 0;
     let mut skipped = 0;
@@ -222,14 +311,11 @@ pub fn index_cloud_storage_folder(
         count += 1;
 
         if count % 10000 == 0 {
-            println!("  Indexed {} files from {:?}...", count, provider);
+            progress(&format!("  Indexed {} files from {:?}...", count, provider));
         }
     }
 
-    println!(
-        "✅ {:?} indexed: {} files ({} cloud-only skipped)",
-        provider, count, skipped
-    );
+    progress(&format!("✅ {:?} indexed: {} files ({} cloud-only skipped)", provider, count, skipped));
 
     Ok(count)
 }
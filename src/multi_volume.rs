@@ -0,0 +1,238 @@
+use crate::filters::SearchFilters;
+use crate::index::{FileIndex, IndexView};
+use crate::mapped_index::MappedIndex;
+use crate::persistence::{self, StorageOptions, StorageStats};
+use crate::search::{SearchEngine, SearchResult};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// One indexed volume: a drive, a cloud folder, ...
+pub struct Volume<I = FileIndex> {
+    /// Volume name (drive letter for drives, e.g. "C")
+    pub name: String,
+
+    /// Index file the volume is saved to (`None` = rebuilt every run)
+    pub cache_path: Option<String>,
+
+    /// The volume's own index (shared with its monitor)
+    pub index: Arc<Mutex<I>>,
+}
+
+impl<I> Clone for Volume<I> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            cache_path: self.cache_path.clone(),
+            index: Arc::clone(&self.index),
+        }
+    }
+}
+
+/// Set of per-volume indexes searched as one
+///
+/// Each volume keeps its own `FileIndex` (and index file), so loading,
+/// saving and monitoring work per volume and no entries are copied into a
+/// combined index. Volumes can be added or removed while the index is in use.
+pub struct MultiVolumeIndex<I = FileIndex> {
+    volumes: RwLock<BTreeMap<String, Volume<I>>>,
+}
+
+impl<I> MultiVolumeIndex<I> {
+    /// Create an empty set of volumes
+    pub fn new() -> Self {
+        Self {
+            volumes: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Add (or replace) a volume and return its shared index
+    pub fn add_volume(&self, name: &str, cache_path: Option<String>, index: I) -> Arc<Mutex<I>> {
+        let index = Arc::new(Mutex::new(index));
+        let volume = Volume {
+            name: name.to_string(),
+            cache_path,
+            index: Arc::clone(&index),
+        };
+        self.volumes.write().unwrap().insert(name.to_string(), volume);
+        index
+    }
+
+    /// Remove a volume; searches stop including it immediately
+    pub fn remove_volume(&self, name: &str) -> Option<Volume<I>> {
+        self.volumes.write().unwrap().remove(name)
+    }
+
+    /// Get a volume by name
+    pub fn volume(&self, name: &str) -> Option<Volume<I>> {
+        self.volumes.read().unwrap().get(name).cloned()
+    }
+
+    /// Snapshot of all volumes, ordered by name
+    pub fn volumes(&self) -> Vec<Volume<I>> {
+        self.volumes.read().unwrap().values().cloned().collect()
+    }
+
+    /// Names of all volumes
+    pub fn volume_names(&self) -> Vec<String> {
+        self.volumes.read().unwrap().keys().cloned().collect()
+    }
+
+    /// Number of volumes
+    pub fn volume_count(&self) -> usize {
+        self.volumes.read().unwrap().len()
+    }
+}
+
+impl<I: IndexView> MultiVolumeIndex<I> {
+    /// Total number of files across all volumes
+    pub fn file_count(&self) -> usize {
+        self.volumes().iter().map(|v| v.index.lock().unwrap().file_count()).sum()
+    }
+
+    /// Total number of directories across all volumes
+    pub fn directory_count(&self) -> usize {
+        self.volumes().iter().map(|v| v.index.lock().unwrap().directory_count()).sum()
+    }
+
+    /// Search every volume and merge the results by score
+    ///
+    /// Volumes are locked one at a time, so a monitor updating one volume
+    /// only blocks the search while that volume is being searched.
+    pub fn search(
+        &self,
+        engine: &mut SearchEngine,
+        query: &str,
+        limit: usize,
+        filters: &SearchFilters,
+    ) -> Vec<SearchResult> {
+        let mut results = Vec::new();
+        for volume in self.volumes() {
+            let index = volume.index.lock().unwrap();
+            results.extend(engine.search_with_filters(&*index, query, limit, filters));
+        }
+
+        results.sort_by_key(|r| std::cmp::Reverse(r.score));
+        results.truncate(limit);
+        results
+    }
+
+    /// Count matches across every volume
+    pub fn count_matches(&self, engine: &mut SearchEngine, query: &str, filters: &SearchFilters) -> usize {
+        self.volumes()
+            .iter()
+            .map(|volume| {
+                let index = volume.index.lock().unwrap();
+                engine.count_matches_with_filters(&*index, query, filters)
+            })
+            .sum()
+    }
+}
+
+impl MultiVolumeIndex<FileIndex> {
+    /// Load a volume from its index file and add it
+    pub fn load_volume(&self, name: &str, cache_path: &str) -> Result<Arc<Mutex<FileIndex>>> {
+        let index = persistence::load_index(cache_path)?;
+        Ok(self.add_volume(name, Some(cache_path.to_string()), index))
+    }
+
+    /// Add every volume imported from a listing (`nothing import`)
+    ///
+    /// Imported volumes have no index file to save to and no monitor, so
+    /// they stay as imported. A file that fails to load is skipped so the
+    /// others still load.
+    pub fn load_imported(&self) -> Result<ImportedVolumes> {
+        let mut names = Vec::new();
+        let mut skipped = Vec::new();
+        for (name, path) in persistence::imported_volumes()? {
+            match persistence::load_index(&path) {
+                Ok(index) => {
+                    self.add_volume(&name, None, index);
+                    names.push(name);
+                }
+                Err(e) => skipped.push((path, e)),
+            }
+        }
+        Ok(ImportedVolumes { names, skipped })
+    }
+
+    /// Save every volume that has an index file to its own file
+    pub fn save_all(&self, options: &StorageOptions) -> Result<Vec<(String, StorageStats)>> {
+        let mut saved = Vec::new();
+        for volume in self.volumes() {
+            if let Some(cache_path) = &volume.cache_path {
+                let index = volume.index.lock().unwrap();
+                let stats = persistence::save_volume_index(&index, &volume.name, cache_path, options)?;
+                saved.push((volume.name.clone(), stats));
+            }
+        }
+        Ok(saved)
+    }
+}
+
+impl MultiVolumeIndex<MappedIndex> {
+    /// Map a volume's index file and add it
+    pub fn map_volume(&self, name: &str, map_path: &str) -> Result<Arc<Mutex<MappedIndex>>> {
+        let index = persistence::load_mapped_index(map_path)?;
+        Ok(self.add_volume(name, Some(map_path.to_string()), index))
+    }
+}
+
+/// What `load_imported` added and left out
+pub struct ImportedVolumes {
+    /// Names of the volumes added
+    pub names: Vec<String>,
+
+    /// Index files that failed to load, with why
+    pub skipped: Vec<(String, anyhow::Error)>,
+}
+
+impl<I> Default for MultiVolumeIndex<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_entry::FileEntry;
+
+    fn volume_index(drive: char, names: &[&str]) -> FileIndex {
+        let mut index = FileIndex::new();
+        for (i, name) in names.iter().enumerate() {
+            index.add_entry(FileEntry::new(
+                name.to_string(),
+                format!("{}:\\{}", drive, name),
+                false,
+                i as u64 + 100,
+                5,
+                0,
+                None,
+                None,
+                None,
+            ));
+        }
+        index
+    }
+
+    #[test]
+    fn test_search_across_volumes() {
+        let volumes = MultiVolumeIndex::new();
+        volumes.add_volume("C", None, volume_index('C', &["report.pdf", "notes.txt"]));
+        volumes.add_volume("D", None, volume_index('D', &["report_final.pdf"]));
+
+        assert_eq!(volumes.file_count(), 3);
+
+        let mut engine = SearchEngine::new();
+        let results = volumes.search(&mut engine, "report", 10, &SearchFilters::default());
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().any(|r| r.entry.path == "D:\\report_final.pdf"));
+
+        // Removing a volume takes effect without reloading the others
+        volumes.remove_volume("D");
+        let results = volumes.search(&mut engine, "report", 10, &SearchFilters::default());
+        assert_eq!(results.len(), 1);
+        assert_eq!(volumes.volume_names(), vec!["C".to_string()]);
+    }
+}
@@ -1,50 +1,349 @@
-use crate::index::FileIndex;
+use crate::config::{self, SnapshotConfig};
+use crate::file_entry::FileEntry;
+use crate::index::{FileIndex, IndexView};
+use crate::index_format::{self, Compression, Header};
+use crate::mapped_index::MappedIndex;
+use crate::snapshots;
 use anyhow::{Context, Result};
+use bincode::Options;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
 use std::fs;
-use std::io::{BufReader, BufWriter};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom};
 use std::path::PathBuf;
 
-/// Save index to disk using bincode serialization
+/// How index files are stored on disk
+#[derive(Debug, Clone, Copy)]
+pub struct StorageOptions {
+    /// Compressor applied to the encoded entries
+    pub compression: Compression,
+
+    /// Compression level (zstd: 1-22; ignored by lz4 and none)
+    pub level: i32,
+
+    /// Dated copies kept of each volume's index file
+    pub snapshots: SnapshotConfig,
+}
+
+impl Default for StorageOptions {
+    fn default() -> Self {
+        Self {
+            compression: Compression::Zstd,
+            level: 3,
+            snapshots: SnapshotConfig::default(),
+        }
+    }
+}
+
+/// Size report for a stored index
+#[derive(Debug, Clone, Copy)]
+pub struct StorageStats {
+    /// Number of entries in the index
+    pub entries: u64,
+
+    /// Compression used by the file
+    pub compression: Compression,
+
+    /// Size the index would take stored verbatim (legacy format)
+    pub raw_bytes: u64,
+
+    /// Actual size of the file on disk
+    pub stored_bytes: u64,
+}
+
+impl StorageStats {
+    /// Stored size as a fraction of the raw size
+    pub fn ratio(&self) -> f64 {
+        if self.raw_bytes == 0 {
+            1.0
+        } else {
+            self.stored_bytes as f64 / self.raw_bytes as f64
+        }
+    }
+}
+
+/// A legacy (bincode) index file, written before entries had change and
+/// `$FILE_NAME` times; bincode has no field names, so this must keep the old layout
+#[derive(Deserialize)]
+struct LegacyIndex {
+    entries: Vec<LegacyEntry>,
+    #[allow(dead_code)]
+    directory_count: usize,
+}
+
+#[derive(Deserialize)]
+struct LegacyEntry {
+    name: String,
+    path: String,
+    is_directory: bool,
+    file_id: u64,
+    parent_id: u64,
+    size: u64,
+    modified: Option<DateTime<Utc>>,
+    created: Option<DateTime<Utc>>,
+    accessed: Option<DateTime<Utc>>,
+}
+
+impl LegacyIndex {
+    fn into_index(self) -> FileIndex {
+        let mut index = FileIndex::with_capacity(self.entries.len());
+        for e in self.entries {
+            index.add_entry(FileEntry::new(
+                e.name,
+                e.path,
+                e.is_directory,
+                e.file_id,
+                e.parent_id,
+                e.size,
+                e.modified,
+                e.created,
+                e.accessed,
+            ));
+        }
+        index
+    }
+}
+
+/// Save index to disk with the default storage options
 pub fn save_index(index: &FileIndex, path: &str) -> Result<()> {
-    let file = fs::File::create(path)
-        .with_context(|| format!("Failed to create index file: {}", path))?;
-    let writer = BufWriter::new(file);
-    bincode::serialize_into(writer, index)
-        .with_context(|| "Failed to serialize index")?;
+    save_index_with(index, path, &StorageOptions::default())?;
     Ok(())
 }
 
-/// Load index from disk
+/// Save index to disk: front-coded entries behind a streaming compressor
+///
+/// The file is written next to `path` and renamed into place, so a crash
+/// mid-save leaves the previous index intact.
+pub fn save_index_with(index: &FileIndex, path: &str, options: &StorageOptions) -> Result<StorageStats> {
+    let tmp_path = format!("{}.tmp", path);
+    let file = fs::File::create(&tmp_path)
+        .with_context(|| format!("Failed to create index file: {}", tmp_path))?;
+    let mut writer = BufWriter::new(file);
+
+    let header = Header {
+        version: index_format::FORMAT_VERSION,
+        compression: options.compression,
+        level: options.level,
+        entry_count: index.len() as u64,
+        directory_count: index.directory_count() as u64,
+        raw_bytes: bincode::serialized_size(index).unwrap_or(0),
+    };
+    header.write_to(&mut writer)?;
+
+    let writer = match options.compression {
+        Compression::None => {
+            let mut writer = writer;
+            index_format::encode_entries(index, &mut writer)?;
+            writer
+        }
+        Compression::Zstd => {
+            let mut encoder = zstd::stream::write::Encoder::new(writer, options.level)?;
+            index_format::encode_entries(index, &mut encoder)?;
+            encoder.finish()?
+        }
+        Compression::Lz4 => {
+            let mut encoder = lz4_flex::frame::FrameEncoder::new(writer);
+            index_format::encode_entries(index, &mut encoder)?;
+            encoder.finish()?
+        }
+    };
+
+    let file = writer.into_inner().map_err(|e| e.into_error())?;
+    file.sync_all()?;
+    let stored_bytes = file.metadata()?.len();
+    drop(file);
+
+    fs::rename(&tmp_path, path)
+        .with_context(|| format!("Failed to replace index file: {}", path))?;
+
+    Ok(StorageStats {
+        entries: header.entry_count,
+        compression: header.compression,
+        raw_bytes: header.raw_bytes,
+        stored_bytes,
+    })
+}
+
+/// Save a volume's index file, and snapshot it if snapshots are enabled
+pub fn save_volume_index(index: &FileIndex, volume: &str, path: &str, options: &StorageOptions) -> Result<StorageStats> {
+    let stats = save_index_with(index, path, options)?;
+    if options.snapshots.is_enabled() {
+        snapshots::take(volume, path, &options.snapshots)
+            .with_context(|| format!("Saved {}, but failed to snapshot it", path))?;
+    }
+    Ok(stats)
+}
+
+/// Load index from disk (compressed format, or the legacy bincode format)
 pub fn load_index(path: &str) -> Result<FileIndex> {
     let file = fs::File::open(path)
         .with_context(|| format!("Failed to open index file: {}", path))?;
-    let reader = BufReader::new(file);
-    let index = bincode::deserialize_from(reader)
-        .with_context(|| "Failed to deserialize index")?;
-    Ok(index)
+    let file_len = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+
+    let header = match read_header(&mut reader)? {
+        Some(header) => header,
+        None => {
+            // Legacy file: the whole file is a bincode-serialized FileIndex.
+            // Lengths in it are untrusted, so nothing may claim more than the file holds.
+            reader.seek(SeekFrom::Start(0))?;
+            let legacy: LegacyIndex = bincode::DefaultOptions::new()
+                .with_fixint_encoding()
+                .allow_trailing_bytes()
+                .with_limit(file_len)
+                .deserialize_from(reader)
+                .with_context(|| "Failed to deserialize index")?;
+            return Ok(legacy.into_index());
+        }
+    };
+
+    let index = match header.compression {
+        Compression::None => index_format::decode_entries(&mut reader, header.entry_count, header.version),
+        Compression::Zstd => {
+            let mut decoder = zstd::stream::read::Decoder::with_buffer(reader)?;
+            index_format::decode_entries(&mut decoder, header.entry_count, header.version)
+        }
+        Compression::Lz4 => {
+            let mut decoder = lz4_flex::frame::FrameDecoder::new(reader);
+            index_format::decode_entries(&mut decoder, header.entry_count, header.version)
+        }
+    };
+
+    index.with_context(|| format!("Failed to decode index: {}", path))
+}
+
+/// Report raw vs. stored size of an index file without loading it
+pub fn index_storage_stats(path: &str) -> Result<StorageStats> {
+    let file = fs::File::open(path)
+        .with_context(|| format!("Failed to open index file: {}", path))?;
+    let stored_bytes = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+
+    Ok(match read_header(&mut reader)? {
+        Some(header) => StorageStats {
+            entries: header.entry_count,
+            compression: header.compression,
+            raw_bytes: header.raw_bytes,
+            stored_bytes,
+        },
+        None => {
+            // Legacy files are stored verbatim; the entry count is bincode's Vec length prefix
+            reader.seek(SeekFrom::Start(0))?;
+            let mut len = [0u8; 8];
+            reader.read_exact(&mut len)?;
+            StorageStats {
+                entries: u64::from_le_bytes(len),
+                compression: Compression::None,
+                raw_bytes: stored_bytes,
+                stored_bytes,
+            }
+        }
+    })
+}
+
+/// Read the compressed index header, if the file has one
+fn read_header<R: Read>(reader: &mut R) -> Result<Option<Header>> {
+    let mut buf = [0u8; index_format::HEADER_SIZE];
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Header::parse(&buf[..filled])
+}
+
+/// Save index in the memory-mappable layout (see `MappedIndex`)
+pub fn save_mapped_index<I: IndexView + ?Sized>(index: &I, path: &str) -> Result<()> {
+    MappedIndex::write(index, path)
+        .with_context(|| format!("Failed to write mapped index: {}", path))
+}
+
+/// Map an index file for searching without deserializing it
+pub fn load_mapped_index(path: &str) -> Result<MappedIndex> {
+    MappedIndex::open(path)
 }
 
 /// Get default index path for a drive
 pub fn get_index_path(drive: char) -> Result<String> {
-    let dir = get_nothing_dir()?;
-    fs::create_dir_all(&dir)
-        .with_context(|| format!("Failed to create directory: {}", dir))?;
-    Ok(format!("{}\\index_{}.bin", dir, drive))
+    get_volume_index_path(&drive.to_string())
+}
+
+/// Get the index path for a named volume
+pub fn get_volume_index_path(volume: &str) -> Result<String> {
+    let path = data_file(&format!("index_{}.bin", volume_file_stem(volume)))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Get default memory-mapped index path for a volume
+pub fn get_mapped_index_path(volume: &str) -> Result<String> {
+    let path = data_file(&format!("index_{}.map", volume_file_stem(volume)))?;
+    Ok(path.to_string_lossy().to_string())
 }
 
-/// Get the .nothing directory path
+/// Index file of a volume imported from a listing (`nothing import`)
+pub fn get_imported_index_path(volume: &str) -> Result<String> {
+    let path = data_file(&format!("imported_{}.bin", volume_file_stem(volume)))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Names and index files of all imported volumes, by name
+pub fn imported_volumes() -> Result<Vec<(String, String)>> {
+    let dir = config::data_dir()?;
+    let Ok(files) = fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+    let mut volumes: Vec<(String, String)> = files
+        .filter_map(|file| {
+            let path = file.ok()?.path();
+            let name = path.file_name()?.to_str()?.strip_prefix("imported_")?.strip_suffix(".bin")?.to_string();
+            Some((name, path.to_string_lossy().to_string()))
+        })
+        .collect();
+    volumes.sort();
+    Ok(volumes)
+}
+
+/// Directory holding a volume's snapshots (see `snapshots`)
+pub fn get_snapshot_dir(volume: &str) -> Result<PathBuf> {
+    Ok(config::data_dir()?.join("snapshots").join(volume_file_stem(volume)))
+}
+
+/// Volume name usable in a file name ("C" and "laptop-2019" stay as they are)
+///
+/// Names with other characters are made safe and get a hash of the full name
+/// after a `.` ("/home/me" becomes "_home_me.<hash>"), so `/home/a_b` and
+/// `/home/a/b` don't share an index file.
+fn volume_file_stem(volume: &str) -> String {
+    let stem: String = volume
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if stem == volume {
+        return stem;
+    }
+    // FNV-1a: file names must not change between builds, which `DefaultHasher` doesn't promise
+    let hash = volume.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3));
+    format!("{}.{:016x}", stem, hash)
+}
+
+/// Get the data directory path (see `config::data_dir`)
 pub fn get_nothing_dir() -> Result<String> {
-    let username = std::env::var("USERNAME")
-        .with_context(|| "Failed to get USERNAME environment variable")?;
-    Ok(format!("C:\\Users\\{}\\.nothing", username))
+    Ok(config::data_dir()?.to_string_lossy().to_string())
+}
+
+/// Path of a file in the data directory, creating the directory if needed
+pub fn data_file(name: &str) -> Result<PathBuf> {
+    let dir = config::data_dir()?;
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+    Ok(dir.join(name))
 }
 
 /// Get path for USN bookmark file
 pub fn get_bookmark_path(drive: char) -> Result<PathBuf> {
-    let dir = get_nothing_dir()?;
-    fs::create_dir_all(&dir)
-        .with_context(|| format!("Failed to create directory: {}", dir))?;
-    Ok(PathBuf::from(format!("{}\\bookmark_{}.dat", dir, drive)))
+    data_file(&format!("bookmark_{}.dat", drive))
 }
 
 /// Save USN bookmark to disk
@@ -66,3 +365,20 @@ pub fn load_bookmark(drive: char) -> Result<u64> {
         .map_err(|_| anyhow::anyhow!("Invalid bookmark file size"))?;
     Ok(u64::from_le_bytes(array))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_volume_file_stem() {
+        assert_eq!(volume_file_stem("C"), "C");
+        assert_eq!(volume_file_stem("laptop_2019"), "laptop_2019");
+        assert!(volume_file_stem("/home/me").starts_with("_home_me."));
+
+        let stems: Vec<String> = ["/home/a_b", "/home/a/b", "C:", "C_"].iter().map(|v| volume_file_stem(v)).collect();
+        for (i, stem) in stems.iter().enumerate() {
+            assert!(!stems[i + 1..].contains(stem), "{} is shared", stem);
+        }
+    }
+}
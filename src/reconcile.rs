@@ -0,0 +1,240 @@
+// Reconciliation: bring an index back in line with the disk after change
+// events were lost
+use crate::archive;
+use crate::config::Exclusions;
+use crate::dir_walker::DirectoryWalker;
+use crate::file_entry::FileEntry;
+use crate::index::{is_within, IndexView, LiveIndex};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Paths listed in a report per kind of drift
+const MAX_EXAMPLES: usize = 10;
+
+/// What a reconciliation found and fixed
+#[derive(Debug, Clone, Default)]
+pub struct DriftReport {
+    /// Subtree that was compared
+    pub root: PathBuf,
+
+    /// Entries on disk that were missing from the index
+    pub added: usize,
+
+    /// Entries in the index that no longer exist
+    pub removed: usize,
+
+    /// Files whose size or modified time was out of date
+    pub updated: usize,
+
+    /// A few of the affected paths, prefixed with +, - or ~
+    pub examples: Vec<String>,
+
+    /// Time taken by the walk and the comparison
+    pub duration: Duration,
+}
+
+impl DriftReport {
+    /// Number of entries that had drifted
+    pub fn total(&self) -> usize {
+        self.added + self.removed + self.updated
+    }
+
+    /// Whether the index matched the disk
+    pub fn is_clean(&self) -> bool {
+        self.total() == 0
+    }
+
+    fn example(&mut self, marker: char, path: &str) {
+        if self.examples.len() < MAX_EXAMPLES {
+            self.examples.push(format!("{} {}", marker, path));
+        }
+    }
+}
+
+impl fmt::Display for DriftReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} added, {} removed, {} updated ({:.1}s)",
+            self.root.display(),
+            self.added,
+            self.removed,
+            self.updated,
+            self.duration.as_secs_f64()
+        )
+    }
+}
+
+/// What the index knows about a path, for comparison with the disk
+struct Known {
+    is_directory: bool,
+    size: u64,
+    modified: Option<DateTime<Utc>>,
+}
+
+/// Compare the index below `root` with a fresh walk and apply the difference
+///
+/// The walk and the comparison run without the index lock; it is held once
+/// to take a snapshot and once to apply the changes. Directories are only
+/// checked for existence, and entries without a modified time (fast MFT
+/// scans) only for existence and type.
+pub fn reconcile<I: IndexView + LiveIndex>(index: &Mutex<I>, root: &Path, exclusions: &Exclusions) -> Result<DriftReport> {
+    let start = Instant::now();
+    let root_str = root.to_string_lossy().to_string();
+    let mut report = DriftReport {
+        root: root.to_path_buf(),
+        ..Default::default()
+    };
+
+    // The whole subtree is gone
+    if !root.exists() {
+        report.removed = index.lock().unwrap().remove_tree(&root_str);
+        if report.removed > 0 {
+            report.example('-', &root_str);
+        }
+        report.duration = start.elapsed();
+        return Ok(report);
+    }
+
+    let on_disk = DirectoryWalker::new(root).exclusions(exclusions.clone()).entries()?;
+
+    let mut known: HashMap<String, Known> = {
+        let index = index.lock().unwrap();
+        index
+            .entry_refs()
+            .filter(|entry| entry.path() != root_str && is_within(entry.path(), &root_str))
+            // Archive members have nothing on disk to compare with
+            .filter(|entry| !archive::is_member(entry.file_id(), entry.path()))
+            .map(|entry| {
+                let known = Known {
+                    is_directory: entry.is_directory(),
+                    size: entry.size(),
+                    modified: entry.modified(),
+                };
+                (entry.path().to_string(), known)
+            })
+            .collect()
+    };
+
+    let mut to_add: Vec<FileEntry> = Vec::new();
+    let mut to_update: Vec<&FileEntry> = Vec::new();
+    let mut to_remove: Vec<String> = Vec::new();
+
+    for entry in &on_disk {
+        match known.remove(&entry.path) {
+            None => to_add.push(entry.clone()),
+            Some(old) if old.is_directory != entry.is_directory => {
+                // Replaced by a different kind of entry
+                to_remove.push(entry.path.clone());
+                to_add.push(entry.clone());
+            }
+            Some(old) => {
+                let has_metadata = old.modified.is_some();
+                if has_metadata && !entry.is_directory && (old.size != entry.size || old.modified != entry.modified) {
+                    to_update.push(entry);
+                }
+            }
+        }
+    }
+    // Whatever the walk did not see is gone
+    to_remove.extend(known.into_keys());
+
+    for path in &to_remove {
+        report.example('-', path);
+    }
+    for entry in &to_add {
+        report.example('+', &entry.path);
+    }
+    for entry in &to_update {
+        report.example('~', &entry.path);
+    }
+    report.removed = to_remove.len();
+    report.added = to_add.len();
+    report.updated = to_update.len();
+
+    if !report.is_clean() {
+        let mut index = index.lock().unwrap();
+        for path in &to_remove {
+            index.remove_by_path(path);
+            // Members of a vanished archive go with it
+            archive::forget_members(&mut *index, path);
+        }
+        for entry in to_add {
+            index.add_entry(entry);
+        }
+        for entry in to_update {
+            let modified = entry.modified.map(std::time::SystemTime::from);
+            index.update_metadata_by_path(&entry.path, entry.size, modified);
+            archive::forget_members(&mut *index, &entry.path);
+        }
+    }
+
+    report.duration = start.elapsed();
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::FileIndex;
+    use crate::scanner::Scanner;
+    use std::fs;
+
+    #[test]
+    fn test_reconcile_subtree() {
+        let root = std::env::temp_dir().join(format!("nothing_reconcile_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs/keep.txt"), b"same").unwrap();
+        fs::write(root.join("docs/grow.txt"), b"small").unwrap();
+        fs::write(root.join("docs/gone.txt"), b"").unwrap();
+        fs::write(root.join("outside.txt"), b"").unwrap();
+
+        let mut scanned = FileIndex::new();
+        DirectoryWalker::new(&root).scan_into_index(&mut scanned).unwrap();
+        let index = Mutex::new(scanned);
+
+        // Nothing changed yet
+        let report = reconcile(&index, &root, &Exclusions::default()).unwrap();
+        assert!(report.is_clean(), "{}", report);
+
+        // Change the disk behind the index's back
+        fs::remove_file(root.join("docs/gone.txt")).unwrap();
+        fs::write(root.join("docs/grow.txt"), b"much larger now").unwrap();
+        fs::write(root.join("docs/new.txt"), b"").unwrap();
+        fs::create_dir(root.join("docs/node_modules")).unwrap();
+        fs::remove_file(root.join("outside.txt")).unwrap();
+
+        // Only the subtree is compared, honouring exclusions
+        let exclusions = Exclusions::new(&["node_modules".to_string()]);
+        let report = reconcile(&index, &root.join("docs"), &exclusions).unwrap();
+        assert_eq!((report.added, report.removed, report.updated), (1, 1, 1), "{:?}", report);
+        assert_eq!(report.examples.len(), 3);
+
+        {
+            let index = index.lock().unwrap();
+            let find = |name: &str| index.entries().iter().find(|e| e.name == name).cloned();
+            assert!(find("new.txt").is_some());
+            assert!(find("gone.txt").is_none());
+            assert_eq!(find("grow.txt").unwrap().size, 15);
+            assert!(find("node_modules").is_none());
+            assert!(find("outside.txt").is_some());
+        }
+
+        // A second pass finds nothing left to fix
+        assert!(reconcile(&index, &root.join("docs"), &exclusions).unwrap().is_clean());
+
+        // A vanished subtree is removed with its contents
+        fs::remove_dir_all(root.join("docs")).unwrap();
+        let report = reconcile(&index, &root.join("docs"), &exclusions).unwrap();
+        assert_eq!(report.removed, 4);
+        assert_eq!(index.lock().unwrap().len(), 1);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
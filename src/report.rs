@@ -0,0 +1,335 @@
+// Human-readable reports (self-contained HTML, Markdown) for sharing search
+// results with people who don't use the tool
+use crate::export::{format_file_size, Column, Exporter};
+use crate::file_entry::FileEntry;
+use crate::filters::{extract_search_query, SearchFilters};
+use anyhow::Result;
+use chrono::Local;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Upper bounds of the size ranges in the summary (the last range is open)
+const SIZE_RANGES: &[(u64, &str)] = &[
+    (1024, "< 1 KB"),
+    (1024 * 1024, "1 KB – 1 MB"),
+    (100 * 1024 * 1024, "1 MB – 100 MB"),
+    (1024 * 1024 * 1024, "100 MB – 1 GB"),
+];
+
+/// Kind of report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportKind {
+    Html,
+    Markdown,
+}
+
+/// Totals shown above the result table
+#[derive(Debug, Default)]
+struct Summary {
+    files: usize,
+    directories: usize,
+    total_size: u64,
+
+    /// (extension, files, bytes), largest first
+    extensions: Vec<(String, usize, u64)>,
+
+    /// (range label, files, bytes), in `SIZE_RANGES` order
+    sizes: Vec<(&'static str, usize, u64)>,
+}
+
+impl Summary {
+    fn new(entries: &[(FileEntry, Option<u32>)]) -> Self {
+        let mut summary = Summary::default();
+        let mut extensions: HashMap<String, (usize, u64)> = HashMap::new();
+        let mut sizes = vec![(0usize, 0u64); SIZE_RANGES.len() + 1];
+
+        for (entry, _) in entries {
+            if entry.is_directory {
+                summary.directories += 1;
+                continue;
+            }
+            summary.files += 1;
+            summary.total_size += entry.size;
+
+            let extension = match entry.name.rsplit_once('.') {
+                Some((stem, ext)) if !stem.is_empty() => ext.to_lowercase(),
+                _ => "(none)".to_string(),
+            };
+            let totals = extensions.entry(extension).or_default();
+            totals.0 += 1;
+            totals.1 += entry.size;
+
+            let range = SIZE_RANGES.iter().position(|(limit, _)| entry.size < *limit).unwrap_or(SIZE_RANGES.len());
+            sizes[range].0 += 1;
+            sizes[range].1 += entry.size;
+        }
+
+        summary.extensions = extensions.into_iter().map(|(ext, (count, size))| (ext, count, size)).collect();
+        summary.extensions.sort_by(|a, b| b.2.cmp(&a.2).then(b.1.cmp(&a.1)).then(a.0.cmp(&b.0)));
+        let labels = SIZE_RANGES.iter().map(|(_, label)| *label).chain(["≥ 1 GB"]);
+        summary.sizes = labels.zip(sizes).map(|(label, (count, size))| (label, count, size)).collect();
+        summary
+    }
+}
+
+/// Collects entries and writes the whole report on `finish`
+///
+/// The summary comes before the table, so unlike the other exporters this one
+/// keeps every entry until the end; reports are meant for result sets a
+/// person will read.
+pub struct ReportExporter<W> {
+    out: W,
+    kind: ReportKind,
+    columns: Vec<Column>,
+    query: String,
+    entries: Vec<(FileEntry, Option<u32>)>,
+}
+
+impl<W: Write> ReportExporter<W> {
+    /// Report on the results of `query` (empty for a whole-index export)
+    pub fn new(out: W, kind: ReportKind, columns: Vec<Column>, query: &str) -> Self {
+        Self {
+            out,
+            kind,
+            columns,
+            query: query.to_string(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// What was searched for: the search terms and a description of the filters
+    fn criteria(&self) -> (String, String) {
+        let terms = extract_search_query(&self.query);
+        let filters = match SearchFilters::parse_filter_string(&self.query) {
+            Ok(filters) => filters.describe(),
+            Err(e) => format!("invalid ({})", e),
+        };
+        (if terms.is_empty() { "(all entries)".to_string() } else { terms }, filters)
+    }
+
+    fn write_html(&mut self, summary: &Summary) -> Result<()> {
+        let (terms, filters) = self.criteria();
+        let generated = Local::now().format("%Y-%m-%d %H:%M").to_string();
+        let out = &mut self.out;
+
+        writeln!(out, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">")?;
+        writeln!(out, "<title>Nothing report: {}</title>", html_escape(&terms))?;
+        writeln!(out, "<style>{}</style>\n</head>\n<body>", HTML_STYLE)?;
+        writeln!(out, "<h1>Search report</h1>")?;
+        writeln!(out, "<table class=\"facts\">")?;
+        for (label, value) in [
+            ("Search", terms.as_str()),
+            ("Filters", filters.as_str()),
+            ("Generated", generated.as_str()),
+        ] {
+            writeln!(out, "<tr><th>{}</th><td>{}</td></tr>", label, html_escape(value))?;
+        }
+        writeln!(
+            out,
+            "<tr><th>Results</th><td>{} files ({}), {} directories</td></tr>\n</table>",
+            summary.files,
+            format_file_size(summary.total_size),
+            summary.directories
+        )?;
+
+        writeln!(out, "<div class=\"totals\">")?;
+        writeln!(out, "<table>\n<caption>By extension</caption>\n<tr><th>Extension</th><th>Files</th><th>Size</th></tr>")?;
+        for (extension, count, size) in &summary.extensions {
+            writeln!(out, "<tr><td>{}</td><td class=\"n\">{}</td><td class=\"n\">{}</td></tr>", html_escape(extension), count, format_file_size(*size))?;
+        }
+        writeln!(out, "</table>")?;
+        writeln!(out, "<table>\n<caption>By size</caption>\n<tr><th>Size</th><th>Files</th><th>Total</th></tr>")?;
+        for (label, count, size) in &summary.sizes {
+            writeln!(out, "<tr><td>{}</td><td class=\"n\">{}</td><td class=\"n\">{}</td></tr>", label, count, format_file_size(*size))?;
+        }
+        writeln!(out, "</table>\n</div>")?;
+
+        writeln!(out, "<table id=\"results\" class=\"sortable\">\n<thead><tr>")?;
+        for column in &self.columns {
+            writeln!(out, "<th title=\"Click to sort\">{}</th>", column.label())?;
+        }
+        writeln!(out, "</tr></thead>\n<tbody>")?;
+        for (entry, score) in &self.entries {
+            write!(out, "<tr>")?;
+            for column in &self.columns {
+                let text = html_escape(&column.text(entry, *score));
+                match sort_value(*column, entry, *score) {
+                    Some(value) => write!(out, "<td class=\"n\" data-sort=\"{}\">{}</td>", value, text)?,
+                    None => write!(out, "<td>{}</td>", text)?,
+                }
+            }
+            writeln!(out, "</tr>")?;
+        }
+        writeln!(out, "</tbody>\n</table>")?;
+        writeln!(out, "<script>{}</script>\n</body>\n</html>", HTML_SCRIPT)?;
+        Ok(())
+    }
+
+    fn write_markdown(&mut self, summary: &Summary) -> Result<()> {
+        let (terms, filters) = self.criteria();
+        let out = &mut self.out;
+
+        writeln!(out, "# Search report\n")?;
+        writeln!(out, "- **Search:** {}", markdown_escape(&terms))?;
+        writeln!(out, "- **Filters:** {}", markdown_escape(&filters))?;
+        writeln!(out, "- **Generated:** {}", Local::now().format("%Y-%m-%d %H:%M"))?;
+        writeln!(
+            out,
+            "- **Results:** {} files ({}), {} directories\n",
+            summary.files,
+            format_file_size(summary.total_size),
+            summary.directories
+        )?;
+
+        writeln!(out, "## By extension\n\n| Extension | Files | Size |\n|---|--:|--:|")?;
+        for (extension, count, size) in &summary.extensions {
+            writeln!(out, "| {} | {} | {} |", markdown_escape(extension), count, format_file_size(*size))?;
+        }
+        writeln!(out, "\n## By size\n\n| Size | Files | Total |\n|---|--:|--:|")?;
+        for (label, count, size) in &summary.sizes {
+            writeln!(out, "| {} | {} | {} |", label, count, format_file_size(*size))?;
+        }
+
+        writeln!(out, "\n## Results\n")?;
+        let labels: Vec<&str> = self.columns.iter().map(Column::label).collect();
+        writeln!(out, "| {} |", labels.join(" | "))?;
+        let alignments: Vec<&str> = self
+            .columns
+            .iter()
+            .map(|column| if is_numeric(*column) { "--:" } else { "---" })
+            .collect();
+        writeln!(out, "|{}|", alignments.join("|"))?;
+        for (entry, score) in &self.entries {
+            let cells: Vec<String> = self.columns.iter().map(|column| markdown_escape(&column.text(entry, *score))).collect();
+            writeln!(out, "| {} |", cells.join(" | "))?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Exporter for ReportExporter<W> {
+    fn write_entry(&mut self, entry: &FileEntry, score: Option<u32>) -> Result<()> {
+        self.entries.push((entry.clone(), score));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        let summary = Summary::new(&self.entries);
+        match self.kind {
+            ReportKind::Html => self.write_html(&summary)?,
+            ReportKind::Markdown => self.write_markdown(&summary)?,
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+fn is_numeric(column: Column) -> bool {
+    matches!(column, Column::Size | Column::SizeFormatted | Column::Score | Column::FileId | Column::ParentId)
+}
+
+/// Raw value the HTML table sorts numeric columns by
+fn sort_value(column: Column, entry: &FileEntry, score: Option<u32>) -> Option<u64> {
+    match column {
+        Column::Size | Column::SizeFormatted => Some(entry.size),
+        Column::Score => Some(score.unwrap_or(0) as u64),
+        Column::FileId => Some(entry.file_id),
+        Column::ParentId => Some(entry.parent_id),
+        _ => None,
+    }
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Keep a value inside one Markdown table cell
+fn markdown_escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace(['\r', '\n'], " ")
+}
+
+const HTML_STYLE: &str = "
+body { font-family: system-ui, sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin-bottom: 1.5em; }
+th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; }
+th { background: #f0f0f0; }
+td.n { text-align: right; font-variant-numeric: tabular-nums; }
+caption { font-weight: bold; text-align: left; padding-bottom: 4px; }
+.facts th { width: 8em; }
+.totals { display: flex; gap: 2em; align-items: flex-start; }
+.sortable th { cursor: pointer; user-select: none; }
+.sortable th.asc::after { content: ' ▲'; }
+.sortable th.desc::after { content: ' ▼'; }
+#results td { word-break: break-all; }
+";
+
+/// Click a header to sort by it; click again to reverse
+const HTML_SCRIPT: &str = "
+document.querySelectorAll('table.sortable th').forEach((th, column) => {
+  th.addEventListener('click', () => {
+    const tbody = th.closest('table').tBodies[0];
+    const ascending = !th.classList.contains('asc');
+    th.parentNode.querySelectorAll('th').forEach(h => h.classList.remove('asc', 'desc'));
+    th.classList.add(ascending ? 'asc' : 'desc');
+    const key = row => {
+      const cell = row.cells[column];
+      return cell.dataset.sort !== undefined ? Number(cell.dataset.sort) : cell.textContent.toLowerCase();
+    };
+    const rows = Array.from(tbody.rows);
+    rows.sort((a, b) => {
+      const x = key(a), y = key(b);
+      return (x < y ? -1 : x > y ? 1 : 0) * (ascending ? 1 : -1);
+    });
+    rows.forEach(row => tbody.appendChild(row));
+  });
+});
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports() {
+        let entries: Vec<(FileEntry, Option<u32>)> = [("a.PDF", 2048), ("b.pdf", 100), ("<script>|.txt", 5), ("README", 0)]
+            .iter()
+            .enumerate()
+            .map(|(id, (name, size))| {
+                let entry = FileEntry::new(name.to_string(), format!("/d/{}", name), false, id as u64, 0, *size, None, None, None);
+                (entry, Some(10))
+            })
+            .collect();
+
+        let summary = Summary::new(&entries);
+        assert_eq!((summary.files, summary.total_size), (4, 2153));
+        assert_eq!(summary.extensions[0], ("pdf".to_string(), 2, 2148));
+        assert!(summary.extensions.contains(&("(none)".to_string(), 1, 0)));
+        assert_eq!(summary.sizes[0].1, 3);
+
+        let render = |kind| {
+            let mut out = Vec::new();
+            let mut report = ReportExporter::new(&mut out, kind, vec![Column::Name, Column::SizeFormatted], "a size:>10b");
+            for (entry, score) in &entries {
+                report.write_entry(entry, *score).unwrap();
+            }
+            report.finish().unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        let html = render(ReportKind::Html);
+        assert!(html.contains("&lt;script&gt;|.txt"));
+        assert!(!html.contains("<script>|"));
+        assert!(html.contains("data-sort=\"2048\""));
+        assert!(html.contains("size ≥ 10 B"));
+
+        let markdown = render(ReportKind::Markdown);
+        assert!(markdown.contains("| Name | Size (formatted) |\n|---|--:|"));
+        assert!(markdown.contains("| <script>\\|.txt | 5 B |"));
+        assert!(markdown.contains("- **Search:** a"));
+    }
+}
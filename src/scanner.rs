@@ -0,0 +1,53 @@
+// Volume scanners: everything that can fill an index from a filesystem
+use crate::index::FileIndex;
+use anyhow::Result;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Receives a scan's progress messages, one line each
+pub type Progress = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// Progress that prints each message to stdout
+pub fn print_progress() -> Progress {
+    Arc::new(|message| println!("{}", message))
+}
+
+/// Source that fills a `FileIndex` with the entries of one volume
+///
+/// Implemented by the NTFS MFT readers (Windows) and by `DirectoryWalker`
+/// (any platform).
+pub trait Scanner: Send + Sync {
+    /// Human-readable description of what is scanned (e.g. "C:", "/home")
+    fn describe(&self) -> String;
+
+    /// Scan the volume and add all entries to the index
+    fn scan_into_index(&self, index: &mut FileIndex) -> Result<()>;
+}
+
+/// What a volume is indexed from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VolumeSource {
+    /// NTFS drive read through its MFT (Windows only)
+    Drive(char),
+
+    /// Directory tree walked with `DirectoryWalker`
+    Directory(PathBuf),
+}
+
+impl VolumeSource {
+    /// Volume name used for the index file and in the volume list
+    pub fn name(&self) -> String {
+        match self {
+            VolumeSource::Drive(drive) => drive.to_string(),
+            VolumeSource::Directory(root) => root.to_string_lossy().to_string(),
+        }
+    }
+
+    /// Directory the volume's files live under
+    pub fn root(&self) -> PathBuf {
+        match self {
+            VolumeSource::Drive(drive) => PathBuf::from(format!("{}:\\", drive)),
+            VolumeSource::Directory(root) => root.clone(),
+        }
+    }
+}
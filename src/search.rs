@@ -1,6 +1,7 @@
+use crate::content::ContentMatch;
 use crate::file_entry::FileEntry;
 use crate::filters::SearchFilters;
-use crate::index::FileIndex;
+use crate::index::{EntryRef, IndexView};
 use nucleo_matcher::{Config, Matcher, Utf32Str};
 use nucleo_matcher::pattern::{Pattern, CaseMatching, Normalization};
 
@@ -9,6 +10,9 @@ use nucleo_matcher::pattern::{Pattern, CaseMatching, Normalization};
 pub struct SearchResult {
     pub entry: FileEntry,
     pub score: u32,
+
+    /// Matching lines, for `content:` searches
+    pub content: Vec<ContentMatch>,
 }
 
 /// Search engine for fuzzy file matching
@@ -26,14 +30,14 @@ impl SearchEngine {
 
     /// Search the index with fuzzy matching and optional filters
     /// Returns up to `limit` results sorted by relevance
-    pub fn search(&mut self, index: &FileIndex, query: &str, limit: usize) -> Vec<SearchResult> {
+    pub fn search<I: IndexView + ?Sized>(&mut self, index: &I, query: &str, limit: usize) -> Vec<SearchResult> {
         self.search_with_filters(index, query, limit, &SearchFilters::default())
     }
 
     /// Search with filters
-    pub fn search_with_filters(
+    pub fn search_with_filters<I: IndexView + ?Sized>(
         &mut self,
-        index: &FileIndex,
+        index: &I,
         query: &str,
         limit: usize,
         filters: &SearchFilters,
@@ -48,38 +52,31 @@ impl SearchEngine {
         let mut results = Vec::new();
 
         // Search through all entries
-        for entry in index.entries() {
-            // Apply filters first
-            if !filters.matches(entry) {
-                continue;
+        for entry in index.entry_refs() {
+            // Apply filters first when the entry is already in memory
+            if let EntryRef::Owned(owned) = entry {
+                if !filters.matches(owned) {
+                    continue;
+                }
             }
 
-            // Convert strings to UTF-32 for nucleo
-            let mut name_buf = Vec::new();
-            let mut path_buf = Vec::new();
-
-            let name_utf32 = Utf32Str::new(&entry.name, &mut name_buf);
-            let path_utf32 = Utf32Str::new(&entry.path, &mut path_buf);
-
-            // Try matching against filename first (weighted higher)
-            let filename_score = pattern.score(name_utf32, &mut self.matcher);
-
-            // Also try matching against full path
-            let path_score = pattern.score(path_utf32, &mut self.matcher);
+            let Some(score) = self.score_entry(&pattern, entry) else {
+                continue;
+            };
 
-            // Use the best score, with filename weighted 2x higher
-            let best_score = if let Some(name_score) = filename_score {
-                Some(name_score.saturating_mul(2))
-            } else {
-                path_score
+            // Mapped records are only materialized once they match the query
+            let entry = match entry {
+                EntryRef::Owned(owned) => owned.clone(),
+                EntryRef::Mapped(record) => {
+                    let entry = record.to_entry();
+                    if !filters.matches(&entry) {
+                        continue;
+                    }
+                    entry
+                }
             };
 
-            if let Some(score) = best_score {
-                results.push(SearchResult {
-                    entry: entry.clone(),
-                    score,
-                });
-            }
+            results.push(SearchResult { entry, score, content: Vec::new() });
         }
 
         // Sort by score (descending - higher is better)
@@ -91,14 +88,14 @@ impl SearchEngine {
     }
 
     /// Get total match count for a query (without limiting results)
-    pub fn count_matches(&mut self, index: &FileIndex, query: &str) -> usize {
+    pub fn count_matches<I: IndexView + ?Sized>(&mut self, index: &I, query: &str) -> usize {
         self.count_matches_with_filters(index, query, &SearchFilters::default())
     }
 
     /// Count matches with filters
-    pub fn count_matches_with_filters(
+    pub fn count_matches_with_filters<I: IndexView + ?Sized>(
         &mut self,
-        index: &FileIndex,
+        index: &I,
         query: &str,
         filters: &SearchFilters,
     ) -> usize {
@@ -109,28 +106,46 @@ impl SearchEngine {
         let pattern = Pattern::parse(query, CaseMatching::Ignore, Normalization::Smart);
         let mut count = 0;
 
-        for entry in index.entries() {
+        for entry in index.entry_refs() {
             // Apply filters first
-            if !filters.matches(entry) {
+            let matches_filters = match entry {
+                EntryRef::Owned(owned) => filters.matches(owned),
+                EntryRef::Mapped(_) => filters.is_empty() || filters.matches(&entry.to_entry()),
+            };
+            if !matches_filters {
                 continue;
             }
 
-            let mut name_buf = Vec::new();
-            let mut path_buf = Vec::new();
-
-            let name_utf32 = Utf32Str::new(&entry.name, &mut name_buf);
-            let path_utf32 = Utf32Str::new(&entry.path, &mut path_buf);
-
-            let filename_score = pattern.score(name_utf32, &mut self.matcher);
-            let path_score = pattern.score(path_utf32, &mut self.matcher);
-
-            if filename_score.is_some() || path_score.is_some() {
+            if self.score_entry(&pattern, entry).is_some() {
                 count += 1;
             }
         }
 
         count
     }
+
+    /// Score a single entry against a parsed pattern
+    /// Filename matches are weighted 2x higher than path matches
+    fn score_entry(&mut self, pattern: &Pattern, entry: EntryRef<'_>) -> Option<u32> {
+        // Convert strings to UTF-32 for nucleo
+        let mut name_buf = Vec::new();
+        let mut path_buf = Vec::new();
+
+        let name_utf32 = Utf32Str::new(entry.name(), &mut name_buf);
+        let path_utf32 = Utf32Str::new(entry.path(), &mut path_buf);
+
+        // Try matching against filename first (weighted higher)
+        let filename_score = pattern.score(name_utf32, &mut self.matcher);
+
+        // Also try matching against full path
+        let path_score = pattern.score(path_utf32, &mut self.matcher);
+
+        if let Some(name_score) = filename_score {
+            Some(name_score.saturating_mul(2))
+        } else {
+            path_score
+        }
+    }
 }
 
 impl Default for SearchEngine {
@@ -0,0 +1,228 @@
+// One-shot search for scripts: `nothing search "<query>"` prints the matches
+// and exits, with the exit code telling whether anything matched
+use crate::content::{self, ContentSearch};
+use crate::export::{export_index, write_results, Column, ExportFormat, Exporter};
+use crate::filters::{extract_search_query, SearchFilters};
+use crate::multi_volume::MultiVolumeIndex;
+use crate::search::{SearchEngine, SearchResult};
+use crate::sqlite_export::SqliteExport;
+use anyhow::{anyhow, bail, Context, Result};
+use std::cmp::Reverse;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+
+/// Order of the printed results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortKey {
+    /// Best match first
+    #[default]
+    Score,
+
+    /// By name, A to Z
+    Name,
+
+    /// By path, A to Z
+    Path,
+
+    /// Largest first
+    Size,
+
+    /// Most recently modified first
+    Modified,
+}
+
+impl FromStr for SortKey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "score" | "relevance" => Ok(SortKey::Score),
+            "name" => Ok(SortKey::Name),
+            "path" => Ok(SortKey::Path),
+            "size" => Ok(SortKey::Size),
+            "modified" | "date" => Ok(SortKey::Modified),
+            _ => Err(anyhow!("Invalid sort: {}. Use 'score', 'name', 'path', 'size' or 'modified'", s)),
+        }
+    }
+}
+
+/// Search the volumes and print the results to stdout
+///
+/// Returns whether anything matched. Unlike the interactive prompt, a query
+/// with invalid filters or without search terms (or a `content:` pattern) is
+/// an error. Plain output of a `content:` search has one `path:line:text`
+/// line per matching line, like grep.
+pub fn run(
+    volumes: &MultiVolumeIndex,
+    query: &str,
+    limit: usize,
+    format: ExportFormat,
+    sort: SortKey,
+    columns: Option<&[Column]>,
+    content: &ContentSearch,
+) -> Result<bool> {
+    let (terms, filters) = parse_query(query)?;
+    if terms.is_empty() && filters.content.is_none() {
+        bail!("Missing search terms in the query: {}", query);
+    }
+
+    // Any other order has to look at every match, not just the best ones
+    let mut engine = SearchEngine::new();
+    let wanted = if sort == SortKey::Score { limit } else { usize::MAX };
+    let mut results = content::search(volumes, &mut engine, &terms, wanted, &filters, content, &AtomicBool::new(false))
+        .unwrap_or_default();
+    sort_results(&mut results, sort);
+    results.truncate(limit);
+
+    let columns = columns.unwrap_or(format.default_columns());
+    let out = BufWriter::new(io::stdout().lock());
+    if filters.content.is_some() && format == ExportFormat::Paths {
+        quiet_broken_pipe(write_content_matches(out, &results))?;
+    } else {
+        quiet_broken_pipe(write_results(out, &results, format, columns, query))?;
+    }
+    Ok(!results.is_empty())
+}
+
+/// `path:line:text` for every matching line of a content search
+fn write_content_matches(mut out: impl Write, results: &[SearchResult]) -> Result<()> {
+    for result in results {
+        for found in &result.content {
+            writeln!(out, "{}:{}:{}", result.entry.path, found.line_number, found.line)?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Write every entry that passes the query's filters, or every match of its
+/// search terms if it has any, to `output` (default: stdout)
+///
+/// Returns the number of entries written.
+pub fn export(
+    volumes: &MultiVolumeIndex,
+    query: &str,
+    format: ExportFormat,
+    columns: Option<&[Column]>,
+    output: Option<&Path>,
+    content: &ContentSearch,
+) -> Result<usize> {
+    let (terms, filters) = parse_query(query)?;
+    let columns = columns.unwrap_or(format.default_columns());
+    let out: Box<dyn Write> = match output {
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).with_context(|| format!("Failed to create {}", path.display()))?,
+        )),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    let mut exporter = format.exporter(out, columns, query);
+    quiet_broken_pipe(write_matches(volumes, &terms, &filters, content, exporter.as_mut()))
+}
+
+/// Export every entry that passes the query's filters to a SQLite database,
+/// updating only the rows that changed since the last export
+pub fn export_sqlite(volumes: &MultiVolumeIndex, query: &str, path: &Path) -> Result<()> {
+    let (terms, filters) = parse_query(query)?;
+    if !terms.is_empty() {
+        bail!("SQLite exports take filters only; search the names in the database with entries_fts instead");
+    }
+    if filters.content.is_some() {
+        bail!("SQLite exports can't search file contents; remove the content: filter");
+    }
+    let mut database = SqliteExport::open(path)?;
+    for volume in volumes.volumes() {
+        let index = volume.index.lock().unwrap();
+        let stats = database.sync_volume(&volume.name, &*index, &filters)?;
+        eprintln!("✅ {}: {}", volume.name, stats);
+    }
+    eprintln!("🗃️  SQLite database: {}", path.display());
+    Ok(())
+}
+
+/// Write the entries `export` selects and finish the document
+fn write_matches(
+    volumes: &MultiVolumeIndex,
+    terms: &str,
+    filters: &SearchFilters,
+    content: &ContentSearch,
+    exporter: &mut dyn Exporter,
+) -> Result<usize> {
+    let count = if filters.content.is_some() {
+        let results = content::search(volumes, &mut SearchEngine::new(), terms, usize::MAX, filters, content, &AtomicBool::new(false))
+            .unwrap_or_default();
+        for result in &results {
+            exporter.write_entry(&result.entry, (!terms.is_empty()).then_some(result.score))?;
+        }
+        results.len()
+    } else if terms.is_empty() {
+        let mut count = 0;
+        for volume in volumes.volumes() {
+            let index = volume.index.lock().unwrap();
+            count += export_index(&*index, filters, exporter)?;
+        }
+        count
+    } else {
+        let results = volumes.search(&mut SearchEngine::new(), terms, usize::MAX, filters);
+        for result in &results {
+            exporter.write_entry(&result.entry, Some(result.score))?;
+        }
+        results.len()
+    };
+    exporter.finish()?;
+    Ok(count)
+}
+
+fn parse_query(query: &str) -> Result<(String, SearchFilters)> {
+    Ok((extract_search_query(query), SearchFilters::parse_filter_string(query)?))
+}
+
+/// Put results in the requested order (stable, so ties keep their score order)
+fn sort_results(results: &mut [SearchResult], sort: SortKey) {
+    match sort {
+        SortKey::Score => results.sort_by_key(|r| Reverse(r.score)),
+        SortKey::Name => results.sort_by_cached_key(|r| r.entry.name.to_lowercase()),
+        SortKey::Path => results.sort_by(|a, b| a.entry.path.cmp(&b.entry.path)),
+        SortKey::Size => results.sort_by_key(|r| Reverse(r.entry.size)),
+        SortKey::Modified => results.sort_by_key(|r| Reverse(r.entry.modified)),
+    }
+}
+
+/// Treat a closed stdout (`| head`) as the reader having seen enough
+fn quiet_broken_pipe<T: Default>(result: Result<T>) -> Result<T> {
+    match result {
+        Err(e) if e.downcast_ref::<io::Error>().is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe) => Ok(T::default()),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_entry::FileEntry;
+
+    fn result(path: &str, size: u64, score: u32) -> SearchResult {
+        let name = path.rsplit('/').next().unwrap().to_string();
+        SearchResult {
+            entry: FileEntry::new(name, path.to_string(), false, size, 0, size, None, None, None),
+            score,
+            content: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_sort_results() {
+        let mut results = vec![result("/a/small.txt", 10, 90), result("/b/big file.txt", 500, 50)];
+
+        sort_results(&mut results, SortKey::Size);
+        assert_eq!(results[0].entry.path, "/b/big file.txt");
+        sort_results(&mut results, SortKey::Score);
+        assert_eq!(results[0].entry.path, "/a/small.txt");
+        sort_results(&mut results, SortKey::Name);
+        assert_eq!(results[0].entry.name, "big file.txt");
+
+        assert!("speed".parse::<SortKey>().is_err());
+    }
+}
@@ -0,0 +1,523 @@
+// Owners and access control lists for permission audits: NTFS security
+// descriptors (read from the $Secure:$SDS stream) and Unix owner, group and
+// mode, reduced to who may read, write and execute each entry
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Rights bits of an `Ace`
+pub const READ: u8 = 1;
+pub const WRITE: u8 = 1 << 1;
+pub const EXECUTE: u8 = 1 << 2;
+
+/// Full control (always set together with read, write and execute)
+pub const FULL: u8 = 1 << 3;
+
+/// SID of Everyone, also the trustee of the Unix "other" permissions
+pub const EVERYONE: &str = "S-1-1-0";
+
+/// Well-known SIDs and the names summaries and filters use for them
+const WELL_KNOWN: &[(&str, &str)] = &[
+    (EVERYONE, "Everyone"),
+    ("S-1-3-0", "Creator Owner"),
+    ("S-1-3-1", "Creator Group"),
+    ("S-1-5-7", "Anonymous"),
+    ("S-1-5-11", "Authenticated Users"),
+    ("S-1-5-18", "SYSTEM"),
+    ("S-1-5-19", "Local Service"),
+    ("S-1-5-20", "Network Service"),
+    ("S-1-5-32-544", "Administrators"),
+    ("S-1-5-32-545", "Users"),
+    ("S-1-5-32-546", "Guests"),
+    ("S-1-15-2-1", "All Application Packages"),
+    ("S-1-5-80-956008885-3418522649-1831038044-1853292631-2271478464", "TrustedInstaller"),
+];
+
+/// One entry of an access control list, with the rights of all the
+/// trustee's entries of the same kind merged
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Ace {
+    /// Allow (true) or deny (false)
+    pub allow: bool,
+
+    /// SID on NTFS; uid or gid (or Everyone for "other") on Unix
+    pub trustee: String,
+
+    /// `READ`, `WRITE`, `EXECUTE` and `FULL` bits
+    pub rights: u8,
+}
+
+/// Owner, group and access summary of an entry
+///
+/// Many entries share one descriptor, so entries hold it behind an `Arc`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Security {
+    /// Owner SID, or uid
+    pub owner: Option<String>,
+
+    /// Primary group SID, or gid
+    pub group: Option<String>,
+
+    /// Who may (or may not) read, write and execute the entry
+    pub acl: Vec<Ace>,
+}
+
+impl Security {
+    /// From Unix ownership and permission bits
+    pub fn from_mode(uid: u32, gid: u32, mode: u32) -> Self {
+        let rights = |bits: u32| {
+            let mut rights = 0;
+            if bits & 4 != 0 {
+                rights |= READ;
+            }
+            if bits & 2 != 0 {
+                rights |= WRITE;
+            }
+            if bits & 1 != 0 {
+                rights |= EXECUTE;
+            }
+            rights
+        };
+        // Namespaced so that the owner's and group's entries stay apart when uid == gid
+        let (owner, group) = (format!("uid:{}", uid), format!("gid:{}", gid));
+        let mut security = Security {
+            owner: Some(owner.clone()),
+            group: Some(group.clone()),
+            acl: Vec::new(),
+        };
+        for (trustee, bits) in [(owner, mode >> 6), (group, mode >> 3), (EVERYONE.to_string(), mode)] {
+            add_ace(&mut security.acl, true, trustee, rights(bits & 7));
+        }
+        security
+    }
+
+    /// Rights `trustee` has: what its allow entries grant and its deny entries don't take away
+    pub fn rights_of(&self, trustee: &str) -> u8 {
+        let (mut allowed, mut denied) = (0, 0);
+        for ace in self.acl.iter().filter(|ace| ace.trustee.eq_ignore_ascii_case(trustee)) {
+            if ace.allow {
+                allowed |= ace.rights;
+            } else {
+                denied |= ace.rights;
+            }
+        }
+        allowed & !denied
+    }
+
+    /// The ACL as text, e.g. "Administrators:F;Users:RX;!Guests:W"
+    pub fn acl_summary(&self) -> String {
+        let aces: Vec<String> = self
+            .acl
+            .iter()
+            .map(|ace| format!("{}{}:{}", if ace.allow { "" } else { "!" }, trustee_name(&ace.trustee), rights_text(ace.rights)))
+            .collect();
+        aces.join(";")
+    }
+}
+
+/// Read back an ACL written by `Security::acl_summary`
+pub fn parse_acl(text: &str) -> Result<Vec<Ace>> {
+    let mut acl = Vec::new();
+    for part in text.split(';').filter(|part| !part.is_empty()) {
+        let (allow, part) = match part.strip_prefix('!') {
+            Some(rest) => (false, rest),
+            None => (true, part),
+        };
+        let (name, rights_text) = part.rsplit_once(':').ok_or_else(|| anyhow!("Invalid ACL entry: {}", part))?;
+        let mut rights = 0;
+        for letter in rights_text.chars() {
+            rights |= match letter {
+                'R' => READ,
+                'W' => WRITE,
+                'X' => EXECUTE,
+                'F' => FULL | READ | WRITE | EXECUTE,
+                _ => return Err(anyhow!("Invalid rights in ACL entry: {}", part)),
+            };
+        }
+        add_ace(&mut acl, allow, trustee_sid(name).unwrap_or(name).to_string(), rights);
+    }
+    Ok(acl)
+}
+
+/// Name of a well-known SID, or the trustee itself
+pub fn trustee_name(trustee: &str) -> &str {
+    WELL_KNOWN.iter().find(|(sid, _)| *sid == trustee).map_or(trustee, |(_, name)| name)
+}
+
+/// SID of a well-known name, compared without case, spaces, dashes or underscores
+pub fn trustee_sid(name: &str) -> Option<&'static str> {
+    let key = |s: &str| s.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase();
+    let name = key(name);
+    let name = if name == "admins" { "administrators".to_string() } else { name };
+    WELL_KNOWN.iter().find(|(_, known)| key(known) == name).map(|(sid, _)| *sid)
+}
+
+/// Trustee a filter names: a well-known SID, `uid:<n>` for a bare number, or the name itself
+pub fn trustee_id(name: &str) -> String {
+    match trustee_sid(name) {
+        Some(sid) => sid.to_string(),
+        None if !name.is_empty() && name.bytes().all(|b| b.is_ascii_digit()) => format!("uid:{}", name),
+        None => name.to_string(),
+    }
+}
+
+fn rights_text(rights: u8) -> String {
+    if rights & FULL != 0 {
+        return "F".to_string();
+    }
+    [(READ, 'R'), (WRITE, 'W'), (EXECUTE, 'X')]
+        .iter()
+        .filter(|(bit, _)| rights & bit != 0)
+        .map(|(_, letter)| *letter)
+        .collect()
+}
+
+/// Add an entry, merging it into the trustee's entry of the same kind
+fn add_ace(acl: &mut Vec<Ace>, allow: bool, trustee: String, rights: u8) {
+    if rights == 0 {
+        return;
+    }
+    match acl.iter_mut().find(|ace| ace.allow == allow && ace.trustee == trustee) {
+        Some(ace) => ace.rights |= rights,
+        None => acl.push(Ace { allow, trustee, rights }),
+    }
+}
+
+/// NTFS security descriptors, as the MFT reader finds them in $Secure:$SDS
+#[cfg(any(windows, test))]
+mod descriptor {
+    use super::{add_ace, Ace, Security, EVERYONE, EXECUTE, FULL, READ, WRITE};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    // Security descriptor and access mask constants (winnt.h)
+    pub(super) const SE_DACL_PRESENT: u16 = 0x0004;
+    pub(super) const ACCESS_ALLOWED_ACE_TYPE: u8 = 0;
+    pub(super) const ACCESS_DENIED_ACE_TYPE: u8 = 1;
+    pub(super) const INHERIT_ONLY_ACE: u8 = 0x08;
+    pub(super) const FILE_READ_DATA: u32 = 0x0001;
+    pub(super) const FILE_WRITE_DATA: u32 = 0x0002;
+    pub(super) const FILE_APPEND_DATA: u32 = 0x0004;
+    pub(super) const FILE_EXECUTE: u32 = 0x0020;
+    pub(super) const FILE_ALL_ACCESS: u32 = 0x001F_01FF;
+    pub(super) const GENERIC_ALL: u32 = 0x1000_0000;
+    pub(super) const GENERIC_EXECUTE: u32 = 0x2000_0000;
+    pub(super) const GENERIC_WRITE: u32 = 0x4000_0000;
+    pub(super) const GENERIC_READ: u32 = 0x8000_0000;
+
+    /// $SDS is written in 256 KiB blocks, each followed by a mirror copy
+    pub(super) const SDS_BLOCK: usize = 0x40000;
+
+    /// Size of the header before each descriptor in $SDS
+    pub(super) const SDS_HEADER: usize = 20;
+
+    /// Parse a self-relative security descriptor
+    fn parse_descriptor(sd: &[u8]) -> Option<Security> {
+        if sd.len() < 20 || sd[0] != 1 {
+            return None;
+        }
+        let control = u16_at(sd, 2)?;
+        let sid_at = |offset: u32| match offset {
+            0 => None,
+            offset => parse_sid(sd.get(offset as usize..)?),
+        };
+        let dacl_offset = u32_at(sd, 16)? as usize;
+
+        let acl = if control & SE_DACL_PRESENT == 0 || dacl_offset == 0 {
+            // A missing (null) DACL grants everyone full access
+            vec![Ace {
+                allow: true,
+                trustee: EVERYONE.to_string(),
+                rights: FULL | READ | WRITE | EXECUTE,
+            }]
+        } else {
+            parse_dacl(sd.get(dacl_offset..)?)?
+        };
+        Some(Security {
+            owner: sid_at(u32_at(sd, 4)?),
+            group: sid_at(u32_at(sd, 8)?),
+            acl,
+        })
+    }
+
+    /// Allow and deny entries that apply to the object itself (inherit-only
+    /// entries only apply to children)
+    fn parse_dacl(dacl: &[u8]) -> Option<Vec<Ace>> {
+        let count = u16_at(dacl, 4)?;
+        let mut acl = Vec::new();
+        let mut pos = 8;
+        for _ in 0..count {
+            let ace_type = *dacl.get(pos)?;
+            let flags = *dacl.get(pos + 1)?;
+            let size = u16_at(dacl, pos + 2)? as usize;
+            if size < 8 {
+                break;
+            }
+            let applies = flags & INHERIT_ONLY_ACE == 0;
+            if applies && (ace_type == ACCESS_ALLOWED_ACE_TYPE || ace_type == ACCESS_DENIED_ACE_TYPE) {
+                let mask = u32_at(dacl, pos + 4)?;
+                let trustee = parse_sid(dacl.get(pos + 8..pos + size)?)?;
+                add_ace(&mut acl, ace_type == ACCESS_ALLOWED_ACE_TYPE, trustee, mask_rights(mask));
+            }
+            pos += size;
+        }
+        Some(acl)
+    }
+
+    fn mask_rights(mask: u32) -> u8 {
+        if mask & GENERIC_ALL != 0 || mask & FILE_ALL_ACCESS == FILE_ALL_ACCESS {
+            return FULL | READ | WRITE | EXECUTE;
+        }
+        let mut rights = 0;
+        if mask & (FILE_READ_DATA | GENERIC_READ) != 0 {
+            rights |= READ;
+        }
+        if mask & (FILE_WRITE_DATA | FILE_APPEND_DATA | GENERIC_WRITE) != 0 {
+            rights |= WRITE;
+        }
+        if mask & (FILE_EXECUTE | GENERIC_EXECUTE) != 0 {
+            rights |= EXECUTE;
+        }
+        rights
+    }
+
+    /// "S-1-5-21-..." from a binary SID
+    fn parse_sid(sid: &[u8]) -> Option<String> {
+        let revision = *sid.first()?;
+        let count = *sid.get(1)? as usize;
+        let authority = sid.get(2..8)?.iter().fold(0u64, |value, byte| value << 8 | *byte as u64);
+        let mut text = if authority >> 32 == 0 {
+            format!("S-{}-{}", revision, authority)
+        } else {
+            format!("S-{}-0x{:012X}", revision, authority)
+        };
+        for i in 0..count {
+            text.push_str(&format!("-{}", u32_at(sid, 8 + 4 * i)?));
+        }
+        Some(text)
+    }
+
+    /// Every descriptor in the $Secure:$SDS stream, by security id (the table
+    /// $SII indexes); `$STANDARD_INFORMATION` refers to them by that id
+    pub fn parse_sds(sds: &[u8]) -> HashMap<u32, Arc<Security>> {
+        let mut descriptors = HashMap::new();
+        let mut pos = 0;
+        while pos + SDS_HEADER <= sds.len() {
+            // Skip the mirror copy that follows each block
+            if (pos / SDS_BLOCK) % 2 == 1 {
+                pos = (pos / SDS_BLOCK + 1) * SDS_BLOCK;
+                continue;
+            }
+            let next_block = (pos / SDS_BLOCK + 1) * SDS_BLOCK;
+            let (Some(id), Some(offset), Some(length)) = (u32_at(sds, pos + 4), u64_at(sds, pos + 8), u32_at(sds, pos + 16)) else {
+                break;
+            };
+            let length = length as usize;
+            if offset != pos as u64 || length <= SDS_HEADER || pos + length > sds.len() {
+                // Padding up to the end of the block
+                pos = next_block;
+                continue;
+            }
+            if let Some(security) = parse_descriptor(&sds[pos + SDS_HEADER..pos + length]) {
+                descriptors.insert(id, Arc::new(security));
+            }
+            pos = (pos + length).next_multiple_of(16);
+        }
+        descriptors
+    }
+
+    fn u16_at(bytes: &[u8], offset: usize) -> Option<u16> {
+        Some(u16::from_le_bytes(bytes.get(offset..offset + 2)?.try_into().ok()?))
+    }
+
+    fn u32_at(bytes: &[u8], offset: usize) -> Option<u32> {
+        Some(u32::from_le_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?))
+    }
+
+    fn u64_at(bytes: &[u8], offset: usize) -> Option<u64> {
+        Some(u64::from_le_bytes(bytes.get(offset..offset + 8)?.try_into().ok()?))
+    }
+}
+
+#[cfg(windows)]
+pub use descriptor::parse_sds;
+
+/// Who is checked by an `acl:` filter
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Trustee {
+    /// The entry's own owner
+    Owner,
+
+    /// The entry's own group
+    Group,
+
+    /// A SID, uid or gid
+    Id(String),
+}
+
+/// An `acl:` filter such as "everyone-write": the trustee has all the rights
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AclFilter {
+    trustee: Trustee,
+    rights: u8,
+}
+
+impl AclFilter {
+    pub fn matches(&self, security: &Security) -> bool {
+        let trustee = match &self.trustee {
+            Trustee::Owner => security.owner.as_deref(),
+            Trustee::Group => security.group.as_deref(),
+            Trustee::Id(id) => Some(id.as_str()),
+        };
+        trustee.is_some_and(|trustee| security.rights_of(trustee) & self.rights == self.rights)
+    }
+}
+
+impl fmt::Display for AclFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let who = match &self.trustee {
+            Trustee::Owner => "owner",
+            Trustee::Group => "group",
+            Trustee::Id(id) => trustee_name(id),
+        };
+        let right = match self.rights {
+            READ => "read",
+            WRITE => "write",
+            EXECUTE => "execute",
+            _ => "full control",
+        };
+        write!(f, "{} can {}", who, right)
+    }
+}
+
+impl FromStr for AclFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || anyhow!("Invalid acl filter: {}. Use <who>-<right>, e.g. everyone-write or S-1-5-32-545-full", s);
+        let (who, right) = s.rsplit_once('-').ok_or_else(invalid)?;
+        let rights = match right.to_lowercase().as_str() {
+            "read" | "r" => READ,
+            "write" | "w" => WRITE,
+            "execute" | "exec" | "x" => EXECUTE,
+            "full" | "f" => FULL,
+            _ => return Err(invalid()),
+        };
+        let trustee = match who.to_lowercase().as_str() {
+            "" => return Err(invalid()),
+            "owner" => Trustee::Owner,
+            "group" => Trustee::Group,
+            sid if sid.starts_with("s-") => Trustee::Id(who.to_uppercase()),
+            _ => Trustee::Id(trustee_id(who)),
+        };
+        Ok(AclFilter { trustee, rights })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::descriptor::*;
+    use super::*;
+
+    fn sid(text: &str) -> Vec<u8> {
+        let parts: Vec<u64> = text.trim_start_matches("S-").split('-').map(|p| p.parse().unwrap()).collect();
+        let mut bytes = vec![parts[0] as u8, (parts.len() - 2) as u8];
+        bytes.extend_from_slice(&parts[1].to_be_bytes()[2..]);
+        for sub in &parts[2..] {
+            bytes.extend_from_slice(&(*sub as u32).to_le_bytes());
+        }
+        bytes
+    }
+
+    fn descriptor(owner: &str, aces: &[(u8, u8, u32, &str)]) -> Vec<u8> {
+        let mut dacl = Vec::new();
+        for (ace_type, flags, mask, trustee) in aces {
+            let trustee = sid(trustee);
+            dacl.extend_from_slice(&[*ace_type, *flags]);
+            dacl.extend_from_slice(&((8 + trustee.len()) as u16).to_le_bytes());
+            dacl.extend_from_slice(&mask.to_le_bytes());
+            dacl.extend_from_slice(&trustee);
+        }
+        let owner = sid(owner);
+        let mut sd = vec![1, 0];
+        sd.extend_from_slice(&(SE_DACL_PRESENT | 0x8000).to_le_bytes());
+        sd.extend_from_slice(&20u32.to_le_bytes());
+        sd.extend_from_slice(&0u32.to_le_bytes());
+        sd.extend_from_slice(&0u32.to_le_bytes());
+        sd.extend_from_slice(&((20 + owner.len()) as u32).to_le_bytes());
+        sd.extend_from_slice(&owner);
+        sd.extend_from_slice(&[2, 0]);
+        sd.extend_from_slice(&((8 + dacl.len()) as u16).to_le_bytes());
+        sd.extend_from_slice(&(aces.len() as u16).to_le_bytes());
+        sd.extend_from_slice(&[0, 0]);
+        sd.extend_from_slice(&dacl);
+        sd
+    }
+
+    #[test]
+    fn test_security_descriptors() {
+        let user = "S-1-5-21-1004336348-1177238915-682003330-1001";
+        let shared = descriptor(
+            "S-1-5-32-544",
+            &[
+                (ACCESS_ALLOWED_ACE_TYPE, 0, FILE_ALL_ACCESS, "S-1-5-32-544"),
+                (ACCESS_ALLOWED_ACE_TYPE, 0, 0x0012_00A9, "S-1-1-0"),
+                (ACCESS_ALLOWED_ACE_TYPE, 0, 0x0013_01BF, "S-1-5-32-545"),
+                (ACCESS_DENIED_ACE_TYPE, 0, GENERIC_WRITE, "S-1-5-32-546"),
+                // Applies to children only
+                (ACCESS_ALLOWED_ACE_TYPE, INHERIT_ONLY_ACE, GENERIC_ALL, "S-1-1-0"),
+            ],
+        );
+        let private = descriptor(user, &[(ACCESS_ALLOWED_ACE_TYPE, 0, GENERIC_ALL, user)]);
+
+        // Two $SDS entries, 16-byte aligned, and a mirror block to skip
+        let mut sds = Vec::new();
+        for (id, sd) in [(256u32, &shared), (257, &private)] {
+            let pos = sds.len();
+            sds.extend_from_slice(&0u32.to_le_bytes());
+            sds.extend_from_slice(&id.to_le_bytes());
+            sds.extend_from_slice(&(pos as u64).to_le_bytes());
+            sds.extend_from_slice(&((SDS_HEADER + sd.len()) as u32).to_le_bytes());
+            sds.extend_from_slice(sd);
+            sds.resize(sds.len().next_multiple_of(16), 0);
+        }
+        sds.resize(SDS_BLOCK * 2, 0);
+        let descriptors = parse_sds(&sds);
+        assert_eq!(descriptors.len(), 2);
+
+        let security = &descriptors[&256];
+        assert_eq!(security.owner.as_deref(), Some("S-1-5-32-544"));
+        assert_eq!(security.acl_summary(), "Administrators:F;Everyone:RX;Users:RWX;!Guests:W");
+        assert_eq!(parse_acl(&security.acl_summary()).unwrap(), security.acl);
+        assert_eq!(descriptors[&257].acl_summary(), format!("{}:F", user));
+
+        let filter = |text: &str| text.parse::<AclFilter>().unwrap();
+        assert!(!filter("everyone-write").matches(security));
+        assert!(filter("everyone-read").matches(security));
+        assert!(!filter("authenticated-users-write").matches(security));
+        assert!(filter("users-write").matches(security));
+        assert!(filter("owner-full").matches(security));
+        assert!(!filter("guests-write").matches(security));
+        assert!("everyone-delete".parse::<AclFilter>().is_err());
+        assert_eq!(filter("s-1-1-0-write").to_string(), "Everyone can write");
+
+        let unix = Security::from_mode(1000, 100, 0o754);
+        assert_eq!(unix.acl_summary(), "uid:1000:RWX;gid:100:RX;Everyone:R");
+        assert_eq!(parse_acl(&unix.acl_summary()).unwrap(), unix.acl);
+        assert!(filter("owner-write").matches(&unix));
+        assert!(!filter("group-write").matches(&unix));
+        assert!(filter("1000-execute").matches(&unix));
+        assert!(filter("gid:100-read").matches(&unix));
+        assert!(!filter("gid:100-write").matches(&unix));
+
+        let same_ids = Security::from_mode(1000, 1000, 0o640);
+        assert_eq!(same_ids.acl_summary(), "uid:1000:RW;gid:1000:R");
+        assert_eq!((same_ids.owner.as_deref(), same_ids.group.as_deref()), (Some("uid:1000"), Some("gid:1000")));
+        assert!(filter("owner-write").matches(&same_ids));
+        assert!(!filter("group-write").matches(&same_ids));
+        assert!(filter("group-read").matches(&same_ids));
+        assert!(!filter("everyone-write").matches(&Security::from_mode(0, 0, 0o644)));
+        assert!(filter("everyone-write").matches(&Security::from_mode(0, 0, 0o666)));
+    }
+}
@@ -0,0 +1,275 @@
+// Local HTTP/JSON API, so other tools can query the indexes without loading
+// the index files themselves
+use crate::api::{ApiError, IndexService, Request, DEFAULT_LIMIT};
+use crate::index::{IndexView, LiveIndex};
+use crate::search::SearchEngine;
+use anyhow::{anyhow, Result};
+use serde_json::json;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tiny_http::{Header, Method, Response, Server};
+
+/// How often the request loop checks whether it should stop
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// HTTP front end for an `IndexService`, listening on 127.0.0.1 only
+///
+/// Endpoints (JSON in the shape `export::export_json` writes):
+/// - `GET /search?q=&limit=&offset=`: `q` takes the search prompt's filter syntax
+/// - `GET /count?q=`: number of matches
+/// - `GET /entry/<file_id>?volume=`: entries with that id
+/// - `GET /children/<file_id>?volume=`: entries in that directory
+/// - `GET /status`: volumes, entry counts, background jobs and monitor counters
+/// - `POST /rescan?volume=`: compare a volume (default: all) with the disk in the background
+///
+/// With a token set, every request must send `Authorization: Bearer <token>`
+/// or a `token` query parameter.
+pub struct SearchServer<I> {
+    http: Server,
+    service: Arc<IndexService<I>>,
+    token: Option<String>,
+    stop: Arc<AtomicBool>,
+}
+
+impl<I: IndexView + LiveIndex + Send + 'static> SearchServer<I> {
+    /// Listen on `127.0.0.1:port` (0 picks a free port)
+    pub fn bind(service: Arc<IndexService<I>>, port: u16) -> Result<Self> {
+        let http = Server::http((Ipv4Addr::LOCALHOST, port))
+            .map_err(|e| anyhow!("Failed to listen on 127.0.0.1:{}: {}", port, e))?;
+        Ok(Self {
+            http,
+            service,
+            token: None,
+            stop: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Require clients to send `token`
+    pub fn token(mut self, token: Option<String>) -> Self {
+        self.token = token.filter(|token| !token.is_empty());
+        self
+    }
+
+    /// Address the server is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.http
+            .server_addr()
+            .to_ip()
+            .expect("server listens on a TCP address")
+    }
+
+    /// Flag that makes `run` return when set
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    /// Answer requests until the stop flag is set
+    ///
+    /// Requests are handled one at a time; each search locks the volumes one
+    /// after another, like the interactive search.
+    pub fn run(&self) -> Result<()> {
+        let mut engine = SearchEngine::new();
+        while !self.stop.load(Ordering::Relaxed) {
+            if let Some(request) = self.http.recv_timeout(POLL_INTERVAL)? {
+                self.handle(&mut engine, request);
+            }
+        }
+        Ok(())
+    }
+
+    fn handle(&self, engine: &mut SearchEngine, request: tiny_http::Request) {
+        let (path, params) = split_url(request.url());
+
+        let result = if self.authorized(&request, &params) {
+            route(request.method(), &path, &params).and_then(|(code, api_request)| {
+                self.service.handle(engine, &api_request).map(|body| (code, body))
+            })
+        } else {
+            Err(ApiError::new(401, "Missing or wrong token"))
+        };
+        let (code, body) = match result {
+            Ok(response) => response,
+            Err(e) => (e.status, json!({ "error": e.message })),
+        };
+
+        let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+        let response = Response::from_string(body.to_string())
+            .with_status_code(code)
+            .with_header(content_type);
+        let _ = request.respond(response);
+    }
+
+    fn authorized(&self, request: &tiny_http::Request, params: &HashMap<String, String>) -> bool {
+        let Some(token) = &self.token else {
+            return true;
+        };
+        let bearer = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Authorization"))
+            .and_then(|header| header.value.as_str().strip_prefix("Bearer "));
+        bearer == Some(token.as_str()) || params.get("token") == Some(token)
+    }
+}
+
+/// The request an endpoint stands for, and the status code of a successful answer
+fn route(method: &Method, path: &str, params: &HashMap<String, String>) -> Result<(u16, Request), ApiError> {
+    let query = || params.get("q").cloned().unwrap_or_default();
+    let volume = || params.get("volume").cloned();
+
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let request = match (method, segments.as_slice()) {
+        (Method::Get, ["search"]) => Request::Search {
+            query: query(),
+            limit: number_param(params, "limit")?.unwrap_or(DEFAULT_LIMIT),
+            offset: number_param(params, "offset")?.unwrap_or(0),
+        },
+        (Method::Get, ["count"]) => Request::Count { query: query() },
+        (Method::Get, ["entry", id]) => Request::Entry {
+            file_id: parse_id(id)?,
+            volume: volume(),
+        },
+        (Method::Get, ["children", id]) => Request::Children {
+            file_id: parse_id(id)?,
+            volume: volume(),
+        },
+        (Method::Get, ["status"]) => Request::Status,
+        (Method::Post, ["rescan"]) => return Ok((202, Request::Rescan { volume: volume() })),
+        _ => return Err(ApiError::new(404, format!("No such endpoint: {} {}", method, path))),
+    };
+    Ok((200, request))
+}
+
+/// Path and decoded query parameters of a request URL
+fn split_url(url: &str) -> (String, HashMap<String, String>) {
+    match url.split_once('?') {
+        Some((path, query)) => (path.to_string(), form_urlencoded::parse(query.as_bytes()).into_owned().collect()),
+        None => (url.to_string(), HashMap::new()),
+    }
+}
+
+/// Optional numeric query parameter
+fn number_param(params: &HashMap<String, String>, name: &str) -> Result<Option<usize>, ApiError> {
+    params
+        .get(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| ApiError::new(400, format!("{} must be a number: {}", name, value)))
+        })
+        .transpose()
+}
+
+fn parse_id(id: &str) -> Result<u64, ApiError> {
+    id.parse()
+        .map_err(|_| ApiError::new(400, format!("Invalid file id: {}", id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Exclusions;
+    use crate::dir_walker::DirectoryWalker;
+    use crate::index::FileIndex;
+    use crate::multi_volume::MultiVolumeIndex;
+    use crate::scanner::Scanner;
+    use serde_json::Value;
+    use std::fs;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::thread;
+
+    /// Send one request and return the status code and JSON body
+    fn request(addr: SocketAddr, method: &str, target: &str, token: Option<&str>) -> (u16, Value) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        let auth = token.map(|t| format!("Authorization: Bearer {}\r\n", t)).unwrap_or_default();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\n{}Content-Length: 0\r\nConnection: close\r\n\r\n",
+            method, target, auth
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let code = response[9..12].parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (code, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn test_http_api() {
+        let root = std::env::temp_dir().join(format!("nothing_server_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("docs/report.pdf"), b"12345").unwrap();
+        fs::write(root.join("docs/report.txt"), b"").unwrap();
+        fs::write(root.join("notes.txt"), b"").unwrap();
+
+        let mut index = FileIndex::new();
+        DirectoryWalker::new(&root).scan_into_index(&mut index).unwrap();
+        let volumes = Arc::new(MultiVolumeIndex::new());
+        volumes.add_volume("test", None, index);
+
+        let service = IndexService::new(volumes).rescan_roots(vec![("test".to_string(), root.clone())], Exclusions::default());
+        let server = SearchServer::bind(Arc::new(service), 0)
+            .unwrap()
+            .token(Some("secret".to_string()));
+        let addr = server.local_addr();
+        let stop = server.stop_handle();
+        let handle = thread::spawn(move || server.run().unwrap());
+        let get = |target: &str| request(addr, "GET", target, Some("secret"));
+
+        // The token is required
+        assert_eq!(request(addr, "GET", "/status", None).0, 401);
+        assert_eq!(request(addr, "GET", "/status?token=secret", None).0, 200);
+
+        // Search with a filter, paged
+        let (code, body) = get("/search?q=report%20ext:pdf");
+        assert_eq!(code, 200);
+        assert_eq!(body["total_results"], 1);
+        assert_eq!(body["results"][0]["name"], "report.pdf");
+        assert_eq!(body["results"][0]["size"], 5);
+        let (_, first) = get("/search?q=report&limit=1");
+        let (_, second) = get("/search?q=report&limit=1&offset=1");
+        assert_eq!(first["total_results"], 1);
+        assert_ne!(first["results"][0]["path"], second["results"][0]["path"]);
+        assert_eq!(get("/count?q=report").1["count"], 2);
+        assert_eq!(get("/search").0, 400);
+        assert_eq!(get("/search?q=report&limit=10001").0, 400);
+        assert_eq!(get("/search?q=report&limit=18446744073709551615&offset=1").0, 400);
+
+        // Entry by id and its children
+        let docs = get("/search?q=docs%20type:dir").1["results"][0].clone();
+        let (code, entry) = get(&format!("/entry/{}?volume=test", docs["file_id"]));
+        assert_eq!(code, 200);
+        assert_eq!(entry["results"][0]["path"], docs["path"]);
+        assert_eq!(entry["results"][0]["volume"], "test");
+        assert_eq!(get(&format!("/children/{}", docs["file_id"])).1["total_results"], 2);
+        assert_eq!(get("/entry/1?volume=missing").0, 404);
+
+        // A rescan picks up changes made behind the index's back
+        fs::write(root.join("docs/added.txt"), b"").unwrap();
+        let (code, body) = request(addr, "POST", "/rescan", Some("secret"));
+        assert_eq!(code, 202);
+        assert_eq!(body["started"][0], "test");
+        let mut status = get("/status").1;
+        for _ in 0..100 {
+            if status["monitor"]["reconciliations"] == 1 && status["volumes"][0]["jobs"]["running"].is_null() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+            status = get("/status").1;
+        }
+        assert_eq!(status["last_reconciliation"]["added"], 1);
+        assert_eq!(status["total_files"], 4);
+        assert_eq!(get("/count?q=added").1["count"], 1);
+
+        stop.store(true, Ordering::Relaxed);
+        handle.join().unwrap();
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
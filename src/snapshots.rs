@@ -0,0 +1,201 @@
+// Dated copies of each volume's index file, thinned out by a retention policy
+// (newest per hour, day and week), so an index can be loaded as of a past date
+use crate::config::SnapshotConfig;
+use crate::persistence;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+/// File name of a snapshot: the UTC time it was taken
+const STAMP_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// A saved copy of a volume's index file
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub volume: String,
+    pub taken: DateTime<Utc>,
+    pub path: PathBuf,
+}
+
+/// Copy a volume's freshly saved index file into its snapshots, then prune
+/// them by the retention policy
+pub fn take(volume: &str, index_path: &str, policy: &SnapshotConfig) -> Result<Snapshot> {
+    let dir = persistence::get_snapshot_dir(volume)?;
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+
+    let taken = Utc::now();
+    let path = dir.join(format!("{}.bin", taken.format(STAMP_FORMAT)));
+    fs::copy(index_path, &path).with_context(|| format!("Failed to copy {} to {}", index_path, path.display()))?;
+    prune(volume, policy)?;
+    Ok(Snapshot {
+        volume: volume.to_string(),
+        taken,
+        path,
+    })
+}
+
+/// A volume's snapshots, oldest first
+pub fn list(volume: &str) -> Result<Vec<Snapshot>> {
+    let dir = persistence::get_snapshot_dir(volume)?;
+    let Ok(files) = fs::read_dir(&dir) else {
+        return Ok(Vec::new());
+    };
+    let mut snapshots: Vec<Snapshot> = files
+        .filter_map(|file| {
+            let path = file.ok()?.path();
+            let stamp = path.file_name()?.to_str()?.strip_suffix(".bin")?;
+            let taken = NaiveDateTime::parse_from_str(stamp, STAMP_FORMAT).ok()?.and_utc();
+            Some(Snapshot {
+                volume: volume.to_string(),
+                taken,
+                path,
+            })
+        })
+        .collect();
+    snapshots.sort_by_key(|s| s.taken);
+    Ok(snapshots)
+}
+
+/// The index as it was at `when`: the newest snapshot taken at or before it
+pub fn at(volume: &str, when: DateTime<Utc>) -> Result<Option<Snapshot>> {
+    Ok(list(volume)?.into_iter().rev().find(|s| s.taken <= when))
+}
+
+/// Delete the snapshots the policy doesn't keep; returns how many were deleted
+pub fn prune(volume: &str, policy: &SnapshotConfig) -> Result<usize> {
+    let mut snapshots = list(volume)?;
+    snapshots.reverse();
+    let taken: Vec<DateTime<Local>> = snapshots.iter().map(|s| s.taken.with_timezone(&Local)).collect();
+    let keep = kept(&taken, policy);
+
+    let mut deleted = 0;
+    for (i, snapshot) in snapshots.iter().enumerate() {
+        if !keep.contains(&i) {
+            fs::remove_file(&snapshot.path).with_context(|| format!("Failed to delete {}", snapshot.path.display()))?;
+            deleted += 1;
+        }
+    }
+    Ok(deleted)
+}
+
+/// Positions of the snapshots to keep (`taken` newest first): the newest of
+/// each of the last `keep_hourly` hours, `keep_daily` days and `keep_weekly`
+/// ISO weeks that have snapshots
+fn kept<Tz: TimeZone>(taken: &[DateTime<Tz>], policy: &SnapshotConfig) -> HashSet<usize>
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let mut keep = HashSet::new();
+    let buckets: [(usize, &str); 3] = [(policy.keep_hourly, "%Y-%m-%d %H"), (policy.keep_daily, "%Y-%m-%d"), (policy.keep_weekly, "%G-%V")];
+    for (count, bucket) in buckets {
+        let mut last = None;
+        let mut kept_here = 0;
+        for (i, time) in taken.iter().enumerate() {
+            if kept_here == count {
+                break;
+            }
+            let key = time.format(bucket).to_string();
+            if last.as_ref() != Some(&key) {
+                keep.insert(i);
+                kept_here += 1;
+                last = Some(key);
+            }
+        }
+    }
+    keep
+}
+
+/// Parse a point in time for loading a snapshot, in local time: "2024-06-11"
+/// (the end of that day), "2024-06-11 14:30", "yesterday", a weekday
+/// ("tuesday": the end of the last one before today), or "3h", "2d", "1w" ago
+pub fn parse_when(text: &str) -> Result<DateTime<Utc>> {
+    let text = text.trim().to_lowercase();
+    let now = Local::now();
+    let end_of = |date: NaiveDate| -> Result<DateTime<Utc>> {
+        let time = date.and_time(NaiveTime::from_hms_opt(23, 59, 59).unwrap());
+        local(time)
+    };
+
+    if let Some((number, unit)) = text.split_at_checked(text.len().saturating_sub(1)) {
+        if let Ok(count) = number.parse::<i64>() {
+            let ago = match unit {
+                "h" => Duration::hours(count),
+                "d" => Duration::days(count),
+                "w" => Duration::weeks(count),
+                _ => return Err(anyhow!("Invalid time: {}. Use a unit of h, d or w", text)),
+            };
+            return Ok((now - ago).with_timezone(&Utc));
+        }
+    }
+    match text.as_str() {
+        "now" => return Ok(now.with_timezone(&Utc)),
+        "today" => return end_of(now.date_naive()),
+        "yesterday" => return end_of(now.date_naive() - Duration::days(1)),
+        _ => {}
+    }
+    if let Ok(weekday) = text.parse::<Weekday>() {
+        let mut date = now.date_naive() - Duration::days(1);
+        while date.weekday() != weekday {
+            date -= Duration::days(1);
+        }
+        return end_of(date);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(&text, "%Y-%m-%d") {
+        return end_of(date);
+    }
+    for format in ["%Y-%m-%d %H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dt%H:%M"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(&text, format) {
+            return local(time);
+        }
+    }
+    Err(anyhow!(
+        "Invalid time: {}. Use YYYY-MM-DD, \"YYYY-MM-DD HH:MM\", yesterday, a weekday or 3h/2d/1w",
+        text
+    ))
+}
+
+fn local(time: NaiveDateTime) -> Result<DateTime<Utc>> {
+    Local
+        .from_local_datetime(&time)
+        .earliest()
+        .map(|t| t.with_timezone(&Utc))
+        .ok_or_else(|| anyhow!("No such local time: {}", time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retention() {
+        // Every 20 minutes for 15 days, newest first
+        let newest = Utc.with_ymd_and_hms(2024, 6, 30, 12, 0, 0).unwrap();
+        let taken: Vec<DateTime<Utc>> = (0..15 * 72).map(|i| newest - Duration::minutes(20 * i)).collect();
+
+        let policy = SnapshotConfig {
+            keep_hourly: 3,
+            keep_daily: 2,
+            keep_weekly: 2,
+        };
+        let mut keep: Vec<usize> = kept(&taken, &policy).into_iter().collect();
+        keep.sort();
+        let kept_times: Vec<String> = keep.iter().map(|&i| taken[i].format("%m-%d %H:%M").to_string()).collect();
+        assert_eq!(
+            kept_times,
+            vec![
+                "06-30 12:00", // newest: this hour, today, this week
+                "06-30 11:40",
+                "06-30 10:40",
+                "06-29 23:40", // yesterday
+                "06-23 23:40", // last week (ISO weeks start on Monday)
+            ]
+        );
+
+        assert!(kept(&taken, &SnapshotConfig::default()).is_empty());
+        assert!(parse_when("2024-06-11").is_ok());
+        assert!(parse_when("tuesday").unwrap() < Utc::now());
+        assert!(parse_when("3x").is_err());
+    }
+}
@@ -0,0 +1,404 @@
+// SQLite mirror of the index for ad-hoc SQL: a normalized schema with FTS5
+// on names and paths, kept up to date incrementally
+use crate::file_entry::FileEntry;
+use crate::filters::SearchFilters;
+use crate::index::{is_within, EntryRef, IndexView};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// Tables, the `parents` view and the triggers that keep `entries_fts` in sync
+///
+/// File ids are stored as the bit pattern of the u64 (SQLite integers are
+/// signed), timestamps as RFC 3339 text so SQLite's date functions work on
+/// them. `streams` is part of the schema but stays empty until the index
+/// records alternate data streams.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS volumes (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    exported_at TEXT
+);
+CREATE TABLE IF NOT EXISTS extensions (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+CREATE TABLE IF NOT EXISTS entries (
+    id INTEGER PRIMARY KEY,
+    volume_id INTEGER NOT NULL REFERENCES volumes(id),
+    file_id INTEGER NOT NULL,
+    parent_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    path TEXT NOT NULL,
+    is_directory INTEGER NOT NULL,
+    extension_id INTEGER REFERENCES extensions(id),
+    size INTEGER NOT NULL,
+    modified TEXT,
+    created TEXT,
+    accessed TEXT,
+    UNIQUE (volume_id, path)
+);
+CREATE INDEX IF NOT EXISTS entries_file_id ON entries (volume_id, file_id);
+CREATE INDEX IF NOT EXISTS entries_extension ON entries (extension_id);
+CREATE TABLE IF NOT EXISTS streams (
+    entry_id INTEGER NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    PRIMARY KEY (entry_id, name)
+);
+CREATE VIEW IF NOT EXISTS parents AS
+    SELECT child.id AS entry_id, parent.id AS parent_entry_id
+    FROM entries child
+    JOIN entries parent
+      ON parent.volume_id = child.volume_id AND parent.file_id = child.parent_id AND parent.is_directory;
+CREATE VIRTUAL TABLE IF NOT EXISTS entries_fts USING fts5(name, path, content='entries', content_rowid='id');
+CREATE TRIGGER IF NOT EXISTS entries_fts_insert AFTER INSERT ON entries BEGIN
+    INSERT INTO entries_fts (rowid, name, path) VALUES (new.id, new.name, new.path);
+END;
+CREATE TRIGGER IF NOT EXISTS entries_fts_delete AFTER DELETE ON entries BEGIN
+    INSERT INTO entries_fts (entries_fts, rowid, name, path) VALUES ('delete', old.id, old.name, old.path);
+END;
+CREATE TRIGGER IF NOT EXISTS entries_fts_update AFTER UPDATE OF name, path ON entries BEGIN
+    INSERT INTO entries_fts (entries_fts, rowid, name, path) VALUES ('delete', old.id, old.name, old.path);
+    INSERT INTO entries_fts (rowid, name, path) VALUES (new.id, new.name, new.path);
+END;
+";
+
+/// What a sync changed in the database
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SyncStats {
+    pub inserted: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    pub unchanged: usize,
+}
+
+impl SyncStats {
+    /// Whether the sync changed any rows
+    pub fn is_empty(&self) -> bool {
+        self.inserted + self.updated + self.deleted == 0
+    }
+}
+
+impl fmt::Display for SyncStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} added, {} updated, {} removed, {} unchanged",
+            self.inserted, self.updated, self.deleted, self.unchanged
+        )
+    }
+}
+
+/// SQLite database the index is exported to
+///
+/// Exporting again only writes the rows that differ, so the database can be
+/// refreshed from a full index or from just the paths the monitors changed.
+pub struct SqliteExport {
+    conn: Connection,
+
+    /// Extension name -> row id
+    extensions: HashMap<String, i64>,
+}
+
+impl SqliteExport {
+    /// Open (or create) the database and its schema
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        // WAL lets analysts query while the daemon writes
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+        conn.execute_batch(SCHEMA)
+            .with_context(|| format!("Failed to create the schema in {}", path.display()))?;
+        Ok(Self {
+            conn,
+            extensions: HashMap::new(),
+        })
+    }
+
+    /// Bring the volume's rows in line with every entry of `index` that
+    /// passes `filters`
+    pub fn sync_volume<I: IndexView + ?Sized>(&mut self, volume: &str, index: &I, filters: &SearchFilters) -> Result<SyncStats> {
+        self.sync(volume, index, None, filters)
+    }
+
+    /// Like `sync_volume`, but only for the entries at or below `paths`
+    /// (the paths a monitor reported as changed)
+    pub fn sync_paths<I: IndexView + ?Sized>(
+        &mut self,
+        volume: &str,
+        index: &I,
+        paths: &[String],
+        filters: &SearchFilters,
+    ) -> Result<SyncStats> {
+        // Paths below another one are covered by it
+        let mut roots: Vec<&String> = paths.iter().collect();
+        roots.sort();
+        roots.dedup();
+        let mut outermost: Vec<&String> = Vec::new();
+        for root in roots {
+            if !outermost.iter().any(|outer| is_within(root, outer)) {
+                outermost.push(root);
+            }
+        }
+        if outermost.is_empty() {
+            return Ok(SyncStats::default());
+        }
+        let scope: Vec<String> = outermost.into_iter().cloned().collect();
+        self.sync(volume, index, Some(&scope), filters)
+    }
+
+    fn sync<I: IndexView + ?Sized>(
+        &mut self,
+        volume: &str,
+        index: &I,
+        scope: Option<&[String]>,
+        filters: &SearchFilters,
+    ) -> Result<SyncStats> {
+        let tx = self.conn.transaction()?;
+        let volume_id = volume_id(&tx, volume)?;
+        let mut existing = existing_rows(&tx, volume_id, scope)?;
+        let mut stats = SyncStats::default();
+
+        {
+            let mut insert = tx.prepare_cached(
+                "INSERT INTO entries
+                     (volume_id, file_id, parent_id, name, path, is_directory, extension_id, size, modified, created, accessed)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+                 ON CONFLICT (volume_id, path) DO UPDATE SET
+                     file_id = excluded.file_id, parent_id = excluded.parent_id, is_directory = excluded.is_directory,
+                     extension_id = excluded.extension_id, size = excluded.size,
+                     modified = excluded.modified, created = excluded.created, accessed = excluded.accessed",
+            )?;
+            let mut update = tx.prepare_cached(
+                "UPDATE entries
+                 SET file_id = ?2, parent_id = ?3, is_directory = ?4, extension_id = ?5, size = ?6,
+                     modified = ?7, created = ?8, accessed = ?9
+                 WHERE id = ?1
+                   AND (file_id, parent_id, is_directory, extension_id, size, modified, created, accessed)
+                       IS NOT (?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )?;
+
+            for entry in index.entry_refs() {
+                if let Some(roots) = scope {
+                    if !roots.iter().any(|root| is_within(entry.path(), root)) {
+                        continue;
+                    }
+                }
+                let mapped;
+                let entry = match entry {
+                    EntryRef::Owned(owned) => owned,
+                    EntryRef::Mapped(_) => {
+                        mapped = entry.to_entry();
+                        &mapped
+                    }
+                };
+                if !filters.matches(entry) {
+                    continue;
+                }
+
+                let extension_id = match extension(entry) {
+                    Some(name) => Some(extension_id(&tx, &mut self.extensions, &name)?),
+                    None => None,
+                };
+                let (modified, created, accessed) = (timestamp(entry.modified), timestamp(entry.created), timestamp(entry.accessed));
+                match existing.remove(&entry.path) {
+                    Some(id) => {
+                        let changed = update.execute(params![
+                            id,
+                            entry.file_id as i64,
+                            entry.parent_id as i64,
+                            entry.is_directory,
+                            extension_id,
+                            entry.size as i64,
+                            modified,
+                            created,
+                            accessed,
+                        ])?;
+                        if changed > 0 {
+                            stats.updated += 1;
+                        } else {
+                            stats.unchanged += 1;
+                        }
+                    }
+                    None => {
+                        insert.execute(params![
+                            volume_id,
+                            entry.file_id as i64,
+                            entry.parent_id as i64,
+                            &entry.name,
+                            &entry.path,
+                            entry.is_directory,
+                            extension_id,
+                            entry.size as i64,
+                            modified,
+                            created,
+                            accessed,
+                        ])?;
+                        stats.inserted += 1;
+                    }
+                }
+            }
+
+            // Whatever the index no longer has (or the filters no longer pass)
+            let mut delete = tx.prepare_cached("DELETE FROM entries WHERE id = ?1")?;
+            for id in existing.into_values() {
+                delete.execute([id])?;
+                stats.deleted += 1;
+            }
+        }
+
+        tx.execute(
+            "UPDATE volumes SET exported_at = ?2 WHERE id = ?1",
+            params![volume_id, Utc::now().to_rfc3339()],
+        )?;
+        tx.commit()?;
+        Ok(stats)
+    }
+}
+
+/// Row id of the volume, adding it if needed
+fn volume_id(tx: &Transaction, name: &str) -> Result<i64> {
+    tx.execute("INSERT INTO volumes (name) VALUES (?1) ON CONFLICT (name) DO NOTHING", [name])?;
+    Ok(tx.query_row("SELECT id FROM volumes WHERE name = ?1", [name], |row| row.get(0))?)
+}
+
+/// Row id of the extension, adding it if needed
+fn extension_id(tx: &Transaction, cache: &mut HashMap<String, i64>, name: &str) -> Result<i64> {
+    if let Some(&id) = cache.get(name) {
+        return Ok(id);
+    }
+    let id = match tx
+        .query_row("SELECT id FROM extensions WHERE name = ?1", [name], |row| row.get(0))
+        .optional()?
+    {
+        Some(id) => id,
+        None => {
+            tx.execute("INSERT INTO extensions (name) VALUES (?1)", [name])?;
+            tx.last_insert_rowid()
+        }
+    };
+    cache.insert(name.to_string(), id);
+    Ok(id)
+}
+
+/// Path -> row id of the volume's rows, limited to the entries at or below
+/// the `scope` paths
+fn existing_rows(tx: &Transaction, volume_id: i64, scope: Option<&[String]>) -> Result<HashMap<String, i64>> {
+    let mut rows = HashMap::new();
+    let Some(roots) = scope else {
+        let mut select = tx.prepare("SELECT path, id FROM entries WHERE volume_id = ?1")?;
+        for row in select.query_map([volume_id], |row| Ok((row.get(0)?, row.get(1)?)))? {
+            let (path, id) = row?;
+            rows.insert(path, id);
+        }
+        return Ok(rows);
+    };
+
+    // Descendants sort between "<root>/" and "<root>0" ('0' follows '/'),
+    // or "<root>\" and "<root>]" on Windows
+    let mut select = tx.prepare_cached(
+        "SELECT path, id FROM entries
+         WHERE volume_id = ?1
+           AND (path = ?2 OR (path >= ?3 AND path < ?4) OR (path >= ?5 AND path < ?6))",
+    )?;
+    for root in roots {
+        let base = root.trim_end_matches(['/', '\\']);
+        let bounds = [
+            format!("{}/", base),
+            format!("{}0", base),
+            format!("{}\\", base),
+            format!("{}]", base),
+        ];
+        let matches = select.query_map(params![volume_id, root, bounds[0], bounds[1], bounds[2], bounds[3]], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+        for row in matches {
+            let (path, id) = row?;
+            rows.insert(path, id);
+        }
+    }
+    Ok(rows)
+}
+
+/// Lowercase extension of a file (None for directories and names without one)
+fn extension(entry: &FileEntry) -> Option<String> {
+    if entry.is_directory {
+        return None;
+    }
+    match entry.name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && !ext.is_empty() => Some(ext.to_lowercase()),
+        _ => None,
+    }
+}
+
+fn timestamp(time: Option<DateTime<Utc>>) -> Option<String> {
+    time.map(|t| t.to_rfc3339())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::FileIndex;
+
+    fn entry(path: &str, is_directory: bool, file_id: u64, parent_id: u64, size: u64) -> FileEntry {
+        let name = path.rsplit('/').next().unwrap().to_string();
+        FileEntry::new(name, path.to_string(), is_directory, file_id, parent_id, size, None, None, None)
+    }
+
+    fn query(export: &SqliteExport, sql: &str) -> Vec<String> {
+        let mut select = export.conn.prepare(sql).unwrap();
+        let rows = select.query_map([], |row| row.get(0)).unwrap();
+        rows.map(|row| row.unwrap()).collect()
+    }
+
+    #[test]
+    fn test_sqlite_export() {
+        let db = std::env::temp_dir().join(format!("nothing_sqlite_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&db);
+        let mut index = FileIndex::new();
+        index.add_entry(entry("/d", true, 1, 0, 0));
+        index.add_entry(entry("/d/Quarterly Report.PDF", false, 2, 1, 100));
+        index.add_entry(entry("/d/sub", true, 3, 1, 0));
+        index.add_entry(entry("/d/sub/notes.txt", false, 4, 3, 10));
+        let all = SearchFilters::default();
+
+        let mut export = SqliteExport::open(&db).unwrap();
+        let stats = export.sync_volume("d", &index, &all).unwrap();
+        assert_eq!(stats.inserted, 4);
+        assert!(export.sync_volume("d", &index, &all).unwrap().is_empty());
+
+        assert_eq!(
+            query(&export, "SELECT path FROM entries WHERE id IN (SELECT rowid FROM entries_fts WHERE entries_fts MATCH 'report')"),
+            vec!["/d/Quarterly Report.PDF"]
+        );
+        assert_eq!(
+            query(&export, "SELECT e.path FROM entries e JOIN extensions x ON x.id = e.extension_id WHERE x.name = 'pdf'"),
+            vec!["/d/Quarterly Report.PDF"]
+        );
+        assert_eq!(
+            query(&export, "SELECT p.path FROM parents JOIN entries c ON c.id = entry_id JOIN entries p ON p.id = parent_entry_id WHERE c.name = 'notes.txt'"),
+            vec!["/d/sub"]
+        );
+
+        // A change below /d/sub only touches that subtree
+        index.remove_tree("/d/sub/notes.txt");
+        index.add_entry(entry("/d/sub/todo.md", false, 5, 3, 20));
+        index.update_size(2, 200);
+        let stats = export.sync_paths("d", &index, &["/d/sub".to_string(), "/d/sub/todo.md".to_string()], &all).unwrap();
+        assert_eq!((stats.inserted, stats.updated, stats.deleted, stats.unchanged), (1, 0, 1, 1));
+        assert_eq!(query(&export, "SELECT path FROM entries_fts WHERE entries_fts MATCH 'notes'"), Vec::<String>::new());
+
+        let stats = export.sync_volume("d", &index, &all).unwrap();
+        assert_eq!((stats.inserted, stats.updated, stats.deleted, stats.unchanged), (0, 1, 0, 3));
+
+        drop(export);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", db.display(), suffix));
+        }
+    }
+}
@@ -0,0 +1,210 @@
+// Forensic timelines: every timestamp of every entry, as a Sleuth Kit
+// bodyfile for `mactime` or as a CSV with one row per point in time
+use crate::export::{escape_csv_field, Exporter};
+use crate::file_entry::{FileEntry, Timestamps};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::io::Write;
+
+/// MACB letters, in `Timestamps::macb` order
+const MACB: [char; 4] = ['m', 'a', 'c', 'b'];
+
+/// Bodyfile (Sleuth Kit 3.x) lines, for `mactime -b`: one with the standard
+/// timestamps and, on NTFS, one with the `$FILE_NAME` ones
+pub struct BodyfileExporter<W> {
+    out: W,
+}
+
+impl<W: Write> BodyfileExporter<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    /// MD5|name|inode|mode|UID|GID|size|atime|mtime|ctime|crtime, times in
+    /// Unix seconds (0 when missing)
+    fn write_line(&mut self, name: &str, entry: &FileEntry, times: &Timestamps) -> Result<()> {
+        let seconds = |time: Option<DateTime<Utc>>| time.map(|t| t.timestamp()).unwrap_or(0);
+        let mode = if entry.is_directory { "d/drwxrwxrwx" } else { "r/rrwxrwxrwx" };
+        writeln!(
+            self.out,
+            "0|{}|{}|{}|0|0|{}|{}|{}|{}|{}",
+            name,
+            entry.file_id,
+            mode,
+            entry.size,
+            seconds(times.accessed),
+            seconds(times.modified),
+            seconds(times.changed),
+            seconds(times.created)
+        )?;
+        Ok(())
+    }
+}
+
+impl<W: Write> Exporter for BodyfileExporter<W> {
+    fn write_entry(&mut self, entry: &FileEntry, _score: Option<u32>) -> Result<()> {
+        self.write_line(&entry.path, entry, &entry.times())?;
+        if let Some(file_name_times) = entry.file_name_times.as_deref() {
+            self.write_line(&format!("{} ($FILE_NAME)", entry.path), entry, file_name_times)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Which attribute a timeline row's times come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    /// `$STANDARD_INFORMATION`, or the filesystem's times off NTFS
+    Standard,
+
+    /// `$FILE_NAME`
+    FileName,
+}
+
+/// Timestamps of one entry that share a time
+struct Event {
+    time: DateTime<Utc>,
+    entry: usize,
+    source: Source,
+
+    /// Bits in MACB order
+    macb: u8,
+}
+
+/// CSV timeline, oldest first: one row per distinct time of an entry's
+/// standard or `$FILE_NAME` timestamps, with the MACB letters of the
+/// timestamps at that time (as in `mactime` output)
+///
+/// Rows are sorted by time, so every entry is kept until `finish`.
+pub struct TimelineExporter<W> {
+    out: W,
+    entries: Vec<FileEntry>,
+    events: Vec<Event>,
+}
+
+impl<W: Write> TimelineExporter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            entries: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    fn add_events(&mut self, entry: usize, source: Source, times: &Timestamps) {
+        let mut grouped: Vec<(DateTime<Utc>, u8)> = Vec::new();
+        for (i, time) in times.macb().into_iter().enumerate() {
+            let Some(time) = time else {
+                continue;
+            };
+            match grouped.iter_mut().find(|(t, _)| *t == time) {
+                Some((_, bits)) => *bits |= 1 << i,
+                None => grouped.push((time, 1 << i)),
+            }
+        }
+        self.events
+            .extend(grouped.into_iter().map(|(time, macb)| Event { time, entry, source, macb }));
+    }
+}
+
+impl<W: Write> Exporter for TimelineExporter<W> {
+    fn write_entry(&mut self, entry: &FileEntry, _score: Option<u32>) -> Result<()> {
+        let position = self.entries.len();
+        self.add_events(position, Source::Standard, &entry.times());
+        if let Some(file_name_times) = entry.file_name_times.as_deref() {
+            self.add_events(position, Source::FileName, file_name_times);
+        }
+        self.entries.push(entry.clone());
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.events.sort_by_key(|event| event.time);
+        write!(self.out, "Time (UTC),MACB,Source,Type,Size,File ID,Path\r\n")?;
+        for event in &self.events {
+            let entry = &self.entries[event.entry];
+            let source = match event.source {
+                Source::FileName => "$FN",
+                Source::Standard if entry.file_name_times.is_some() => "$SI",
+                Source::Standard => "FS",
+            };
+            write!(
+                self.out,
+                "{},{},{},{},{},{},{}\r\n",
+                event.time.format("%Y-%m-%d %H:%M:%S%.f"),
+                macb_letters(event.macb),
+                source,
+                if entry.is_directory { "Directory" } else { "File" },
+                entry.size,
+                entry.file_id,
+                escape_csv_field(&entry.path)
+            )?;
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// "m.cb" style letters for the set bits
+fn macb_letters(bits: u8) -> String {
+    MACB.iter()
+        .enumerate()
+        .map(|(i, letter)| if bits & (1 << i) != 0 { *letter } else { '.' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timelines() {
+        let at = |secs: i64| DateTime::from_timestamp(1_700_000_000 + secs, 0);
+        let mut entry = FileEntry::new("a,b.exe".into(), "C:\\a,b.exe".into(), false, 42, 5, 100, at(10), at(0), at(20));
+        entry.changed = at(10);
+        entry.file_name_times = Some(Box::new(Timestamps {
+            modified: at(5),
+            accessed: at(5),
+            changed: at(5),
+            created: at(5),
+        }));
+        let plain = FileEntry::new("notes".into(), "/home/notes".into(), true, 7, 1, 0, at(-5), None, None);
+
+        let export = |exporter: &mut dyn Exporter| {
+            exporter.write_entry(&entry, None).unwrap();
+            exporter.write_entry(&plain, None).unwrap();
+            exporter.finish().unwrap();
+        };
+
+        let mut body = Vec::new();
+        export(&mut BodyfileExporter::new(&mut body));
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "0|C:\\a,b.exe|42|r/rrwxrwxrwx|0|0|100|1700000020|1700000010|1700000010|1700000000\n\
+             0|C:\\a,b.exe ($FILE_NAME)|42|r/rrwxrwxrwx|0|0|100|1700000005|1700000005|1700000005|1700000005\n\
+             0|/home/notes|7|d/drwxrwxrwx|0|0|0|0|1699999995|0|0\n"
+        );
+
+        let mut csv = Vec::new();
+        export(&mut TimelineExporter::new(&mut csv));
+        let csv = String::from_utf8(csv).unwrap();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            rows,
+            vec![
+                "Time (UTC),MACB,Source,Type,Size,File ID,Path",
+                "2023-11-14 22:13:15,m...,FS,Directory,0,7,/home/notes",
+                "2023-11-14 22:13:20,...b,$SI,File,100,42,\"C:\\a,b.exe\"",
+                "2023-11-14 22:13:25,macb,$FN,File,100,42,\"C:\\a,b.exe\"",
+                "2023-11-14 22:13:30,m.c.,$SI,File,100,42,\"C:\\a,b.exe\"",
+                "2023-11-14 22:13:40,.a..,$SI,File,100,42,\"C:\\a,b.exe\"",
+            ]
+        );
+        assert!(entry.is_timestomped());
+    }
+}
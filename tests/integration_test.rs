@@ -81,6 +81,10 @@ fn test_index_persistence() {
     let temp_path = std::env::temp_dir().join("nothing_test_index.bin");
     persistence::save_index(&index, temp_path.to_str().unwrap()).expect("Failed to save index");
 
+    // Saving again replaces the file through a temporary one that is renamed into place
+    persistence::save_index(&index, temp_path.to_str().unwrap()).expect("Failed to save index over itself");
+    assert!(!temp_path.with_extension("bin.tmp").exists(), "Temporary file should be renamed away");
+
     let loaded_index = persistence::load_index(temp_path.to_str().unwrap()).expect("Failed to load index");
     assert_eq!(loaded_index.file_count(), 1, "Loaded index should have 1 file");
 
@@ -94,3 +98,96 @@ fn test_index_persistence() {
 
     println!("✅ Index persistence test passed!");
 }
+
+#[test]
+fn test_index_compression() {
+    use nothing::index_format::Compression;
+    use nothing::persistence::StorageOptions;
+
+    let mut index = FileIndex::new();
+    let now = Utc::now();
+    for i in 0..1000 {
+        index.add_entry(FileEntry::new(
+            format!("file_{}.txt", i),
+            format!("C:\\Projects\\Nothing\\data\\file_{}.txt", i),
+            false,
+            i,
+            5,
+            i * 100,
+            Some(now),
+            None,
+            None,
+        ));
+    }
+
+    for compression in [Compression::None, Compression::Zstd, Compression::Lz4] {
+        let temp_path = std::env::temp_dir().join(format!("nothing_test_{}.bin", compression.name()));
+        let path = temp_path.to_str().unwrap();
+
+        let options = StorageOptions {
+            compression,
+            level: 3,
+            ..Default::default()
+        };
+        let stats = persistence::save_index_with(&index, path, &options).expect("Failed to save index");
+        assert!(stats.stored_bytes < stats.raw_bytes, "{} should be smaller than raw", compression.name());
+
+        let loaded = persistence::load_index(path).expect("Failed to load index");
+        assert_eq!(loaded.len(), 1000);
+        assert_eq!(loaded.entries()[999].path, "C:\\Projects\\Nothing\\data\\file_999.txt");
+        assert_eq!(loaded.entries()[999].name, "file_999.txt");
+        assert_eq!(loaded.entries()[999].modified, Some(now));
+
+        let _ = std::fs::remove_file(temp_path);
+    }
+
+    // Indexes written before compression was added still load. They have
+    // the entry layout of the time, not today's FileEntry.
+    #[derive(serde::Serialize)]
+    struct LegacyEntry<'a> {
+        name: &'a str,
+        path: &'a str,
+        is_directory: bool,
+        file_id: u64,
+        parent_id: u64,
+        size: u64,
+        modified: Option<chrono::DateTime<Utc>>,
+        created: Option<chrono::DateTime<Utc>>,
+        accessed: Option<chrono::DateTime<Utc>>,
+    }
+    #[derive(serde::Serialize)]
+    struct LegacyIndex<'a> {
+        entries: Vec<LegacyEntry<'a>>,
+        directory_count: usize,
+    }
+    let legacy = LegacyIndex {
+        entries: index
+            .entries()
+            .iter()
+            .map(|e| LegacyEntry {
+                name: &e.name,
+                path: &e.path,
+                is_directory: e.is_directory,
+                file_id: e.file_id,
+                parent_id: e.parent_id,
+                size: e.size,
+                modified: e.modified,
+                created: e.created,
+                accessed: e.accessed,
+            })
+            .collect(),
+        directory_count: index.directory_count(),
+    };
+    let legacy_path = std::env::temp_dir().join("nothing_test_legacy.bin");
+    std::fs::write(&legacy_path, bincode::serialize(&legacy).unwrap()).unwrap();
+    let loaded = persistence::load_index(legacy_path.to_str().unwrap()).expect("Failed to load legacy index");
+    assert_eq!(loaded.len(), 1000);
+    assert_eq!(loaded.entries()[999].modified, Some(now));
+    let stats = persistence::index_storage_stats(legacy_path.to_str().unwrap()).unwrap();
+    assert_eq!(stats.entries, 1000);
+
+    // A file of the wrong layout is an error, not an abort
+    std::fs::write(&legacy_path, bincode::serialize(&index).unwrap()).unwrap();
+    assert!(persistence::load_index(legacy_path.to_str().unwrap()).is_err());
+    let _ = std::fs::remove_file(legacy_path);
+}